@@ -0,0 +1,94 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use jchip8_rs::arch::{chip8::Chip8, Emulator};
+use jchip8_rs::config::{Config, Frontend};
+
+// How many instructions to execute per benchmark iteration.
+const CYCLES: u64 = 10_000;
+
+// ROMs that exercise a representative mix of game logic.
+const GAMES: [&str; 3] = ["c8games/PONG2", "c8games/BRIX", "c8games/INVADERS"];
+
+// A tight loop that draws the 'F' font sprite across the screen
+// as fast as possible, stressing `draw_sprite` and `Screen`.
+const SPRITE_LOOP: [u8; 14] = [
+    0xA0, 0x4B, // I = font sprite 'F'
+    0x60, 0x00, // V0 = 0
+    0x61, 0x00, // V1 = 0
+    0xD0, 0x15, // Draw 5 rows at (V0, V1)
+    0x70, 0x07, // V0 += 7
+    0x71, 0x03, // V1 += 3
+    0x12, 0x06, // Jump back to the draw
+];
+
+fn headless_config() -> Config {
+    Config {
+        // (No window, since benchmarks cannot rely on a display being present.)
+        frontend: Frontend::Headless,
+        ..Default::default()
+    }
+}
+
+fn load(path: &str) -> Chip8 {
+    let config = Config {
+        game_path: Some(String::from(path)),
+        ..headless_config()
+    };
+    Chip8::new(&config).expect("Failed to load game")
+}
+
+fn run_cycles(c8: &mut Chip8) {
+    for _ in 0..CYCLES {
+        c8.emulate_cycle().expect("Failed to emulate a cycle");
+    }
+}
+
+fn game_loops(c: &mut Criterion) {
+    let mut group = c.benchmark_group("game_loops");
+    group.throughput(Throughput::Elements(CYCLES));
+
+    for game in GAMES {
+        group.bench_function(game, |b| {
+            b.iter_batched(
+                || load(game),
+                |mut c8| run_cycles(&mut c8),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn sprite_heavy(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sprite_heavy");
+    group.throughput(Throughput::Elements(CYCLES));
+    group.bench_function("draw_loop", |b| {
+        b.iter_batched(
+            || Chip8::from_bytes(&SPRITE_LOOP, &headless_config()).unwrap(),
+            |mut c8| run_cycles(&mut c8),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+fn serialization(c: &mut Criterion) {
+    // Serialize a game that has been running for a while,
+    // so that memory and the screen hold realistic contents.
+    let mut c8 = load(GAMES[0]);
+    run_cycles(&mut c8);
+    let state = serde_json::to_vec(&c8).expect("Failed to serialize state");
+
+    let mut group = c.benchmark_group("serialization");
+    group.bench_function("save_state", |b| {
+        b.iter(|| serde_json::to_vec(&c8).unwrap())
+    });
+    group.bench_function("load_state", |b| {
+        b.iter(|| serde_json::from_slice::<Chip8>(&state).unwrap())
+    });
+    group.bench_function("snapshot", |b| b.iter(|| c8.snapshot()));
+    group.finish();
+}
+
+criterion_group!(benches, game_loops, sprite_heavy, serialization);
+criterion_main!(benches);
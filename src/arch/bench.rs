@@ -0,0 +1,57 @@
+use std::fmt;
+use std::time::Duration;
+
+/// How long running a game flat out took (see `Chip8::bench()`), in all and by phase.
+#[derive(Debug, Default)]
+pub struct BenchReport {
+    // How many instructions ran (fewer than asked for, if the game exited first).
+    pub instructions: u64,
+    // How long running took in all (including timing each phase).
+    pub elapsed: Duration,
+    // How long was spent reading instructions from memory, running them
+    // (along with the timers), and drawing the screen.
+    pub fetch: Duration,
+    pub execute: Duration,
+    pub draw: Duration,
+}
+
+impl BenchReport {
+    /// How many instructions ran per second.
+    pub fn instructions_per_second(&self) -> u64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            (self.instructions as f64 / seconds).round() as u64
+        } else {
+            0
+        }
+    }
+
+    fn phase(&self, f: &mut fmt::Formatter, name: &str, time: Duration) -> fmt::Result {
+        // A phase's time, and its share of the whole.
+        let share = if self.elapsed.is_zero() {
+            0.0
+        } else {
+            time.as_secs_f64() / self.elapsed.as_secs_f64() * 100.0
+        };
+        writeln!(f, "  {name}: {:.3}s ({share:.1}%)", time.as_secs_f64())
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Instructions executed: {}", self.instructions)?;
+        writeln!(
+            f,
+            "Speed: {} instructions per second",
+            self.instructions_per_second()
+        )?;
+        writeln!(f, "Time: {:.3}s", self.elapsed.as_secs_f64())?;
+        self.phase(f, "Fetch", self.fetch)?;
+        self.phase(f, "Execute", self.execute)?;
+        self.phase(f, "Draw", self.draw)?;
+        let overhead = self
+            .elapsed
+            .saturating_sub(self.fetch + self.execute + self.draw);
+        write!(f, "  (Timing overhead: {:.3}s)", overhead.as_secs_f64())
+    }
+}
@@ -1,34 +1,221 @@
-use super::{Emulator, InstructionSet, Opcode};
-#[cfg(not(test))]
-use crate::gfx::Hardware;
+#[cfg(feature = "recorder")]
+use super::Movie;
+use super::{
+    apply_ips, effects, explain, install_panic_hook, read_rom, write_crash_dump, BenchReport,
+    Control, ControlCommand, ControlEvent, Effect, Emulator, ExtensionContext, InstructionSet,
+    Opcode, OpcodeCosts, OpcodeHandler, OpcodePattern, OpcodeStats, Quirks, RecentInstructions,
+    RunStats, SchipInstructionSet, StateFormat, Variant,
+};
+#[cfg(all(feature = "debugger", feature = "scripting"))]
+use super::{disassemble, WebDebugger};
+#[cfg(feature = "scripting")]
+use super::{MemorySearch, RemoteCommand, RemoteControl};
+#[cfg(any(feature = "netplay", feature = "recorder"))]
+use crate::config::rom_hash;
+use crate::config::{Config, Frontend, STDIN_PATH};
+use crate::error::Chip8Error;
+#[cfg(feature = "minifb")]
+use crate::gfx::MinifbHardware;
 #[cfg(test)]
 use crate::gfx::MockHardware;
-use crate::gfx::{Drawable, Interactible, Screen, SetKeysResult};
-
-use std::io::{Error, ErrorKind, Write};
+#[cfg(feature = "pixels")]
+use crate::gfx::PixelsHardware;
+#[cfg(feature = "tui")]
+use crate::gfx::TerminalHardware;
+#[cfg(feature = "wgpu")]
+use crate::gfx::WgpuHardware;
+use crate::gfx::{
+    Drawable, Interactible, MachineState, NullHardware, PlaybackSpeed, Screen, SetKeysResult,
+};
+#[cfg(feature = "sdl")]
+use crate::gfx::{Hardware, RenderThread};
+#[cfg(feature = "netplay")]
+use crate::gfx::{Netplay, NetplaySettings};
+use std::any::Any;
+#[cfg(feature = "scripting")]
+use std::collections::{BTreeMap, VecDeque};
+use std::future::Future;
+use std::io::{BufWriter, Error, ErrorKind, Read, Write};
+#[cfg(feature = "netplay")]
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::mpsc::{Receiver, Sender};
 use std::{fmt, fs, thread, time};
 
+use log::{debug, error, info, trace, warn};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::de::{self, DeserializeOwned, Deserializer};
 use serde::{Deserialize, Serialize};
-use serde_json::error::Category;
+#[cfg(feature = "scripting")]
+use serde_json::{json, Value};
 use serde_with::serde_as;
 
 // Emulator constants.
 const NO_GAME_LOADED: &str = "No game loaded";
 const DEFAULT_TITLE: &str = "Chip-8 Emulator";
 const TITLE_PREFIX: &str = "chip8";
-const START_PC: u16 = 0x200;
-const CYCLE_RATE: u64 = 1666667; // ~60hz
+const STDIN_TITLE: &str = "stdin"; // The title of games read from standard input.
+const BYTES_TITLE: &str = "untitled"; // The title of games given as bytes (unless named).
+const START_PC: u16 = 0x200; // Where programs start, unless told otherwise.
+const LAST_PC: u16 = 0xFFE; // The last address a whole instruction can be fetched from.
+const STACK_SIZE: usize = 16; // How many calls deep subroutines can go.
+const CYCLE_RATE: u64 = 1666667; // ~600hz
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+const FRAME_RATE: u64 = 16666667; // ~60hz
+const FRAMES_PER_SECOND: u64 = 60;
+const FAST_FORWARD_FACTOR: u64 = 8; // How many times faster fast-forwarding runs.
+const SLOW_MOTION_FACTOR: u64 = 10; // How many times slower slow motion runs.
+const MAX_INSTRUCTIONS_PER_FRAME: u64 = 1000; // The fastest the speed keys can go.
+const SPIN_MARGIN: u64 = 1_000_000; // How close to a deadline sleeping gives way to spinning.
+const MAX_LAG: u64 = FRAME_RATE; // How far behind schedule running can fall before giving up on catching up.
+const LORES: (u32, u32) = (64, 32); // The resolution of the regular screen.
+const HIRES: (u32, u32) = (128, 64); // The resolution of the SCHIP high-resolution screen.
+const SCROLL_COLUMNS: u16 = 4; // How far horizontal scrolls move the screen.
+const HEX_DUMP_ROW_LENGTH: usize = 16; // How many bytes each row of a hex dump shows.
+#[cfg(feature = "recorder")]
+const CHECKPOINT_INTERVAL: u64 = 600; // How many cycles apart movies take checkpoints.
+#[cfg(feature = "scripting")]
+const HISTORY_LENGTH: usize = 1000; // How many instructions back debuggers can step.
+#[cfg(feature = "scripting")]
+const MAX_SEARCH_RESULTS: usize = 64; // How many candidates memory search replies list.
+#[cfg(all(feature = "debugger", feature = "scripting"))]
+const DISASSEMBLY_BEFORE: u16 = 6; // How many instructions before the PC the web debugger shows.
+#[cfg(all(feature = "debugger", feature = "scripting"))]
+const DISASSEMBLY_LENGTH: usize = 20; // How many instructions the web debugger shows in all.
+
+// The SCHIP high-resolution (8x10) digit sprites, for 0-9.
+// These are stored in memory right after the regular fontset.
+const LARGE_FONTSET: [u8; 100] = [
+    0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C, // 0
+    0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, // 1
+    0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF, // 2
+    0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C, // 3
+    0x06, 0x0E, 0x1E, 0x36, 0x66, 0xC6, 0xFF, 0xFF, 0x06, 0x06, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFE, 0x03, 0xC3, 0x7E, 0x3C, // 5
+    0x3E, 0x7C, 0xC0, 0xC0, 0xFC, 0xFE, 0xC3, 0xC3, 0x7E, 0x3C, // 6
+    0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60, // 7
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C, // 8
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C, // 9
+];
+const LARGE_SPRITE_HEIGHT: u16 = 10;
 
 #[cfg(test)]
 mod tests;
 
-// A simple abstraction of our Hardware types
-// to avoid calling SDL methods during testing
-// (see `MockHardware` for more info).
-#[cfg(test)]
-type Hw = MockHardware;
-#[cfg(not(test))]
-type Hw = Hardware;
+// (Without SDL, the screen and title may go unused.)
+#[cfg_attr(not(feature = "sdl"), allow(unused_variables))]
+fn new_hardware(
+    screen: &Screen,
+    config: &Config,
+    title: &str,
+) -> Result<Box<dyn Interactible>, Chip8Error> {
+    // Create the hardware for the frontend the user picked.
+    // (Frontends that aren't built in can be plugged in with `Chip8::set_hardware()`.)
+    Ok(match config.frontend {
+        #[cfg(feature = "sdl")]
+        Frontend::Sdl if config.render_thread => {
+            Box::new(RenderThread::new(screen, config, title)?)
+        }
+        #[cfg(feature = "sdl")]
+        Frontend::Sdl => Box::new(Hardware::new(screen, config, title)?),
+        #[cfg(feature = "tui")]
+        Frontend::Terminal => Box::new(TerminalHardware::new(config, title)),
+        #[cfg(feature = "minifb")]
+        Frontend::Minifb => Box::new(MinifbHardware::new(screen, config, title)?),
+        #[cfg(feature = "pixels")]
+        Frontend::Pixels => Box::new(PixelsHardware::new(screen, config, title)?),
+        #[cfg(feature = "wgpu")]
+        Frontend::Wgpu => Box::new(WgpuHardware::new(screen, config, title)?),
+        Frontend::Headless => Box::new(NullHardware::new(config)),
+    })
+}
+
+fn wait_until(deadline: time::Instant) {
+    // Sleep until shortly before the deadline, then spin the rest of the way
+    // (sleeping alone can overshoot by a lot, especially on loaded systems).
+    let margin = time::Duration::from_nanos(SPIN_MARGIN);
+    let now = time::Instant::now();
+    if deadline > now + margin {
+        thread::sleep(deadline - now - margin);
+    }
+    while time::Instant::now() < deadline {
+        thread::yield_now();
+    }
+}
+
+// What a run keeps track of from one frame to the next (see `Chip8::run()`).
+struct RunProgress {
+    // When how fast we're running was last measured, and how many instructions
+    // have been run since (and how many frames had been drawn by then).
+    measured_since: time::Instant,
+    instructions: u64,
+    frames_drawn: u64,
+    // When running started (and how many frames had been drawn by then).
+    started: time::Instant,
+    frames_drawn_before: u64,
+    // When the next frame is due. (Frames are paced against this, so that
+    // however long each one takes, running doesn't drift below 60hz.)
+    deadline: time::Instant,
+    // How the machine was when running started, for a host application to reset to.
+    start: Option<Snapshot>,
+    // Why running stopped, if an instruction couldn't run.
+    fault: Option<Chip8Error>,
+}
+
+fn placeholder_hardware() -> Box<dyn Interactible> {
+    // Hardware that's only used until the real hardware is created
+    // (so that no window is opened just to be thrown away).
+    Box::new(NullHardware::new(&Config::default()))
+}
+
+/// A copy of the emulated machine's state, captured in memory
+/// (without serializing it or touching the filesystem) so that it can be
+/// restored later with `Chip8::restore()`.
+///
+/// Snapshots don't hold anything outside of the machine itself
+/// (such as the hardware, or user options like the save state path).
+/// They can also be read from saved states, which hold the same fields.
+#[serde_as]
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Snapshot {
+    #[serde_as(as = "[_; 4096]")]
+    memory: [u8; 4096],
+    #[serde(default)]
+    program_len: u16,
+    #[serde(default = "default_start_address")]
+    start_address: u16,
+    registers: [u8; 16],
+    index_reg: u16,
+    #[serde(deserialize_with = "program_counter")]
+    pc: u16,
+    delay_timer: u8,
+    sound_timer: u8,
+    stack: [u16; 16],
+    #[serde(deserialize_with = "stack_pointer")]
+    sp: u8,
+    #[serde(default)]
+    rpl_flags: [u8; 8],
+    #[serde(default = "random_seed")]
+    seed: u64,
+    #[serde(default = "unseeded_rng")]
+    rng: ChaCha8Rng,
+    update_pc_cycles: u16,
+    #[serde(deserialize_with = "cycle_rate")]
+    cycle_rate: u64,
+    #[serde(default)]
+    variant: Variant,
+    #[serde(default)]
+    quirks: Quirks,
+    #[serde(default)]
+    opcode_costs: OpcodeCosts,
+    #[serde(default)]
+    exited: bool,
+    screen: Screen,
+    draw_flag: bool,
+    count: u64,
+}
 
 #[serde_as]
 #[derive(Serialize, Deserialize)]
@@ -38,10 +225,18 @@ pub struct Chip8 {
     opcode: Opcode,
     #[serde_as(as = "[_; 4096]")]
     // Core memory.
-    // [0x0, START_PC) are reserved for our own use.
+    // [0x0, start_address) are reserved for our own use.
     memory: [u8; 4096],
+    // How long the loaded game is (0 if unknown, for states saved before this was kept).
+    #[serde(default)]
+    program_len: u16,
+    // Where the game is loaded, and starts running
+    // (START_PC, unless the game was written for an interpreter that starts elsewhere).
+    #[serde(default = "default_start_address")]
+    start_address: u16,
     registers: [u8; 16],
     index_reg: u16,
+    #[serde(deserialize_with = "program_counter")]
     pc: u16,
     // A timer for emulated programs to use,
     // decremented once per cycle.
@@ -51,17 +246,53 @@ pub struct Chip8 {
     // We are responsible for emitting a sound when it hits zero.
     sound_timer: u8,
     stack: [u16; 16],
+    #[serde(deserialize_with = "stack_pointer")]
     sp: u8,
+    // The HP-48 RPL user flags, which SCHIP programs
+    // can save registers to (and restore them from) for persistence.
+    #[serde(default)]
+    rpl_flags: [u8; 8],
     // The amount of cycles to update the PC at the end of this cycle.
     update_pc_cycles: u16,
     // How fast to run one cycle in nanoseconds.
+    #[serde(deserialize_with = "cycle_rate")]
     cycle_rate: u64,
+    // The interpreter being emulated, its quirks, and how many cycles its instructions take.
+    // (Like the cycle rate, these are saved with states so that
+    // loading a state reproduces the exact same behavior.)
+    #[serde(default)]
+    variant: Variant,
+    #[serde(default)]
+    quirks: Quirks,
+    #[serde(default)]
+    opcode_costs: OpcodeCosts,
+    // Whether the emulated program has asked to stop running.
+    #[serde(default)]
+    exited: bool,
+    #[serde(skip)]
+    // Handlers for the instructions extensions add, in the order they were added
+    // (see `add_opcode_handler()`).
+    extensions: Vec<(OpcodePattern, OpcodeHandler)>,
+    #[serde(skip)]
+    // Why the last instruction couldn't run, if it couldn't
+    // (taken once the instruction finishes).
+    fault: Option<Chip8Error>,
+    // Our random number generator (seeded, so that runs can be reproduced),
+    // and the seed it started from.
+    #[serde(default = "random_seed")]
+    seed: u64,
+    #[serde(default = "unseeded_rng")]
+    rng: ChaCha8Rng,
 
     // Interactive components.
     screen: Screen,
-    #[serde(skip)]
+    #[serde(skip, default = "placeholder_hardware")]
     // The Interactible portion of the emulator.
-    hardware: Hw,
+    hardware: Box<dyn Interactible>,
+    #[serde(skip)]
+    // The keypad state seen by the emulated program
+    // (taken from the hardware, or from a movie being played back).
+    keypad: [bool; 16],
     #[serde_as(as = "[_; 80]")]
     // Essentially hardcoded fonts to draw with.
     // We could skip serializing this, but it would require a
@@ -69,6 +300,18 @@ pub struct Chip8 {
     // so we just allow it to be serialized.
     fontset: [u8; 80],
     draw_flag: bool,
+    #[serde(skip)]
+    // Whether drawing waits for the display to refresh,
+    // and if so, the earliest the screen may next be drawn.
+    vsync: bool,
+    #[serde(skip)]
+    next_frame: Option<time::Instant>,
+    #[serde(skip)]
+    // How many times the screen has been drawn (for measuring the frame rate).
+    frames_drawn: u64,
+    #[serde(skip)]
+    // The run that `run_frame()` is partway through, if any.
+    frame_run: Option<RunProgress>,
 
     #[serde(skip)]
     // The game title (for use in the displayed window's title).
@@ -77,13 +320,176 @@ pub struct Chip8 {
     #[serde(skip)]
     // Path to save a game state to (or overwrite), if any.
     save_state_path: Option<String>,
+    #[serde(skip)]
+    // Path to load a game state from while running, if any.
+    load_state_path: Option<String>,
+    #[serde(skip)]
+    // The encoding to write game states in, if not the one their path calls for
+    // (see `StateFormat::from_path()`).
+    state_format: Option<StateFormat>,
+    #[serde(skip)]
+    // Whether to save the game state when the user quits.
+    save_on_exit: bool,
+    #[serde(skip)]
+    // Path to dump memory to (or overwrite), if any.
+    memory_dump_path: Option<String>,
+    #[serde(skip)]
+    // Whether memory dumps only hold the program region.
+    memory_dump_program_only: bool,
+    #[serde(skip)]
+    // Path to write the game state to once running stops (or - for standard output), if any.
+    final_state_path: Option<String>,
+    #[serde(skip)]
+    // Path to write a crash dump to if running panics, if any,
+    // and the latest instructions run (kept for it to list, when there is one).
+    crash_dump_path: Option<String>,
+    #[serde(skip)]
+    recent_instructions: RecentInstructions,
+    #[serde(skip)]
+    // Path to write the screen to once running stops, if any,
+    // and how many frames to also write it after (to numbered files), if any.
+    screen_dump_path: Option<String>,
+    #[serde(skip)]
+    screen_dump_interval: Option<u64>,
 
-    // Debug components.
+    // Run limit components.
+    #[serde(skip)]
+    // How many cycles, and 60hz frames (of emulated time), to run for at most, if limited.
+    max_cycles: Option<u64>,
+    #[serde(skip)]
+    max_frames: Option<u64>,
+    #[serde(skip)]
+    // Whether to run as fast as possible (for runs of a fixed number of cycles).
+    unpaced: bool,
+    #[serde(skip)]
+    // How many cycles, and whole frames, have been run so far
+    // (and how far into the current frame we are, in nanoseconds).
+    cycles_run: u64,
+    #[serde(skip)]
+    frames_run: u64,
+    #[serde(skip)]
+    frame_progress: u64,
+    #[serde(skip)]
+    // Whether to stop once the game halts (loops forever doing nothing),
+    // and whether waiting for a key counts (when no key can ever be pressed).
+    stop_on_halt: bool,
+    #[serde(skip)]
+    key_wait_halts: bool,
+    #[serde(skip)]
+    // Whether the game has halted (and running stopped because of it).
+    halted: bool,
+    #[serde(skip)]
+    // Statistics about the latest run.
+    run_stats: RunStats,
+    #[serde(skip)]
+    // Path to write how many times each instruction ran to once running stops, if any,
+    // and the counts so far (only kept when there is one).
+    opcode_stats_path: Option<String>,
+    #[serde(skip)]
+    opcode_stats: OpcodeStats,
+    #[serde(skip)]
+    // How many instructions to run (and explain) per second, when explaining each one,
+    // and how many frames have been run since explaining started.
+    explain_rate: Option<u64>,
+    #[serde(skip)]
+    explained_frames: u64,
+
+    // Recording components.
+    #[cfg(feature = "recorder")]
+    #[serde(skip)]
+    // A movie being recorded (and the path to save it to), if any.
+    recording: Option<(Movie, String)>,
+    #[cfg(feature = "recorder")]
     #[serde(skip)]
-    debug: bool,
+    // A movie being played back, if any.
+    playback: Option<Movie>,
+    #[cfg(feature = "recorder")]
+    #[serde(skip)]
+    // Whether playing the movie back verifies it reproduces the recorded run.
+    verify_replay: bool,
+
+    // Remote control components.
+    #[cfg(feature = "scripting")]
+    #[serde(skip)]
+    // The server remote clients drive the emulator through, if any.
+    remote: Option<RemoteControl>,
+    #[cfg(all(feature = "debugger", feature = "scripting"))]
+    #[serde(skip)]
+    // The server for the debugger in the browser, if any.
+    web_debugger: Option<WebDebugger>,
+    #[serde(skip)]
+    // Whether running is paused (see `pause()`).
+    paused: bool,
+    #[serde(skip)]
+    // The host application's control over running, if it has any.
+    control: Option<Control>,
+    #[cfg(feature = "scripting")]
+    #[serde(skip)]
+    // The memory search remote clients are narrowing down, if any,
+    // and the bytes of memory they froze (and the values they're kept at).
+    memory_search: Option<MemorySearch>,
+    #[cfg(feature = "scripting")]
+    #[serde(skip)]
+    frozen: BTreeMap<usize, u8>,
+    #[cfg(feature = "scripting")]
+    #[serde(skip)]
+    // The machine's state before each of the latest instructions (and the frame each
+    // was run in), oldest first, for stepping backwards through (kept while debugging).
+    history: VecDeque<(u64, Snapshot)>,
+
+    // Debug components.
+    // How many instructions have been run.
     count: u64,
 }
 
+fn default_start_address() -> u16 {
+    // (States saved before the start address could change all started at START_PC.)
+    START_PC
+}
+
+fn program_counter<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u16, D::Error> {
+    // (A PC past the last instruction in memory would have the next fetch read out of bounds.)
+    let pc = u16::deserialize(deserializer)?;
+    if pc > LAST_PC {
+        return Err(de::Error::custom(format!(
+            "program counter {pc:#05X} is past the end of memory"
+        )));
+    }
+    Ok(pc)
+}
+
+fn stack_pointer<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+    // (A stack pointer past the end of the stack would have it read out of bounds.)
+    let sp = u8::deserialize(deserializer)?;
+    if usize::from(sp) > STACK_SIZE {
+        return Err(de::Error::custom(format!(
+            "stack pointer {sp} is past the end of the stack"
+        )));
+    }
+    Ok(sp)
+}
+
+fn cycle_rate<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    // (Cycles taking no time at all would leave no telling how many fit in a frame.)
+    let cycle_rate = u64::deserialize(deserializer)?;
+    if cycle_rate == 0 {
+        return Err(de::Error::custom(
+            "cycle rate must be at least 1 nanosecond",
+        ));
+    }
+    Ok(cycle_rate)
+}
+
+fn random_seed() -> u64 {
+    // Seed runs randomly unless asked otherwise (including states saved before seeding existed).
+    rand::random()
+}
+
+fn unseeded_rng() -> ChaCha8Rng {
+    // (States saved before the generator itself was can't carry on where it left off.)
+    ChaCha8Rng::from_entropy()
+}
+
 // The implementation of hardware instructions for the Chip8 platform.
 impl InstructionSet for Chip8 {
     fn clear_screen(&mut self) {
@@ -94,31 +500,45 @@ impl InstructionSet for Chip8 {
     fn draw_sprite(&mut self) {
         let x_coord: u16 = self.registers[self.opcode.xreg].into();
         let y_coord: u16 = self.registers[self.opcode.yreg].into();
-        let height: u16 = self.opcode.value & 0xF;
-        let width: u16 = 8; // Width is hardcoded on this platform.
-        let shift_constant: u16 = 0x80; // Shifting 128 bits right allow us to check individual bits.
+        let mut height: u16 = self.opcode.value & 0xF;
+        let mut width: u16 = 8; // Sprites are one byte wide...
+        if height == 0 && self.is_hires() {
+            // ...except for SCHIP's 16x16 sprites (two bytes per row),
+            // which are drawn with a height of 0 in hi-res mode.
+            width = 16;
+            height = 16;
+        }
+        let bytes_per_row = width / 8;
+        let wrap = self.quirks.wrap_sprites;
+
+        // (The whole sprite is checked first, so that none of it is drawn if it can't all be.)
+        let sprite = usize::from(self.index_reg);
+        if sprite + usize::from(height * bytes_per_row) > self.memory.len() {
+            return self.out_of_bounds(sprite.max(self.memory.len()));
+        }
 
         self.registers[0xF] = 0; // Assume we don't unset any pixels.
 
         for y_line in 0..height {
-            let pixel_offset: usize = (self.index_reg + y_line).into();
-            let pixel: u16 = self.memory[pixel_offset].into();
-
-            for x_line in 0..width {
-                let x = x_coord + x_line;
-                let y = y_coord + y_line;
-
-                // If we need to draw this pixel...
-                // (hedging against illegal code in the emulated program)
-                if (pixel & (shift_constant >> x_line)) > 0
-                    && self.screen.in_bounds(u32::from(x), u32::from(y))
-                {
-                    // XOR the pixel, saving whether we set it here.
-                    if self.screen.get_pixel(x, y) {
-                        self.registers[0xF] = 1;
-                    }
-                    self.screen.xor_pixel(x, y);
-                }
+            // Read the whole row of the sprite at once.
+            let row_offset = sprite + usize::from(y_line * bytes_per_row);
+            let row: u16 = (0..usize::from(bytes_per_row)).fold(0, |row, byte| {
+                (row << 8) | u16::from(self.memory[row_offset + byte])
+            });
+
+            let mut x = u32::from(x_coord);
+            let mut y = u32::from(y_coord + y_line);
+            if wrap {
+                x %= self.screen.res_width;
+                y %= self.screen.res_height;
+            }
+
+            // If we need to draw this row...
+            // (hedging against illegal code in the emulated program)
+            // XOR it, saving whether we unset any pixels.
+            if self.screen.in_bounds(0, y) && self.screen.xor_row(x, y, row, u32::from(width), wrap)
+            {
+                self.registers[0xF] = 1;
             }
         }
 
@@ -128,7 +548,7 @@ impl InstructionSet for Chip8 {
     fn set_index_reg_to_sprite(&mut self) {
         let character = u16::from(self.registers[self.opcode.xreg]);
         // Number of sprites per character. (If this overflows, something is very very wrong...)
-        let offset = (self.fontset.len() / self.hardware.get_keys().len()) as u16;
+        let offset = (self.fontset.len() / self.keypad.len()) as u16;
 
         // Set the index register to the location of the
         // first fontset sprite of the matching character.
@@ -136,25 +556,47 @@ impl InstructionSet for Chip8 {
     }
 
     fn call(&mut self) {
-        self.stack[self.sp as usize] = self.pc;
-        self.sp += 1; // Allow overflow to panic - the stack is only 16 entries anyway.
+        let Some(entry) = self.stack.get_mut(self.sp as usize) else {
+            return self.stack_fault(Chip8Error::StackOverflow {
+                opcode: self.opcode.value,
+                pc: self.pc,
+            });
+        };
+        *entry = self.pc;
+        self.sp += 1;
 
         self.pc = self.opcode.literal;
         self.update_pc_cycles = 0; // Since we just changed PC manually.
     }
 
     fn r#return(&mut self) {
+        if self.sp == 0 {
+            return self.stack_fault(Chip8Error::StackUnderflow {
+                opcode: self.opcode.value,
+                pc: self.pc,
+            });
+        }
         self.sp -= 1;
         self.pc = self.stack[self.sp as usize];
     }
 
     fn jump(&mut self) {
+        if self.opcode.literal == self.pc {
+            // Jumping to itself is how most programs end, since there is no way out.
+            self.halt("jumped to itself");
+        }
         self.pc = self.opcode.literal;
         self.update_pc_cycles = 0;
     }
 
     fn jump_with_offset(&mut self) {
-        self.pc = self.opcode.literal + u16::from(self.registers[0]);
+        // Normally the offset is V0, but some interpreters use VX instead.
+        let offset_reg = if self.quirks.jump_uses_vx {
+            self.opcode.xreg
+        } else {
+            0
+        };
+        self.pc = self.opcode.literal + u16::from(self.registers[offset_reg]);
         self.update_pc_cycles = 0;
     }
 
@@ -187,19 +629,13 @@ impl InstructionSet for Chip8 {
     }
 
     fn skip_if_key_pressed(&mut self) {
-        if self
-            .hardware
-            .key_is_pressed(self.registers[self.opcode.xreg])
-        {
+        if self.key_pressed() == Some(true) {
             self.update_pc_cycles = 4;
         }
     }
 
     fn skip_if_key_not_pressed(&mut self) {
-        if !self
-            .hardware
-            .key_is_pressed(self.registers[self.opcode.xreg])
-        {
+        if self.key_pressed() == Some(false) {
             self.update_pc_cycles = 4;
         }
     }
@@ -229,17 +665,26 @@ impl InstructionSet for Chip8 {
 
     fn or(&mut self) {
         let opcode = &self.opcode;
-        self.registers[opcode.xreg] = self.registers[opcode.xreg] | self.registers[opcode.yreg];
+        self.registers[opcode.xreg] |= self.registers[opcode.yreg];
+        if self.quirks.logic_resets_vf {
+            self.registers[0xF] = 0;
+        }
     }
 
     fn and(&mut self) {
         let opcode = &self.opcode;
-        self.registers[opcode.xreg] = self.registers[opcode.xreg] & self.registers[opcode.yreg];
+        self.registers[opcode.xreg] &= self.registers[opcode.yreg];
+        if self.quirks.logic_resets_vf {
+            self.registers[0xF] = 0;
+        }
     }
 
     fn xor(&mut self) {
         let opcode = &self.opcode;
-        self.registers[opcode.xreg] = self.registers[opcode.xreg] ^ self.registers[opcode.yreg];
+        self.registers[opcode.xreg] ^= self.registers[opcode.yreg];
+        if self.quirks.logic_resets_vf {
+            self.registers[0xF] = 0;
+        }
     }
 
     fn sub_x_from_y(&mut self) {
@@ -259,7 +704,7 @@ impl InstructionSet for Chip8 {
     }
 
     fn shift_right(&mut self) {
-        let val = self.registers[self.opcode.xreg];
+        let val = self.shift_source();
 
         // Set VF to least significant bit of Xreg before shifting.
         self.registers[0xF] = val & 0x1;
@@ -267,7 +712,7 @@ impl InstructionSet for Chip8 {
     }
 
     fn shift_left(&mut self) {
-        let val = self.registers[self.opcode.xreg];
+        let val = self.shift_source();
 
         // Set VF to most significant bit of Xreg before shifting.
         self.registers[0xF] = (val >> 7) & 0x1;
@@ -276,7 +721,9 @@ impl InstructionSet for Chip8 {
 
     fn set_reg_random_mask(&mut self) {
         let mask = self.opcode.value as u8; // "as u8" chops to 0xFF for us.
-        let random_number = rand::random::<u8>();
+
+        // Draw from our own (seeded) generator, so that runs can be reproduced.
+        let random_number = self.rng.gen::<u8>();
 
         self.registers[self.opcode.xreg] = mask & random_number;
     }
@@ -288,14 +735,19 @@ impl InstructionSet for Chip8 {
         // the hundreths digit of the value is in Mem[Index],
         // the tenths digit is in Mem[Index+1], and
         // the ones digit is in Mem[Index+2].
-        self.memory[self.index_reg as usize] = val / 100;
-        self.memory[(self.index_reg + 1) as usize] = (val / 10) % 10;
-        self.memory[(self.index_reg + 2) as usize] = (val % 100) % 10;
+        let loc = usize::from(self.index_reg);
+        let Some(digits) = self.memory.get_mut(loc..loc + 3) else {
+            return self.out_of_bounds(loc.max(self.memory.len()));
+        };
+        digits.copy_from_slice(&[val / 100, (val / 10) % 10, (val % 100) % 10]);
     }
 
     // Manipulating special registers.
     fn add_reg_to_index_reg(&mut self) {
-        self.index_reg += u16::from(self.registers[self.opcode.xreg]);
+        // (I is a 16-bit register, so it wraps around; reading past memory is caught later.)
+        self.index_reg = self
+            .index_reg
+            .wrapping_add(u16::from(self.registers[self.opcode.xreg]));
     }
 
     fn set_index_reg_to_literal(&mut self) {
@@ -303,9 +755,7 @@ impl InstructionSet for Chip8 {
     }
 
     fn get_key_press(&mut self) {
-        let keyboard = self.hardware.get_keys();
-
-        for (key, pressed) in keyboard.iter().enumerate() {
+        for (key, pressed) in self.keypad.iter().enumerate() {
             if *pressed {
                 // If key as u8 overflows u8, the instruction was invalid!
                 self.registers[self.opcode.xreg] = key as u8;
@@ -315,6 +765,13 @@ impl InstructionSet for Chip8 {
 
         // Else, don't increment the PC, we'll wait another cycle for the key.
         self.update_pc_cycles = 0;
+        #[cfg(feature = "recorder")]
+        if self.playback.is_some() {
+            return;
+        }
+        if self.key_wait_halts {
+            self.halt("waited for a key that can never be pressed");
+        }
     }
 
     fn get_delay_timer(&mut self) {
@@ -335,11 +792,13 @@ impl InstructionSet for Chip8 {
         // starting in memory at the location in the index register.
         for (loc, reg) in (usize::from(self.index_reg)..).zip(0..=self.opcode.xreg) {
             if loc >= self.memory.len() {
-                panic!("Cannot save register {reg} to memory location {loc}: out of bounds!");
+                return self.out_of_bounds(loc);
             }
 
             self.memory[loc] = self.registers[reg];
         }
+
+        self.increment_index_after_load_store();
     }
 
     fn restore_registers(&mut self) {
@@ -347,25 +806,105 @@ impl InstructionSet for Chip8 {
         // starting in memory at the location in the index register.
         for (loc, reg) in (usize::from(self.index_reg)..).zip(0..=self.opcode.xreg) {
             if loc >= self.memory.len() {
-                panic!("Cannot load register {reg} from memory location {loc}: out of bounds!");
+                return self.out_of_bounds(loc);
             }
 
             self.registers[reg] = self.memory[loc];
         }
+
+        self.increment_index_after_load_store();
     }
 
     // Save state handling.
     fn save_state(&mut self) {
         if let Some(path) = self.save_state_path.clone() {
-            if let Err(error) = self.to_state(&path) {
-                if self.debug {
-                    println!("Failed to save state: {error}");
+            match self.to_state(&path) {
+                Ok(()) => {
+                    info!(path; "Saved state to {path}");
+                    self.run_stats.states_saved += 1;
+                }
+                Err(error) => warn!(path, error:%; "Failed to save state: {error}"),
+            }
+        }
+    }
+
+    fn load_state(&mut self) {
+        // Replace our state in place (keeping the hardware and user options).
+        if let Some(path) = self.load_state_path.clone() {
+            let snapshot = fs::read(&path)
+                .map_err(Chip8Error::from)
+                .and_then(|contents| Self::parse_state(&contents));
+            match snapshot {
+                Ok(snapshot) => {
+                    self.load_snapshot(&snapshot);
+                    info!(path; "Loaded state from {path}");
                 }
+                Err(error) => warn!(path, error:%; "Failed to load state: {error}"),
             }
         }
     }
 }
 
+// The SUPER-CHIP extensions (only decoded when emulating SCHIP, see `Variant`).
+impl SchipInstructionSet for Chip8 {
+    fn scroll_down(&mut self) {
+        // The number of rows to scroll is the last hex digit of the instruction.
+        let rows = self.opcode.value & 0xF;
+        self.screen.scroll_down(rows);
+        self.draw_flag = true;
+    }
+
+    fn scroll_left(&mut self) {
+        self.screen.scroll_left(SCROLL_COLUMNS);
+        self.draw_flag = true;
+    }
+
+    fn scroll_right(&mut self) {
+        self.screen.scroll_right(SCROLL_COLUMNS);
+        self.draw_flag = true;
+    }
+
+    fn low_resolution(&mut self) {
+        self.screen.set_resolution(LORES.0, LORES.1);
+        self.draw_flag = true;
+    }
+
+    fn high_resolution(&mut self) {
+        self.screen.set_resolution(HIRES.0, HIRES.1);
+        self.draw_flag = true;
+    }
+
+    fn set_index_reg_to_large_sprite(&mut self) {
+        let character = u16::from(self.registers[self.opcode.xreg]);
+        // The large fontset is stored right after the regular fontset.
+        let start = self.fontset.len() as u16;
+
+        // Set the index register to the location of the
+        // first large fontset sprite of the matching character.
+        self.index_reg = start + character * LARGE_SPRITE_HEIGHT;
+    }
+
+    fn exit(&mut self) {
+        self.exited = true;
+        self.update_pc_cycles = 0; // Stay put, there's nothing left to run.
+    }
+
+    fn save_registers_to_flags(&mut self) {
+        // Store all registers up to AND INCLUDING the last register in the RPL flags.
+        // (There are fewer flags than registers, so any extra registers are ignored.)
+        for (flag, reg) in self.rpl_flags.iter_mut().zip(0..=self.opcode.xreg) {
+            *flag = self.registers[reg];
+        }
+    }
+
+    fn restore_registers_from_flags(&mut self) {
+        // Load all registers up to AND INCLUDING the last register from the RPL flags.
+        for (flag, reg) in self.rpl_flags.iter().zip(0..=self.opcode.xreg) {
+            self.registers[reg] = *flag;
+        }
+    }
+}
+
 // Mostly useful for debugging.
 impl fmt::Display for Chip8 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -397,16 +936,19 @@ impl fmt::Display for Chip8 {
 // so that serde / serde_json can populate them as well when reading
 // a state from disk (which does not store all of these members).
 //
-// Note that the default Hw instance / debug / opcode / save_state_path members
+// Note that the default hardware instance / debug / opcode / save_state_path members
 // are placeholders and must be overridden when using this default.
 impl Default for Chip8 {
     fn default() -> Chip8 {
         let screen = Screen::default();
-        let hardware = Hw::new(&screen, false, NO_GAME_LOADED);
+        let hardware = placeholder_hardware();
+        let seed = random_seed();
         let mut c8 = Chip8 {
             opcode: Opcode::default(), // Will be replaced at fetch_opcode() time.
 
             memory: [0; 4096],
+            program_len: 0,
+            start_address: START_PC,
             registers: [0; 16], // We use wrapping arithmetic.
             index_reg: 0,
             pc: START_PC,
@@ -414,6 +956,7 @@ impl Default for Chip8 {
             sound_timer: 0,
             stack: [0; 16],
             sp: 0,
+            rpl_flags: [0; 8],
             update_pc_cycles: 0,
 
             screen,
@@ -437,184 +980,1267 @@ impl Default for Chip8 {
                 0xF0, 0x80, 0xF0, 0x80, 0x80, // F
             ],
             draw_flag: false,
+            vsync: false,
+            next_frame: None,
+            frames_drawn: 0,
+            frame_run: None,
             cycle_rate: CYCLE_RATE,
+            variant: Variant::default(),
+            quirks: Quirks::default(),
+            opcode_costs: OpcodeCosts::default(),
+            exited: false,
+            extensions: Vec::new(),
+            fault: None,
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            keypad: [false; 16],
 
             game_title: String::from(NO_GAME_LOADED),
             save_state_path: None,
+            load_state_path: None,
+            state_format: None,
+            save_on_exit: false,
+            memory_dump_path: None,
+            memory_dump_program_only: false,
+            final_state_path: None,
+            crash_dump_path: None,
+            recent_instructions: RecentInstructions::default(),
+            screen_dump_path: None,
+            screen_dump_interval: None,
+
+            max_cycles: None,
+            max_frames: None,
+            unpaced: false,
+            cycles_run: 0,
+            frames_run: 0,
+            frame_progress: 0,
+            stop_on_halt: false,
+            key_wait_halts: false,
+            halted: false,
+            run_stats: RunStats::default(),
+            opcode_stats_path: None,
+            opcode_stats: OpcodeStats::default(),
+            explain_rate: None,
+            explained_frames: 0,
+
+            #[cfg(feature = "recorder")]
+            recording: None,
+            #[cfg(feature = "recorder")]
+            playback: None,
+            #[cfg(feature = "recorder")]
+            verify_replay: false,
+
+            #[cfg(feature = "scripting")]
+            remote: None,
+            #[cfg(all(feature = "debugger", feature = "scripting"))]
+            web_debugger: None,
+            paused: false,
+            control: None,
+            #[cfg(feature = "scripting")]
+            memory_search: None,
+            #[cfg(feature = "scripting")]
+            frozen: BTreeMap::new(),
+            #[cfg(feature = "scripting")]
+            history: VecDeque::new(),
 
-            debug: false,
             count: 0,
         };
 
-        // Load the fontset into memory.
-        for (item, value) in c8.fontset.iter().enumerate() {
-            c8.memory[item] = *value;
-        }
-
+        c8.load_fontsets();
         c8
     }
 }
 
 impl Chip8 {
-    fn set_debug(&mut self, debug: bool) {
-        // Override the debug value with a new one (useful when loading a state).
-        self.hardware.debug = debug;
-        self.debug = debug;
+    fn load_fontsets(&mut self) {
+        // Load the fontsets into memory (the large fontset follows the regular one).
+        let fonts = self.fontset.iter().chain(LARGE_FONTSET.iter());
+        for (item, value) in fonts.enumerate() {
+            self.memory[item] = *value;
+        }
     }
 
-    fn load_game(&mut self, file_path: &str) -> Result<(), Error> {
-        // Load a game file from disk (without a saved state,
-        // but with an already-initialized Chip8 instance).
+    fn shift_source(&self) -> u8 {
+        // The value shifted by 8XY6/8XYE: normally VX, but some interpreters use VY.
+        if self.quirks.shift_uses_vy {
+            self.registers[self.opcode.yreg]
+        } else {
+            self.registers[self.opcode.xreg]
+        }
+    }
 
-        // Set the game's title.
-        self.hardware
-            .set_title(&format!("{}: {}", TITLE_PREFIX, file_path))?; // Handles title errors.
-        self.game_title = String::from(file_path);
+    fn increment_index_after_load_store(&mut self) {
+        // Some interpreters leave the index register
+        // pointing past the last register used by FX55/FX65.
+        if self.quirks.load_store_increments_index {
+            self.index_reg += self.opcode.xreg as u16 + 1;
+        }
+    }
 
-        // Load the game into memory.
-        let contents: Vec<u8> = fs::read(file_path)?; // Handles all read errors.
-        for (index, value) in contents.iter().enumerate() {
-            self.memory[usize::from(START_PC) + index] = *value; // Essentially memcpy().
+    fn apply_settings(&mut self, config: &Config) {
+        // Apply any behavior settings the user explicitly chose, on top of
+        // either the defaults or the settings stored in a loaded state.
+        if let Some(variant) = config.variant {
+            self.variant = variant;
+        }
+        for (quirk, enabled) in &config.quirks {
+            self.quirks.set(*quirk, *enabled);
+        }
+        for (class, cycles) in &config.opcode_costs {
+            self.opcode_costs.set(*class, *cycles);
+        }
+        if let Some(speed) = config.speed {
+            self.cycle_rate = NANOS_PER_SECOND / speed;
+        }
+        if let Some(seed) = config.seed {
+            self.reseed(seed);
         }
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        // Start the random number generator over from a seed.
+        self.seed = seed;
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+    }
 
+    #[cfg(feature = "netplay")]
+    fn start_netplay(&mut self, config: &Config) -> Result<(), Error> {
+        // Connect to the other player (once the game is loaded, so that both can check
+        // they're playing the same one), and take on the settings agreed on.
+        let Some(peer) = &config.netplay else {
+            return Ok(());
+        };
+        let settings = NetplaySettings {
+            rom_hash: rom_hash(&self.memory),
+            seed: self.seed,
+            cycle_rate: self.cycle_rate,
+            variant: self.variant,
+            quirks: self.quirks,
+            opcode_costs: self.opcode_costs,
+            delay: config.netplay_delay,
+        };
+        let hardware = mem::replace(&mut self.hardware, placeholder_hardware());
+        let (netplay, settings) = Netplay::connect(hardware, peer, settings)?;
+        self.reseed(settings.seed);
+        self.cycle_rate = settings.cycle_rate;
+        self.variant = settings.variant;
+        self.quirks = settings.quirks;
+        self.opcode_costs = settings.opcode_costs;
+        self.hardware = Box::new(netplay);
         Ok(())
     }
 
-    fn from_state(
-        file_path: &str,
-        debug: bool,
-        save_state_path: Option<String>,
-    ) -> Result<Chip8, Error> {
-        // Load a game's state from disk (this includes the game data itself).
-        // Here we do not have an existing Chip8 instance and must create one with serde and friends.
+    fn load_snapshot(&mut self, snapshot: &Snapshot) {
+        // Replace our state in place with a loaded (or earlier) one.
+        self.restore(snapshot);
+        // (There's no stepping back from here into another timeline.)
+        #[cfg(feature = "scripting")]
+        self.history.clear();
+        // (States saved before the large fontset existed don't hold it in memory.)
+        self.load_fontsets();
+    }
 
-        // Read the game state and deserialize it into a Chip8 instance.
-        let contents: Vec<u8> = fs::read(file_path)?; // Return errors inline.
-        let parsed_c8: Result<Chip8, serde_json::Error> = serde_json::from_slice(&contents);
-        match parsed_c8 {
-            Ok(mut c8) => {
-                // Update state not settable from default().
-                c8.hardware
-                    .set_title(&format!("{}: {}", TITLE_PREFIX, file_path))?; // Handles title errors.
-
-                // Update state overridden by the user.
-                c8.save_state_path = save_state_path;
-                c8.set_debug(debug); // Debug is not stored in the state, so this only enables it.
-
-                // Draw the screen once to start.
-                c8.hardware.update_display(&c8.screen);
-                Ok(c8)
+    fn start_running(&mut self) -> Option<RunProgress> {
+        // Get ready to run a frame at a time (see `run()`),
+        // unless there's nothing to run.
+        // (If there's no instruction at the PC at all, the first one run fails instead.)
+        if self.fetch_opcode().is_ok() && self.opcode == Opcode::default() {
+            // No game is loaded, so just exit.
+            // (This is mostly useful when a 'game' has been loaded that does not
+            // contain valid Chip8 instructions.)
+            return None;
+        }
+        self.hardware.init();
+
+        let now = time::Instant::now();
+        Some(RunProgress {
+            measured_since: now,
+            instructions: 0,
+            frames_drawn: self.frames_drawn,
+            started: now,
+            frames_drawn_before: self.frames_drawn,
+            deadline: now,
+            // (A host application can reset the machine to how it was now.)
+            start: self.control.is_some().then(|| self.snapshot()),
+            fault: None,
+        })
+    }
+
+    fn run_next_frame(&mut self, progress: &mut RunProgress) -> bool {
+        // Run a frame (of a run started with `start_running()`), setting when the next
+        // one is due, and returning false once running should stop instead.
+        if self.reached_run_limit() {
+            return false;
+        }
+        // Do what the host application asked for first...
+        if !self.serve_control(&progress.start) {
+            return false;
+        }
+        // ...then what remote clients asked for.
+        #[cfg(feature = "scripting")]
+        if let Err(error) = self.serve_remote() {
+            progress.fault = Some(error);
+            return false;
+        }
+        #[cfg(feature = "scripting")]
+        self.apply_freezes();
+        if self.exited || self.halted {
+            return false;
+        }
+
+        // Run a frame's worth of instructions (unless paused)...
+        // (Or rather, of cycles, which some instructions can take several of.)
+        // (When explaining each instruction, only so many run a second, to read along with.)
+        let frame_cycles = if self.paused {
+            0
+        } else if let Some(rate) = self.explain_rate {
+            self.explained_frames += 1;
+            self.explained_frames * rate / FRAMES_PER_SECOND
+                - (self.explained_frames - 1) * rate / FRAMES_PER_SECOND
+        } else {
+            self.instructions_per_frame()
+        };
+        let mut cycles = 0;
+        while cycles < frame_cycles {
+            if self.reached_run_limit() {
+                return false;
             }
-            Err(error) => {
-                // Serde was not able to deserialize the state into a valid Chip8 instance.
-                match error.classify() {
-                    // We allow I/O errors to pass through because they may indicate a problem
-                    // on the host system (i.e. the path to the saved state is not present).
-                    Category::Io => Err(Error::other(error)),
-                    // We assume all Syntax/Data/Eof errors are due to malformed input.
-                    _ => Err(Error::new(
-                        ErrorKind::InvalidInput,
-                        "Load state path does not appear to point to a valid saved state!",
-                    )),
+            match self.run_instruction() {
+                Ok(true) => {
+                    progress.instructions += 1;
+                    cycles += self.opcode_costs.cycles(self.opcode.value);
+                }
+                Ok(false) => return false,
+                Err(error) => {
+                    progress.fault = Some(error);
+                    return false;
                 }
             }
         }
-    }
 
-    fn to_state(&mut self, to_file_path: &str) -> Result<(), Error> {
-        // Save a Chip8 instance to disk (where it can be loaded again later).
+        // ...then read input (and act on it), update the timers, and draw, once each.
+        let keys_result = self.hardware.set_keys(&self.screen);
+        self.keypad.copy_from_slice(self.hardware.get_keys());
+        if let SetKeysResult::ShouldExit = keys_result {
+            if self.save_on_exit {
+                self.save_state();
+            }
+            return false;
+        }
+        self.act_on_keys(&keys_result);
+        if !self.paused {
+            self.update_timers();
+        }
+        if self.draw_flag {
+            self.present();
+            if let Some(control) = &self.control {
+                control.send(ControlEvent::FrameRendered {
+                    frame: self.frames_run,
+                    screen: self.screen.clone(),
+                });
+            }
+        }
+        self.hardware.show_machine_state(&MachineState {
+            memory: &self.memory,
+            registers: &self.registers,
+            index_reg: self.index_reg,
+            pc: self.pc,
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+            stack: &self.stack[..self.sp as usize],
+        });
+        let elapsed = progress.measured_since.elapsed();
+        if elapsed >= time::Duration::from_secs(1) {
+            let per_second = |count: u64| count * NANOS_PER_SECOND / elapsed.as_nanos() as u64;
+            self.hardware.show_stats(
+                per_second(self.frames_drawn - progress.frames_drawn),
+                per_second(progress.instructions),
+            );
+            progress.measured_since = time::Instant::now();
+            progress.instructions = 0;
+            progress.frames_drawn = self.frames_drawn;
+        }
 
-        let mut save_file = fs::File::create(to_file_path)?;
-        match serde_json::to_vec(self) {
-            Ok(serialized_c8) => save_file.write_all(&serialized_c8),
-            Err(error) => Err(Error::other(error)),
+        // The next frame is due a frame from now
+        // (sooner when fast-forwarding, and later in slow motion).
+        let delay = match self.hardware.playback_speed() {
+            PlaybackSpeed::Normal => FRAME_RATE,
+            PlaybackSpeed::FastForward => FRAME_RATE / FAST_FORWARD_FACTOR,
+            PlaybackSpeed::SlowMotion => FRAME_RATE * SLOW_MOTION_FACTOR,
+        };
+        // (Verifying a replay only needs the machine to run, not to be watched.)
+        #[cfg(feature = "recorder")]
+        let delay = if self.verify_replay { 0 } else { delay };
+        // (Runs of a fixed number of cycles only want the resulting state, so they never wait.)
+        let delay = if self.unpaced { 0 } else { delay };
+        progress.deadline += time::Duration::from_nanos(delay);
+        let now = time::Instant::now();
+        if now.saturating_duration_since(progress.deadline) > time::Duration::from_nanos(MAX_LAG) {
+            // We fell far behind (say, while paused), so start over from now
+            // instead of rushing through every frame we missed.
+            progress.deadline = now;
         }
+        true
     }
 
-    pub fn new(
-        debug: bool,
-        game_path: Option<String>,
-        load_state_path: Option<String>,
-        save_state_path: Option<String>,
-    ) -> Result<Chip8, Error> {
-        // Create a Chip8 instance, given one of {path to game, save state to load}.
-        // Optionally, provide a path to save game states to (which may be the same
-        // as the path to the save state to load, in case the user wants to overwrite it).
-
-        if let Some(game) = game_path {
-            // Start a game from scratch.
-            // (A provided path to a game file *always* overrides a load-state.)
-            let hardware = Hw::new(&Screen::default(), debug, DEFAULT_TITLE);
-            let mut c8 = Chip8 {
-                hardware,
-                debug,
-                save_state_path,
-                ..Default::default()
-            };
-
-            c8.load_game(&game)?;
-            Ok(c8)
-        } else if let Some(state) = load_state_path {
-            // Load an existing game's state.
-            Self::from_state(&state, debug, save_state_path)
-        } else {
-            Err(Error::new(
-                ErrorKind::NotFound,
-                "Neither a game nor a load state path was specified. Please check usage with '-h'.",
-            ))
+    fn finish_running(&mut self, progress: RunProgress) -> Result<(), Chip8Error> {
+        // Wrap up a run once it stops, returning why, if it stopped at a fault.
+        self.run_stats.elapsed += progress.started.elapsed();
+        self.run_stats.frames_drawn += self.frames_drawn - progress.frames_drawn_before;
+
+        let fault = progress.fault;
+        #[cfg(feature = "recorder")]
+        let fault = fault.or_else(|| {
+            (self.verify_replay && !self.finished_verifying()).then(|| Chip8Error::ReplayDiverged {
+                cycle: self.count,
+                detail: String::from("running stopped before the recording did."),
+            })
+        });
+
+        // (Everything is still saved when running stops at a fault, to help find its cause.)
+        #[cfg(feature = "recorder")]
+        self.save_movie();
+        self.write_final_state();
+        if let Some(path) = &self.opcode_stats_path {
+            // (Like final states, these are for scripts, so always report failures.)
+            if let Err(error) = fs::write(path, self.opcode_stats.to_csv()) {
+                error!("Failed to write the opcode statistics: {error}");
+            }
+        }
+        if let Some(path) = &self.screen_dump_path {
+            self.dump_screen(path);
         }
+        fault.map_or(Ok(()), Err)
     }
 
-    #[cfg(test)]
-    pub fn tester(debug: bool) -> Chip8 {
-        // Create a Chip8 instance for unit testing.
-        // Why not use Hw::default() here? Really only to pass debug.
-        let hardware = Hw::new(&Screen::default(), debug, DEFAULT_TITLE);
-        Chip8 {
-            hardware,
-            debug,
-            ..Default::default()
+    /// Run like `run()` does, but as a future, for async applications (such as ones
+    /// built on tokio) to run alongside everything else on their runtime.
+    /// Instead of blocking the thread until each frame is due, it awaits `sleep(deadline)`,
+    /// which should complete once the deadline passes, using the application's own timer
+    /// (for example, `|deadline| tokio::time::sleep_until(deadline.into())`).
+    /// (Each frame itself still runs without yielding, but it's over in a fraction of a frame.)
+    pub async fn run_async<S, F>(&mut self, mut sleep: S) -> Result<(), Chip8Error>
+    where
+        S: FnMut(time::Instant) -> F,
+        F: Future<Output = ()>,
+    {
+        let Some(mut progress) = self.start_running() else {
+            return Ok(());
+        };
+        while self.run_next_frame(&mut progress) {
+            let waiting_since = time::Instant::now();
+            sleep(progress.deadline).await;
+            self.run_stats.sleeping += waiting_since.elapsed();
         }
+        self.finish_running(progress)
     }
 
-    fn fetch_opcode(&mut self) {
-        // Read the 8 bytes at Memory[PC], save them into a 16-bit variable
-        // and shift them to the lower 8 bits.
-        let mut new_opcode = (u16::from(self.memory[self.pc as usize])) << 8;
-        // Then, read the 8 bytes at Memory[PC + 1],
-        // and save them into the higher 8 bits of our variable.
-        // Together, these bits are our complete opcode.
-        new_opcode |= u16::from(self.memory[(self.pc + 1) as usize]);
+    /// Pause running: until resumed, running (with `run()`, or a frame at a time
+    /// with `run_frame()`) carries on reading input and drawing, but runs no instructions,
+    /// and the timers (and the buzzer) stop.
+    pub fn pause(&mut self) {
+        self.paused = true;
+        #[cfg(feature = "audio")]
+        self.hardware.set_buzzer(false);
+    }
 
-        self.opcode = Opcode::new(new_opcode);
+    /// Carry on running where a pause left off.
+    pub fn resume(&mut self) {
+        self.paused = false;
     }
 
-    fn decode_execute(&mut self) {
-        // Decode and execute the current Opcode value.
+    /// Whether running is paused (see `pause()`).
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
 
-        self.update_pc_cycles = 2; // Unless overridden.
-        let value = self.opcode.value;
-        let lower_value = value as u8;
+    /// Let a host application control the machine from another thread while it runs:
+    /// returns where to send it commands (such as pausing, or loading a state),
+    /// and where it tells the host about what happened (such as drawing the screen).
+    /// (Hardware, such as an SDL window, may need to stay on the thread that created it,
+    /// so machines are usually created on the thread that runs them, handing these back.)
+    pub fn control(&mut self) -> (Sender<ControlCommand>, Receiver<ControlEvent>) {
+        let (control, commands, events) = Control::new();
+        self.control = Some(control);
+        (commands, events)
+    }
 
-        if self.debug {
-            println!("Registers: {:?}", self.registers);
-            println!("Executing opcode: {}", self.opcode);
+    fn serve_control(&mut self, start: &Option<Snapshot>) -> bool {
+        // Carry out every command the host application sent since the last frame
+        // (given how the machine was when it started running, to reset to),
+        // returning false if it asked to stop.
+        let Some(control) = self.control.take() else {
+            return true;
+        };
+        let mut running = true;
+        for command in control.poll() {
+            debug!("Host command: {command:?}");
+            match command {
+                ControlCommand::Pause => self.pause(),
+                ControlCommand::Resume => self.resume(),
+                ControlCommand::Reset => {
+                    if let Some(snapshot) = start {
+                        self.load_snapshot(snapshot);
+                        self.halted = false;
+                    }
+                }
+                ControlCommand::LoadState(contents) => match Self::parse_state(&contents) {
+                    Ok(snapshot) => self.load_snapshot(&snapshot),
+                    Err(error) => control.send(ControlEvent::CommandFailed(error.to_string())),
+                },
+                ControlCommand::SaveState => {
+                    let mut state = Vec::new();
+                    match self.to_state_writer(&mut state) {
+                        Ok(()) => control.send(ControlEvent::StateSaved(state)),
+                        Err(error) => control.send(ControlEvent::CommandFailed(error.to_string())),
+                    }
+                }
+                ControlCommand::SetSpeed(speed) => {
+                    let fastest = FRAME_RATE / MAX_INSTRUCTIONS_PER_FRAME;
+                    self.cycle_rate = (NANOS_PER_SECOND / speed.max(1)).max(fastest);
+                    self.hardware.show_speed(self.instructions_per_frame());
+                }
+                ControlCommand::Quit => {
+                    if self.save_on_exit {
+                        self.save_state();
+                    }
+                    running = false;
+                    break;
+                }
+            }
         }
+        self.control = Some(control);
+        running
+    }
 
-        // Make sure that xreg / yreg are sane,
-        // since we don't have Opcode::new() check that for us
-        // for easier indexing later on.
-        let max_register = self.registers.len() - 1;
-        if self.opcode.xreg > max_register || self.opcode.yreg > max_register {
-            panic!("Invalid register in opcode: {}", self.opcode);
+    #[cfg(feature = "scripting")]
+    fn start_remote(&mut self, config: &Config) -> Result<(), Error> {
+        // Start listening for remote clients (and serving the web debugger), if asked to.
+        if let Some(address) = &config.remote_address {
+            self.remote = Some(RemoteControl::new(address)?);
+        }
+        #[cfg(feature = "debugger")]
+        if let Some(address) = &config.web_debugger_address {
+            self.web_debugger = Some(WebDebugger::new(address)?);
         }
+        Ok(())
+    }
 
-        match value >> 12 {
-            0x0 => match lower_value {
-                0xE0 => self.clear_screen(),
-                0xEE => self.r#return(),
-                _ => self.unknown_instruction(),
-            },
+    #[cfg(feature = "scripting")]
+    fn serve_remote(&mut self) -> Result<(), Chip8Error> {
+        // Carry out every command remote clients (and web debugger pages) sent
+        // since the last frame, and show the pages the machine's state,
+        // returning why an instruction stepped through couldn't run, if one couldn't.
+        let mut fault = None;
+        if let Some(mut remote) = self.remote.take() {
+            for (client, command) in remote.poll() {
+                debug!(client; "Remote command: {command:?}");
+                let result = self.run_remote_command(command, &mut fault);
+                remote.reply(client, result);
+                if fault.is_some() {
+                    break;
+                }
+            }
+            self.remote = Some(remote);
+        }
+        #[cfg(feature = "debugger")]
+        if let Some(mut debugger) = self.web_debugger.take() {
+            for (page, command) in debugger.poll() {
+                debug!(page; "Debugger command: {command:?}");
+                let result = self.run_remote_command(command, &mut fault);
+                debugger.reply(page, result);
+                if fault.is_some() {
+                    break;
+                }
+            }
+            if debugger.has_sockets() {
+                debugger.broadcast(&self.debug_state());
+            }
+            self.web_debugger = Some(debugger);
+        }
+        fault.map_or(Ok(()), Err)
+    }
+
+    #[cfg(all(feature = "debugger", feature = "scripting"))]
+    fn debug_state(&self) -> Value {
+        // The machine's state as the web debugger shows it,
+        // with the instructions around the PC disassembled.
+        let start = self.pc.saturating_sub(DISASSEMBLY_BEFORE * 2);
+        let disassembly: Vec<Value> = (start..self.memory.len() as u16 - 1)
+            .step_by(2)
+            .take(DISASSEMBLY_LENGTH)
+            .map(|address| {
+                let value = u16::from_be_bytes([
+                    self.memory[address as usize],
+                    self.memory[address as usize + 1],
+                ]);
+                json!({
+                    "address": address,
+                    "opcode": format!("{value:04X}"),
+                    "mnemonic": disassemble(value),
+                })
+            })
+            .collect();
+        json!({
+            "title": self.game_title,
+            "paused": self.paused,
+            "instructions": self.count,
+            "registers": self.registers,
+            "index": self.index_reg,
+            "pc": self.pc,
+            "delay_timer": self.delay_timer,
+            "sound_timer": self.sound_timer,
+            "stack": &self.stack[..self.sp as usize],
+            "disassembly": disassembly,
+            "screen": self.screen.to_pbm(),
+        })
+    }
+
+    #[cfg(feature = "scripting")]
+    fn run_remote_command(
+        &mut self,
+        command: RemoteCommand,
+        fault: &mut Option<Chip8Error>,
+    ) -> Result<Value, String> {
+        // Carry out a command from a remote client, returning what to reply with
+        // (and keeping why an instruction stepped through couldn't run, if one couldn't).
+        match command {
+            RemoteCommand::Pause => {
+                self.pause();
+                Ok(json!({ "pc": self.pc }))
+            }
+            RemoteCommand::Resume => {
+                self.resume();
+                Ok(Value::Null)
+            }
+            RemoteCommand::Step { count } => match self.step_instructions(count.unwrap_or(1)) {
+                Ok(()) => Ok(json!({ "pc": self.pc, "exited": self.exited })),
+                Err(error) => {
+                    let message = error.to_string();
+                    *fault = Some(error);
+                    Err(message)
+                }
+            },
+            RemoteCommand::LoadRom { path } => match self.load_new_game(&path) {
+                Ok(()) => Ok(json!({ "title": self.game_title })),
+                Err(error) => Err(error.to_string()),
+            },
+            RemoteCommand::ReadMemory { address, length } => address
+                .checked_add(length)
+                .and_then(|end| self.memory.get(address..end))
+                .map(|bytes| {
+                    let hex: String = bytes.iter().map(|byte| format!("{byte:02X}")).collect();
+                    json!({ "memory": hex })
+                })
+                .ok_or_else(|| String::from("That reaches past the end of memory.")),
+            RemoteCommand::Screenshot => Ok(json!({ "screen": self.screen.to_pbm() })),
+            RemoteCommand::StepBack { count } => self.step_back(count.unwrap_or(1), false),
+            RemoteCommand::FrameBack { count } => self.step_back(count.unwrap_or(1), true),
+            RemoteCommand::SearchReset => {
+                let search = MemorySearch::new(&self.memory, usize::from(self.start_address));
+                let results = self.search_results(&search);
+                self.memory_search = Some(search);
+                Ok(results)
+            }
+            RemoteCommand::Search { condition, value } => {
+                // (A first search starts from memory as it is now.)
+                let mut search = self.memory_search.take().unwrap_or_else(|| {
+                    MemorySearch::new(&self.memory, usize::from(self.start_address))
+                });
+                let result = search
+                    .narrow(&self.memory, condition, value)
+                    .map(|()| self.search_results(&search));
+                self.memory_search = Some(search);
+                result
+            }
+            RemoteCommand::WriteMemory { address, value } => match self.memory.get_mut(address) {
+                Some(byte) => {
+                    *byte = value;
+                    Ok(Value::Null)
+                }
+                None => Err(String::from("That's past the end of memory.")),
+            },
+            RemoteCommand::Freeze { address, value } => {
+                if address >= self.memory.len() {
+                    return Err(String::from("That's past the end of memory."));
+                }
+                self.frozen.insert(address, value);
+                self.apply_freezes();
+                Ok(json!({ "frozen": self.frozen }))
+            }
+            RemoteCommand::Unfreeze { address } => {
+                self.frozen.remove(&address);
+                Ok(json!({ "frozen": self.frozen }))
+            }
+        }
+    }
+
+    #[cfg(feature = "scripting")]
+    fn step_back(&mut self, count: u64, frames: bool) -> Result<Value, String> {
+        // Return to the state before the latest instructions run (or to the start
+        // of the latest frames run), pausing there.
+        let mut earliest = None;
+        for _ in 0..count {
+            let Some((frame, mut snapshot)) = self.history.pop_back() else {
+                break;
+            };
+            while frames
+                && self
+                    .history
+                    .back()
+                    .is_some_and(|(earlier, _)| *earlier == frame)
+            {
+                if let Some((_, earlier)) = self.history.pop_back() {
+                    snapshot = earlier;
+                }
+            }
+            earliest = Some(snapshot);
+        }
+        let Some(snapshot) = earliest else {
+            return Err(String::from("There's nothing earlier to step back to."));
+        };
+        self.restore(&snapshot);
+        self.pause();
+        Ok(json!({ "pc": self.pc, "history": self.history.len() }))
+    }
+
+    #[cfg(feature = "scripting")]
+    fn record_history(&mut self) {
+        // Keep the state before an instruction (while debugging, since it takes a while),
+        // to step back to, forgetting the oldest once there are too many.
+        #[cfg(feature = "debugger")]
+        let debugging = self.remote.is_some() || self.web_debugger.is_some();
+        #[cfg(not(feature = "debugger"))]
+        let debugging = self.remote.is_some();
+        if !debugging {
+            return;
+        }
+        if self.history.len() == HISTORY_LENGTH {
+            self.history.pop_front();
+        }
+        self.history.push_back((self.frames_run, self.snapshot()));
+    }
+
+    #[cfg(feature = "scripting")]
+    fn search_results(&self, search: &MemorySearch) -> Value {
+        // How many candidates a memory search has left, and the first few
+        // (with what they hold now).
+        let candidates: Vec<Value> = search
+            .candidates()
+            .iter()
+            .take(MAX_SEARCH_RESULTS)
+            .map(|&address| json!({ "address": address, "value": self.memory[address] }))
+            .collect();
+        json!({ "count": search.candidates().len(), "candidates": candidates })
+    }
+
+    #[cfg(feature = "scripting")]
+    fn apply_freezes(&mut self) {
+        // Put every frozen byte of memory back to the value it's frozen at.
+        for (&address, &value) in &self.frozen {
+            self.memory[address] = value;
+        }
+    }
+
+    #[cfg(feature = "scripting")]
+    fn step_instructions(&mut self, count: u64) -> Result<(), Chip8Error> {
+        // Run some instructions outside of a frame (say, while paused),
+        // drawing anything they changed.
+        for _ in 0..count {
+            if !self.run_instruction()? {
+                break;
+            }
+        }
+        if self.draw_flag {
+            self.present();
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "scripting")]
+    fn load_new_game(&mut self, path: &str) -> Result<(), Chip8Error> {
+        // Start another game from scratch, keeping the current behavior settings.
+        let contents = read_rom(path)?;
+        let mut fresh = Chip8 {
+            cycle_rate: self.cycle_rate,
+            variant: self.variant,
+            quirks: self.quirks,
+            opcode_costs: self.opcode_costs,
+            start_address: self.start_address,
+            pc: self.start_address,
+            screen: self.screen.clone(),
+            ..Default::default()
+        };
+        fresh.screen.set_resolution(LORES.0, LORES.1);
+        fresh.load_rom(path, &contents)?;
+        self.restore(&fresh.snapshot());
+        self.halted = false;
+        // (What was found in, or frozen in, the last game's memory means nothing now.)
+        self.memory_search = None;
+        self.frozen.clear();
+        self.history.clear();
+        self.set_game_title(path)
+    }
+
+    #[cfg(feature = "recorder")]
+    fn start_movies(&mut self, config: &Config) -> Result<(), Error> {
+        // Start playing back and/or recording movies. (When doing both,
+        // the recording continues with live input once playback finishes.)
+        if let Some(path) = &config.play_movie_path {
+            let movie = Movie::from_file(path)?;
+            self.reseed(movie.seed);
+            if config.verify_movie && movie.checkpoint_count() == 0 {
+                warn!("The movie has no checkpoints to verify the replay against.");
+            }
+            self.playback = Some(movie);
+            self.verify_replay = config.verify_movie;
+        }
+        if let Some(path) = &config.record_movie_path {
+            self.recording = Some((Movie::new(self.seed), path.clone()));
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "recorder")]
+    fn play_and_record_keys(&mut self) -> Result<(), Chip8Error> {
+        // Replace the keypad with the next recorded state while playing back,
+        // and record whatever the keypad ended up as (taking checkpoints every so often),
+        // returning where the replay diverged from the recording, if verifying it and it did.
+        let checkpoint = self.count.is_multiple_of(CHECKPOINT_INTERVAL);
+        if let Some(movie) = &mut self.playback {
+            match movie.play() {
+                Some(keys) => self.keypad = keys,
+                None => {
+                    debug!("Movie finished playing!");
+                    self.playback = None;
+                }
+            }
+        }
+        if checkpoint && self.verify_replay {
+            if let Some(expected) = self
+                .playback
+                .as_ref()
+                .and_then(|movie| movie.checkpoint(self.count).map(String::from))
+            {
+                let actual = self.state_hash();
+                if actual != expected {
+                    return Err(Chip8Error::ReplayDiverged {
+                        cycle: self.count,
+                        detail: format!("its state hashes to {actual}, not {expected}."),
+                    });
+                }
+            }
+        }
+        if let Some((movie, _)) = &mut self.recording {
+            movie.record(&self.keypad);
+        }
+        if checkpoint && self.recording.is_some() {
+            let hash = self.state_hash();
+            if let Some((movie, _)) = &mut self.recording {
+                movie.add_checkpoint(self.count, hash);
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "recorder")]
+    fn state_hash(&self) -> String {
+        // A hash of the machine's whole state (to check replays against).
+        let state = serde_json::to_vec(&self.snapshot()).expect("Snapshots always serialize.");
+        rom_hash(&state)
+    }
+
+    #[cfg(feature = "recorder")]
+    fn finished_verifying(&self) -> bool {
+        // Whether a replay being verified has played all the way through.
+        self.verify_replay && self.playback.as_ref().is_none_or(Movie::finished)
+    }
+
+    #[cfg(feature = "recorder")]
+    fn save_movie(&self) {
+        if let Some((movie, path)) = &self.recording {
+            // (Unlike states, this is our only chance to save the movie, so always report failures.)
+            if let Err(error) = movie.to_file(path) {
+                error!("Failed to save movie: {error}");
+            }
+        }
+    }
+
+    fn write_final_state(&self) {
+        // Write the game's state out once running stops, for scripts to inspect.
+        if let Some(path) = &self.final_state_path {
+            let result = if path == STDIN_PATH {
+                self.to_state_writer(std::io::stdout().lock())
+            } else {
+                self.to_state(path)
+            };
+            // (Like movies, this is our only chance to write it, so always report failures.)
+            if let Err(error) = result {
+                error!("Failed to write the final state: {error}");
+            }
+        }
+    }
+
+    fn dump_screen(&self, path: &str) {
+        // (Like final states, screen dumps are usually checked by scripts, so always report failures.)
+        if let Err(error) = fs::write(path, self.screen.to_pbm()) {
+            error!("Failed to dump the screen: {error}");
+        }
+    }
+
+    fn dump_screen_numbered(&self) {
+        // Write the screen to a file numbered after the current frame,
+        // next to the screen dump path (e.g. screen.pbm -> screen-000060.pbm).
+        if let Some(path) = &self.screen_dump_path {
+            let path = Path::new(path);
+            let mut name = path.file_stem().unwrap_or_default().to_os_string();
+            name.push(format!("-{:06}", self.frames_run));
+            if let Some(extension) = path.extension() {
+                name.push(".");
+                name.push(extension);
+            }
+            self.dump_screen(&path.with_file_name(name).to_string_lossy());
+        }
+    }
+
+    fn count_cycle(&mut self) {
+        // Count a cycle run (and the emulated time it took) towards the run limits,
+        // dumping the screen whenever another interval's worth of frames has passed.
+        let frames_run = self.frames_run;
+        self.cycles_run += 1;
+        self.frame_progress += self.cycle_rate * self.opcode_costs.cycles(self.opcode.value);
+        self.frames_run += self.frame_progress / FRAME_RATE;
+        self.frame_progress %= FRAME_RATE;
+
+        if let Some(interval) = self.screen_dump_interval {
+            if self.frames_run / interval > frames_run / interval {
+                self.dump_screen_numbered();
+            }
+        }
+    }
+
+    fn halt(&mut self, reason: &str) {
+        // Stop running once the game can't do anything more (if asked to).
+        if self.stop_on_halt && !self.halted {
+            info!(pc = self.pc; "Program finished: {reason} at {:#05X}", self.pc);
+            self.halted = true;
+        }
+    }
+
+    fn is_hires(&self) -> bool {
+        // Whether the screen is in (SCHIP) high-resolution mode.
+        self.screen.res_width >= HIRES.0
+    }
+
+    #[cfg(test)]
+    fn load_game(&mut self, file_path: &str) -> Result<(), Chip8Error> {
+        // Load a game file from disk (or from an archive, or standard input, given STDIN_PATH).
+        let (title, contents) = Self::read_game(file_path)?;
+        self.load_rom(title, &contents)
+    }
+
+    fn load_memory_image(&mut self, title: &str, contents: &[u8]) -> Result<(), Chip8Error> {
+        // Load all of memory (including the interpreter area) from a raw image,
+        // starting at the start address like a game would.
+        // (Images shorter than memory leave the rest empty.)
+        if contents.len() > self.memory.len() {
+            return Err(Chip8Error::RomTooLarge {
+                size: contents.len(),
+                max: self.memory.len(),
+            });
+        }
+        self.set_game_title(title)?;
+        self.memory = [0; 4096];
+        self.memory[..contents.len()].copy_from_slice(contents);
+        // (Which part of the image is the program isn't known.)
+        self.program_len = 0;
+        Ok(())
+    }
+
+    fn read_game(file_path: &str) -> Result<(&str, Vec<u8>), Chip8Error> {
+        // Read a game file, and what to title it.
+        if file_path == STDIN_PATH {
+            let mut contents = Vec::new();
+            std::io::stdin().lock().read_to_end(&mut contents)?;
+            return Ok((STDIN_TITLE, contents));
+        }
+        let contents: Vec<u8> = read_rom(file_path)?; // Handles all read errors.
+        Ok((file_path, contents))
+    }
+
+    fn set_game_title(&mut self, title: &str) -> Result<(), Chip8Error> {
+        // Set the game's title (and show it in the hardware's title).
+        self.hardware
+            .set_title(&format!("{}: {}", TITLE_PREFIX, title))?; // Handles title errors.
+        self.game_title = String::from(title);
+        Ok(())
+    }
+
+    /// Load a game or a saved state (whichever the file holds) without any hardware,
+    /// for inspecting rather than playing.
+    pub fn from_file(file_path: &str) -> Result<Chip8, Chip8Error> {
+        let config = Config {
+            frontend: Frontend::Headless,
+            ..Default::default()
+        };
+        let contents = read_rom(file_path)?;
+        match Self::from_state_reader(&contents[..], &config) {
+            // (Games are never valid states, so anything else read is taken to be a game.)
+            Err(Chip8Error::InvalidState(_)) => {
+                let mut c8 = Chip8::default();
+                c8.load_rom(file_path, &contents)?;
+                Ok(c8)
+            }
+            result => result,
+        }
+    }
+
+    /// A hex dump of memory, split into (and labelled by) the regions that make it up:
+    /// the fonts, the reserved space after them, the program, and any data past it.
+    /// Repeated rows are collapsed into a single `*` line (like `hexdump`).
+    pub fn memory_hex_dump(&self) -> String {
+        let fonts_end = self.fontset.len() + LARGE_FONTSET.len();
+        let program_start = usize::from(self.start_address);
+        // (For states that don't know how long their game is, everything after the start is program.)
+        let program_end = match self.program_len {
+            0 => self.memory.len(),
+            len => program_start + usize::from(len),
+        };
+        let regions = [
+            ("Font", 0, self.fontset.len()),
+            ("Large font", self.fontset.len(), fonts_end),
+            ("Reserved", fonts_end, program_start),
+            ("Program", program_start, program_end),
+            ("Data past the program", program_end, self.memory.len()),
+        ];
+
+        let mut dump = format!("PC: {:#05X}  I: {:#05X}\n", self.pc, self.index_reg);
+        for (name, start, end) in regions {
+            // (States can claim a game longer than fits, or one starting in the fonts,
+            // so regions are cut short to what's there, and skipped if that's nothing.)
+            let (start, end) = (start.min(self.memory.len()), end.min(self.memory.len()));
+            if start >= end {
+                continue;
+            }
+            dump.push_str(&format!("\n{name} ({start:#05X}-{:#05X}):\n", end - 1));
+            let mut last_row: Option<&[u8]> = None;
+            let mut collapsed = false;
+            for (row, bytes) in self.memory[start..end]
+                .chunks(HEX_DUMP_ROW_LENGTH)
+                .enumerate()
+            {
+                // (The last row is always shown, so that the region's end is clear.)
+                let is_last = start + (row + 1) * HEX_DUMP_ROW_LENGTH >= end;
+                if last_row == Some(bytes) && !is_last {
+                    if !collapsed {
+                        dump.push_str("*\n");
+                        collapsed = true;
+                    }
+                    continue;
+                }
+                last_row = Some(bytes);
+                collapsed = false;
+
+                let hex: Vec<String> = bytes.iter().map(|byte| format!("{byte:02X}")).collect();
+                let text: String = bytes
+                    .iter()
+                    .map(|byte| {
+                        if byte.is_ascii_graphic() || *byte == b' ' {
+                            char::from(*byte)
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect();
+                dump.push_str(&format!(
+                    "{:#05X}  {:<width$}  |{text}|\n",
+                    start + row * HEX_DUMP_ROW_LENGTH,
+                    hex.join(" "),
+                    width = HEX_DUMP_ROW_LENGTH * 3 - 1,
+                ));
+            }
+        }
+        dump
+    }
+
+    fn from_state(file_path: &str, config: &Config) -> Result<Chip8, Chip8Error> {
+        // Load a game's state from disk (this includes the game data itself).
+        let state_file = fs::File::open(file_path)?; // Return errors inline.
+        let mut c8 = Self::from_state_reader(state_file, config)?;
+        c8.hardware
+            .set_title(&format!("{}: {}", TITLE_PREFIX, file_path))?; // Handles title errors.
+        Ok(c8)
+    }
+
+    /// Load a game's state (which includes the game data itself) from any reader,
+    /// such as a file, an in-memory buffer, or a network socket.
+    pub fn from_state_reader<R: Read>(mut reader: R, config: &Config) -> Result<Chip8, Chip8Error> {
+        // Here we do not have an existing Chip8 instance and must create one with serde and friends.
+
+        // Read the game state and deserialize it into a Chip8 instance.
+        let mut contents: Vec<u8> = Vec::new();
+        reader.read_to_end(&mut contents)?; // Return errors inline.
+        let mut c8: Chip8 = Self::parse_state(&contents)?;
+
+        // Update state not settable from default().
+        // (States saved before the large fontset existed don't hold it in memory.)
+        c8.load_fontsets();
+        // (The hardware is rebuilt so that it honors the user's display options.)
+        if let Some(scale) = config.scale {
+            c8.screen.set_scale(scale);
+        }
+        c8.hardware = new_hardware(&c8.screen, config, DEFAULT_TITLE)?;
+
+        // Update state overridden by the user.
+        c8.save_state_path = config.save_state_path();
+        c8.load_state_path = config.load_state_path();
+        c8.state_format = config.state_format;
+        c8.save_on_exit = config.save_on_exit;
+        c8.memory_dump_path = config.memory_dump_path();
+        c8.memory_dump_program_only = config.memory_dump_program_only;
+        c8.final_state_path = config.final_state_path.clone();
+        c8.crash_dump_path = config.crash_dump_path();
+        c8.opcode_stats_path = config.opcode_stats_path.clone();
+        c8.explain_rate = config.explain_rate;
+        c8.screen_dump_path = config.screen_dump_path.clone();
+        c8.screen_dump_interval = config.screen_dump_interval;
+        c8.max_cycles = config.max_cycles.or(config.run_cycles);
+        c8.max_frames = config.max_frames;
+        c8.unpaced = config.run_cycles.is_some();
+        c8.stop_on_halt = config.stop_on_halt;
+        c8.key_wait_halts = config.key_wait_halts();
+        c8.vsync = config.vsync;
+        c8.apply_settings(config);
+        #[cfg(feature = "scripting")]
+        c8.start_remote(config)?;
+
+        // Draw the screen once to start.
+        c8.hardware.update_display(&c8.screen);
+        Ok(c8)
+    }
+
+    fn parse_state<T: DeserializeOwned>(contents: &[u8]) -> Result<T, Chip8Error> {
+        // Deserialize a saved game state (into a Chip8 instance, or just a Snapshot),
+        // in whichever encoding it was written in.
+        // (Anything that doesn't deserialize is assumed to be malformed.)
+        StateFormat::detect(contents).read(contents)
+    }
+
+    fn dump_memory(&mut self) {
+        if let Some(path) = self.memory_dump_path.clone() {
+            match self.to_memory_dump(&path) {
+                Ok(()) => info!(path; "Dumped memory to {path}"),
+                Err(error) => warn!(path, error:%; "Failed to dump memory: {error}"),
+            }
+        }
+    }
+
+    fn to_memory_dump(&self, to_file_path: &str) -> Result<(), Error> {
+        // Write the raw contents of memory to disk (for analysis with external tools).
+        // The program region starts at the start address, so its offsets in the dump
+        // are relative to there instead of to the start of memory.
+        let start = if self.memory_dump_program_only {
+            usize::from(self.start_address)
+        } else {
+            0
+        };
+        fs::write(to_file_path, &self.memory[start..])
+    }
+
+    fn to_state(&self, to_file_path: &str) -> Result<(), Error> {
+        // Save a Chip8 instance to disk (where it can be loaded again later),
+        // in the encoding asked for, or else the one its path calls for.
+        let save_file = fs::File::create(to_file_path)?;
+        let format = self
+            .state_format
+            .unwrap_or_else(|| StateFormat::from_path(to_file_path));
+        format.write(self, BufWriter::new(save_file))
+    }
+
+    /// Save the game's state (which can be loaded again later
+    /// with `from_state_reader()`) to any writer, such as a file,
+    /// an in-memory buffer, or a network socket
+    /// (in the configured `StateFormat`, which is JSON unless set otherwise).
+    pub fn to_state_writer<W: Write>(&self, writer: W) -> Result<(), Error> {
+        self.state_format.unwrap_or_default().write(self, writer)
+    }
+
+    pub fn new(config: &Config) -> Result<Chip8, Chip8Error> {
+        // Create a Chip8 instance, given one of {path to game, save state to load}.
+        // Optionally, provide a path to save game states to (which may be the same
+        // as the path to the save state to load, in case the user wants to overwrite it).
+
+        if let Some(game) = &config.game_path {
+            // Start a game from scratch.
+            // (A provided path to a game file *always* overrides a load-state.)
+            let (title, contents) = Self::read_game(game)?;
+            Self::start_game(title, contents, config)
+        } else if let Some(state) = &config.load_state_path {
+            // Load an existing game's state.
+            Self::from_state(state, config)
+        } else {
+            Err(Chip8Error::Io(Error::new(
+                ErrorKind::NotFound,
+                "Neither a game nor a load state path was specified. Please check usage with '-h'.",
+            )))
+        }
+    }
+
+    /// Start a game from its bytes, rather than from a file (for embedders, tests,
+    /// and frontends without a filesystem), configured just like `new()` otherwise.
+    /// (The game is titled after `config.game_title`, if given.)
+    pub fn from_bytes(contents: &[u8], config: &Config) -> Result<Chip8, Chip8Error> {
+        Self::start_game(BYTES_TITLE, contents.to_vec(), config)
+    }
+
+    fn start_game(
+        title: &str,
+        mut contents: Vec<u8>,
+        config: &Config,
+    ) -> Result<Chip8, Chip8Error> {
+        // Start a game from scratch, given its contents.
+        let mut screen = Screen::default();
+        if let Some(scale) = config.scale {
+            screen.set_scale(scale);
+        }
+        let hardware = new_hardware(&screen, config, DEFAULT_TITLE)?;
+        let mut c8 = Chip8 {
+            screen,
+            hardware,
+            save_state_path: config.save_state_path(),
+            load_state_path: config.load_state_path(),
+            state_format: config.state_format,
+            save_on_exit: config.save_on_exit,
+            memory_dump_path: config.memory_dump_path(),
+            memory_dump_program_only: config.memory_dump_program_only,
+            final_state_path: config.final_state_path.clone(),
+            crash_dump_path: config.crash_dump_path(),
+            opcode_stats_path: config.opcode_stats_path.clone(),
+            explain_rate: config.explain_rate,
+            screen_dump_path: config.screen_dump_path.clone(),
+            screen_dump_interval: config.screen_dump_interval,
+            max_cycles: config.max_cycles.or(config.run_cycles),
+            max_frames: config.max_frames,
+            unpaced: config.run_cycles.is_some(),
+            stop_on_halt: config.stop_on_halt,
+            key_wait_halts: config.key_wait_halts(),
+            vsync: config.vsync,
+            ..Default::default()
+        };
+
+        c8.apply_settings(config);
+        if let Some(address) = config.start_address {
+            c8.start_address = address;
+            c8.pc = address;
+        }
+        if let Some(patch) = &config.ips_path {
+            apply_ips(&mut contents, &fs::read(patch)?)?;
+        }
+        if config.memory_image {
+            c8.load_memory_image(title, &contents)?;
+        } else {
+            c8.load_rom(title, &contents)?;
+        }
+        // (Known games are titled by name, instead of by path.)
+        if let Some(title) = &config.game_title {
+            c8.set_game_title(title)?;
+        }
+        // (Both players start from the same settings, which movies record.)
+        #[cfg(feature = "netplay")]
+        c8.start_netplay(config)?;
+        // (Movies always start from the beginning of a game.)
+        #[cfg(feature = "recorder")]
+        c8.start_movies(config)?;
+        #[cfg(feature = "scripting")]
+        c8.start_remote(config)?;
+        Ok(c8)
+    }
+
+    /// Present the game with different hardware than the configured frontend's,
+    /// such as a frontend the emulator doesn't know about (drawing to a web page, say).
+    pub fn set_hardware(&mut self, hardware: Box<dyn Interactible>) -> Result<(), Chip8Error> {
+        self.hardware = hardware;
+        self.hardware
+            .set_title(&format!("{}: {}", TITLE_PREFIX, self.game_title))?;
+        self.hardware.update_display(&self.screen);
+        Ok(())
+    }
+
+    /// Run instructions matching a pattern with a handler, for extending the
+    /// instruction set (with experimental instructions, say) without changing it.
+    /// Handlers take over matching instructions before the built-in ones do,
+    /// and the first one added wins where several patterns match.
+    pub fn add_opcode_handler<F>(&mut self, pattern: OpcodePattern, handler: F)
+    where
+        F: FnMut(&mut ExtensionContext) -> Result<(), Chip8Error> + 'static,
+    {
+        self.extensions.push((pattern, Box::new(handler)));
+    }
+
+    #[cfg(test)]
+    pub fn tester() -> Chip8 {
+        // Create a Chip8 instance for unit testing
+        // (with the hardware tests use, instead of the placeholder).
+        Chip8 {
+            hardware: Box::<MockHardware>::default(),
+            ..Default::default()
+        }
+    }
+
+    fn fetch_opcode(&mut self) -> Result<(), Chip8Error> {
+        // Read the 2 bytes at Memory[PC] and Memory[PC + 1],
+        // failing if the PC has run (or jumped) to where they aren't both in memory.
+        let pc = usize::from(self.pc);
+        let Some(&[high, low]) = self.memory.get(pc..pc + 2) else {
+            return Err(Chip8Error::OutOfBounds {
+                // (What there is of the instruction, at least.)
+                opcode: u16::from(self.memory.get(pc).copied().unwrap_or(0)) << 8,
+                pc: self.pc,
+                address: pc.max(self.memory.len()),
+            });
+        };
+        // Save the first into the higher 8 bits of a 16-bit variable,
+        // and the second into the lower 8 bits.
+        // Together, these bits are our complete opcode.
+        self.opcode = Opcode::new(u16::from_be_bytes([high, low]));
+        Ok(())
+    }
+
+    fn decode_execute(&mut self) {
+        // Decode and execute the current Opcode value.
+
+        self.update_pc_cycles = 2; // Unless overridden.
+        let value = self.opcode.value;
+        let lower_value = value as u8;
+        // Whether SCHIP instructions are allowed (if not, they're unknown).
+        let schip = self.variant.has_schip();
+
+        trace!(registers:? = self.registers; "Registers: {:?}", self.registers);
+        trace!(opcode:% = format_args!("{:04X}", self.opcode.value); "Executing opcode: {}", self.opcode);
+
+        // Make sure that xreg / yreg are sane,
+        // since we don't have Opcode::new() check that for us
+        // for easier indexing later on.
+        let max_register = self.registers.len() - 1;
+        if self.opcode.xreg > max_register || self.opcode.yreg > max_register {
+            return self.unknown_instruction();
+        }
+        if self.run_extension() {
+            return;
+        }
+
+        match value >> 12 {
+            0x0 => match lower_value {
+                0xC0..=0xCF if schip => self.scroll_down(),
+                0xE0 => self.clear_screen(),
+                0xEE => self.r#return(),
+                0xFB if schip => self.scroll_right(),
+                0xFC if schip => self.scroll_left(),
+                0xFD if schip => self.exit(),
+                0xFE if schip => self.low_resolution(),
+                0xFF if schip => self.high_resolution(),
+                _ => self.unknown_instruction(),
+            },
             0x1 => self.jump(),
             0x2 => self.call(),
             0x3 => self.skip_if_eq_literal(),
@@ -652,9 +2278,12 @@ impl Chip8 {
                 0x18 => self.set_sound_timer(),
                 0x1E => self.add_reg_to_index_reg(),
                 0x29 => self.set_index_reg_to_sprite(),
+                0x30 if schip => self.set_index_reg_to_large_sprite(),
                 0x33 => self.save_binary_coded_decimal(),
                 0x55 => self.save_registers(),
                 0x65 => self.restore_registers(),
+                0x75 if schip => self.save_registers_to_flags(),
+                0x85 if schip => self.restore_registers_from_flags(),
                 _ => self.unknown_instruction(),
             },
             _ => self.unknown_instruction(),
@@ -663,22 +2292,52 @@ impl Chip8 {
 
     fn draw_screen(&mut self) {
         // Draw the screen, if required.
-        if self.draw_flag {
-            self.hardware.update_display(&self.screen);
-            self.draw_flag = false;
+        if !self.draw_flag {
+            return;
+        }
+
+        if self.vsync {
+            // Drawing waits for the display to refresh, so only draw once per frame
+            // (instead of after every drawing instruction), and leave
+            // any changes made in between for the next frame.
+            let now = time::Instant::now();
+            if self.next_frame.is_some_and(|next_frame| now < next_frame) {
+                return;
+            }
+            self.next_frame = Some(now + time::Duration::from_nanos(FRAME_RATE));
+        }
+        self.present();
+    }
+
+    fn present(&mut self) {
+        // Draw the screen as it is now.
+        self.hardware.update_display(&self.screen);
+        self.draw_flag = false;
+        self.frames_drawn += 1;
+    }
+
+    fn act_on_keys(&mut self, keys_result: &SetKeysResult) {
+        // Do what the user asked for with hotkeys (other than exiting).
+        match keys_result {
+            SetKeysResult::ShouldSaveState => self.save_state(),
+            SetKeysResult::ShouldLoadState => self.load_state(),
+            SetKeysResult::ShouldSpeedUp => self.adjust_speed(1),
+            SetKeysResult::ShouldSlowDown => self.adjust_speed(-1),
+            SetKeysResult::ShouldDumpMemory => self.dump_memory(),
+            _ => (),
         }
     }
 
     fn update_timers(&mut self) {
         // Update delay and sound timers,
-        // and beep if the sound timer has reached zero.
+        // and sound the buzzer for as long as the sound timer is nonzero.
 
         if self.delay_timer > 0 {
             self.delay_timer -= 1;
         }
+        #[cfg(feature = "audio")]
+        self.hardware.set_buzzer(self.sound_timer > 0);
         if self.sound_timer > 0 {
-            print!("\x07"); // BEEP!
-            let _ = std::io::stdout().flush(); // If this fails, it's not a catastrophe.
             self.sound_timer -= 1;
         }
     }
@@ -688,51 +2347,424 @@ impl Chip8 {
         self.pc += self.update_pc_cycles;
     }
 
-    fn emulate_cycle(&mut self) -> bool {
-        // Emulate one cycle of our operation.
-        // Returns false if we decided to stop.
+    fn run_instruction(&mut self) -> Result<bool, Chip8Error> {
+        // Run the instruction at the PC (as part of a run), moving on to the next one.
+        // Returns false if the game exited or halted instead.
+        #[cfg(feature = "scripting")]
+        self.record_history();
+        self.execute_instruction()?;
+        if self.exited || self.halted {
+            return Ok(false);
+        }
+        #[cfg(feature = "recorder")]
+        self.play_and_record_keys()?;
+        self.increment_pc();
+        self.run_stats.instructions += 1;
+        self.count_cycle();
+        #[cfg(feature = "recorder")]
+        if self.finished_verifying() {
+            return Ok(false);
+        }
+        Ok(true)
+    }
 
-        self.fetch_opcode();
-        if self.debug {
-            println!("On cycle {}, at memory location {}", self.count, self.pc);
-            self.count += 1;
+    fn execute_instruction(&mut self) -> Result<(), Chip8Error> {
+        // Fetch and execute the instruction at the PC
+        // (without moving the PC past it), returning why it couldn't run, if it couldn't.
+        self.fetch_opcode()?;
+        self.execute_fetched()
+    }
+
+    fn execute_fetched(&mut self) -> Result<(), Chip8Error> {
+        // Execute the instruction just fetched (see `execute_instruction()`).
+        trace!(cycle = self.count, pc = self.pc; "On cycle {}, at memory location {}", self.count, self.pc);
+        self.count += 1;
+        if self.crash_dump_path.is_some() {
+            self.recent_instructions.push(self.pc, self.opcode.value);
+        }
+        if self.opcode_stats_path.is_some() {
+            self.opcode_stats.count(self.pc, self.opcode.value);
         }
 
+        let explaining = self.explain_rate.map(|_| (self.pc, self.index_reg));
         self.decode_execute();
-        self.draw_screen();
-        match self.hardware.set_keys(&self.screen) {
-            SetKeysResult::ShouldSaveState => self.save_state(),
-            SetKeysResult::ShouldExit => return false,
-            _ => (),
+        if let Some((pc, index_reg)) = explaining {
+            eprintln!("{}", self.explanation(pc, index_reg));
         }
-        self.update_timers();
-        self.increment_pc();
+        self.fault.take().map_or(Ok(()), Err)
+    }
 
-        // Continue to the next cycle.
+    fn explanation(&self, pc: u16, index_reg: u16) -> String {
+        // Explain the instruction just run (from where it was, and with I as it was),
+        // along with what it changed, e.g. "0200  8134: add V3 to V1 with carry → V1=0x2A, VF=0".
+        let value = self.opcode.value;
+        let mut explanation = format!("{pc:04X}  {value:04X}: ");
+        if self
+            .extensions
+            .iter()
+            .any(|(pattern, _)| pattern.matches(value))
+        {
+            explanation.push_str("run by an extension");
+            return explanation;
+        }
+        explanation.push_str(&explain(value));
+        let results: Vec<String> = effects(value)
+            .into_iter()
+            .map(|effect| match effect {
+                Effect::Register(x) => format!("V{x:X}=0x{:02X}", self.registers[x]),
+                Effect::Registers(x) => (0..=x)
+                    .map(|x| format!("V{x:X}=0x{:02X}", self.registers[x]))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                Effect::Flag => format!("VF={}", self.registers[0xF]),
+                Effect::Index => format!("I=0x{:03X}", self.index_reg),
+                Effect::DelayTimer => format!("DT={}", self.delay_timer),
+                Effect::SoundTimer => format!("ST={}", self.sound_timer),
+                Effect::Memory(count) => {
+                    // (Only what's in memory is shown, if the instruction reached past it.)
+                    let start = usize::from(index_reg).min(self.memory.len());
+                    let end = (start + usize::from(count)).min(self.memory.len());
+                    let bytes: Vec<String> = self.memory[start..end]
+                        .iter()
+                        .map(|byte| format!("{byte:02X}"))
+                        .collect();
+                    if bytes.is_empty() {
+                        format!("memory[0x{index_reg:03X}] is past the end of memory")
+                    } else {
+                        format!("memory[0x{index_reg:03X}]={}", bytes.join(" "))
+                    }
+                }
+                Effect::Jump => format!("PC=0x{:03X}", self.pc.wrapping_add(self.update_pc_cycles)),
+                Effect::Skip if self.update_pc_cycles > 2 => String::from("skipped"),
+                Effect::Skip => String::from("not skipped"),
+            })
+            .collect();
+        if !results.is_empty() {
+            explanation.push_str(" → ");
+            explanation.push_str(&results.join(", "));
+        }
+        explanation
+    }
+
+    /// Run this many instructions as fast as possible (or until the game exits),
+    /// without waiting between frames or reading input, timing how long each phase
+    /// of running them takes: fetching, executing (along with the timers), and drawing.
+    /// This gives a consistent yardstick for how fast the emulator itself is.
+    pub fn bench(&mut self, instructions: u64) -> Result<BenchReport, Chip8Error> {
+        let mut report = BenchReport::default();
+        let started = time::Instant::now();
+        // (The timers count down once per frame's worth of cycles, as when running.)
+        let mut frame_cycles = 0;
+        while report.instructions < instructions && !self.exited {
+            let fetching = time::Instant::now();
+            self.fetch_opcode()?;
+            let executing = time::Instant::now();
+            self.execute_fetched()?;
+            frame_cycles += self.opcode_costs.cycles(self.opcode.value);
+            if frame_cycles >= self.instructions_per_frame() {
+                self.update_timers();
+                frame_cycles = 0;
+            }
+            self.increment_pc();
+            let drawing = time::Instant::now();
+            self.draw_screen();
+            let done = time::Instant::now();
+
+            report.fetch += executing - fetching;
+            report.execute += drawing - executing;
+            report.draw += done - drawing;
+            report.instructions += 1;
+        }
+        report.elapsed = started.elapsed();
+        Ok(report)
+    }
+
+    fn adjust_speed(&mut self, steps: i64) {
+        // Run more (or fewer) instructions per frame, and let the user know.
+        let instructions = self.instructions_per_frame().saturating_add_signed(steps);
+        let instructions = instructions.clamp(1, MAX_INSTRUCTIONS_PER_FRAME);
+        self.cycle_rate = FRAME_RATE / instructions;
+        self.hardware.show_speed(instructions);
+        self.hardware.update_display(&self.screen);
+    }
+
+    fn instructions_per_frame(&self) -> u64 {
+        // How many instructions run in one frame at the current cycle rate
+        // (rounded to the nearest instruction, but always at least one).
+        ((FRAME_RATE + self.cycle_rate / 2) / self.cycle_rate).max(1)
+    }
+
+    /// Capture the current state of the emulated machine.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            memory: self.memory,
+            program_len: self.program_len,
+            start_address: self.start_address,
+            registers: self.registers,
+            index_reg: self.index_reg,
+            pc: self.pc,
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+            stack: self.stack,
+            sp: self.sp,
+            rpl_flags: self.rpl_flags,
+            seed: self.seed,
+            rng: self.rng.clone(),
+            update_pc_cycles: self.update_pc_cycles,
+            cycle_rate: self.cycle_rate,
+            variant: self.variant,
+            quirks: self.quirks,
+            opcode_costs: self.opcode_costs,
+            exited: self.exited,
+            screen: self.screen.clone(),
+            draw_flag: self.draw_flag,
+            count: self.count,
+        }
+    }
+
+    /// Return the emulated machine to a previously captured state
+    /// (and redraw the display to match it).
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.memory = snapshot.memory;
+        self.program_len = snapshot.program_len;
+        self.start_address = snapshot.start_address;
+        self.registers = snapshot.registers;
+        self.index_reg = snapshot.index_reg;
+        self.pc = snapshot.pc;
+        self.delay_timer = snapshot.delay_timer;
+        self.sound_timer = snapshot.sound_timer;
+        self.stack = snapshot.stack;
+        self.sp = snapshot.sp;
+        self.rpl_flags = snapshot.rpl_flags;
+        self.seed = snapshot.seed;
+        self.rng = snapshot.rng.clone();
+        self.update_pc_cycles = snapshot.update_pc_cycles;
+        self.cycle_rate = snapshot.cycle_rate;
+        self.variant = snapshot.variant;
+        self.quirks = snapshot.quirks;
+        self.opcode_costs = snapshot.opcode_costs;
+        self.exited = snapshot.exited;
+        self.screen = snapshot.screen.clone();
+        self.draw_flag = snapshot.draw_flag;
+        self.count = snapshot.count;
+
+        self.hardware.update_display(&self.screen);
+    }
+
+    fn run_extension(&mut self) -> bool {
+        // Run the current instruction with the first extension handling it,
+        // returning whether one did.
+        let value = self.opcode.value;
+        let Some((_, handler)) = self
+            .extensions
+            .iter_mut()
+            .find(|(pattern, _)| pattern.matches(value))
+        else {
+            return false;
+        };
+
+        let mut context = ExtensionContext {
+            opcode: value,
+            pc: self.pc,
+            registers: &mut self.registers,
+            index_reg: &mut self.index_reg,
+            delay_timer: &mut self.delay_timer,
+            sound_timer: &mut self.sound_timer,
+            memory: &mut self.memory,
+            screen: &mut self.screen,
+            pc_step: self.update_pc_cycles,
+            jump: None,
+            redraw: false,
+        };
+        let result = handler(&mut context);
+        let (pc_step, jump, redraw) = (context.pc_step, context.jump, context.redraw);
+        self.draw_flag |= redraw;
+        match (result, jump) {
+            (Err(error), _) => {
+                self.fault = Some(error);
+                self.update_pc_cycles = 0; // Stay put, like with any other fault.
+            }
+            (Ok(()), Some(address)) => {
+                self.pc = address;
+                self.update_pc_cycles = 0;
+            }
+            (Ok(()), None) => self.update_pc_cycles = pc_step,
+        }
         true
     }
 
-    fn unknown_instruction(&self) {
-        panic!("Unimplemented opcode: {}", self.opcode);
+    fn run_until_done(&mut self) -> Result<(), Chip8Error> {
+        // Run a frame at a time, waiting in between, until running stops.
+        let Some(mut progress) = self.start_running() else {
+            return Ok(());
+        };
+        while self.run_next_frame(&mut progress) {
+            let waiting_since = time::Instant::now();
+            wait_until(progress.deadline);
+            self.run_stats.sleeping += waiting_since.elapsed();
+        }
+        self.finish_running(progress)
+    }
+
+    fn write_crash_dump(&self, payload: &(dyn Any + Send)) {
+        // Write down what crashed us, so that it can be reported
+        // (or at least say why that failed too).
+        let Some(path) = &self.crash_dump_path else {
+            return;
+        };
+        match write_crash_dump(path, self, &self.recent_instructions, payload) {
+            Ok(()) => error!(path; "The emulator crashed! Wrote a crash dump to {path}"),
+            Err(err) => {
+                error!(path; "The emulator crashed, and writing a crash dump failed: {err}")
+            }
+        }
+    }
+
+    fn unknown_instruction(&mut self) {
+        self.fault = Some(Chip8Error::BadOpcode {
+            opcode: self.opcode.value,
+            pc: self.pc,
+        });
+        self.update_pc_cycles = 0; // Stay put, so that the faulting instruction can be inspected.
+    }
+
+    fn out_of_bounds(&mut self, address: usize) {
+        self.fault = Some(Chip8Error::OutOfBounds {
+            opcode: self.opcode.value,
+            pc: self.pc,
+            address,
+        });
+        self.update_pc_cycles = 0;
+    }
+
+    fn stack_fault(&mut self, error: Chip8Error) {
+        self.fault = Some(error);
+        self.update_pc_cycles = 0;
+    }
+
+    fn key_pressed(&mut self) -> Option<bool> {
+        // Whether the key in VX is pressed (or None, with a fault, if there's no such key).
+        let key = self.registers[self.opcode.xreg];
+        let pressed = self.keypad.get(usize::from(key)).copied();
+        if pressed.is_none() {
+            self.fault = Some(Chip8Error::BadKey {
+                opcode: self.opcode.value,
+                pc: self.pc,
+                key,
+            });
+            self.update_pc_cycles = 0;
+        }
+        pressed
     }
 }
 
 impl Emulator for Chip8 {
-    fn run(&mut self) {
-        // Run the emulated device, returning only when the game or user quits.
+    fn run(&mut self) -> Result<(), Chip8Error> {
+        // Run the emulated device, returning only when the game or user quits
+        // (or the game runs an instruction that can't run).
+        // If the emulator itself panics along the way, what it was doing is written
+        // to the crash dump (if there is one) before the panic carries on.
+        if self.crash_dump_path.is_none() {
+            return self.run_until_done();
+        }
+        install_panic_hook();
+        match panic::catch_unwind(AssertUnwindSafe(|| self.run_until_done())) {
+            Ok(result) => result,
+            Err(payload) => {
+                self.write_crash_dump(payload.as_ref());
+                panic::resume_unwind(payload)
+            }
+        }
+    }
 
-        self.fetch_opcode();
-        if self.opcode == Opcode::default() {
-            // No game is loaded, so just exit.
-            // (This is mostly useful when a 'game' has been loaded that does not
-            // contain valid Chip8 instructions.)
-            return;
+    fn emulate_cycle(&mut self) -> Result<bool, Chip8Error> {
+        self.execute_instruction()?;
+        if self.exited || self.halted {
+            return Ok(false);
         }
-        self.hardware.init();
+        self.draw_screen();
+        let keys_result = self.hardware.set_keys(&self.screen);
+        self.keypad.copy_from_slice(self.hardware.get_keys());
+        #[cfg(feature = "recorder")]
+        self.play_and_record_keys()?;
+        self.act_on_keys(&keys_result);
+        self.update_timers();
+        self.increment_pc();
+        #[cfg(feature = "recorder")]
+        if self.finished_verifying() {
+            return Ok(false);
+        }
+
+        if let SetKeysResult::ShouldExit = keys_result {
+            // The cycle is finished first, so that a state saved here
+            // resumes at the next instruction.
+            if self.save_on_exit {
+                self.save_state();
+            }
+            return Ok(false);
+        }
+
+        // Continue to the next cycle.
+        Ok(true)
+    }
+
+    fn run_frame(&mut self) -> Result<bool, Chip8Error> {
+        // Run the next frame of a run like `run()`'s (starting one if need be),
+        // leaving the waiting in between frames to the caller.
+        let Some(mut progress) = self.frame_run.take().or_else(|| self.start_running()) else {
+            return Ok(false);
+        };
+        let frames_drawn = self.frames_drawn;
+        if self.run_next_frame(&mut progress) {
+            self.frame_run = Some(progress);
+        } else {
+            self.finish_running(progress)?;
+        }
+        Ok(self.frames_drawn > frames_drawn)
+    }
 
-        while self.emulate_cycle() {
-            // Emulate a cycle, and then wait the proper amount to match the cycle rate.
-            thread::sleep(time::Duration::from_nanos(self.cycle_rate));
+    fn load_rom(&mut self, title: &str, contents: &[u8]) -> Result<(), Chip8Error> {
+        let program = &mut self.memory[usize::from(self.start_address)..];
+        if contents.len() > program.len() {
+            return Err(Chip8Error::RomTooLarge {
+                size: contents.len(),
+                max: program.len(),
+            });
         }
+
+        // Set the game's title.
+        self.hardware
+            .set_title(&format!("{}: {}", TITLE_PREFIX, title))?; // Handles title errors.
+        self.game_title = String::from(title);
+
+        // Load the game into memory.
+        program[..contents.len()].copy_from_slice(contents);
+        self.program_len = contents.len() as u16; // (It fits, so this doesn't overflow.)
+        Ok(())
+    }
+
+    fn screen(&self) -> &Screen {
+        &self.screen
+    }
+
+    fn has_exited(&self) -> bool {
+        self.exited
+    }
+
+    fn has_halted(&self) -> bool {
+        self.halted
+    }
+
+    fn run_stats(&self) -> &RunStats {
+        &self.run_stats
+    }
+
+    fn reached_run_limit(&self) -> bool {
+        self.max_cycles.is_some_and(|max| self.cycles_run >= max)
+            || self.max_frames.is_some_and(|max| self.frames_run >= max)
+    }
+
+    fn write_state(&self, writer: &mut dyn Write) -> Result<(), Error> {
+        self.to_state_writer(writer)
     }
 }
@@ -1,4 +1,14 @@
 use super::*;
+use crate::arch::{Profile, Quirk};
+use crate::rom_db::RomDatabase;
+
+fn headless_config() -> Config {
+    // A configuration that never opens a window (for tests that create hardware).
+    Config {
+        frontend: Frontend::Headless,
+        ..Default::default()
+    }
+}
 
 fn run_opcode(c8: &mut Chip8, instruction: u16) {
     c8.opcode = Opcode::new(instruction);
@@ -7,11 +17,11 @@ fn run_opcode(c8: &mut Chip8, instruction: u16) {
 
 #[test]
 fn setup() {
-    let mut c8 = Chip8::tester(true);
+    let mut c8 = Chip8::tester();
     assert_eq!(c8.pc, 0x200);
 
     let fontset_clear = c8.fontset.iter().all(|x| *x == 0);
-    assert_eq!(fontset_clear, false);
+    assert!(!fontset_clear);
 
     // Load a game and assert some well-known values were loaded into memory.
     c8.load_game("c8games/PONG2").unwrap();
@@ -21,7 +31,7 @@ fn setup() {
 
 #[test]
 fn skip_instruction() {
-    let mut c8 = Chip8::tester(true);
+    let mut c8 = Chip8::tester();
 
     // First, add the literal (A3) to a register.
     run_opcode(&mut c8, 0x71A3);
@@ -53,7 +63,7 @@ fn skip_instruction() {
 
 #[test]
 fn clear_screen() {
-    let mut c8 = Chip8::tester(true);
+    let mut c8 = Chip8::tester();
 
     // Draw something to the screen and assert that
     // some pixels were set.
@@ -69,7 +79,7 @@ fn clear_screen() {
 
 #[test]
 fn call_return() {
-    let mut c8 = Chip8::tester(true);
+    let mut c8 = Chip8::tester();
 
     // Make sure the stack is initially empty.
     assert_eq!(c8.sp, 0);
@@ -96,7 +106,7 @@ fn call_return() {
 
 #[test]
 fn add() {
-    let mut c8 = Chip8::tester(true);
+    let mut c8 = Chip8::tester();
 
     run_opcode(&mut c8, 0x7212);
     // Test that value is now correct.
@@ -113,7 +123,7 @@ fn add() {
 
 #[test]
 fn add_with_carry() {
-    let mut c8 = Chip8::tester(true);
+    let mut c8 = Chip8::tester();
 
     // Test adding the max value without overflow.
     run_opcode(&mut c8, 0x73FF); // Add FF to reg 3 (0).
@@ -134,7 +144,7 @@ fn add_with_carry() {
 
 #[test]
 fn sub() {
-    let mut c8 = Chip8::tester(true);
+    let mut c8 = Chip8::tester();
 
     run_opcode(&mut c8, 0x71A2); // Add A2 to reg 1 (0).
     run_opcode(&mut c8, 0x7203); // Add 03 to reg 2 (0).
@@ -167,7 +177,7 @@ fn sub() {
 
 #[test]
 fn shift() {
-    let mut c8 = Chip8::tester(true);
+    let mut c8 = Chip8::tester();
 
     run_opcode(&mut c8, 0x7101); // Load register 1 with 1.
     assert_eq!(c8.registers[1], 1);
@@ -186,7 +196,7 @@ fn shift() {
 
 #[test]
 fn save_restore_registers() {
-    let mut c8 = Chip8::tester(true);
+    let mut c8 = Chip8::tester();
 
     run_opcode(&mut c8, 0x71A1); // Reg 1 has A1.
     run_opcode(&mut c8, 0x7206); // Reg 2 has 06.
@@ -227,3 +237,1959 @@ fn save_restore_registers() {
         "Index register was spuriously updated!"
     );
 }
+
+#[test]
+fn run_frame() {
+    let mut c8 = Chip8::tester();
+
+    // Load a program that sets the delay timer and then only adds to a register.
+    let program = [0x60, 0x05, 0xF0, 0x15, 0x70, 0x01];
+    c8.memory[0x200..0x206].copy_from_slice(&program);
+    for loc in (0x206..0x240).step_by(2) {
+        c8.memory[loc] = 0x71; // Add 1 to reg 1.
+        c8.memory[loc + 1] = 0x01;
+    }
+
+    // A frame at the default cycle rate is ten instructions,
+    // and the timer should only tick once for all of them.
+    assert!(
+        !c8.run_frame().unwrap(),
+        "Display was changed without drawing!"
+    );
+    assert_eq!(c8.pc, 0x214);
+    assert_eq!(c8.registers[1], 7);
+    assert_eq!(c8.delay_timer, 4);
+    // (Frames are counted the same as when running them all with `run()`.)
+    assert_eq!((c8.count, c8.run_stats.instructions), (10, 10));
+
+    // Now, draw something and check that the frame reports it.
+    c8.memory[0x214] = 0xD0;
+    c8.memory[0x215] = 0x05;
+    assert!(
+        c8.run_frame().unwrap(),
+        "Drawing did not change the display!"
+    );
+    assert_ne!(c8.screen().into_iter().count(), 0);
+    assert_eq!(c8.delay_timer, 3);
+    assert_eq!(c8.frames_drawn, 1);
+    assert!(!c8.draw_flag);
+}
+
+#[test]
+fn exit() {
+    let mut c8 = Chip8::tester();
+
+    c8.memory[0x200] = 0x00;
+    c8.memory[0x201] = 0xFD;
+    assert!(!c8.has_exited());
+
+    // Exiting should stop the cycle (and keep stopping it).
+    assert!(!c8.emulate_cycle().unwrap(), "Exit did not stop emulation!");
+    assert!(c8.has_exited());
+    assert_eq!(c8.pc, 0x200);
+    assert!(!c8.emulate_cycle().unwrap());
+}
+
+#[test]
+fn bad_opcode() {
+    let mut c8 = Chip8::tester();
+    c8.load_rom("bad", &[0x00, 0xE0, 0x80, 0x0F]).unwrap();
+    assert!(c8.emulate_cycle().unwrap());
+
+    // Unknown instructions fail the cycle (and stay put, so they can be inspected).
+    let error = c8.emulate_cycle().unwrap_err();
+    assert!(matches!(
+        error,
+        Chip8Error::BadOpcode {
+            opcode: 0x800F,
+            pc: 0x202
+        }
+    ));
+    assert_eq!(c8.pc, 0x202);
+    assert!(c8.run_frame().is_err());
+
+    // So do ones reaching past the end of memory.
+    let mut c8 = Chip8::tester();
+    c8.index_reg = 0xFFE;
+    c8.load_rom("bad", &[0xF2, 0x55]).unwrap();
+    assert!(matches!(
+        c8.emulate_cycle(),
+        Err(Chip8Error::OutOfBounds {
+            address: 0x1000,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn out_of_bounds_memory() {
+    // Sprites reaching past the end of memory fail, without drawing any of the sprite.
+    let mut c8 = Chip8::tester();
+    c8.index_reg = 0xFFC;
+    run_opcode(&mut c8, 0xD015);
+    assert!(matches!(
+        c8.fault.take(),
+        Some(Chip8Error::OutOfBounds {
+            address: 0x1000,
+            ..
+        })
+    ));
+    assert!(!c8.screen.get_pixel(0, 0));
+
+    // So do BCD stores.
+    c8.index_reg = 0xFFE;
+    run_opcode(&mut c8, 0xF033);
+    assert!(matches!(
+        c8.fault.take(),
+        Some(Chip8Error::OutOfBounds {
+            address: 0x1000,
+            ..
+        })
+    ));
+    c8.index_reg = 0x1234;
+    run_opcode(&mut c8, 0xF033);
+    assert!(matches!(
+        c8.fault.take(),
+        Some(Chip8Error::OutOfBounds {
+            address: 0x1234,
+            ..
+        })
+    ));
+
+    // Adding to I wraps around rather than overflowing.
+    c8.index_reg = 0xFFFF;
+    c8.registers[0] = 2;
+    run_opcode(&mut c8, 0xF01E);
+    assert_eq!(c8.index_reg, 1);
+    assert!(c8.fault.is_none());
+}
+
+#[test]
+fn out_of_bounds_pc() {
+    // Running off the end of memory fails the fetch, whether by jumping there...
+    let mut c8 = Chip8::tester();
+    c8.load_rom("off the end", &[0x1F, 0xFF]).unwrap();
+    assert!(c8.emulate_cycle().unwrap());
+    assert!(matches!(
+        c8.emulate_cycle(),
+        Err(Chip8Error::OutOfBounds {
+            pc: 0xFFF,
+            address: 0x1000,
+            ..
+        })
+    ));
+
+    // ...or by jumping there with an offset (past memory altogether).
+    let mut c8 = Chip8::tester();
+    c8.load_rom("offset", &[0x60, 0xFF, 0xBF, 0xFF]).unwrap();
+    assert!(c8.emulate_cycle().unwrap());
+    assert!(c8.emulate_cycle().unwrap());
+    assert!(matches!(
+        c8.emulate_cycle(),
+        Err(Chip8Error::OutOfBounds {
+            pc: 0x10FE,
+            address: 0x10FE,
+            ..
+        })
+    ));
+    assert!(c8.run_frame().is_err());
+
+    // States with the PC past the last instruction are rejected.
+    let mut state = Vec::new();
+    c8.pc = 0x200;
+    c8.to_state_writer(&mut state).unwrap();
+    let mut state: serde_json::Value = serde_json::from_slice(&state).unwrap();
+    state["pc"] = 0xFFF.into();
+    let error = Chip8::from_state_reader(state.to_string().as_bytes(), &headless_config())
+        .err()
+        .unwrap();
+    assert!(matches!(error, Chip8Error::InvalidState(_)));
+}
+
+#[test]
+fn stack_faults() {
+    // Calling with the stack full overflows it...
+    let mut c8 = Chip8::tester();
+    for _ in 0..16 {
+        run_opcode(&mut c8, 0x2300);
+        assert!(c8.fault.is_none());
+    }
+    run_opcode(&mut c8, 0x2300);
+    assert!(matches!(
+        c8.fault.take(),
+        Some(Chip8Error::StackOverflow { opcode: 0x2300, .. })
+    ));
+    assert_eq!(c8.sp, 16);
+
+    // ...and returning with it empty underflows it.
+    let mut c8 = Chip8::tester();
+    c8.load_rom("underflow", &[0x00, 0xEE]).unwrap();
+    assert!(matches!(
+        c8.emulate_cycle(),
+        Err(Chip8Error::StackUnderflow {
+            opcode: 0x00EE,
+            pc: 0x200
+        })
+    ));
+    assert_eq!(c8.pc, 0x200);
+
+    // States with the stack pointer past the stack are rejected.
+    let mut state = Vec::new();
+    c8.to_state_writer(&mut state).unwrap();
+    let mut state: serde_json::Value = serde_json::from_slice(&state).unwrap();
+    state["sp"] = 17.into();
+    let error = Chip8::from_state_reader(state.to_string().as_bytes(), &headless_config())
+        .err()
+        .unwrap();
+    assert!(matches!(error, Chip8Error::InvalidState(_)));
+}
+
+#[test]
+fn bad_keys() {
+    // Checking a key past the keypad's 16 fails (and stays put), whether skipping if it's pressed...
+    let mut c8 = Chip8::tester();
+    c8.load_rom("bad key", &[0x60, 0x10, 0xE0, 0x9E]).unwrap();
+    assert!(c8.emulate_cycle().unwrap());
+    assert!(matches!(
+        c8.emulate_cycle(),
+        Err(Chip8Error::BadKey {
+            opcode: 0xE09E,
+            pc: 0x202,
+            key: 0x10
+        })
+    ));
+    assert_eq!(c8.pc, 0x202);
+
+    // ...or if it isn't.
+    c8.registers[0] = 0xFF;
+    run_opcode(&mut c8, 0xE0A1);
+    assert!(matches!(
+        c8.fault.take(),
+        Some(Chip8Error::BadKey { key: 0xFF, .. })
+    ));
+    assert_eq!(c8.update_pc_cycles, 0);
+
+    // (The last key on the keypad is still fine.)
+    c8.registers[0] = 0xF;
+    run_opcode(&mut c8, 0xE0A1);
+    assert!(c8.fault.is_none());
+    assert_eq!(c8.update_pc_cycles, 4);
+}
+
+#[test]
+fn opcode_handlers() {
+    assert_eq!(
+        "8XYF".parse::<OpcodePattern>(),
+        Ok(OpcodePattern::new(0xF00F, 0x800F))
+    );
+    assert!("8XY".parse::<OpcodePattern>().is_err());
+    assert!("8X-F".parse::<OpcodePattern>().is_err());
+
+    // Extensions handle instructions the built-in set doesn't have...
+    let mut c8 = Chip8::tester();
+    c8.add_opcode_handler("8XYF".parse().unwrap(), |context| {
+        let (x, y) = (context.x(), context.y());
+        context.registers[x] = context.registers[x].wrapping_mul(context.registers[y]);
+        Ok(())
+    });
+    c8.registers[1] = 3;
+    c8.registers[2] = 4;
+    c8.load_rom("extended", &[0x81, 0x2F]).unwrap();
+    assert!(c8.emulate_cycle().unwrap());
+    assert_eq!(c8.registers[1], 12);
+    assert_eq!(c8.pc, 0x202);
+
+    // ...take over ones it does have, and can move the PC or fail.
+    c8.add_opcode_handler("1NNN".parse().unwrap(), |context| {
+        context.skip_next();
+        Ok(())
+    });
+    c8.add_opcode_handler(OpcodePattern::new(0xFFFF, 0x0000), |context| {
+        Err(Chip8Error::BadOpcode {
+            opcode: context.opcode,
+            pc: context.pc,
+        })
+    });
+    run_opcode(&mut c8, 0x1300);
+    c8.increment_pc();
+    assert_eq!(c8.pc, 0x206);
+    run_opcode(&mut c8, 0x0000);
+    assert!(matches!(
+        c8.fault,
+        Some(Chip8Error::BadOpcode { pc: 0x206, .. })
+    ));
+}
+
+#[test]
+fn crash_dump() {
+    let path = std::env::temp_dir().join("jchip8-test-crash.json");
+    let config = Config {
+        crash_dump_path: Some(path.to_string_lossy().into_owned()),
+        ..headless_config()
+    };
+
+    // A panic while running writes down what was going on before carrying on.
+    let mut c8 = Chip8::from_bytes(&[0x61, 0x07, 0x81, 0x2F], &config).unwrap();
+    c8.add_opcode_handler("8XYF".parse().unwrap(), |_| panic!("Unimplemented!"));
+    let result = panic::catch_unwind(AssertUnwindSafe(|| c8.run()));
+    assert!(result.is_err());
+
+    let dump: serde_json::Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+    assert!(dump["panic"]
+        .as_str()
+        .unwrap()
+        .starts_with("Unimplemented!"));
+    let recent = dump["recent_instructions"].as_array().unwrap();
+    assert_eq!(recent.len(), 2);
+    assert_eq!(recent[0]["instruction"], "LD V1, 0x07");
+    assert_eq!(recent[1]["pc"], "0202");
+    assert_eq!(recent[1]["opcode"], "812F");
+    assert_eq!(dump["state"]["registers"][1], 7);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn opcode_stats() {
+    let path = std::env::temp_dir().join("jchip8-test-opcode-stats.csv");
+    let config = Config {
+        max_cycles: Some(7),
+        opcode_stats_path: Some(path.to_string_lossy().into_owned()),
+        ..headless_config()
+    };
+
+    // Every instruction run is counted, by where it was and what it was.
+    let program = [0x60, 0x01, 0x70, 0x01, 0x12, 0x02];
+    let mut c8 = Chip8::from_bytes(&program, &config).unwrap();
+    c8.run().unwrap();
+    assert_eq!(c8.opcode_stats.at_address(0x202), 3);
+    assert_eq!(c8.opcode_stats.of_opcode(0x1202), 3);
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "address,opcode,instruction,count\n\
+         0200,6001,\"LD V0, 0x01\",1\n\
+         0202,7001,\"ADD V0, 0x01\",3\n\
+         0204,1202,\"JP 0x202\",3\n"
+    );
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn bench() {
+    let program = [0x60, 0x01, 0x70, 0x01, 0x12, 0x02];
+    let mut c8 = Chip8::from_bytes(&program, &headless_config()).unwrap();
+    let report = c8.bench(100).unwrap();
+    assert_eq!(report.instructions, 100);
+    assert_eq!(c8.count, 100);
+    assert!(report.fetch + report.execute + report.draw <= report.elapsed);
+    assert!(report
+        .to_string()
+        .starts_with("Instructions executed: 100\n"));
+
+    // The timers count down once a frame (of 10 instructions), not once an instruction.
+    let program = [0x60, 0xFF, 0xF0, 0x15, 0x70, 0x01, 0x12, 0x04];
+    let mut c8 = Chip8::from_bytes(&program, &headless_config()).unwrap();
+    c8.bench(100).unwrap();
+    assert_eq!(c8.delay_timer, 0xFF - 10);
+
+    // Faults stop the benchmark, like they stop running.
+    let mut c8 = Chip8::from_bytes(&[0x80, 0x0F], &headless_config()).unwrap();
+    assert!(matches!(c8.bench(100), Err(Chip8Error::BadOpcode { .. })));
+}
+
+#[test]
+fn scroll() {
+    let mut c8 = Chip8::tester();
+
+    // Draw the top row of the '0' sprite (0xF0) at (0, 0).
+    run_opcode(&mut c8, 0xD011);
+    assert!(c8.screen.get_pixel(0, 0));
+    assert!(c8.screen.get_pixel(3, 0));
+    assert!(!c8.screen.get_pixel(4, 0));
+
+    // Scroll down three rows.
+    run_opcode(&mut c8, 0x00C3);
+    assert!(!c8.screen.get_pixel(0, 0));
+    assert!(c8.screen.get_pixel(0, 3));
+    assert!(c8.screen.get_pixel(3, 3));
+
+    // Scroll right (by four columns).
+    run_opcode(&mut c8, 0x00FB);
+    assert!(!c8.screen.get_pixel(0, 3));
+    assert!(c8.screen.get_pixel(4, 3));
+    assert!(c8.screen.get_pixel(7, 3));
+    assert!(!c8.screen.get_pixel(8, 3));
+
+    // Scroll left twice, pushing the row off of the screen.
+    run_opcode(&mut c8, 0x00FC);
+    assert!(c8.screen.get_pixel(0, 3));
+    assert!(!c8.screen.get_pixel(4, 3));
+    run_opcode(&mut c8, 0x00FC);
+    let pixels_count = c8.screen.into_iter().count();
+    assert_eq!(pixels_count, 0, "Scrolling left did not clear the screen!");
+}
+
+#[test]
+fn large_font() {
+    let mut c8 = Chip8::tester();
+
+    // Point the index register at the large '3' sprite.
+    run_opcode(&mut c8, 0x6503);
+    run_opcode(&mut c8, 0xF530);
+    assert_eq!(c8.index_reg, 80 + 30);
+    assert_eq!(
+        c8.memory[c8.index_reg as usize..c8.index_reg as usize + 10],
+        LARGE_FONTSET[30..40]
+    );
+
+    // The regular fontset should be untouched.
+    run_opcode(&mut c8, 0xF529);
+    assert_eq!(c8.memory[c8.index_reg as usize], 0xF0);
+}
+
+#[test]
+fn save_restore_flags() {
+    let mut c8 = Chip8::tester();
+
+    run_opcode(&mut c8, 0x6011); // Reg 0 has 11.
+    run_opcode(&mut c8, 0x6322); // Reg 3 has 22.
+    run_opcode(&mut c8, 0x6833); // Reg 8 has 33 (past the last flag).
+
+    // Save registers (up to register 3) into the flags.
+    run_opcode(&mut c8, 0xF375);
+    assert_eq!(c8.rpl_flags, [0x11, 0, 0, 0x22, 0, 0, 0, 0]);
+
+    // Saving more registers than there are flags should ignore the extras.
+    run_opcode(&mut c8, 0xFF75);
+    assert_eq!(c8.rpl_flags[3], 0x22);
+
+    // Now, clobber the registers and restore them.
+    run_opcode(&mut c8, 0x6000);
+    run_opcode(&mut c8, 0x6300);
+    run_opcode(&mut c8, 0xF385);
+    assert_eq!(c8.registers[0], 0x11);
+    assert_eq!(c8.registers[3], 0x22);
+    assert_eq!(
+        c8.registers[8], 0x33,
+        "Register past the flags was modified!"
+    );
+}
+
+#[test]
+fn draw_large_sprite() {
+    let mut c8 = Chip8::tester();
+
+    // In low-res mode, a height of 0 draws nothing.
+    run_opcode(&mut c8, 0xD000);
+    assert_eq!(c8.screen.into_iter().count(), 0);
+
+    // In hi-res mode, it draws a 16x16 sprite from 32 bytes of memory.
+    c8.screen = Screen::new(640, 320, 128, 64);
+    for (offset, row) in [0xFF, 0x01, 0x80, 0x00].iter().cycle().take(32).enumerate() {
+        c8.memory[0x300 + offset] = *row;
+    }
+    run_opcode(&mut c8, 0xA300);
+    run_opcode(&mut c8, 0xD000);
+    assert!(c8.screen.get_pixel(0, 0));
+    assert!(c8.screen.get_pixel(7, 0));
+    assert!(!c8.screen.get_pixel(8, 0));
+    assert!(c8.screen.get_pixel(15, 0));
+    assert!(c8.screen.get_pixel(0, 15));
+    assert!(!c8.screen.get_pixel(1, 15));
+    assert_eq!(c8.screen.into_iter().count(), 8 * (8 + 1 + 1));
+    assert_eq!(c8.registers[0xF], 0);
+
+    // Drawing it again should erase it, and report the collision.
+    run_opcode(&mut c8, 0xD000);
+    assert_eq!(c8.screen.into_iter().count(), 0);
+    assert_eq!(c8.registers[0xF], 1);
+}
+
+#[test]
+fn resolution_switch() {
+    let mut c8 = Chip8::tester();
+    c8.screen.set_scale(5);
+    run_opcode(&mut c8, 0xD011);
+
+    // Switching to hi-res doubles the resolution (clearing the screen),
+    // keeping the display size by halving the display scales.
+    run_opcode(&mut c8, 0x00FF);
+    assert_eq!((c8.screen.res_width, c8.screen.res_height), (128, 64));
+    assert_eq!((c8.screen.width, c8.screen.height), (320, 160));
+    assert_eq!(c8.screen.x_display_scale, 2);
+    assert_eq!(c8.screen.into_iter().count(), 0);
+    run_opcode(&mut c8, 0xD011);
+    assert!(c8.screen.get_pixel(0, 0));
+
+    // And back again.
+    run_opcode(&mut c8, 0x00FE);
+    assert_eq!((c8.screen.res_width, c8.screen.res_height), (64, 32));
+    assert_eq!(c8.screen.x_display_scale, 5);
+
+    // Displays too small for hi-res grow to fit.
+    c8.screen.set_scale(1);
+    run_opcode(&mut c8, 0x00FF);
+    assert_eq!((c8.screen.width, c8.screen.height), (128, 64));
+    assert_eq!(c8.screen.y_display_scale, 1);
+}
+
+#[test]
+fn quirks() {
+    let mut c8 = Chip8::tester();
+    run_opcode(&mut c8, 0x6003); // Reg 0 has 3.
+    run_opcode(&mut c8, 0x6106); // Reg 1 has 6.
+    run_opcode(&mut c8, 0x6205); // Reg 2 has 5.
+
+    // Without quirks, shifts use VX and logic leaves VF alone.
+    run_opcode(&mut c8, 0x8016);
+    assert_eq!(c8.registers[0], 1);
+    run_opcode(&mut c8, 0x6F09);
+    run_opcode(&mut c8, 0x8121);
+    assert_eq!(c8.registers[0xF], 9);
+
+    // Now, enable all of the quirks.
+    let config = Config {
+        quirks: vec![
+            (Quirk::ShiftUsesVy, true),
+            (Quirk::LoadStoreIncrementsIndex, true),
+            (Quirk::JumpUsesVx, true),
+            (Quirk::LogicResetsVf, true),
+        ],
+        ..Default::default()
+    };
+    c8.apply_settings(&config);
+
+    run_opcode(&mut c8, 0x8026); // V0 = V2 >> 1.
+    assert_eq!(c8.registers[0], 2);
+    assert_eq!(c8.registers[0xF], 1);
+
+    run_opcode(&mut c8, 0x8122);
+    assert_eq!(c8.registers[0xF], 0);
+
+    run_opcode(&mut c8, 0xA300);
+    run_opcode(&mut c8, 0xF255);
+    assert_eq!(c8.index_reg, 0x303);
+
+    run_opcode(&mut c8, 0xB210); // Jump to 0x210 + V2.
+    assert_eq!(c8.pc, 0x215);
+}
+
+#[test]
+fn wrap_sprites() {
+    let mut c8 = Chip8::tester();
+    run_opcode(&mut c8, 0x603E); // Reg 0 has 62 (2 pixels from the right edge).
+    run_opcode(&mut c8, 0x611E); // Reg 1 has 30 (2 pixels from the bottom edge).
+    run_opcode(&mut c8, 0xA000); // The '0' font sprite (4 pixels wide, 5 tall).
+
+    // By default, sprites are clipped at the edges.
+    run_opcode(&mut c8, 0xD015);
+    assert!(!c8.screen.get_pixel(0, 0));
+    run_opcode(&mut c8, 0x00E0);
+
+    // With the quirk, they wrap around to the other side.
+    c8.apply_settings(&Config {
+        quirks: vec![(Quirk::WrapSprites, true)],
+        ..Default::default()
+    });
+    run_opcode(&mut c8, 0xD015);
+    assert!(c8.screen.get_pixel(62, 30));
+    assert!(c8.screen.get_pixel(1, 0));
+    assert!(c8.screen.get_pixel(1, 2));
+}
+
+#[test]
+fn sprite_collisions() {
+    let mut c8 = Chip8::tester();
+    run_opcode(&mut c8, 0x603E); // Reg 0 has 62 (2 pixels from the right edge).
+    run_opcode(&mut c8, 0x6100); // Reg 1 has 0.
+    run_opcode(&mut c8, 0xA000); // The '0' font sprite (its top row is 1111).
+
+    // Only the pixels drawn (not the ones clipped) count as collisions.
+    run_opcode(&mut c8, 0xD011);
+    c8.screen.xor_pixel(0, 0);
+    run_opcode(&mut c8, 0xD011);
+    assert_eq!(c8.registers[0xF], 1);
+    assert_eq!(c8.screen.into_iter().collect::<Vec<_>>(), [(0, 0)]);
+
+    // With the quirk, the wrapped pixels collide too.
+    c8.apply_settings(&Config {
+        quirks: vec![(Quirk::WrapSprites, true)],
+        ..Default::default()
+    });
+    run_opcode(&mut c8, 0xD011);
+    assert_eq!(c8.registers[0xF], 1);
+    assert_eq!(
+        c8.screen.into_iter().collect::<Vec<_>>(),
+        [(1, 0), (62, 0), (63, 0)]
+    );
+}
+
+#[test]
+fn legacy_screen_state() {
+    // States saved before the screen was bit-packed hold it as columns of pixels.
+    let mut pixels = vec![vec![false; 32]; 64];
+    pixels[3][5] = true;
+    let state = serde_json::json!({
+        "width": 640,
+        "height": 320,
+        "res_width": 64,
+        "res_height": 32,
+        "x_display_scale": 10,
+        "y_display_scale": 10,
+        "pixels": pixels,
+    });
+
+    let screen: Screen = serde_json::from_value(state).unwrap();
+    assert_eq!(screen.into_iter().collect::<Vec<_>>(), [(3, 5)]);
+}
+
+#[test]
+fn invalid_screen_state() {
+    // Screens that couldn't be drawn on are rejected when they're loaded...
+    let screen = serde_json::to_value(Screen::default()).unwrap();
+    let mut short = screen.clone();
+    short["rows"].as_array_mut().unwrap().pop();
+    let mut wide = screen.clone();
+    wide["res_width"] = 200.into();
+    let mut flat = screen.clone();
+    flat["res_height"] = 0.into();
+    for screen in [short, wide, flat] {
+        assert!(serde_json::from_value::<Screen>(screen).is_err());
+    }
+    let mut legacy = screen.clone();
+    legacy["rows"] = serde_json::Value::Array(Vec::new());
+    legacy["pixels"] = serde_json::to_value(vec![vec![true; 33]; 64]).unwrap();
+    assert!(serde_json::from_value::<Screen>(legacy).is_err());
+
+    // ...failing the whole state.
+    let c8 = Chip8::tester();
+    let mut state = Vec::new();
+    c8.to_state_writer(&mut state).unwrap();
+    let mut state: serde_json::Value = serde_json::from_slice(&state).unwrap();
+    state["screen"]["res_width"] = 129.into();
+    let error = Chip8::from_state_reader(state.to_string().as_bytes(), &headless_config())
+        .err()
+        .unwrap();
+    assert!(matches!(error, Chip8Error::InvalidState(_)));
+}
+
+#[test]
+fn profiles() {
+    let mut c8 = Chip8::tester();
+
+    // A profile sets the variant and every quirk, but later quirks still win.
+    c8.apply_settings(&Config {
+        variant: Some(Profile::Chip8.variant()),
+        quirks: Profile::Chip8
+            .quirks()
+            .into_iter()
+            .chain([(Quirk::LogicResetsVf, false)])
+            .collect(),
+        ..Default::default()
+    });
+    assert_eq!(c8.variant, Variant::Chip8);
+    assert!(c8.quirks.shift_uses_vy);
+    assert!(c8.quirks.load_store_increments_index);
+    assert!(!c8.quirks.jump_uses_vx);
+    assert!(!c8.quirks.logic_resets_vf);
+
+    c8.apply_settings(&Config {
+        variant: Some(Profile::Schip.variant()),
+        quirks: Profile::Schip.quirks().to_vec(),
+        ..Default::default()
+    });
+    assert_eq!(c8.variant, Variant::Schip);
+    assert!(!c8.quirks.shift_uses_vy);
+    assert!(c8.quirks.jump_uses_vx);
+}
+
+#[test]
+fn chip8_variant_rejects_schip() {
+    let mut c8 = Chip8::tester();
+    c8.apply_settings(&Config {
+        variant: Some(Variant::Chip8),
+        ..Default::default()
+    });
+    run_opcode(&mut c8, 0x00FB);
+    assert!(matches!(c8.fault, Some(Chip8Error::BadOpcode { .. })));
+}
+
+#[test]
+fn settings_saved_in_state() {
+    let mut c8 = Chip8::tester();
+    c8.apply_settings(&Config {
+        variant: Some(Variant::Chip8),
+        quirks: vec![(Quirk::JumpUsesVx, true)],
+        speed: Some(1000),
+        ..Default::default()
+    });
+
+    let state = serde_json::to_vec(&c8).unwrap();
+    let loaded: Chip8 = serde_json::from_slice(&state).unwrap();
+    assert_eq!(loaded.variant, Variant::Chip8);
+    assert!(loaded.quirks.jump_uses_vx);
+    assert_eq!(loaded.cycle_rate, 1_000_000);
+}
+
+#[test]
+fn default_save_state_path() {
+    // A game's states are saved next to it by default...
+    let mut config = Config {
+        game_path: Some(String::from("c8games/PONG2")),
+        ..headless_config()
+    };
+    let c8 = Chip8::new(&config).unwrap();
+    assert_eq!(c8.save_state_path.as_deref(), Some("c8games/PONG2.state"));
+
+    // ...or in the state directory, if there is one...
+    config.state_dir = Some(String::from("states"));
+    assert_eq!(
+        config.save_state_path().as_deref(),
+        Some("states/PONG2.state")
+    );
+
+    // ...but an explicit path always wins.
+    config.save_state_path = Some(String::from("pong.json"));
+    assert_eq!(config.save_state_path().as_deref(), Some("pong.json"));
+
+    // A loaded state is saved back over itself.
+    let config = Config {
+        load_state_path: Some(String::from("pong.json")),
+        ..Default::default()
+    };
+    assert_eq!(config.save_state_path().as_deref(), Some("pong.json"));
+}
+
+#[test]
+fn memory_dump() {
+    let mut c8 = Chip8::tester();
+    c8.load_game("c8games/PONG2").unwrap();
+    let path = std::env::temp_dir().join("jchip8-test-memory-dump.mem");
+    let path = path.to_str().unwrap();
+
+    // By default, all of memory (including the fontsets) is dumped.
+    c8.to_memory_dump(path).unwrap();
+    let dump = fs::read(path).unwrap();
+    assert_eq!(dump.len(), 4096);
+    assert_eq!(dump[0], 0xF0);
+    assert_eq!(dump[0x200], 0x22);
+
+    // Otherwise, only the program region is.
+    c8.memory_dump_program_only = true;
+    c8.to_memory_dump(path).unwrap();
+    let dump = fs::read(path).unwrap();
+    assert_eq!(dump.len(), 4096 - 0x200);
+    assert_eq!(dump[0], 0x22);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn snapshot_restore() {
+    let mut c8 = Chip8::tester();
+    c8.load_game("c8games/PONG2").unwrap();
+    for _ in 0..100 {
+        c8.emulate_cycle().unwrap();
+    }
+
+    let snapshot = c8.snapshot();
+    let (pc, registers, index_reg) = (c8.pc, c8.registers, c8.index_reg);
+    let pixels: Vec<_> = c8.screen.into_iter().collect();
+
+    // Run further, and clobber memory as well.
+    for _ in 0..100 {
+        c8.emulate_cycle().unwrap();
+    }
+    c8.memory[0x300] ^= 0xFF;
+    c8.screen.clear_all_pixels();
+
+    // Restoring the snapshot should put everything back.
+    c8.restore(&snapshot);
+    assert_eq!(c8.pc, pc);
+    assert_eq!(c8.registers, registers);
+    assert_eq!(c8.index_reg, index_reg);
+    assert_eq!(c8.memory[0x300], snapshot.memory[0x300]);
+    assert_eq!(c8.screen.into_iter().collect::<Vec<_>>(), pixels);
+}
+
+#[test]
+fn start_address() {
+    use crate::config::parse_start_address;
+
+    assert_eq!(parse_start_address("0x600"), Ok(0x600));
+    assert_eq!(parse_start_address("eti-660"), Ok(0x600));
+    assert_eq!(parse_start_address("2A0"), Ok(0x2A0));
+    assert!(parse_start_address("0x100").is_err());
+    assert!(parse_start_address("0x1000").is_err());
+
+    // Games given a start address are loaded, and start running, there.
+    let config = Config {
+        game_path: Some(String::from("c8games/PONG2")),
+        start_address: Some(0x600),
+        ..headless_config()
+    };
+    let mut c8 = Chip8::new(&config).unwrap();
+    assert_eq!(c8.pc, 0x600);
+    assert_eq!((c8.memory[0x200], c8.memory[0x600]), (0, 0x22));
+    // (PONG2 starts by calling a subroutine.)
+    c8.emulate_cycle().unwrap();
+    assert_eq!(c8.stack[0], 0x600);
+
+    // (The start address is kept in saved states.)
+    let mut buffer: Vec<u8> = Vec::new();
+    c8.to_state_writer(&mut buffer).unwrap();
+    let loaded = Chip8::from_state_reader(buffer.as_slice(), &headless_config()).unwrap();
+    assert_eq!(loaded.start_address, 0x600);
+}
+
+#[test]
+fn memory_image() {
+    let path = std::env::temp_dir().join("jchip8-test-memory-image.mem");
+    let path = path.to_str().unwrap();
+
+    // Memory dumps load back as they were, interpreter area and all.
+    let mut c8 = Chip8::tester();
+    c8.load_game("c8games/PONG2").unwrap();
+    c8.memory[0x1A0] = 0x5A;
+    c8.to_memory_dump(path).unwrap();
+    let config = Config {
+        game_path: Some(String::from(path)),
+        memory_image: true,
+        ..headless_config()
+    };
+    let loaded = Chip8::new(&config).unwrap();
+    assert_eq!(loaded.memory, c8.memory);
+    assert_eq!(loaded.pc, 0x200);
+
+    // Images can't be larger than memory.
+    fs::write(path, [0; 4097]).unwrap();
+    let error = Chip8::new(&config).err().unwrap();
+    assert!(matches!(error, Chip8Error::RomTooLarge { max: 4096, .. }));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn from_bytes() {
+    // Games given as bytes load just like games read from files...
+    let contents = fs::read("c8games/PONG2").unwrap();
+    let c8 = Chip8::from_bytes(&contents, &headless_config()).unwrap();
+    let mut expected = Chip8::tester();
+    expected.load_game("c8games/PONG2").unwrap();
+    assert_eq!(c8.memory, expected.memory);
+    assert_eq!(c8.game_title, "untitled");
+
+    // ...with the rest of the configuration applied.
+    let config = Config {
+        start_address: Some(0x600),
+        game_title: Some(String::from("Pong 2")),
+        ..headless_config()
+    };
+    let c8 = Chip8::from_bytes(&contents, &config).unwrap();
+    assert_eq!(c8.memory[0x600..0x600 + contents.len()], contents[..]);
+    assert_eq!(c8.pc, 0x600);
+    assert_eq!(c8.game_title, "Pong 2");
+}
+
+#[test]
+fn ips_patch() {
+    use crate::arch::apply_ips;
+
+    // A record writing two bytes, and one filling three bytes (past the end) with 0xEE.
+    let patch = b"PATCH\x00\x00\x01\x00\x02\xAB\xCD\x00\x00\x03\x00\x00\x00\x03\xEEEOF";
+    let mut contents = vec![0x00, 0x11, 0x22, 0x33];
+    apply_ips(&mut contents, patch).unwrap();
+    assert_eq!(contents, [0x00, 0xAB, 0xCD, 0xEE, 0xEE, 0xEE]);
+
+    // Patches can also end by truncating what they patched.
+    let mut contents = vec![0x00, 0x11, 0x22, 0x33];
+    apply_ips(&mut contents, b"PATCHEOF\x00\x00\x02").unwrap();
+    assert_eq!(contents, [0x00, 0x11]);
+
+    assert!(apply_ips(&mut contents, b"PATCH\x00\x00\x01\x00\x05\xAB").is_err());
+    assert!(apply_ips(&mut contents, b"not a patch").is_err());
+
+    // Games are patched before they're loaded.
+    let path = std::env::temp_dir().join("jchip8-test-patch.ips");
+    fs::write(&path, b"PATCH\x00\x00\x00\x00\x01\x12EOF").unwrap();
+    let config = Config {
+        game_path: Some(String::from("c8games/PONG2")),
+        ips_path: Some(String::from(path.to_str().unwrap())),
+        ..headless_config()
+    };
+    let c8 = Chip8::new(&config).unwrap();
+    assert_eq!((c8.memory[0x200], c8.memory[0x307]), (0x12, 0xEE));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn opcode_costs() {
+    use crate::arch::OpcodeClass;
+
+    assert_eq!(OpcodeClass::of(0x00E0), OpcodeClass::Clear);
+    assert_eq!(OpcodeClass::of(0xD125), OpcodeClass::Draw);
+    assert_eq!(OpcodeClass::of(0x00FB), OpcodeClass::Scroll);
+    assert_eq!(OpcodeClass::of(0x3A12), OpcodeClass::Flow);
+    assert_eq!(OpcodeClass::of(0xE19E), OpcodeClass::Flow);
+    assert_eq!(OpcodeClass::of(0xF355), OpcodeClass::Memory);
+    assert_eq!(OpcodeClass::of(0x8124), OpcodeClass::Register);
+    assert_eq!(
+        OpcodeCosts::parse_cost("draw=4"),
+        Ok((OpcodeClass::Draw, 4))
+    );
+    assert!(OpcodeCosts::parse_cost("draw=0").is_err());
+    assert!(OpcodeCosts::parse_cost("blit=2").is_err());
+
+    // A frame runs a speed's worth of cycles: here, a draw (taking 5) and 5 additions.
+    let mut c8 = Chip8::tester();
+    c8.memory[0x200..0x202].copy_from_slice(&[0xD0, 0x01]);
+    for loc in (0x202..0x240).step_by(2) {
+        c8.memory[loc..loc + 2].copy_from_slice(&[0x71, 0x01]); // Add 1 to reg 1.
+    }
+    c8.apply_settings(&Config {
+        opcode_costs: vec![(OpcodeClass::Draw, 5)],
+        ..Default::default()
+    });
+    assert_eq!(c8.instructions_per_frame(), 10);
+    c8.run_frame().unwrap();
+    assert_eq!((c8.pc, c8.registers[1]), (0x20C, 5));
+
+    // (By default, every instruction takes one cycle.)
+    c8.opcode_costs = OpcodeCosts::default();
+    c8.run_frame().unwrap();
+    assert_eq!(c8.registers[1], 15);
+}
+
+#[test]
+fn state_reader_writer() {
+    let mut c8 = Chip8::tester();
+    c8.load_game("c8games/PONG2").unwrap();
+    for _ in 0..100 {
+        c8.emulate_cycle().unwrap();
+    }
+
+    // Round-trip the state through an in-memory buffer.
+    let mut buffer: Vec<u8> = Vec::new();
+    c8.to_state_writer(&mut buffer).unwrap();
+    let loaded = Chip8::from_state_reader(buffer.as_slice(), &headless_config()).unwrap();
+    assert_eq!(loaded.pc, c8.pc);
+    assert_eq!(loaded.registers, c8.registers);
+    assert_eq!(loaded.memory, c8.memory);
+    // (Random numbers carry on where they left off, too.)
+    assert_eq!(loaded.rng.clone().gen::<u64>(), c8.rng.clone().gen::<u64>());
+
+    // Anything that isn't a state should be rejected.
+    let error = Chip8::from_state_reader(&b"not a state"[..], &headless_config())
+        .err()
+        .unwrap();
+    assert!(matches!(error, Chip8Error::InvalidState(_)));
+
+    // So should states whose instructions would take no time at all.
+    let mut state: serde_json::Value = serde_json::from_slice(&buffer).unwrap();
+    state["cycle_rate"] = 0.into();
+    let error = Chip8::from_state_reader(state.to_string().as_bytes(), &headless_config())
+        .err()
+        .unwrap();
+    assert!(matches!(error, Chip8Error::InvalidState(_)));
+    assert!(Chip8::parse_state::<Snapshot>(state.to_string().as_bytes()).is_err());
+}
+
+#[test]
+fn state_formats() {
+    use clap::ValueEnum;
+
+    let mut c8 = Chip8::tester();
+    c8.load_game("c8games/PONG2").unwrap();
+    for _ in 0..100 {
+        c8.emulate_cycle().unwrap();
+    }
+
+    // States load back in whichever format they were written in...
+    for format in StateFormat::value_variants() {
+        c8.state_format = Some(*format);
+        let mut buffer: Vec<u8> = Vec::new();
+        c8.to_state_writer(&mut buffer).unwrap();
+        assert_eq!(StateFormat::detect(&buffer), *format);
+        let loaded = Chip8::from_state_reader(buffer.as_slice(), &headless_config()).unwrap();
+        assert_eq!(loaded.pc, c8.pc);
+        assert_eq!(loaded.memory, c8.memory);
+        assert_eq!(loaded.screen.to_bytes(), c8.screen.to_bytes());
+    }
+
+    // ...which, unless given, is picked by the path's extension.
+    assert_eq!(StateFormat::from_path("pong.state"), StateFormat::Json);
+    #[cfg(feature = "cbor")]
+    assert_eq!(StateFormat::from_path("pong.CBOR"), StateFormat::Cbor);
+    #[cfg(feature = "msgpack")]
+    assert_eq!(
+        StateFormat::from_path("saves/pong.msgpack"),
+        StateFormat::MessagePack
+    );
+}
+
+#[test]
+fn emulator_trait_object() {
+    // Machines are driven through the Emulator trait alone.
+    let config = Config {
+        game_path: Some(String::from("c8games/PONG2")),
+        ..headless_config()
+    };
+    let mut emulator = crate::arch::new_emulator(&config).unwrap();
+    emulator.run_frame().unwrap();
+    assert!(!emulator.has_exited());
+
+    let mut buffer: Vec<u8> = Vec::new();
+    emulator.write_state(&mut buffer).unwrap();
+    let loaded = Chip8::from_state_reader(buffer.as_slice(), &headless_config()).unwrap();
+    assert_eq!(loaded.memory[0x200], 0x22);
+    // Any hardware can be plugged in instead of the configured frontend's.
+    let mut c8 = Chip8::new(&config).unwrap();
+    c8.set_hardware(Box::<MockHardware>::default()).unwrap();
+    assert!(c8.emulate_cycle().unwrap());
+}
+
+#[test]
+fn pause_and_resume() {
+    let mut c8 = Chip8::tester();
+    c8.load_game("c8games/PONG2").unwrap();
+    c8.sound_timer = 10;
+
+    // Paused machines run nothing, and their timers stand still...
+    c8.pause();
+    assert!(c8.is_paused());
+    assert!(!c8.run_frame().unwrap());
+    assert_eq!((c8.pc, c8.count, c8.sound_timer), (0x200, 0, 10));
+
+    // ...until they're resumed.
+    c8.resume();
+    assert!(!c8.is_paused());
+    c8.run_frame().unwrap();
+    assert_eq!(c8.count, 10);
+    assert_eq!(c8.sound_timer, 9);
+}
+
+#[test]
+fn host_control() {
+    use crate::arch::{ControlCommand, ControlEvent};
+
+    let contents = fs::read("c8games/PONG").unwrap();
+    let config = Config {
+        max_frames: Some(5),
+        ..headless_config()
+    };
+
+    // The host is told whenever the screen is drawn...
+    let mut c8 = Chip8::from_bytes(&contents, &config).unwrap();
+    let (_commands, events) = c8.control();
+    c8.run().unwrap();
+    assert!(c8.reached_run_limit());
+    let frames: Vec<u64> = events
+        .try_iter()
+        .map(|event| match event {
+            ControlEvent::FrameRendered { frame, screen } => {
+                assert!(screen.into_iter().next().is_some());
+                frame
+            }
+            event => panic!("Unexpected event: {event:?}"),
+        })
+        .collect();
+    assert!(!frames.is_empty());
+    assert!(frames.is_sorted() && frames.iter().all(|frame| *frame < 5));
+
+    // ...and commands are carried out in order, as soon as the next frame starts.
+    let mut c8 = Chip8::from_bytes(&contents, &config).unwrap();
+    let (commands, events) = c8.control();
+    commands.send(ControlCommand::SetSpeed(1200)).unwrap();
+    commands.send(ControlCommand::SaveState).unwrap();
+    commands
+        .send(ControlCommand::LoadState(b"not a state".to_vec()))
+        .unwrap();
+    commands.send(ControlCommand::Quit).unwrap();
+    c8.run().unwrap();
+    assert!(!c8.reached_run_limit());
+    assert_eq!(c8.instructions_per_frame(), 20);
+    let events: Vec<ControlEvent> = events.try_iter().collect();
+    let [ControlEvent::StateSaved(state), ControlEvent::CommandFailed(_)] = &events[..] else {
+        panic!("Unexpected events: {events:?}");
+    };
+    let saved = Chip8::from_state_reader(state.as_slice(), &headless_config()).unwrap();
+    assert_eq!(saved.cycle_rate, c8.cycle_rate);
+    assert_eq!(saved.count, 0);
+}
+
+#[test]
+fn run_async() {
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    // A timer that always makes its caller wait once (like a real one would),
+    // recording when it was asked to wake up.
+    struct Timer(bool);
+    impl Future for Timer {
+        type Output = ();
+        fn poll(mut self: std::pin::Pin<&mut Self>, context: &mut Context) -> Poll<()> {
+            if std::mem::replace(&mut self.0, true) {
+                Poll::Ready(())
+            } else {
+                context.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+
+    let config = Config {
+        game_path: Some(String::from("c8games/PONG")),
+        max_frames: Some(10),
+        ..headless_config()
+    };
+    let mut c8 = Chip8::new(&config).unwrap();
+    let mut deadlines = Vec::new();
+    // (Running yields to the caller between frames, rather than blocking.)
+    let polls = {
+        let mut run = pin!(c8.run_async(|deadline| {
+            deadlines.push(deadline);
+            Timer(false)
+        }));
+        let mut context = Context::from_waker(Waker::noop());
+        let mut polls = 1;
+        while run.as_mut().poll(&mut context).is_pending() {
+            polls += 1;
+        }
+        polls
+    };
+    assert!(polls > 1);
+    assert_eq!(polls, deadlines.len() + 1);
+    assert!(deadlines.is_sorted());
+    assert!(c8.reached_run_limit());
+}
+
+#[test]
+fn seeded_random_numbers() {
+    let config = Config {
+        seed: Some(1234),
+        ..Default::default()
+    };
+    let mut first = Chip8::tester();
+    let mut second = Chip8::tester();
+    first.apply_settings(&config);
+    second.apply_settings(&config);
+
+    // The same seed should always produce the same numbers.
+    for _ in 0..16 {
+        run_opcode(&mut first, 0xC0FF);
+        run_opcode(&mut second, 0xC0FF);
+        assert_eq!(first.registers[0], second.registers[0]);
+    }
+}
+
+#[test]
+#[cfg(feature = "recorder")]
+fn movie_playback() {
+    let mut movie = Movie::new(1234);
+    let mut keys = [false; 16];
+    movie.record(&keys);
+    movie.record(&keys);
+    keys[0xA] = true;
+    movie.record(&keys);
+    assert_eq!(movie.len(), 3);
+
+    // Playing the movie back should press the recorded keys, cycle by cycle.
+    let mut c8 = Chip8::tester();
+    c8.playback = Some(movie);
+    c8.play_and_record_keys().unwrap();
+    assert!(!c8.keypad[0xA]);
+    c8.play_and_record_keys().unwrap();
+    c8.play_and_record_keys().unwrap();
+    assert!(c8.keypad[0xA]);
+    run_opcode(&mut c8, 0xF00A); // Wait for a key press.
+    assert_eq!(c8.registers[0], 0xA);
+
+    // Once finished, the movie stops playing.
+    c8.play_and_record_keys().unwrap();
+    assert!(c8.playback.is_none());
+}
+
+#[test]
+#[cfg(feature = "recorder")]
+fn replay_verification() {
+    let start = |seed: u64| {
+        let mut c8 = Chip8::tester();
+        c8.load_game("c8games/PONG").unwrap();
+        c8.reseed(seed);
+        c8
+    };
+    let mut c8 = start(99);
+    c8.recording = Some((Movie::new(99), String::new()));
+    for cycle in 0..2000 {
+        // (Holding the paddle's key now and then.)
+        c8.keypad[1] = cycle % 300 < 100;
+        assert!(c8.run_instruction().unwrap());
+    }
+    let (movie, _) = c8.recording.take().unwrap();
+    assert_eq!(movie.checkpoint_count(), 3);
+
+    // Playing the movie back reproduces every checkpoint...
+    let replay = |c8: &mut Chip8| {
+        c8.playback = Some(movie.clone());
+        c8.verify_replay = true;
+        while c8.run_instruction()? {}
+        Ok::<_, Chip8Error>(c8.count)
+    };
+    assert_eq!(replay(&mut start(99)).unwrap(), 2000);
+    // ...unless the machine doesn't behave the same way.
+    let mut c8 = start(99);
+    c8.memory[0x300] ^= 0xFF;
+    let error = replay(&mut c8).unwrap_err();
+    assert!(matches!(
+        error,
+        Chip8Error::ReplayDiverged { cycle: 600, .. }
+    ));
+}
+
+#[test]
+#[cfg(feature = "recorder")]
+fn wav_recording() {
+    let mut wav = crate::gfx::WavRecorder::new(44100);
+    wav.capture(&[0.0, 1.0, -1.0, 0.5]);
+    wav.capture(&[2.0]);
+    let data = wav.encode();
+
+    // A mono, 16-bit WAV file at the given sample rate...
+    assert_eq!(&data[..4], b"RIFF");
+    assert_eq!(&data[8..16], b"WAVEfmt ");
+    assert_eq!(u16::from_le_bytes([data[22], data[23]]), 1);
+    assert_eq!(u32::from_le_bytes(data[24..28].try_into().unwrap()), 44100);
+    assert_eq!(u16::from_le_bytes([data[34], data[35]]), 16);
+    // ...holding every sample captured (clamped to the loudest there is).
+    assert_eq!(&data[36..40], b"data");
+    assert_eq!(u32::from_le_bytes(data[40..44].try_into().unwrap()), 10);
+    assert_eq!(data.len(), 54);
+    let samples: Vec<i16> = data[44..]
+        .chunks(2)
+        .map(|sample| i16::from_le_bytes([sample[0], sample[1]]))
+        .collect();
+    assert_eq!(samples, [0, i16::MAX, -i16::MAX, i16::MAX / 2, i16::MAX]);
+}
+
+#[test]
+fn load_state_in_place() {
+    let path = std::env::temp_dir().join("jchip8-test-load-state.state");
+    let path = path.to_str().unwrap();
+
+    let mut c8 = Chip8::tester();
+    c8.load_game("c8games/PONG2").unwrap();
+    for _ in 0..100 {
+        c8.emulate_cycle().unwrap();
+    }
+    c8.to_state(path).unwrap();
+    let (pc, registers) = (c8.pc, c8.registers);
+
+    // Keep running, then load the state back over ourselves.
+    for _ in 0..100 {
+        c8.emulate_cycle().unwrap();
+    }
+    c8.load_state_path = Some(String::from(path));
+    c8.load_state();
+    assert_eq!(c8.pc, pc);
+    assert_eq!(c8.registers, registers);
+    assert_eq!(c8.load_state_path.as_deref(), Some(path));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn adjust_speed() {
+    let mut c8 = Chip8::tester();
+    assert_eq!(c8.instructions_per_frame(), 10);
+
+    c8.adjust_speed(1);
+    assert_eq!(c8.instructions_per_frame(), 11);
+    c8.adjust_speed(-2);
+    assert_eq!(c8.instructions_per_frame(), 9);
+
+    // The speed never drops below one instruction per frame.
+    c8.adjust_speed(-100);
+    assert_eq!(c8.instructions_per_frame(), 1);
+}
+
+#[test]
+fn vsync_draws_once_per_frame() {
+    let mut c8 = Chip8::tester();
+    c8.vsync = true;
+
+    // The first draw happens right away...
+    run_opcode(&mut c8, 0x00E0);
+    c8.draw_screen();
+    assert!(!c8.draw_flag);
+
+    // ...but the next one waits for the next frame.
+    run_opcode(&mut c8, 0x00E0);
+    c8.draw_screen();
+    assert!(c8.draw_flag);
+}
+
+#[test]
+fn load_rom_bytes() {
+    let mut c8 = Chip8::tester();
+    c8.load_rom("stdin", &[0x00, 0xE0, 0x12, 0x00]).unwrap();
+    assert_eq!(c8.memory[0x200..0x204], [0x00, 0xE0, 0x12, 0x00]);
+    assert_eq!(c8.game_title, "stdin");
+
+    // Games too large to fit in memory are rejected (rather than partly loaded).
+    let too_large = vec![0xFF; 0x1000 - 0x200 + 1];
+    assert!(matches!(
+        c8.load_rom("too large", &too_large),
+        Err(Chip8Error::RomTooLarge { .. })
+    ));
+    assert_eq!(c8.game_title, "stdin");
+}
+
+#[test]
+fn run_limits() {
+    // A game that jumps to itself forever.
+    let mut c8 = Chip8::tester();
+    c8.load_rom("loop", &[0x12, 0x00]).unwrap();
+    c8.max_cycles = Some(5);
+    c8.run().unwrap();
+    assert!(c8.reached_run_limit());
+    assert_eq!(c8.cycles_run, 5);
+
+    // Frames count emulated time, so one frame at the default speed is ten cycles.
+    let mut c8 = Chip8::tester();
+    c8.load_rom("loop", &[0x12, 0x00]).unwrap();
+    c8.max_frames = Some(1);
+    c8.run().unwrap();
+    assert!(c8.reached_run_limit());
+    assert_eq!(c8.cycles_run, 10);
+
+    // Games that exit on their own finish within the limit.
+    let mut c8 = Chip8::tester();
+    c8.variant = Variant::Schip;
+    c8.load_rom("exit", &[0x00, 0xFD]).unwrap();
+    c8.max_cycles = Some(5);
+    c8.run().unwrap();
+    assert!(!c8.reached_run_limit());
+    assert!(c8.has_exited());
+}
+
+#[test]
+fn timers_tick_once_per_frame() {
+    // A game that sets the delay timer to 10, then jumps to itself forever.
+    let mut c8 = Chip8::tester();
+    c8.load_rom("timer", &[0x60, 0x0A, 0xF0, 0x15, 0x12, 0x04])
+        .unwrap();
+    c8.max_frames = Some(3);
+    c8.run().unwrap();
+
+    // However many instructions run in a frame, the timer only counts down once per frame.
+    assert_eq!(c8.cycles_run, 30);
+    assert_eq!(c8.delay_timer, 7);
+    assert_eq!(c8.run_stats().instructions, 30);
+}
+
+#[test]
+fn stop_on_halt() {
+    // Jumping to itself halts the game (but only stops running when asked to).
+    let mut c8 = Chip8::tester();
+    c8.load_rom("loop", &[0x00, 0xE0, 0x12, 0x02]).unwrap();
+    c8.max_cycles = Some(5);
+    c8.stop_on_halt = true;
+    c8.run().unwrap();
+    assert!(c8.has_halted());
+    assert!(!c8.reached_run_limit());
+    assert_eq!(c8.pc, 0x202);
+
+    // Waiting for a key only halts when no key can ever be pressed.
+    let mut c8 = Chip8::tester();
+    c8.load_rom("wait", &[0xF0, 0x0A]).unwrap();
+    c8.stop_on_halt = true;
+    assert!(c8.emulate_cycle().unwrap());
+    c8.key_wait_halts = true;
+    assert!(!c8.emulate_cycle().unwrap());
+    assert!(c8.has_halted());
+    assert!(!c8.run_frame().unwrap());
+}
+
+#[cfg(feature = "recorder")]
+#[test]
+fn gif_recording() {
+    use crate::gfx::GifRecorder;
+
+    // Record a blank screen for half a second, then a 0 drawn on it for another.
+    let mut c8 = Chip8::tester();
+    let started = time::Instant::now();
+    let mut gif = GifRecorder::new(&c8.screen, Config::default().palette, started).unwrap();
+    run_opcode(&mut c8, 0xD015);
+    let drawn_at = started + time::Duration::from_millis(500);
+    gif.capture(&c8.screen, drawn_at).unwrap();
+    let path = std::env::temp_dir().join("jchip8-test-recording.gif");
+    let stopped_at = started + time::Duration::from_secs(1);
+    gif.save(&path.to_string_lossy(), stopped_at).unwrap();
+
+    // Decoding it gives back both frames (4 times as large as the screen),
+    // each lasting as long as it was shown.
+    let file = fs::File::open(&path).unwrap();
+    let mut decoder = gif::DecodeOptions::new().read_info(file).unwrap();
+    assert_eq!((decoder.width(), decoder.height()), (256, 128));
+    let blank = decoder.read_next_frame().unwrap().unwrap();
+    assert_eq!(blank.delay, 50);
+    assert!(blank.buffer.iter().all(|&index| index == 0));
+    let drawn = decoder.read_next_frame().unwrap().unwrap();
+    assert_eq!(drawn.delay, 50);
+    // (The top of the 0 is 4 pixels wide.)
+    assert_eq!(drawn.buffer[..20], [[1; 16].as_slice(), &[0; 4]].concat());
+    assert!(decoder.read_next_frame().unwrap().is_none());
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn screen_pbm() {
+    let mut c8 = Chip8::tester();
+    c8.screen.xor_pixel(0, 0);
+    c8.screen.xor_pixel(63, 31);
+
+    let pbm = c8.screen.to_pbm();
+    let lines: Vec<&str> = pbm.lines().collect();
+    assert_eq!(lines[..2], ["P1", "64 32"]);
+    assert_eq!(lines.len(), 2 + 32);
+    assert_eq!(lines[2], format!("1{}", "0".repeat(63)));
+    assert_eq!(lines[33], format!("{}1", "0".repeat(63)));
+}
+
+#[test]
+fn screen_text() {
+    let mut c8 = Chip8::tester();
+    c8.screen.xor_pixel(0, 0);
+    c8.screen.xor_pixel(63, 31);
+
+    let text = c8.screen.to_text();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 32);
+    assert_eq!(lines[0], format!("█{}", ".".repeat(63)));
+    assert_eq!(lines[31], format!("{}█", ".".repeat(63)));
+    assert_eq!(format!("{:#}", c8.screen), text);
+}
+
+#[test]
+fn screen_bytes() {
+    let mut c8 = Chip8::tester();
+    c8.screen.xor_pixel(0, 0);
+    c8.screen.xor_pixel(9, 1);
+    c8.screen.xor_pixel(63, 31);
+
+    // Eight bytes a row, with the leftmost pixel in the highest bit.
+    let bytes = c8.screen.to_bytes();
+    assert_eq!(bytes.len(), 8 * 32);
+    assert_eq!(bytes[0], 0x80);
+    assert_eq!(bytes[8 + 1], 0x40);
+    assert_eq!(bytes[bytes.len() - 1], 0x01);
+    assert_eq!(bytes.iter().map(|byte| byte.count_ones()).sum::<u32>(), 3);
+
+    // High resolution rows are twice as long.
+    c8.screen.set_resolution(128, 64);
+    assert_eq!(c8.screen.to_bytes(), vec![0; 16 * 64]);
+}
+
+#[test]
+fn rom_database() {
+    // Every bundled game is known.
+    let mut database = RomDatabase::built_in();
+    for game in fs::read_dir("c8games").unwrap() {
+        let path = game.unwrap().path();
+        let rom = fs::read(&path).unwrap();
+        assert!(database.lookup(&rom).is_some(), "{path:?} is unknown");
+    }
+    let brix = database.lookup(&fs::read("c8games/BRIX").unwrap()).unwrap();
+    assert_eq!(brix.full_title(), "Brix by Andreas Gustafsson");
+
+    // Database files add to it.
+    let path = std::env::temp_dir().join("jchip8-test-roms.json");
+    let hash = crate::config::rom_hash(&[0x12, 0x00]);
+    let contents =
+        format!(r#"{{"{hash}": {{"title": "Loop", "profile": "chip8", "tickrate": 20}}}}"#);
+    fs::write(&path, contents).unwrap();
+    assert!(database.lookup(&[0x12, 0x00]).is_none());
+    database.extend_from_file(&path.to_string_lossy()).unwrap();
+    let known = database.lookup(&[0x12, 0x00]).unwrap();
+    assert_eq!(known.full_title(), "Loop");
+    assert_eq!(known.profile, Some(Profile::Chip8));
+    assert_eq!(known.speed(), Some(1200));
+}
+
+#[test]
+fn memory_hex_dump() {
+    let mut c8 = Chip8::tester();
+    c8.load_rom("dump", &[0x12, 0x00, 0x41, 0x42]).unwrap();
+    let dump = c8.memory_hex_dump();
+
+    assert!(dump.contains("Font (0x000-0x04F):\n0x000  F0 90 90 90 F0"));
+    assert!(dump.contains("Program (0x200-0x203):\n0x200  12 00 41 42"));
+    assert!(dump.contains("|..AB|"));
+    // Repeated rows (like all the empty memory past the program) are collapsed.
+    assert!(dump.contains("Data past the program (0x204-0xFFF):\n0x204  00 00"));
+    assert!(dump.contains("*\n0xFF4  00 00"));
+
+    // Regions that don't fit in memory (as loaded states can claim) are cut short.
+    c8.program_len = 0xFFFF;
+    let dump = c8.memory_hex_dump();
+    assert!(dump.contains("Program (0x200-0xFFF):"));
+    assert!(!dump.contains("Data past the program"));
+    c8.start_address = 0x10;
+    assert!(!c8.memory_hex_dump().contains("Reserved"));
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn load_game_from_zip() {
+    use std::io::Write;
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
+    // An archive holding a game (and something else that isn't one).
+    let path = std::env::temp_dir().join("jchip8-test-games.zip");
+    let mut archive = ZipWriter::new(fs::File::create(&path).unwrap());
+    archive
+        .start_file("games/LOOP.ch8", SimpleFileOptions::default())
+        .unwrap();
+    archive.write_all(&[0x12, 0x00]).unwrap();
+    archive
+        .start_file("README.txt", SimpleFileOptions::default())
+        .unwrap();
+    archive.write_all(b"Just a loop.").unwrap();
+    archive.finish().unwrap();
+    let path = path.to_string_lossy();
+
+    // The only game is picked out by itself, or it can be named.
+    let mut c8 = Chip8::tester();
+    c8.load_game(&path).unwrap();
+    assert_eq!(c8.memory[0x200..0x202], [0x12, 0x00]);
+    assert_eq!(
+        read_rom(&format!("{path}#README.txt")).unwrap(),
+        b"Just a loop."
+    );
+    assert!(read_rom(&format!("{path}#MISSING.ch8")).is_err());
+}
+
+#[cfg(feature = "netplay")]
+#[test]
+fn netplay_lockstep() {
+    use crate::gfx::{Netplay, NetplaySettings};
+    use std::net::{TcpListener, TcpStream};
+
+    let settings = |rom_hash: &str, seed| NetplaySettings {
+        rom_hash: String::from(rom_hash),
+        seed,
+        cycle_rate: CYCLE_RATE,
+        variant: Variant::default(),
+        quirks: Quirks::default(),
+        opcode_costs: OpcodeCosts::default(),
+        delay: 2,
+    };
+    let play = |host_settings, guest_settings, frames| {
+        // Connect two players, and play the host for some frames
+        // (and the guest until it stops), returning the settings each ends up with
+        // and how many frames the guest played.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let guest = thread::spawn(move || {
+            let stream = TcpStream::connect(address).unwrap();
+            let hardware = Box::<MockHardware>::default();
+            let (mut guest, settings) =
+                Netplay::from_stream(hardware, stream, false, guest_settings).ok()?;
+            let screen = Screen::default();
+            let frames = (0..100)
+                .take_while(|_| !matches!(guest.set_keys(&screen), SetKeysResult::ShouldExit))
+                .count();
+            Some((settings, frames))
+        });
+        let (stream, _) = listener.accept().unwrap();
+        let hardware = Box::<MockHardware>::default();
+        let host = Netplay::from_stream(hardware, stream, true, host_settings).ok();
+        let host = host.map(|(mut host, settings)| {
+            let screen = Screen::default();
+            for _ in 0..frames {
+                assert!(matches!(
+                    host.set_keys(&screen),
+                    SetKeysResult::ShouldContinue
+                ));
+            }
+            settings
+        });
+        (host, guest.join().unwrap())
+    };
+
+    // Players playing different games can't play together.
+    let (host, guest) = play(settings("brix", 1), settings("pong", 2), 0);
+    assert!(host.is_none() && guest.is_none());
+
+    // Otherwise, both use the host's settings, and play in lockstep
+    // (until one leaves, when the other soon stops too).
+    let (host, guest) = play(settings("pong", 1), settings("pong", 2), 5);
+    let (host_settings, (guest_settings, frames)) = (host.unwrap(), guest.unwrap());
+    assert_eq!(host_settings, settings("pong", 1));
+    assert_eq!(guest_settings, host_settings);
+    // (The guest can play out the host's frames, and its own delayed ones, at most;
+    // fewer if it finds the host gone while sending its own input.)
+    assert!(frames <= 7);
+}
+
+#[cfg(feature = "scripting")]
+#[test]
+fn remote_control() {
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpStream;
+
+    let mut c8 = Chip8::tester();
+    c8.load_game("c8games/MAZE").unwrap();
+    let remote = RemoteControl::new("127.0.0.1:0").unwrap();
+    let mut client = TcpStream::connect(remote.address().unwrap()).unwrap();
+    client
+        .set_read_timeout(Some(time::Duration::from_millis(10)))
+        .unwrap();
+    let mut replies = BufReader::new(client.try_clone().unwrap());
+    c8.remote = Some(remote);
+    let mut ask = |c8: &mut Chip8, command: &str| -> serde_json::Value {
+        // Send a command, and serve it (as running does, once per frame) until it's answered.
+        writeln!(client, "{command}").unwrap();
+        let mut reply = String::new();
+        loop {
+            c8.serve_remote().unwrap();
+            if replies.read_line(&mut reply).is_ok() && reply.ends_with('\n') {
+                return serde_json::from_str(&reply).unwrap();
+            }
+        }
+    };
+
+    assert_eq!(ask(&mut c8, r#"{"command": "pause"}"#)["pc"], 0x200);
+    assert!(c8.paused);
+    let reply = ask(
+        &mut c8,
+        r#"{"command": "read-memory", "address": 512, "length": 2}"#,
+    );
+    assert_eq!(reply["memory"], "A21E");
+    // MAZE starts by pointing the index register at its sprites.
+    let reply = ask(&mut c8, r#"{"command": "step"}"#);
+    assert_eq!((&reply["ok"], &reply["pc"]), (&json!(true), &json!(0x202)));
+    assert_eq!(c8.index_reg, 0x21E);
+    let reply = ask(
+        &mut c8,
+        r#"{"command": "read-memory", "address": 4095, "length": 2}"#,
+    );
+    assert_eq!(reply["ok"], false);
+    let reply = ask(&mut c8, r#"{"command": "screenshot"}"#);
+    assert!(reply["screen"].as_str().unwrap().starts_with("P1\n64 32\n"));
+    assert_eq!(ask(&mut c8, r#"{"command": "rewind"}"#)["ok"], false);
+
+    // Loading another game starts it from scratch.
+    let reply = ask(
+        &mut c8,
+        r#"{"command": "load-rom", "path": "c8games/PONG"}"#,
+    );
+    assert_eq!(reply["title"], "c8games/PONG");
+    assert_eq!((c8.pc, c8.index_reg), (0x200, 0));
+    assert_eq!(ask(&mut c8, r#"{"command": "resume"}"#)["ok"], true);
+    assert!(!c8.paused);
+}
+
+#[cfg(feature = "scripting")]
+#[test]
+fn step_back() {
+    let mut c8 = Chip8::tester();
+    c8.load_game("c8games/PONG").unwrap();
+    let mut fault = None;
+    let mut run = |c8: &mut Chip8, command: &str| {
+        let command = serde_json::from_str(command).unwrap();
+        c8.run_remote_command(command, &mut fault)
+    };
+    // (Nothing is kept to step back to until a debugger is attached.)
+    c8.run_instruction().unwrap();
+    assert!(run(&mut c8, r#"{"command": "step-back"}"#).is_err());
+
+    c8.remote = Some(RemoteControl::new("127.0.0.1:0").unwrap());
+    let state = |c8: &Chip8| (c8.pc, c8.registers, c8.index_reg);
+    let (mut frames, mut states) = (Vec::new(), Vec::new());
+    for _ in 0..40 {
+        frames.push(c8.frames_run);
+        states.push(state(&c8));
+        c8.run_instruction().unwrap();
+    }
+
+    // Stepping back undoes instructions one at a time, pausing...
+    let reply = run(&mut c8, r#"{"command": "step-back"}"#).unwrap();
+    assert_eq!(reply["pc"], states[39].0);
+    assert!(c8.paused);
+    run(&mut c8, r#"{"command": "step-back", "count": 3}"#).unwrap();
+    assert_eq!(state(&c8), states[36]);
+
+    // ...or a frame at a time, back to the first instruction of each.
+    let start = frames
+        .iter()
+        .position(|frame| *frame == frames[35])
+        .unwrap();
+    assert!(start < 35);
+    run(&mut c8, r#"{"command": "frame-back"}"#).unwrap();
+    assert_eq!(state(&c8), states[start]);
+}
+
+#[cfg(feature = "scripting")]
+#[test]
+fn memory_search() {
+    let mut c8 = Chip8::tester();
+    let mut fault = None;
+    let mut run = |c8: &mut Chip8, command: &str| {
+        let command = serde_json::from_str(command).unwrap();
+        c8.run_remote_command(command, &mut fault)
+    };
+
+    // Find the "lives" counter: it holds 3, then goes down to 2.
+    c8.memory[0x300] = 3;
+    c8.memory[0x480] = 3;
+    let reply = run(
+        &mut c8,
+        r#"{"command": "search", "condition": "equal", "value": 3}"#,
+    );
+    assert_eq!(reply.unwrap()["count"], 2);
+    c8.memory[0x300] = 2;
+    let reply = run(
+        &mut c8,
+        r#"{"command": "search", "condition": "decreased"}"#,
+    )
+    .unwrap();
+    assert_eq!(reply["count"], 1);
+    assert_eq!(
+        reply["candidates"],
+        json!([{ "address": 0x300, "value": 2 }])
+    );
+    assert!(run(&mut c8, r#"{"command": "search", "condition": "equal"}"#).is_err());
+
+    // Freezing it keeps it where it is, every frame, until unfrozen.
+    run(
+        &mut c8,
+        r#"{"command": "freeze", "address": 768, "value": 9}"#,
+    )
+    .unwrap();
+    assert_eq!(c8.memory[0x300], 9);
+    c8.memory[0x300] = 1;
+    c8.apply_freezes();
+    assert_eq!(c8.memory[0x300], 9);
+    run(&mut c8, r#"{"command": "unfreeze", "address": 768}"#).unwrap();
+    c8.memory[0x300] = 1;
+    c8.apply_freezes();
+    assert_eq!(c8.memory[0x300], 1);
+
+    // Patching writes a byte once.
+    run(
+        &mut c8,
+        r#"{"command": "write-memory", "address": 768, "value": 5}"#,
+    )
+    .unwrap();
+    assert_eq!(c8.memory[0x300], 5);
+    assert!(run(
+        &mut c8,
+        r#"{"command": "freeze", "address": 4096, "value": 0}"#
+    )
+    .is_err());
+
+    // Starting over makes everything past the reserved memory a candidate again.
+    let reply = run(&mut c8, r#"{"command": "search-reset"}"#).unwrap();
+    assert_eq!(reply["count"], 4096 - 0x200);
+}
+
+#[test]
+fn disassembly() {
+    use crate::arch::disassemble;
+
+    assert_eq!(disassemble(0x00E0), "CLS");
+    assert_eq!(disassemble(0x612A), "LD V1, 0x2A");
+    assert_eq!(disassemble(0x8AB4), "ADD VA, VB");
+    assert_eq!(disassemble(0xA21E), "LD I, 0x21E");
+    assert_eq!(disassemble(0xD015), "DRW V0, V1, 5");
+    assert_eq!(disassemble(0xF265), "LD V2, [I]");
+    // Anything else is data.
+    assert_eq!(disassemble(0x8AB9), "DW 0x8AB9");
+    assert_eq!(disassemble(0xFFFF), "DW 0xFFFF");
+}
+
+#[cfg(all(feature = "debugger", feature = "scripting"))]
+#[test]
+fn web_debugger() {
+    use std::io::Read;
+    use std::net::TcpStream;
+
+    let mut c8 = Chip8::tester();
+    c8.load_game("c8games/MAZE").unwrap();
+    let debugger = WebDebugger::new("127.0.0.1:0").unwrap();
+    let address = debugger.address().unwrap();
+    c8.web_debugger = Some(debugger);
+    let connect = || {
+        let client = TcpStream::connect(&address).unwrap();
+        client
+            .set_read_timeout(Some(time::Duration::from_millis(10)))
+            .unwrap();
+        client
+    };
+    let serve_until = |c8: &mut Chip8, mut client: &TcpStream, done: &dyn Fn(&[u8]) -> bool| {
+        // Serve (as running does, once per frame) until the client has received enough.
+        let mut received = Vec::new();
+        let mut buffer = [0; 4096];
+        while !done(&received) {
+            c8.serve_remote().unwrap();
+            if let Ok(count) = client.read(&mut buffer) {
+                received.extend_from_slice(&buffer[..count]);
+            }
+        }
+        received
+    };
+
+    // The page is served at the root.
+    let mut client = connect();
+    write!(client, "GET / HTTP/1.1\r\nHost: {address}\r\n\r\n").unwrap();
+    let page = serve_until(&mut c8, &client, &|received| {
+        received.ends_with(b"</html>\n")
+    });
+    let page = String::from_utf8(page).unwrap();
+    assert!(page.starts_with("HTTP/1.1 200 OK\r\n") && page.contains("<canvas"));
+
+    // The page's WebSocket is accepted (with the key from RFC 6455's example)...
+    let mut client = connect();
+    write!(
+        client,
+        "GET /socket HTTP/1.1\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+         Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n"
+    )
+    .unwrap();
+    // (The machine's state follows right away, so only the handshake itself is kept.)
+    let received = serve_until(&mut c8, &client, &|received| {
+        received.windows(4).any(|window| window == b"\r\n\r\n")
+    });
+    let end = received
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .unwrap();
+    let handshake = String::from_utf8_lossy(&received[..end + 4]).into_owned();
+    assert!(handshake.starts_with("HTTP/1.1 101"));
+    assert!(handshake.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n"));
+
+    // ...and takes commands (in masked frames, as browsers send them), sending back
+    // the reply, then the machine's state.
+    let command = br#"{"command": "pause"}"#;
+    let mask = [1, 2, 3, 4];
+    let mut frame = vec![0x81, 0x80 | command.len() as u8];
+    frame.extend_from_slice(&mask);
+    frame.extend(
+        command
+            .iter()
+            .zip(mask.iter().cycle())
+            .map(|(byte, mask)| byte ^ mask),
+    );
+    client.write_all(&frame).unwrap();
+    let frames = |received: &[u8]| {
+        // Split the (unmasked) frames received into their JSON messages.
+        let mut messages = Vec::new();
+        let mut rest = received;
+        while rest.len() >= 4 {
+            let (length, offset) = match rest[1] {
+                126 => (u16::from_be_bytes([rest[2], rest[3]]) as usize, 4),
+                length => (length as usize, 2),
+            };
+            let Some(payload) = rest.get(offset..offset + length) else {
+                break;
+            };
+            messages.push(serde_json::from_slice::<serde_json::Value>(payload).unwrap());
+            rest = &rest[offset + length..];
+        }
+        messages
+    };
+    let received = serve_until(&mut c8, &client, &|received| {
+        frames(received)
+            .iter()
+            .any(|message| message["state"]["paused"] == true)
+    });
+    let messages = frames(&received);
+    assert!(messages.contains(&json!({ "ok": true, "pc": 0x200 })));
+    let state = &messages.last().unwrap()["state"];
+    assert_eq!(state["paused"], true);
+    assert_eq!(state["pc"], 0x200);
+    let current = state["disassembly"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|line| line["address"] == 0x200)
+        .unwrap();
+    assert_eq!(current["mnemonic"], "LD I, 0x21E");
+    assert!(c8.paused);
+}
+
+#[test]
+fn run_cycles() {
+    let path = std::env::temp_dir().join("jchip8-test-run-cycles.json");
+    let config = Config {
+        run_cycles: Some(20_001),
+        final_state_path: Some(path.to_string_lossy().into_owned()),
+        ..headless_config()
+    };
+
+    // Exactly that many cycles run (far more than fit in a test, were they paced),
+    // and the resulting state is written out.
+    let program = [0x60, 0x00, 0x70, 0x01, 0x12, 0x02];
+    let mut c8 = Chip8::from_bytes(&program, &config).unwrap();
+    c8.run().unwrap();
+    assert!(c8.reached_run_limit());
+    assert_eq!(c8.cycles_run, 20_001);
+    let state: serde_json::Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+    assert_eq!(state["registers"][0], 10_000 % 256);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn explain() {
+    use crate::arch::explain;
+
+    assert_eq!(explain(0x8134), "add V3 to V1 with carry");
+    assert_eq!(explain(0x5121), "nothing: 0x5121 isn't an instruction");
+
+    // Each instruction run is explained along with what it changed.
+    let config = Config {
+        run_cycles: Some(6),
+        explain_rate: Some(2),
+        ..headless_config()
+    };
+    let program = [
+        0x60, 0x05, 0x61, 0x25, 0x80, 0x14, 0xA3, 0x00, 0xF0, 0x33, 0x30, 0x2A,
+    ];
+    let mut c8 = Chip8::from_bytes(&program, &config).unwrap();
+    let mut explanations = vec![];
+    for _ in 0..6 {
+        let (pc, index_reg) = (c8.pc, c8.index_reg);
+        c8.execute_instruction().unwrap();
+        explanations.push(c8.explanation(pc, index_reg));
+        c8.increment_pc();
+    }
+    assert_eq!(
+        explanations,
+        [
+            "0200  6005: set V0 to 0x05 → V0=0x05",
+            "0202  6125: set V1 to 0x25 → V1=0x25",
+            "0204  8014: add V1 to V0 with carry → V0=0x2A, VF=0",
+            "0206  A300: set I to 0x300 → I=0x300",
+            "0208  F033: store V0 in decimal at I (hundreds, tens, then ones) → memory[0x300]=00 04 02",
+            "020A  302A: skip the next instruction if V0 equals 0x2A → skipped",
+        ]
+    );
+
+    // Instructions reaching past the end of memory (which fail, writing nothing)
+    // are explained with what is there.
+    for (index_reg, explained) in [
+        (0xFFE, "memory[0xFFE]=00 00"),
+        (0xFFFF, "memory[0xFFFF] is past the end of memory"),
+    ] {
+        c8.index_reg = index_reg;
+        c8.opcode = Opcode::new(0xF033);
+        assert!(c8.execute_fetched().is_err());
+        assert!(c8.explanation(0x20C, index_reg).ends_with(explained));
+    }
+
+    // Explaining runs only so many instructions a second (of 60hz frames).
+    let mut c8 = Chip8::from_bytes(&program, &config).unwrap();
+    let mut progress = c8.start_running().unwrap();
+    for _ in 0..60 {
+        c8.run_next_frame(&mut progress);
+    }
+    assert_eq!(c8.count, 2);
+}
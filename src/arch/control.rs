@@ -0,0 +1,62 @@
+use crate::gfx::Screen;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// A command a host application sends to a running emulator (see `Chip8::control()`).
+/// Commands are carried out once per frame, in the order they were sent.
+#[derive(Debug)]
+pub enum ControlCommand {
+    /// Stop running instructions (and the timers), until resumed.
+    Pause,
+    Resume,
+    /// Go back to how the machine was when it started running.
+    Reset,
+    /// Replace the machine's state with a saved one (in any `StateFormat`).
+    LoadState(Vec<u8>),
+    /// Save the machine's state, sending it back (see `ControlEvent::StateSaved`).
+    SaveState,
+    /// Run this many instructions per second.
+    SetSpeed(u64),
+    /// Stop running, as if the user quit.
+    Quit,
+}
+
+/// Something a running emulator tells its host application about.
+#[derive(Debug)]
+pub enum ControlEvent {
+    /// The screen was drawn (as of this many 60hz frames into the run).
+    FrameRendered { frame: u64, screen: Screen },
+    /// The machine's state, as asked for by `ControlCommand::SaveState`
+    /// (in the machine's `StateFormat`).
+    StateSaved(Vec<u8>),
+    /// A command couldn't be carried out, and why.
+    CommandFailed(String),
+}
+
+/// The emulator's ends of a host application's control over it:
+/// where it receives commands from, and sends events to.
+pub struct Control {
+    commands: Receiver<ControlCommand>,
+    events: Sender<ControlEvent>,
+}
+
+impl Control {
+    /// Connect a new control, returning the host's ends of it
+    /// (to send commands with, and to receive events from).
+    pub fn new() -> (Control, Sender<ControlCommand>, Receiver<ControlEvent>) {
+        let (command_sender, commands) = mpsc::channel();
+        let (events, event_receiver) = mpsc::channel();
+        let control = Control { commands, events };
+        (control, command_sender, event_receiver)
+    }
+
+    /// Every command sent since the last poll (without waiting for any).
+    pub fn poll(&self) -> Vec<ControlCommand> {
+        self.commands.try_iter().collect()
+    }
+
+    /// Tell the host about something.
+    /// (A host that stopped listening is just no longer told.)
+    pub fn send(&self, event: ControlEvent) {
+        let _ = self.events.send(event);
+    }
+}
@@ -0,0 +1,109 @@
+use super::disassemble;
+use serde::Serialize;
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Error, Write};
+use std::panic;
+use std::sync::Once;
+
+// How many of the latest instructions crash dumps list.
+const CRASH_HISTORY_LENGTH: usize = 32;
+
+static INSTALL_HOOK: Once = Once::new();
+
+thread_local! {
+    // What the latest panic on this thread said, and where it happened.
+    static LAST_PANIC: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Install a panic hook that remembers what each panic said (and where it happened),
+/// for crash dumps to report, before carrying on to the hook that was there before
+/// (which prints it, by default). Installing it again does nothing.
+pub fn install_panic_hook() {
+    INSTALL_HOOK.call_once(|| {
+        let previous_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let message = match info.location() {
+                Some(location) => format!("{} (at {location})", payload_message(info.payload())),
+                None => payload_message(info.payload()),
+            };
+            LAST_PANIC.with(|last_panic| *last_panic.borrow_mut() = Some(message));
+            previous_hook(info);
+        }));
+    });
+}
+
+fn payload_message(payload: &(dyn Any + Send)) -> String {
+    // Panics almost always carry a message (as either kind of string).
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        String::from(*message)
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        String::from("(no message)")
+    }
+}
+
+/// The latest instructions run (where each was, and what it was), oldest first,
+/// kept for crash dumps to list.
+#[derive(Default)]
+pub struct RecentInstructions(VecDeque<(u16, u16)>);
+
+impl RecentInstructions {
+    /// Remember an instruction as it's run (forgetting the oldest, once there are enough).
+    pub fn push(&mut self, pc: u16, opcode: u16) {
+        if self.0.len() == CRASH_HISTORY_LENGTH {
+            self.0.pop_front();
+        }
+        self.0.push_back((pc, opcode));
+    }
+}
+
+#[derive(Serialize)]
+struct RecentInstruction {
+    pc: String,
+    opcode: String,
+    instruction: String,
+}
+
+#[derive(Serialize)]
+struct CrashDump<'a, T: Serialize> {
+    // What the panic said (and where it happened).
+    panic: String,
+    recent_instructions: Vec<RecentInstruction>,
+    // The machine's state (which loads like any saved state, once taken out of here).
+    state: &'a T,
+}
+
+/// Write what just crashed the emulator to a file (as JSON): what the panic said,
+/// the latest instructions run, and the machine's state.
+pub fn write_crash_dump<T: Serialize>(
+    path: &str,
+    state: &T,
+    recent_instructions: &RecentInstructions,
+    payload: &(dyn Any + Send),
+) -> Result<(), Error> {
+    let panic = LAST_PANIC
+        .with(|last_panic| last_panic.borrow_mut().take())
+        .unwrap_or_else(|| payload_message(payload));
+    let recent_instructions = recent_instructions
+        .0
+        .iter()
+        .map(|&(pc, opcode)| RecentInstruction {
+            pc: format!("{pc:04X}"),
+            opcode: format!("{opcode:04X}"),
+            instruction: disassemble(opcode),
+        })
+        .collect();
+    let dump = CrashDump {
+        panic,
+        recent_instructions,
+        state,
+    };
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(&mut writer, &dump).map_err(Error::other)?;
+    writer.flush()
+}
@@ -1,4 +1,70 @@
-/// A trait that describes a simple emulated device's behavior.
+use super::chip8::Chip8;
+use super::RunStats;
+use crate::config::Config;
+use crate::error::Chip8Error;
+use crate::gfx::Screen;
+use std::io::{Error, Write};
+
+/// A trait that describes a simple emulated device's behavior:
+/// everything the binary (and programs using the emulator as a library) need
+/// to run a game, load it, and read back how it went.
+///
+/// `Chip8` is the only machine implementing it: SUPER-CHIP games run on it too,
+/// as a `Variant` with the extra instructions of `SchipInstructionSet`,
+/// rather than on a machine of their own (hence errors being `Chip8Error`s).
 pub trait Emulator {
-    fn run(&mut self); // Returns when game or user quits.
+    /// Run the emulated device a 60hz frame at a time (a frame's worth of instructions,
+    /// then reading input, updating the timers, and drawing, once each),
+    /// returning when the game or user quits
+    /// (or an error if the game runs an instruction that can't run).
+    fn run(&mut self) -> Result<(), Chip8Error>;
+
+    /// Emulate one cycle of our operation
+    /// (fetch, execute, draw, read input, and update timers).
+    /// Returns false if we decided to stop,
+    /// or an error if the instruction couldn't run.
+    fn emulate_cycle(&mut self) -> Result<bool, Chip8Error>;
+
+    /// Emulate one 60hz frame just as `run()` does: execute every instruction
+    /// belonging to it, then read input, update the timers, and draw, once each.
+    /// Returns true if the display was drawn during the frame,
+    /// or an error if an instruction couldn't run (stopping running there).
+    ///
+    /// Unlike `run()`, this doesn't wait for the frame to be due,
+    /// leaving pacing to the caller. The run finishes (writing out anything
+    /// asked for once running stops) on the frame where the game stops.
+    /// (Paused machines run no instructions, and their timers stand still.)
+    fn run_frame(&mut self) -> Result<bool, Chip8Error>;
+
+    /// Load a game from its bytes (without a saved state,
+    /// but with an already-initialized machine),
+    /// titled after where it came from.
+    fn load_rom(&mut self, title: &str, contents: &[u8]) -> Result<(), Chip8Error>;
+
+    /// The screen as drawn by the emulated program so far.
+    fn screen(&self) -> &Screen;
+
+    /// Whether the emulated program has exited (and should not be run any further).
+    fn has_exited(&self) -> bool;
+
+    /// Whether the emulated program has halted (looping forever doing nothing),
+    /// and running stopped because of it.
+    fn has_halted(&self) -> bool;
+
+    /// Statistics about running so far (instructions executed, frames drawn,
+    /// time spent emulating and waiting, and states saved).
+    fn run_stats(&self) -> &RunStats;
+
+    /// Whether running stopped because it reached the most cycles
+    /// (or frames) it was allowed to run for.
+    fn reached_run_limit(&self) -> bool;
+
+    /// Save the game's state (which the machine can load again later) to any writer.
+    fn write_state(&self, writer: &mut dyn Write) -> Result<(), Error>;
+}
+
+/// Create the machine to play the configured game (or load the configured state) on.
+/// (Every `Variant`, SUPER-CHIP included, runs on `Chip8`.)
+pub fn new_emulator(config: &Config) -> Result<Box<dyn Emulator>, Chip8Error> {
+    Ok(Box::new(Chip8::new(config)?))
 }
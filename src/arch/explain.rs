@@ -0,0 +1,112 @@
+use super::Opcode;
+
+/// Explain what an instruction (given as its two bytes, big-endian) does, in words
+/// (e.g. `8134` as "add V3 to V1 with carry"), for following along with a game as it runs.
+pub fn explain(value: u16) -> String {
+    let opcode = Opcode::new(value);
+    let (x, y, nnn) = (opcode.xreg, opcode.yreg, opcode.literal);
+    let (kk, n) = (value & 0xFF, value & 0xF);
+    match (value >> 12, kk) {
+        (0x0, 0xE0) => String::from("clear the screen"),
+        (0x0, 0xEE) => String::from("return from the current subroutine"),
+        (0x0, 0xC0..=0xCF) => format!("scroll the screen down {n} pixels"),
+        (0x0, 0xFB) => String::from("scroll the screen right 4 pixels"),
+        (0x0, 0xFC) => String::from("scroll the screen left 4 pixels"),
+        (0x0, 0xFD) => String::from("exit the interpreter"),
+        (0x0, 0xFE) => String::from("switch to low resolution (64x32)"),
+        (0x0, 0xFF) => String::from("switch to high resolution (128x64)"),
+        (0x1, _) => format!("jump to 0x{nnn:03X}"),
+        (0x2, _) => format!("call the subroutine at 0x{nnn:03X}"),
+        (0x3, _) => format!("skip the next instruction if V{x:X} equals 0x{kk:02X}"),
+        (0x4, _) => format!("skip the next instruction unless V{x:X} equals 0x{kk:02X}"),
+        (0x5, _) if n == 0 => format!("skip the next instruction if V{x:X} equals V{y:X}"),
+        (0x6, _) => format!("set V{x:X} to 0x{kk:02X}"),
+        (0x7, _) => format!("add 0x{kk:02X} to V{x:X} (without carry)"),
+        (0x8, _) => match n {
+            0x0 => format!("set V{x:X} to V{y:X}"),
+            0x1 => format!("set V{x:X} to V{x:X} OR V{y:X}"),
+            0x2 => format!("set V{x:X} to V{x:X} AND V{y:X}"),
+            0x3 => format!("set V{x:X} to V{x:X} XOR V{y:X}"),
+            0x4 => format!("add V{y:X} to V{x:X} with carry"),
+            0x5 => format!("subtract V{y:X} from V{x:X} with borrow"),
+            0x6 => format!("shift V{x:X} right a bit, keeping the bit shifted out"),
+            0x7 => format!("set V{x:X} to V{y:X} minus V{x:X} with borrow"),
+            0xE => format!("shift V{x:X} left a bit, keeping the bit shifted out"),
+            _ => not_an_instruction(value),
+        },
+        (0x9, _) if n == 0 => format!("skip the next instruction unless V{x:X} equals V{y:X}"),
+        (0xA, _) => format!("set I to 0x{nnn:03X}"),
+        (0xB, _) => format!("jump to 0x{nnn:03X} plus V0"),
+        (0xC, _) => format!("set V{x:X} to a random number AND 0x{kk:02X}"),
+        (0xD, _) if n == 0 => format!("draw the 16x16 sprite at I at (V{x:X}, V{y:X})"),
+        (0xD, _) => format!("draw the {n}-row sprite at I at (V{x:X}, V{y:X})"),
+        (0xE, 0x9E) => format!("skip the next instruction if the key in V{x:X} is pressed"),
+        (0xE, 0xA1) => format!("skip the next instruction unless the key in V{x:X} is pressed"),
+        (0xF, 0x07) => format!("set V{x:X} to the delay timer"),
+        (0xF, 0x0A) => format!("wait for a key press, and put the key in V{x:X}"),
+        (0xF, 0x15) => format!("set the delay timer to V{x:X}"),
+        (0xF, 0x18) => format!("set the sound timer to V{x:X}"),
+        (0xF, 0x1E) => format!("add V{x:X} to I"),
+        (0xF, 0x29) => format!("point I at the font's sprite for the digit in V{x:X}"),
+        (0xF, 0x30) => format!("point I at the large font's sprite for the digit in V{x:X}"),
+        (0xF, 0x33) => format!("store V{x:X} in decimal at I (hundreds, tens, then ones)"),
+        (0xF, 0x55) => format!("store V0 through V{x:X} in memory at I"),
+        (0xF, 0x65) => format!("load V0 through V{x:X} from memory at I"),
+        (0xF, 0x75) => format!("save V0 through V{x:X} to the flag registers"),
+        (0xF, 0x85) => format!("load V0 through V{x:X} from the flag registers"),
+        _ => not_an_instruction(value),
+    }
+}
+
+fn not_an_instruction(value: u16) -> String {
+    format!("nothing: 0x{value:04X} isn't an instruction")
+}
+
+/// Something an instruction changes, to show once it has run.
+#[derive(Debug, PartialEq)]
+pub enum Effect {
+    // A register (or V0 up to one), or the flag register (VF).
+    Register(usize),
+    Registers(usize),
+    Flag,
+    Index,
+    DelayTimer,
+    SoundTimer,
+    // Bytes of memory from where I pointed before the instruction ran.
+    Memory(u16),
+    // Where the instruction went, or whether it skipped the next one.
+    Jump,
+    Skip,
+}
+
+/// What an instruction changes (besides the screen), in the order to show it.
+pub fn effects(value: u16) -> Vec<Effect> {
+    let x = Opcode::new(value).xreg;
+    // (Instructions that set VF as a flag show it once, even when VF is also their target.)
+    let with_flag = |x| {
+        if x == 0xF {
+            vec![Effect::Flag]
+        } else {
+            vec![Effect::Register(x), Effect::Flag]
+        }
+    };
+    match (value >> 12, value & 0xFF) {
+        (0x0, 0xEE) | (0x1, _) | (0x2, _) | (0xB, _) => vec![Effect::Jump],
+        (0x3 | 0x4, _) | (0xE, 0x9E | 0xA1) => vec![Effect::Skip],
+        (0x5 | 0x9, kk) if kk & 0xF == 0 => vec![Effect::Skip],
+        (0x6 | 0x7 | 0xC, _) | (0xF, 0x07 | 0x0A) => vec![Effect::Register(x)],
+        (0x8, kk) => match kk & 0xF {
+            0x0..=0x3 => vec![Effect::Register(x)],
+            0x4..=0x7 | 0xE => with_flag(x),
+            _ => vec![],
+        },
+        (0xA, _) | (0xF, 0x1E | 0x29 | 0x30) => vec![Effect::Index],
+        (0xD, _) => vec![Effect::Flag],
+        (0xF, 0x15) => vec![Effect::DelayTimer],
+        (0xF, 0x18) => vec![Effect::SoundTimer],
+        (0xF, 0x33) => vec![Effect::Memory(3)],
+        (0xF, 0x55) => vec![Effect::Memory(x as u16 + 1)],
+        (0xF, 0x65 | 0x85) => vec![Effect::Registers(x)],
+        _ => vec![],
+    }
+}
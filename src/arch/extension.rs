@@ -0,0 +1,117 @@
+use crate::error::Chip8Error;
+use crate::gfx::Screen;
+use std::str::FromStr;
+
+/// Handles the instructions an extension adds (see `Chip8::add_opcode_handler()`),
+/// returning why one couldn't run, if it couldn't.
+pub type OpcodeHandler = Box<dyn FnMut(&mut ExtensionContext) -> Result<(), Chip8Error>>;
+
+/// Which instructions an extension handles: those whose hex digits match a pattern,
+/// where each digit is either fixed or a wildcard.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OpcodePattern {
+    // Which bits are fixed, and what they're fixed to.
+    mask: u16,
+    value: u16,
+}
+
+impl OpcodePattern {
+    /// A pattern matching the instructions whose bits under the mask equal the value's.
+    pub fn new(mask: u16, value: u16) -> OpcodePattern {
+        OpcodePattern {
+            mask,
+            value: value & mask,
+        }
+    }
+
+    /// Whether an instruction matches the pattern.
+    pub fn matches(self, opcode: u16) -> bool {
+        opcode & self.mask == self.value
+    }
+}
+
+impl FromStr for OpcodePattern {
+    type Err = String;
+
+    /// Parse a pattern written like instructions are documented: four digits,
+    /// each either a hex digit or any other letter (for example, `5XY2` or `FN01`).
+    fn from_str(pattern: &str) -> Result<OpcodePattern, String> {
+        if pattern.chars().count() != 4 || !pattern.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(format!(
+                "'{pattern}' is not an instruction pattern (four hex digits or letters)"
+            ));
+        }
+        let (mut mask, mut value) = (0, 0);
+        for digit in pattern.chars() {
+            mask <<= 4;
+            value <<= 4;
+            if let Some(digit) = digit.to_digit(16) {
+                mask |= 0xF;
+                value |= digit as u16;
+            }
+        }
+        Ok(OpcodePattern::new(mask, value))
+    }
+}
+
+/// What an extension's handler can see and change of the machine,
+/// while it runs one of the extension's instructions.
+pub struct ExtensionContext<'a> {
+    /// The instruction being run.
+    pub opcode: u16,
+    /// Where the instruction is.
+    pub pc: u16,
+    pub registers: &'a mut [u8; 16],
+    pub index_reg: &'a mut u16,
+    pub delay_timer: &'a mut u8,
+    pub sound_timer: &'a mut u8,
+    pub memory: &'a mut [u8; 4096],
+    pub screen: &'a mut Screen,
+    // How far the PC moves once the instruction is done (past it, by default),
+    // or where it jumps to instead.
+    pub(super) pc_step: u16,
+    pub(super) jump: Option<u16>,
+    // Whether the screen changed.
+    pub(super) redraw: bool,
+}
+
+impl ExtensionContext<'_> {
+    /// The 'X' register named in the instruction (its second digit).
+    pub fn x(&self) -> usize {
+        usize::from(self.opcode >> 8 & 0xF)
+    }
+
+    /// The 'Y' register named in the instruction (its third digit).
+    pub fn y(&self) -> usize {
+        usize::from(self.opcode >> 4 & 0xF)
+    }
+
+    /// The instruction's last three digits (an address), two digits (a byte),
+    /// or digit (a nibble).
+    pub fn nnn(&self) -> u16 {
+        self.opcode & 0xFFF
+    }
+
+    pub fn kk(&self) -> u8 {
+        self.opcode as u8
+    }
+
+    pub fn n(&self) -> u8 {
+        self.opcode as u8 & 0xF
+    }
+
+    /// Skip the next instruction (once this one is done).
+    pub fn skip_next(&mut self) {
+        self.pc_step = 4;
+    }
+
+    /// Jump somewhere (instead of moving on to the next instruction).
+    pub fn jump(&mut self, address: u16) {
+        self.jump = Some(address);
+    }
+
+    /// Have the screen drawn again, after changing it.
+    pub fn redraw(&mut self) {
+        self.redraw = true;
+    }
+}
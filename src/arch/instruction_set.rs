@@ -48,4 +48,24 @@ pub trait InstructionSet {
 
     // Save state handling.
     fn save_state(&mut self);
+    fn load_state(&mut self);
+}
+
+/// A trait that describes the SUPER-CHIP extensions to the CHIP-8 instruction set,
+/// for machines that run SCHIP programs as well.
+pub trait SchipInstructionSet: InstructionSet {
+    // Graphics controls.
+    fn set_index_reg_to_large_sprite(&mut self);
+    fn scroll_down(&mut self);
+    fn scroll_left(&mut self);
+    fn scroll_right(&mut self);
+    fn low_resolution(&mut self);
+    fn high_resolution(&mut self);
+
+    // Control flow.
+    fn exit(&mut self);
+
+    // Context switching.
+    fn save_registers_to_flags(&mut self);
+    fn restore_registers_from_flags(&mut self);
 }
@@ -0,0 +1,64 @@
+use serde::Deserialize;
+
+/// How a memory search narrows down its candidates: to bytes holding a value,
+/// or to bytes that changed (or didn't, or went up or down) since the last search.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchCondition {
+    Equal,
+    Changed,
+    Unchanged,
+    Increased,
+    Decreased,
+}
+
+/// A search through memory for the bytes a game keeps something in
+/// (such as its lives or score), by narrowing down every candidate byte
+/// search after search, as the game runs (a cheat finder).
+pub struct MemorySearch {
+    // The addresses still in the running.
+    candidates: Vec<usize>,
+    // Memory as of the last search (to compare against).
+    previous: Vec<u8>,
+}
+
+impl MemorySearch {
+    /// Start a search, with every byte of memory from the given address onward a candidate.
+    pub fn new(memory: &[u8], start: usize) -> MemorySearch {
+        MemorySearch {
+            candidates: (start..memory.len()).collect(),
+            previous: memory.to_vec(),
+        }
+    }
+
+    /// Keep only the candidates meeting the condition
+    /// (comparing against `value` for `Equal`, which needs one).
+    pub fn narrow(
+        &mut self,
+        memory: &[u8],
+        condition: SearchCondition,
+        value: Option<u8>,
+    ) -> Result<(), String> {
+        if condition == SearchCondition::Equal && value.is_none() {
+            return Err(String::from("Searching for a value needs the value."));
+        }
+        let previous = &self.previous;
+        self.candidates.retain(|&address| {
+            let (before, now) = (previous[address], memory[address]);
+            match condition {
+                SearchCondition::Equal => Some(now) == value,
+                SearchCondition::Changed => now != before,
+                SearchCondition::Unchanged => now == before,
+                SearchCondition::Increased => now > before,
+                SearchCondition::Decreased => now < before,
+            }
+        });
+        self.previous.copy_from_slice(memory);
+        Ok(())
+    }
+
+    /// The addresses still in the running, in order.
+    pub fn candidates(&self) -> &[usize] {
+        &self.candidates
+    }
+}
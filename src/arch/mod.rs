@@ -1,8 +1,53 @@
+mod bench;
 pub mod chip8;
+mod control;
+mod crash;
 mod emulator;
+mod explain;
+mod extension;
 mod instruction_set;
+#[cfg(feature = "scripting")]
+mod memory_search;
+#[cfg(feature = "recorder")]
+mod movie;
 mod opcode;
+mod opcode_stats;
+mod quirks;
+#[cfg(feature = "scripting")]
+mod remote;
+mod rom;
+mod run_stats;
+mod state_format;
+mod timing;
+#[cfg(all(feature = "debugger", feature = "scripting"))]
+mod web_debugger;
 
-pub use emulator::Emulator;
-use instruction_set::InstructionSet;
+pub use bench::BenchReport;
+pub use control::{Control, ControlCommand, ControlEvent};
+pub use crash::install_panic_hook;
+use crash::{write_crash_dump, RecentInstructions};
+pub use emulator::{new_emulator, Emulator};
+pub use explain::explain;
+use explain::{effects, Effect};
+pub use extension::{ExtensionContext, OpcodeHandler, OpcodePattern};
+use instruction_set::{InstructionSet, SchipInstructionSet};
+#[cfg(feature = "scripting")]
+pub use memory_search::{MemorySearch, SearchCondition};
+#[cfg(feature = "recorder")]
+pub use movie::Movie;
+pub use opcode::disassemble;
 use opcode::Opcode;
+pub use opcode_stats::OpcodeStats;
+pub use quirks::{Profile, Quirk, Quirks, Variant};
+#[cfg(feature = "scripting")]
+pub use remote::{RemoteCommand, RemoteControl};
+pub use rom::{apply_ips, read_rom, rom_exists};
+pub use run_stats::RunStats;
+#[cfg(feature = "cbor")]
+pub use state_format::Cbor;
+#[cfg(feature = "msgpack")]
+pub use state_format::MessagePack;
+pub use state_format::{Json, StateEncoding, StateFormat};
+pub use timing::{OpcodeClass, OpcodeCosts};
+#[cfg(all(feature = "debugger", feature = "scripting"))]
+pub use web_debugger::WebDebugger;
@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufWriter, Error, ErrorKind, Write};
+
+// The number of keys on the keypad.
+const KEYPAD_SIZE: usize = 16;
+
+/// A recording of every keypad state the emulated program saw, cycle by cycle,
+/// along with the random seed it started with. Playing a movie back from
+/// the start of the same game reproduces the recorded run exactly.
+///
+/// Movies also hold hashes of the machine's state taken every so often while recording
+/// (checkpoints), so that playing one back can verify that it really does.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Movie {
+    // The seed the random number generator started with.
+    pub seed: u64,
+    // The keypad states, stored as runs of identical states:
+    // (how many cycles the state lasted, the state as a bitmask of pressed keys).
+    inputs: Vec<(u64, u16)>,
+    // The checkpoints: (the cycle each was taken on, the hash of the machine's state then).
+    // (Movies recorded before checkpoints existed have none.)
+    #[serde(default)]
+    checkpoints: Vec<(u64, String)>,
+
+    // Where playback is up to: the current run, and how many cycles into it.
+    #[serde(skip)]
+    position: (usize, u64),
+}
+
+impl Movie {
+    /// Start a new (empty) movie, for a run starting with the given seed.
+    pub fn new(seed: u64) -> Movie {
+        Movie {
+            seed,
+            ..Default::default()
+        }
+    }
+
+    /// Load a movie from disk.
+    pub fn from_file(file_path: &str) -> Result<Movie, Error> {
+        let contents = fs::read(file_path)?;
+        serde_json::from_slice(&contents).map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "Movie path does not appear to point to a valid movie!",
+            )
+        })
+    }
+
+    /// Save the movie to disk (where it can be played back later).
+    pub fn to_file(&self, to_file_path: &str) -> Result<(), Error> {
+        let mut writer = BufWriter::new(fs::File::create(to_file_path)?);
+        serde_json::to_writer(&mut writer, self).map_err(Error::other)?;
+        writer.flush()
+    }
+
+    /// Add the keypad state seen during the next cycle to the end of the movie.
+    pub fn record(&mut self, keys: &[bool; KEYPAD_SIZE]) {
+        let mask = keys
+            .iter()
+            .enumerate()
+            .filter(|(_, pressed)| **pressed)
+            .fold(0, |mask, (key, _)| mask | (1 << key));
+
+        match self.inputs.last_mut() {
+            Some((cycles, last_mask)) if *last_mask == mask => *cycles += 1,
+            _ => self.inputs.push((1, mask)),
+        }
+    }
+
+    /// The keypad state recorded for the next cycle,
+    /// or None if the movie has finished playing.
+    pub fn play(&mut self) -> Option<[bool; KEYPAD_SIZE]> {
+        let (run, offset) = self.position;
+        let (cycles, mask) = *self.inputs.get(run)?;
+
+        self.position = if offset + 1 < cycles {
+            (run, offset + 1)
+        } else {
+            (run + 1, 0)
+        };
+        Some(std::array::from_fn(|key| mask & (1 << key) != 0))
+    }
+
+    /// Whether every recorded cycle has been played back.
+    pub fn finished(&self) -> bool {
+        self.position.0 >= self.inputs.len()
+    }
+
+    /// Add a checkpoint: the hash of the machine's state on the given cycle.
+    pub fn add_checkpoint(&mut self, cycle: u64, hash: String) {
+        self.checkpoints.push((cycle, hash));
+    }
+
+    /// The hash of the machine's state recorded on the given cycle, if one was.
+    pub fn checkpoint(&self, cycle: u64) -> Option<&str> {
+        self.checkpoints
+            .binary_search_by_key(&cycle, |(checkpoint, _)| *checkpoint)
+            .ok()
+            .map(|index| self.checkpoints[index].1.as_str())
+    }
+
+    /// How many checkpoints were recorded.
+    pub fn checkpoint_count(&self) -> usize {
+        self.checkpoints.len()
+    }
+
+    /// The total number of cycles recorded.
+    pub fn len(&self) -> u64 {
+        self.inputs.iter().map(|(cycles, _)| cycles).sum()
+    }
+
+    /// Whether no cycles have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+}
@@ -42,6 +42,73 @@ impl Opcode {
             literal,
         }
     }
+
+    /// The instruction in assembly form (e.g. `LD V1, 0x2A`), for debuggers,
+    /// or `DW` (a data word) if it isn't an instruction.
+    /// (SUPER-CHIP instructions are always shown as such.)
+    pub fn mnemonic(&self) -> String {
+        let (x, y, nnn) = (self.xreg, self.yreg, self.literal);
+        let (kk, n) = (self.value & 0xFF, self.value & 0xF);
+        match (self.value >> 12, kk) {
+            (0x0, 0xE0) => String::from("CLS"),
+            (0x0, 0xEE) => String::from("RET"),
+            (0x0, 0xC0..=0xCF) => format!("SCD {n}"),
+            (0x0, 0xFB) => String::from("SCR"),
+            (0x0, 0xFC) => String::from("SCL"),
+            (0x0, 0xFD) => String::from("EXIT"),
+            (0x0, 0xFE) => String::from("LOW"),
+            (0x0, 0xFF) => String::from("HIGH"),
+            (0x1, _) => format!("JP 0x{nnn:03X}"),
+            (0x2, _) => format!("CALL 0x{nnn:03X}"),
+            (0x3, _) => format!("SE V{x:X}, 0x{kk:02X}"),
+            (0x4, _) => format!("SNE V{x:X}, 0x{kk:02X}"),
+            (0x5, _) if n == 0 => format!("SE V{x:X}, V{y:X}"),
+            (0x6, _) => format!("LD V{x:X}, 0x{kk:02X}"),
+            (0x7, _) => format!("ADD V{x:X}, 0x{kk:02X}"),
+            (0x8, _) => match n {
+                0x0 => format!("LD V{x:X}, V{y:X}"),
+                0x1 => format!("OR V{x:X}, V{y:X}"),
+                0x2 => format!("AND V{x:X}, V{y:X}"),
+                0x3 => format!("XOR V{x:X}, V{y:X}"),
+                0x4 => format!("ADD V{x:X}, V{y:X}"),
+                0x5 => format!("SUB V{x:X}, V{y:X}"),
+                0x6 => format!("SHR V{x:X}, V{y:X}"),
+                0x7 => format!("SUBN V{x:X}, V{y:X}"),
+                0xE => format!("SHL V{x:X}, V{y:X}"),
+                _ => self.data_word(),
+            },
+            (0x9, _) if n == 0 => format!("SNE V{x:X}, V{y:X}"),
+            (0xA, _) => format!("LD I, 0x{nnn:03X}"),
+            (0xB, _) => format!("JP V0, 0x{nnn:03X}"),
+            (0xC, _) => format!("RND V{x:X}, 0x{kk:02X}"),
+            (0xD, _) => format!("DRW V{x:X}, V{y:X}, {n}"),
+            (0xE, 0x9E) => format!("SKP V{x:X}"),
+            (0xE, 0xA1) => format!("SKNP V{x:X}"),
+            (0xF, 0x07) => format!("LD V{x:X}, DT"),
+            (0xF, 0x0A) => format!("LD V{x:X}, K"),
+            (0xF, 0x15) => format!("LD DT, V{x:X}"),
+            (0xF, 0x18) => format!("LD ST, V{x:X}"),
+            (0xF, 0x1E) => format!("ADD I, V{x:X}"),
+            (0xF, 0x29) => format!("LD F, V{x:X}"),
+            (0xF, 0x30) => format!("LD HF, V{x:X}"),
+            (0xF, 0x33) => format!("LD B, V{x:X}"),
+            (0xF, 0x55) => format!("LD [I], V{x:X}"),
+            (0xF, 0x65) => format!("LD V{x:X}, [I]"),
+            (0xF, 0x75) => format!("LD R, V{x:X}"),
+            (0xF, 0x85) => format!("LD V{x:X}, R"),
+            _ => self.data_word(),
+        }
+    }
+
+    fn data_word(&self) -> String {
+        format!("DW 0x{:04X}", self.value)
+    }
+}
+
+/// Disassemble an instruction (given as its two bytes, big-endian) into assembly form
+/// (e.g. `612A` into `LD V1, 0x2A`), or into `DW` (a data word) if it isn't one.
+pub fn disassemble(value: u16) -> String {
+    Opcode::new(value).mnemonic()
 }
 
 impl Display for Opcode {
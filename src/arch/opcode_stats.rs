@@ -0,0 +1,50 @@
+use super::disassemble;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// How many times each instruction ran, by where it was and what it was
+/// (an address can hold several, over a run, in self-modifying programs).
+#[derive(Debug, Default)]
+pub struct OpcodeStats {
+    counts: BTreeMap<(u16, u16), u64>,
+}
+
+impl OpcodeStats {
+    /// Count an instruction run.
+    pub fn count(&mut self, pc: u16, opcode: u16) {
+        *self.counts.entry((pc, opcode)).or_default() += 1;
+    }
+
+    /// How many times the instruction at an address ran (whatever it was).
+    pub fn at_address(&self, pc: u16) -> u64 {
+        self.counts
+            .range((pc, 0)..=(pc, u16::MAX))
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    /// How many times an instruction ran (wherever it was).
+    pub fn of_opcode(&self, opcode: u16) -> u64 {
+        self.counts
+            .iter()
+            .filter(|((_, counted), _)| *counted == opcode)
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    /// The counts as CSV, a row per address and instruction (in order of address),
+    /// with the instruction disassembled: per-address and per-opcode counts are then
+    /// a group-by away in a spreadsheet or dataframe.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("address,opcode,instruction,count\n");
+        for ((pc, opcode), count) in &self.counts {
+            // (Instructions are quoted, since their operands are separated by commas.)
+            let _ = writeln!(
+                csv,
+                "{pc:04X},{opcode:04X},\"{}\",{count}",
+                disassemble(*opcode)
+            );
+        }
+        csv
+    }
+}
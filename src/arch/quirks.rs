@@ -0,0 +1,105 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// The family of interpreters an emulated program was written for,
+/// which determines the instructions available to it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize, ValueEnum)]
+pub enum Variant {
+    /// The original COSMAC VIP interpreter
+    #[serde(alias = "chip8")]
+    Chip8,
+    /// SUPER-CHIP (adds scrolling, large fonts, RPL flags, and exiting)
+    #[default]
+    #[serde(alias = "schip")]
+    Schip,
+}
+
+impl Variant {
+    /// Whether or not the SUPER-CHIP instructions are available.
+    pub fn has_schip(&self) -> bool {
+        matches!(self, Variant::Schip)
+    }
+}
+
+/// A single behavior that differs between interpreters.
+/// (In settings files, these are named just like on the command line.)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Quirk {
+    /// 8XY6/8XYE shift VY into VX (instead of shifting VX in place)
+    ShiftUsesVy,
+    /// FX55/FX65 leave the index register pointing past the last register used
+    LoadStoreIncrementsIndex,
+    /// BNNN jumps to NNN + VX (X being the highest digit of NNN) instead of NNN + V0
+    JumpUsesVx,
+    /// 8XY1/8XY2/8XY3 reset VF to 0
+    LogicResetsVf,
+    /// Sprites wrap around the edges of the screen instead of being clipped
+    WrapSprites,
+}
+
+/// A well-known interpreter, standing for its variant and all of its quirks at once.
+/// (In ROM databases, these are named just like on the command line.)
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+    /// The original COSMAC VIP CHIP-8 interpreter
+    Chip8,
+    /// SUPER-CHIP 1.1 (on the HP-48)
+    Schip,
+    /// XO-CHIP's quirks (its extra instructions aren't supported, so SUPER-CHIP's are used)
+    Xochip,
+}
+
+impl Profile {
+    /// The variant this interpreter belongs to.
+    pub fn variant(self) -> Variant {
+        match self {
+            Profile::Chip8 => Variant::Chip8,
+            Profile::Schip | Profile::Xochip => Variant::Schip,
+        }
+    }
+
+    /// Whether each quirk is enabled (true) or disabled (false) on this interpreter.
+    pub fn quirks(self) -> [(Quirk, bool); 5] {
+        let (shift_uses_vy, load_store_increments_index, jump_uses_vx, logic_resets_vf, wrap) =
+            match self {
+                Profile::Chip8 => (true, true, false, true, false),
+                Profile::Schip => (false, false, true, false, false),
+                Profile::Xochip => (true, true, false, false, true),
+            };
+        [
+            (Quirk::ShiftUsesVy, shift_uses_vy),
+            (Quirk::LoadStoreIncrementsIndex, load_store_increments_index),
+            (Quirk::JumpUsesVx, jump_uses_vx),
+            (Quirk::LogicResetsVf, logic_resets_vf),
+            (Quirk::WrapSprites, wrap),
+        ]
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+/// A struct describing which quirks (behaviors that differ between interpreters)
+/// are enabled. By default, none are.
+pub struct Quirks {
+    pub shift_uses_vy: bool,
+    pub load_store_increments_index: bool,
+    pub jump_uses_vx: bool,
+    pub logic_resets_vf: bool,
+    pub wrap_sprites: bool,
+}
+
+impl Quirks {
+    /// Enable or disable a single quirk.
+    pub fn set(&mut self, quirk: Quirk, enabled: bool) {
+        let flag = match quirk {
+            Quirk::ShiftUsesVy => &mut self.shift_uses_vy,
+            Quirk::LoadStoreIncrementsIndex => &mut self.load_store_increments_index,
+            Quirk::JumpUsesVx => &mut self.jump_uses_vx,
+            Quirk::LogicResetsVf => &mut self.logic_resets_vf,
+            Quirk::WrapSprites => &mut self.wrap_sprites,
+        };
+        *flag = enabled;
+    }
+}
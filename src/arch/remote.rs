@@ -0,0 +1,228 @@
+use super::SearchCondition;
+use log::{debug, info, warn};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Error, ErrorKind, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+// How long a reply waits for a client that isn't reading before giving up on it.
+const SEND_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A command sent by a remote client, one JSON object per line, named by its
+/// `command` field (e.g. `{"command": "read-memory", "address": 512, "length": 16}`).
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum RemoteCommand {
+    // Stop running instructions (and the timers), until resumed.
+    Pause,
+    Resume,
+    // Run some instructions (one, unless given), whether paused or not.
+    Step {
+        count: Option<u64>,
+    },
+    // Go back to before some instructions (one, unless given), or to the start of some frames,
+    // pausing there. (Only the latest instructions run are kept to go back to.)
+    StepBack {
+        count: Option<u64>,
+    },
+    FrameBack {
+        count: Option<u64>,
+    },
+    // Start another game from scratch.
+    LoadRom {
+        path: String,
+    },
+    // Read some memory (as hex).
+    ReadMemory {
+        address: usize,
+        length: usize,
+    },
+    // Capture the screen (as a PBM image).
+    Screenshot,
+    // Start searching memory over (see `MemorySearch`), and narrow the search down.
+    SearchReset,
+    Search {
+        condition: SearchCondition,
+        value: Option<u8>,
+    },
+    // Change a byte of memory once, or keep it at a value (every frame) until unfrozen.
+    WriteMemory {
+        address: usize,
+        value: u8,
+    },
+    Freeze {
+        address: usize,
+        value: u8,
+    },
+    Unfreeze {
+        address: usize,
+    },
+}
+
+// A connected client, and the line it's partway through sending.
+struct Client {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    line: Vec<u8>,
+}
+
+/// A server letting external tools, scripts, or a web UI drive a running emulator
+/// over TCP, with a small line-based JSON protocol: each line sent is a `RemoteCommand`,
+/// answered by one line holding `"ok": true` (and whatever the command returns),
+/// or `"ok": false` and an `"error"`.
+///
+/// The server never blocks: the emulator polls it for commands once per frame.
+pub struct RemoteControl {
+    listener: TcpListener,
+    // Connected clients (by the ID each was given when connecting).
+    clients: BTreeMap<u64, Client>,
+    next_id: u64,
+}
+
+impl RemoteControl {
+    /// Start listening for clients on the given address.
+    pub fn new(address: &str) -> Result<RemoteControl, Error> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        info!(
+            "Listening for remote control on {}.",
+            listener.local_addr()?
+        );
+        Ok(RemoteControl {
+            listener,
+            clients: BTreeMap::new(),
+            next_id: 0,
+        })
+    }
+
+    /// Where clients can connect.
+    pub fn address(&self) -> Result<String, Error> {
+        Ok(self.listener.local_addr()?.to_string())
+    }
+
+    /// Accept any new clients, and read every command sent since the last poll,
+    /// along with which client sent it (to answer with `reply()`).
+    /// Lines that aren't commands are answered with an error here.
+    pub fn poll(&mut self) -> Vec<(u64, RemoteCommand)> {
+        while let Ok((stream, address)) = self.listener.accept() {
+            match Self::connect(stream) {
+                Ok(client) => {
+                    debug!("Remote client {address} connected.");
+                    self.clients.insert(self.next_id, client);
+                    self.next_id += 1;
+                }
+                Err(err) => warn!("Failed to accept remote client {address}: {err}"),
+            }
+        }
+
+        let mut commands = Vec::new();
+        let mut disconnected = Vec::new();
+        for (id, client) in &mut self.clients {
+            loop {
+                match client.reader.read_until(b'\n', &mut client.line) {
+                    // (The client hung up.)
+                    Ok(0) => {
+                        disconnected.push(*id);
+                        break;
+                    }
+                    Ok(_) if client.line.ends_with(b"\n") => {
+                        let line = std::mem::take(&mut client.line);
+                        match serde_json::from_slice(&line) {
+                            Ok(command) => commands.push((*id, command)),
+                            Err(err) => {
+                                let reply = Err(format!("Not a command: {err}"));
+                                if Self::send(&mut client.writer, reply).is_err() {
+                                    disconnected.push(*id);
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                    // (The rest of the line hasn't arrived yet.)
+                    Ok(_) => (),
+                    Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                    Err(_) => {
+                        disconnected.push(*id);
+                        break;
+                    }
+                }
+            }
+        }
+        // (Clients that hung up after sending commands are kept until the next poll,
+        // so that they're still answered, since they may only have stopped sending.)
+        disconnected.retain(|id| commands.iter().all(|(client, _)| client != id));
+        for id in disconnected {
+            debug!("Remote client {id} disconnected.");
+            self.clients.remove(&id);
+        }
+        commands
+    }
+
+    /// Answer a client's command with what it returned (an object, whose fields
+    /// are added to the reply), or with why it failed.
+    /// (Clients that have since disconnected are skipped.)
+    pub fn reply(&mut self, client: u64, result: Result<Value, String>) {
+        if let Some(writer) = self
+            .clients
+            .get_mut(&client)
+            .map(|client| &mut client.writer)
+        {
+            if let Err(err) = Self::send(writer, result) {
+                debug!("Failed to answer remote client {client}: {err}");
+                self.clients.remove(&client);
+            }
+        }
+    }
+
+    fn connect(stream: TcpStream) -> Result<Client, Error> {
+        stream.set_nonblocking(true)?;
+        Ok(Client {
+            writer: stream.try_clone()?,
+            reader: BufReader::new(stream),
+            line: Vec::new(),
+        })
+    }
+
+    fn send(writer: &mut TcpStream, result: Result<Value, String>) -> Result<(), Error> {
+        let mut bytes = reply_to_json(result).to_string().into_bytes();
+        bytes.push(b'\n');
+        write_whole(writer, &bytes)
+    }
+}
+
+/// Write all of the given bytes to a connection that doesn't block,
+/// waiting a little while if the client hasn't kept up with reading
+/// (and giving up on it if it still hasn't).
+pub fn write_whole(writer: &mut TcpStream, bytes: &[u8]) -> Result<(), Error> {
+    let mut written = 0;
+    let started = Instant::now();
+    while written < bytes.len() {
+        match writer.write(&bytes[written..]) {
+            Ok(0) => return Err(Error::from(ErrorKind::WriteZero)),
+            Ok(count) => written += count,
+            Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                if started.elapsed() > SEND_TIMEOUT {
+                    return Err(err);
+                }
+                std::thread::yield_now();
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+/// The reply to a command, given what it returned (an object, whose fields
+/// are added to the reply) or why it failed.
+pub fn reply_to_json(result: Result<Value, String>) -> Value {
+    match result {
+        Ok(Value::Object(mut fields)) => {
+            fields.insert(String::from("ok"), Value::Bool(true));
+            Value::Object(fields)
+        }
+        Ok(_) => serde_json::json!({ "ok": true }),
+        Err(error) => serde_json::json!({ "ok": false, "error": error }),
+    }
+}
@@ -0,0 +1,160 @@
+use std::fs;
+#[cfg(feature = "zip")]
+use std::io::Read;
+use std::io::{Error, ErrorKind};
+#[cfg(feature = "zip")]
+use std::path::Path;
+
+// What IPS patches start and end with.
+const IPS_HEADER: &[u8] = b"PATCH";
+const IPS_FOOTER: &[u8] = b"EOF";
+
+// The extension of ZIP archives (ignoring case), and what separates an archive's path
+// from the name of the game to load from it (as in `games.zip#PONG.ch8`).
+#[cfg(feature = "zip")]
+const ZIP_EXTENSION: &str = "zip";
+#[cfg(feature = "zip")]
+const ZIP_ENTRY_SEPARATOR: char = '#';
+// The extension games picked out of archives are expected to have (ignoring case).
+#[cfg(feature = "zip")]
+const GAME_EXTENSION: &str = "ch8";
+
+/// Read a game's contents from its path: either a plain file, or (with the `zip` feature)
+/// a game inside a ZIP archive, given as `path/to/games.zip#GAME` (or just `path/to/games.zip`,
+/// when the archive holds a single game).
+pub fn read_rom(path: &str) -> Result<Vec<u8>, Error> {
+    #[cfg(feature = "zip")]
+    if let Some((archive, entry)) = split_zip_path(path) {
+        return read_zip_rom(archive, entry);
+    }
+    fs::read(path)
+}
+
+/// Whether the game at a path (as given to `read_rom()`) still exists
+/// (for archives, only the archive itself is checked).
+pub fn rom_exists(path: &str) -> bool {
+    #[cfg(feature = "zip")]
+    if let Some((archive, _)) = split_zip_path(path) {
+        return Path::new(archive).is_file();
+    }
+    std::path::Path::new(path).is_file()
+}
+
+/// Apply an IPS patch (the format ROM hacks and translations are distributed in)
+/// to a game's contents, growing them if the patch writes past their end.
+///
+/// A patch is `PATCH`, then records of a 3-byte offset and a 2-byte length (both big-endian)
+/// followed by that many bytes to write there (or, for a length of 0, a 2-byte count and
+/// a byte to write that many times), then `EOF`, optionally followed by a 3-byte length
+/// to truncate the contents to.
+pub fn apply_ips(contents: &mut Vec<u8>, patch: &[u8]) -> Result<(), Error> {
+    let invalid = || Error::new(ErrorKind::InvalidData, "The IPS patch is not valid.");
+    let mut rest = patch.strip_prefix(IPS_HEADER).ok_or_else(invalid)?;
+    let mut take = |count: usize| -> Result<&[u8], Error> {
+        let (taken, remaining) = rest.split_at_checked(count).ok_or_else(invalid)?;
+        rest = remaining;
+        Ok(taken)
+    };
+    let number = |bytes: &[u8]| {
+        bytes
+            .iter()
+            .fold(0, |number, byte| number << 8 | usize::from(*byte))
+    };
+
+    loop {
+        let offset = take(3)?;
+        if offset == IPS_FOOTER {
+            break;
+        }
+        let offset = number(offset);
+        let (length, fill) = match number(take(2)?) {
+            0 => {
+                let run = take(3)?;
+                (number(&run[..2]), Some(run[2]))
+            }
+            length => (length, None),
+        };
+        if contents.len() < offset + length {
+            contents.resize(offset + length, 0);
+        }
+        match fill {
+            Some(byte) => contents[offset..offset + length].fill(byte),
+            None => contents[offset..offset + length].copy_from_slice(take(length)?),
+        }
+    }
+    if let Ok(length) = take(3) {
+        contents.truncate(number(length));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "zip")]
+fn split_zip_path(path: &str) -> Option<(&str, Option<&str>)> {
+    // Split a path into the archive's path and the name of a game inside it, if it names a ZIP
+    // archive. (Files named with a '#' are only split if what comes before it is an archive.)
+    let is_zip = |path: &str| {
+        Path::new(path)
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case(ZIP_EXTENSION))
+    };
+    if let Some((archive, entry)) = path.rsplit_once(ZIP_ENTRY_SEPARATOR) {
+        if is_zip(archive) {
+            return Some((archive, Some(entry)));
+        }
+    }
+    is_zip(path).then_some((path, None))
+}
+
+#[cfg(feature = "zip")]
+fn read_zip_rom(archive_path: &str, entry: Option<&str>) -> Result<Vec<u8>, Error> {
+    // Read a game out of a ZIP archive: the named one, or otherwise the only game in it.
+    let mut archive = zip::ZipArchive::new(fs::File::open(archive_path)?)?;
+    let name = match entry {
+        Some(entry) => entry.to_string(),
+        None => {
+            let files: Vec<&str> = archive
+                .file_names()
+                .filter(|name| !name.ends_with('/'))
+                .collect();
+            let games: Vec<&str> = files
+                .iter()
+                .copied()
+                .filter(|name| {
+                    Path::new(name)
+                        .extension()
+                        .is_some_and(|extension| extension.eq_ignore_ascii_case(GAME_EXTENSION))
+                })
+                .collect();
+            // (Archives holding a single file are taken to hold a game, whatever it's called.)
+            let candidates = if games.is_empty() { files } else { games };
+            match candidates[..] {
+                [name] => name.to_string(),
+                [] => {
+                    return Err(Error::new(
+                        ErrorKind::NotFound,
+                        format!("'{archive_path}' does not contain any games."),
+                    ))
+                }
+                _ => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "'{archive_path}' contains several games, so please pick one with '{archive_path}{ZIP_ENTRY_SEPARATOR}<game>': {}",
+                            candidates.join(", ")
+                        ),
+                    ))
+                }
+            }
+        }
+    };
+
+    let mut file = archive.by_name(&name).map_err(|_| {
+        Error::new(
+            ErrorKind::NotFound,
+            format!("'{archive_path}' does not contain '{name}'."),
+        )
+    })?;
+    let mut contents = Vec::new();
+    file.read_to_end(&mut contents)?;
+    Ok(contents)
+}
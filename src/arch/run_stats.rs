@@ -0,0 +1,53 @@
+use std::fmt;
+use std::time::Duration;
+
+/// Statistics about a run (of `Emulator::run()`), for users tuning the speed
+/// and for spotting performance regressions.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RunStats {
+    // How many instructions ran, and how many frames were drawn.
+    pub instructions: u64,
+    pub frames_drawn: u64,
+    // How long running took in all, and how much of that was spent waiting
+    // (to keep to the emulated speed) rather than emulating.
+    pub elapsed: Duration,
+    pub sleeping: Duration,
+    // How many states were saved.
+    pub states_saved: u64,
+}
+
+impl RunStats {
+    /// How many instructions ran per second, on average.
+    pub fn instructions_per_second(&self) -> u64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            (self.instructions as f64 / seconds).round() as u64
+        } else {
+            0
+        }
+    }
+
+    /// How long was spent emulating (rather than waiting).
+    pub fn executing(&self) -> Duration {
+        self.elapsed.saturating_sub(self.sleeping)
+    }
+}
+
+impl fmt::Display for RunStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Instructions executed: {}", self.instructions)?;
+        writeln!(f, "Frames drawn: {}", self.frames_drawn)?;
+        writeln!(
+            f,
+            "Average speed: {} instructions per second",
+            self.instructions_per_second()
+        )?;
+        writeln!(
+            f,
+            "Time executing: {:.3}s (sleeping: {:.3}s)",
+            self.executing().as_secs_f64(),
+            self.sleeping.as_secs_f64()
+        )?;
+        write!(f, "States saved: {}", self.states_saved)
+    }
+}
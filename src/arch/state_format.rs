@@ -0,0 +1,133 @@
+use crate::error::Chip8Error;
+use clap::ValueEnum;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::error::Error as StdError;
+use std::io::{Error, Write};
+use std::path::Path;
+
+/// An encoding saved states can be written in (and read back from),
+/// for a whole machine or just a snapshot of one.
+pub trait StateEncoding {
+    /// Write a value in this encoding.
+    fn write<T: Serialize, W: Write>(value: &T, writer: W) -> Result<(), Error>;
+
+    /// Read a value back, or why the contents don't hold one.
+    fn read<T: DeserializeOwned>(contents: &[u8]) -> Result<T, Box<dyn StdError + Send + Sync>>;
+}
+
+/// JSON: human-readable (and easy to inspect or edit by hand), but the largest.
+pub struct Json;
+
+impl StateEncoding for Json {
+    fn write<T: Serialize, W: Write>(value: &T, mut writer: W) -> Result<(), Error> {
+        serde_json::to_writer(&mut writer, value).map_err(Error::other)?;
+        writer.flush()
+    }
+
+    fn read<T: DeserializeOwned>(contents: &[u8]) -> Result<T, Box<dyn StdError + Send + Sync>> {
+        Ok(serde_json::from_slice(contents)?)
+    }
+}
+
+/// CBOR: compact, and a standard (RFC 8949) with libraries for most languages.
+#[cfg(feature = "cbor")]
+pub struct Cbor;
+
+#[cfg(feature = "cbor")]
+impl StateEncoding for Cbor {
+    fn write<T: Serialize, W: Write>(value: &T, mut writer: W) -> Result<(), Error> {
+        ciborium::into_writer(value, &mut writer).map_err(Error::other)?;
+        writer.flush()
+    }
+
+    fn read<T: DeserializeOwned>(contents: &[u8]) -> Result<T, Box<dyn StdError + Send + Sync>> {
+        Ok(ciborium::from_reader(contents)?)
+    }
+}
+
+/// MessagePack: compact, and easy to read from JavaScript (for web tools).
+/// (Fields are written with their names, like in the other encodings,
+/// so that states keep loading as fields are added.)
+#[cfg(feature = "msgpack")]
+pub struct MessagePack;
+
+#[cfg(feature = "msgpack")]
+impl StateEncoding for MessagePack {
+    fn write<T: Serialize, W: Write>(value: &T, mut writer: W) -> Result<(), Error> {
+        rmp_serde::encode::write_named(&mut writer, value).map_err(Error::other)?;
+        writer.flush()
+    }
+
+    fn read<T: DeserializeOwned>(contents: &[u8]) -> Result<T, Box<dyn StdError + Send + Sync>> {
+        Ok(rmp_serde::from_slice(contents)?)
+    }
+}
+
+/// Which encoding saved states are written in.
+/// States are read back in whichever one they were written in, whatever it is.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum StateFormat {
+    /// JSON (human-readable)
+    #[default]
+    Json,
+    /// CBOR (compact)
+    #[cfg(feature = "cbor")]
+    Cbor,
+    /// MessagePack (compact, and web-friendly)
+    #[cfg(feature = "msgpack")]
+    #[value(name = "msgpack")]
+    MessagePack,
+}
+
+impl StateFormat {
+    /// The encoding a path's extension calls for
+    /// (`.cbor`, or `.msgpack` or `.mpk`), or JSON for any other path.
+    pub fn from_path(path: &str) -> StateFormat {
+        let extension = Path::new(path)
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+        match extension.as_deref() {
+            #[cfg(feature = "cbor")]
+            Some("cbor") => StateFormat::Cbor,
+            #[cfg(feature = "msgpack")]
+            Some("msgpack" | "mpk") => StateFormat::MessagePack,
+            _ => StateFormat::Json,
+        }
+    }
+
+    /// The encoding a saved state was written in, going by how it starts:
+    /// every state is a map, which each encoding starts differently.
+    pub fn detect(contents: &[u8]) -> StateFormat {
+        match contents.first() {
+            #[cfg(feature = "cbor")]
+            Some(0xA0..=0xBF) => StateFormat::Cbor,
+            #[cfg(feature = "msgpack")]
+            Some(0x80..=0x8F | 0xDE | 0xDF) => StateFormat::MessagePack,
+            _ => StateFormat::Json,
+        }
+    }
+
+    /// Write a value in this encoding.
+    pub fn write<T: Serialize, W: Write>(self, value: &T, writer: W) -> Result<(), Error> {
+        match self {
+            StateFormat::Json => Json::write(value, writer),
+            #[cfg(feature = "cbor")]
+            StateFormat::Cbor => Cbor::write(value, writer),
+            #[cfg(feature = "msgpack")]
+            StateFormat::MessagePack => MessagePack::write(value, writer),
+        }
+    }
+
+    /// Read a value back from a saved state written in this encoding.
+    pub fn read<T: DeserializeOwned>(self, contents: &[u8]) -> Result<T, Chip8Error> {
+        match self {
+            StateFormat::Json => Json::read(contents),
+            #[cfg(feature = "cbor")]
+            StateFormat::Cbor => Cbor::read(contents),
+            #[cfg(feature = "msgpack")]
+            StateFormat::MessagePack => MessagePack::read(contents),
+        }
+        .map_err(Chip8Error::InvalidState)
+    }
+}
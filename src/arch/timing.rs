@@ -0,0 +1,103 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// A kind of instruction, for timing: on the original hardware, some kinds
+/// (drawing, above all) took much longer to run than others.
+/// (In settings files, these are named just like on the command line.)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum OpcodeClass {
+    /// 00E0 (clearing the screen)
+    Clear,
+    /// DXYN (drawing a sprite)
+    Draw,
+    /// 00CN/00FB/00FC (scrolling the screen)
+    Scroll,
+    /// Jumps, calls, returns, and skips
+    Flow,
+    /// FX33/FX55/FX65/FX75/FX85 (reading and writing memory, or the RPL flags)
+    Memory,
+    /// Everything else (arithmetic, loading registers, the timers, and the keypad)
+    Register,
+}
+
+impl OpcodeClass {
+    /// The kind of an instruction (instructions that don't exist count as register ones).
+    pub fn of(opcode: u16) -> OpcodeClass {
+        match (opcode >> 12, opcode & 0xFF) {
+            (0x0, 0xE0) => OpcodeClass::Clear,
+            (0x0, 0xC0..=0xCF | 0xFB | 0xFC) => OpcodeClass::Scroll,
+            (0x0, 0xEE) | (0x1..=0x5 | 0x9 | 0xB, _) | (0xE, 0x9E | 0xA1) => OpcodeClass::Flow,
+            (0xD, _) => OpcodeClass::Draw,
+            (0xF, 0x33 | 0x55 | 0x65 | 0x75 | 0x85) => OpcodeClass::Memory,
+            _ => OpcodeClass::Register,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+/// A table of how many cycles each kind of instruction takes to run, so that
+/// games run at the pace they did on hardware where some took longer than others.
+/// Every frame runs a speed's worth of cycles (see `--speed`), rather than of instructions.
+/// By default, every instruction takes a single cycle.
+pub struct OpcodeCosts {
+    pub clear: u64,
+    pub draw: u64,
+    pub scroll: u64,
+    pub flow: u64,
+    pub memory: u64,
+    pub register: u64,
+}
+
+impl Default for OpcodeCosts {
+    fn default() -> OpcodeCosts {
+        OpcodeCosts {
+            clear: 1,
+            draw: 1,
+            scroll: 1,
+            flow: 1,
+            memory: 1,
+            register: 1,
+        }
+    }
+}
+
+impl OpcodeCosts {
+    /// Set how many cycles a kind of instruction takes (at least one).
+    pub fn set(&mut self, class: OpcodeClass, cycles: u64) {
+        let cost = match class {
+            OpcodeClass::Clear => &mut self.clear,
+            OpcodeClass::Draw => &mut self.draw,
+            OpcodeClass::Scroll => &mut self.scroll,
+            OpcodeClass::Flow => &mut self.flow,
+            OpcodeClass::Memory => &mut self.memory,
+            OpcodeClass::Register => &mut self.register,
+        };
+        *cost = cycles.max(1);
+    }
+
+    /// How many cycles an instruction takes.
+    pub fn cycles(&self, opcode: u16) -> u64 {
+        match OpcodeClass::of(opcode) {
+            OpcodeClass::Clear => self.clear,
+            OpcodeClass::Draw => self.draw,
+            OpcodeClass::Scroll => self.scroll,
+            OpcodeClass::Flow => self.flow,
+            OpcodeClass::Memory => self.memory,
+            OpcodeClass::Register => self.register,
+        }
+    }
+
+    /// Parse a single cost of the form `<class>=<cycles>` (for example, `draw=4`).
+    pub fn parse_cost(cost: &str) -> Result<(OpcodeClass, u64), String> {
+        let (class, cycles) = cost
+            .split_once('=')
+            .ok_or_else(|| format!("'{cost}' is not of the form <class>=<cycles>"))?;
+        let class = OpcodeClass::from_str(class, true)?;
+        match cycles.trim().parse() {
+            Ok(cycles) if cycles > 0 => Ok((class, cycles)),
+            _ => Err(format!("'{cycles}' is not a number of cycles (at least 1)")),
+        }
+    }
+}
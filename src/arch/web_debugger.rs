@@ -0,0 +1,235 @@
+use super::remote::{reply_to_json, write_whole};
+use super::RemoteCommand;
+use log::{debug, info, warn};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind, Read};
+use std::net::{TcpListener, TcpStream};
+use tungstenite::handshake::derive_accept_key;
+use tungstenite::protocol::{Role, WebSocketConfig};
+use tungstenite::{Message, WebSocket};
+
+// The debugging page itself.
+const PAGE: &str = include_str!("web_debugger.html");
+// Where the page opens its WebSocket.
+const SOCKET_PATH: &str = "/socket";
+// The largest request (or WebSocket message) accepted from a client.
+const MAX_MESSAGE_SIZE: usize = 64 * 1024;
+// How much can be waiting to be sent to a page that isn't keeping up before it's dropped.
+const MAX_UNSENT_SIZE: usize = 4 * 1024 * 1024;
+
+// A connection whose HTTP request hasn't been answered yet
+// (and what it has sent so far).
+struct Connection {
+    stream: TcpStream,
+    received: Vec<u8>,
+}
+
+impl Connection {
+    fn receive(&mut self) -> Result<(), Error> {
+        // Read whatever has arrived, failing if the connection closed
+        // (or sent more than it should).
+        let mut buffer = [0; 4096];
+        loop {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => return Err(Error::from(ErrorKind::UnexpectedEof)),
+                Ok(count) => self.received.extend_from_slice(&buffer[..count]),
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(err) => return Err(err),
+            }
+        }
+        if self.received.len() > MAX_MESSAGE_SIZE {
+            return Err(Error::new(ErrorKind::InvalidData, "Message too large."));
+        }
+        Ok(())
+    }
+}
+
+/// A debugger in the browser: an HTTP server, serving a page that shows the running
+/// emulator's registers, disassembly, and screen live, with controls for pausing and
+/// stepping through it. The page gets the machine's state (as JSON) over a WebSocket,
+/// every frame, and sends the same commands as remote control clients (see `RemoteControl`)
+/// back over it, which are answered the same way.
+///
+/// Like `RemoteControl`, the server never blocks, and is polled once per frame.
+pub struct WebDebugger {
+    listener: TcpListener,
+    // Connections whose HTTP requests haven't been answered yet.
+    requests: Vec<Connection>,
+    // Connected WebSockets (by the ID each was given when connecting).
+    sockets: BTreeMap<u64, WebSocket<TcpStream>>,
+    next_id: u64,
+}
+
+impl WebDebugger {
+    /// Start serving the debugger on the given address.
+    pub fn new(address: &str) -> Result<WebDebugger, Error> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        info!("Serving the debugger at http://{}/", listener.local_addr()?);
+        Ok(WebDebugger {
+            listener,
+            requests: Vec::new(),
+            sockets: BTreeMap::new(),
+            next_id: 0,
+        })
+    }
+
+    /// Where the debugger is served.
+    pub fn address(&self) -> Result<String, Error> {
+        Ok(self.listener.local_addr()?.to_string())
+    }
+
+    /// Whether any page is connected (so there's anyone to send the machine's state to).
+    pub fn has_sockets(&self) -> bool {
+        !self.sockets.is_empty()
+    }
+
+    /// Answer any HTTP requests, and read every command sent by the pages connected
+    /// since the last poll, along with which page sent it (to answer with `reply()`).
+    pub fn poll(&mut self) -> Vec<(u64, RemoteCommand)> {
+        while let Ok((stream, address)) = self.listener.accept() {
+            debug!("Debugger client {address} connected.");
+            match stream.set_nonblocking(true) {
+                Ok(()) => self.requests.push(Connection {
+                    stream,
+                    received: Vec::new(),
+                }),
+                Err(err) => warn!("Failed to accept debugger client {address}: {err}"),
+            }
+        }
+
+        // (Connections are dropped, closing them, once answered or failed.)
+        let mut requests = Vec::new();
+        for mut request in std::mem::take(&mut self.requests) {
+            if request.receive().is_err() {
+                continue;
+            }
+            let Some(end) = find(&request.received, b"\r\n\r\n") else {
+                requests.push(request);
+                continue;
+            };
+            let head = String::from_utf8_lossy(&request.received[..end]).into_owned();
+            match self.answer(&mut request.stream, &head) {
+                Ok(true) => {
+                    // (Anything sent after the request is already part of the WebSocket.)
+                    let rest = request.received.split_off(end + 4);
+                    let config = WebSocketConfig::default()
+                        .max_message_size(Some(MAX_MESSAGE_SIZE))
+                        .max_write_buffer_size(MAX_UNSENT_SIZE);
+                    let socket = WebSocket::from_partially_read(
+                        request.stream,
+                        rest,
+                        Role::Server,
+                        Some(config),
+                    );
+                    self.sockets.insert(self.next_id, socket);
+                    self.next_id += 1;
+                }
+                Ok(false) => (),
+                Err(err) => debug!("Failed to answer a debugger request: {err}"),
+            }
+        }
+        self.requests = requests;
+
+        // (Pings and closes are answered by the WebSockets themselves.)
+        let mut commands = Vec::new();
+        let mut closed = Vec::new();
+        for (id, socket) in &mut self.sockets {
+            loop {
+                match socket.read() {
+                    Ok(Message::Text(text)) => match serde_json::from_str(&text) {
+                        Ok(command) => commands.push((*id, command)),
+                        Err(err) => {
+                            let reply = reply_to_json(Err(format!("Not a command: {err}")));
+                            let _ = send(socket, &reply);
+                        }
+                    },
+                    Ok(_) => (),
+                    Err(tungstenite::Error::Io(err)) if err.kind() == ErrorKind::WouldBlock => {
+                        break
+                    }
+                    Err(_) => {
+                        closed.push(*id);
+                        break;
+                    }
+                }
+            }
+        }
+        for id in closed {
+            debug!("Debugger page {id} disconnected.");
+            self.sockets.remove(&id);
+        }
+        commands
+    }
+
+    /// Answer a page's command with what it returned, or with why it failed
+    /// (the same way remote control clients are answered).
+    pub fn reply(&mut self, socket: u64, result: Result<Value, String>) {
+        self.send(socket, &reply_to_json(result));
+    }
+
+    /// Send the machine's state to every page connected.
+    pub fn broadcast(&mut self, state: &Value) {
+        let ids: Vec<u64> = self.sockets.keys().copied().collect();
+        let message = serde_json::json!({ "state": state });
+        for id in ids {
+            self.send(id, &message);
+        }
+    }
+
+    fn send(&mut self, id: u64, message: &Value) {
+        if let Some(socket) = self.sockets.get_mut(&id) {
+            if let Err(err) = send(socket, message) {
+                debug!("Failed to send to debugger page {id}: {err}");
+                self.sockets.remove(&id);
+            }
+        }
+    }
+
+    fn answer(&self, stream: &mut TcpStream, head: &str) -> Result<bool, Error> {
+        // Answer an HTTP request, returning whether it opened a WebSocket.
+        let path = head.split_whitespace().nth(1).unwrap_or_default();
+        let key = head.lines().find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.eq_ignore_ascii_case("sec-websocket-key")
+                .then(|| value.trim())
+        });
+        let response = match (path, key) {
+            ("/", _) => format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\n\
+                 Content-Length: {}\r\nConnection: close\r\n\r\n{PAGE}",
+                PAGE.len()
+            ),
+            (SOCKET_PATH, Some(key)) => {
+                let accept = derive_accept_key(key.as_bytes());
+                let response = format!(
+                    "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\
+                     Connection: Upgrade\r\nSec-WebSocket-Accept: {accept}\r\n\r\n"
+                );
+                write_whole(stream, response.as_bytes())?;
+                return Ok(true);
+            }
+            _ => String::from(
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            ),
+        };
+        write_whole(stream, response.as_bytes())?;
+        Ok(false)
+    }
+}
+
+fn find(bytes: &[u8], pattern: &[u8]) -> Option<usize> {
+    bytes
+        .windows(pattern.len())
+        .position(|window| window == pattern)
+}
+
+fn send(socket: &mut WebSocket<TcpStream>, message: &Value) -> Result<(), tungstenite::Error> {
+    // Send a message (or leave it to be sent along with the next one,
+    // if the connection can't take it yet).
+    match socket.send(Message::text(message.to_string())) {
+        Err(tungstenite::Error::Io(err)) if err.kind() == ErrorKind::WouldBlock => Ok(()),
+        result => result,
+    }
+}
@@ -0,0 +1,849 @@
+use crate::arch::{read_rom, rom_exists, OpcodeClass, Quirk, StateFormat, Variant};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_with::{DeserializeFromStr, SerializeDisplay};
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+// The extension appended to a game's path to derive its default save state path.
+const STATE_EXTENSION: &str = "state";
+// The extension appended to a game's path to derive its default memory dump path.
+const MEMORY_DUMP_EXTENSION: &str = "mem";
+// The extension appended to a game's path to derive its default GIF recording path.
+const GIF_EXTENSION: &str = "gif";
+// The extension appended to a game's path to derive its default audio recording path.
+const WAV_EXTENSION: &str = "wav";
+// The extension appended to a game's path to derive its default crash dump path.
+const CRASH_DUMP_EXTENSION: &str = "crash.json";
+// Where settings persisted between runs are kept (inside the user's config directory).
+const SETTINGS_DIR: &str = "jchip8-rs";
+const SETTINGS_FILE: &str = "settings.json";
+// How many recently played games are remembered.
+const MAX_RECENT_GAMES: usize = 10;
+/// The game path that stands for standard input (so games can be piped in).
+pub const STDIN_PATH: &str = "-";
+/// The fastest speed (in instructions per second) that instructions can be timed at,
+/// each taking a nanosecond. Faster speeds are clamped to it.
+pub const MAX_SPEED: u64 = 1_000_000_000;
+// Where games written for the ETI-660 (whose interpreter is larger) start.
+const ETI_660_START_ADDRESS: u16 = 0x600;
+// The lowest and highest addresses games can start at
+// (past the fonts, and with room for at least one instruction).
+const MIN_START_ADDRESS: u16 = 0x200;
+const MAX_START_ADDRESS: u16 = 0xFFE;
+
+/// The frontends the emulator can be presented with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum Frontend {
+    /// A window, with SDL (with the `sdl` feature)
+    #[cfg(feature = "sdl")]
+    #[default]
+    Sdl,
+    /// The terminal, drawn with block characters (with the `tui` feature)
+    #[cfg(feature = "tui")]
+    Terminal,
+    /// A window, with minifb (with the `minifb` feature): no gamepads, or sound without `cpal`
+    #[cfg(feature = "minifb")]
+    Minifb,
+    /// A window, with winit and pixels (with the `pixels` feature): no gamepads, or sound without `cpal`
+    #[cfg(feature = "pixels")]
+    Pixels,
+    /// A window, drawn with wgpu (with the `wgpu` feature): no gamepads, or sound without `cpal`
+    #[cfg(feature = "wgpu")]
+    Wgpu,
+    /// No window, sound, or input at all (for servers, CI, and scripted analysis)
+    #[cfg_attr(not(feature = "sdl"), default)]
+    Headless,
+}
+
+/// How far (clockwise) the displayed image is rotated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum Rotation {
+    #[default]
+    #[value(name = "0")]
+    None,
+    #[value(name = "90")]
+    Clockwise90,
+    #[value(name = "180")]
+    Clockwise180,
+    #[value(name = "270")]
+    Clockwise270,
+}
+
+/// Which axis the displayed image is mirrored across.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Flip {
+    #[value(name = "h")]
+    Horizontal,
+    #[value(name = "v")]
+    Vertical,
+}
+
+/// The available render filters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum RenderStyle {
+    /// Plain, flat pixels
+    #[default]
+    Flat,
+    /// Pixels extruded into shaded 3D blocks
+    Depth,
+}
+
+/// The shapes of wave the buzzer's tone can be made of.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum Waveform {
+    /// A harsh, classic beep
+    #[default]
+    Square,
+    /// A softer, hollow tone
+    Triangle,
+    /// A pure, gentle tone
+    Sine,
+    /// Pitched static, like a hiss
+    Noise,
+}
+
+/// A color, given as its red, green, and blue components.
+/// (In settings files, colors are written in hex, just like on the command line.)
+#[derive(Clone, Copy, Debug, PartialEq, SerializeDisplay, DeserializeFromStr)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl fmt::Display for Rgb {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{:02X}{:02X}{:02X}", self.0, self.1, self.2)
+    }
+}
+
+impl FromStr for Rgb {
+    type Err = String;
+
+    /// Parse a hex color, such as `#33FF66` (the leading `#` is optional).
+    fn from_str(hex: &str) -> Result<Rgb, String> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        let invalid = || format!("'{hex}' is not a hex color (such as #33FF66)");
+        // (Checking every digit, since parsing each component alone would allow a sign.)
+        if digits.len() != 6 || !digits.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+
+        let component =
+            |index: usize| u8::from_str_radix(&digits[index..index + 2], 16).map_err(|_| invalid());
+        Ok(Rgb(component(0)?, component(2)?, component(4)?))
+    }
+}
+
+/// The colors that screens are drawn with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Palette {
+    // The color of set pixels.
+    pub foreground: Rgb,
+    // The color of everything else.
+    pub background: Rgb,
+}
+
+impl Default for Palette {
+    fn default() -> Palette {
+        // White on black.
+        Palette {
+            foreground: Rgb(255, 255, 255),
+            background: Rgb(0, 0, 0),
+        }
+    }
+}
+
+/// The built-in color themes, which can be cycled through while running.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// White on black
+    #[default]
+    Classic,
+    /// Green on black, like a monochrome monitor
+    Green,
+    /// Amber on black, like a monochrome monitor
+    Amber,
+    /// Dark green on pale green, like an early handheld's LCD
+    Lcd,
+    /// Black on white
+    Paper,
+}
+
+impl Theme {
+    /// Every theme, in the order they're cycled through.
+    const ALL: [Theme; 5] = [
+        Theme::Classic,
+        Theme::Green,
+        Theme::Amber,
+        Theme::Lcd,
+        Theme::Paper,
+    ];
+
+    /// The colors this theme draws screens with.
+    pub fn palette(self) -> Palette {
+        let (foreground, background) = match self {
+            Theme::Classic => (Rgb(255, 255, 255), Rgb(0, 0, 0)),
+            Theme::Green => (Rgb(51, 255, 102), Rgb(0, 17, 0)),
+            Theme::Amber => (Rgb(255, 176, 0), Rgb(20, 12, 0)),
+            Theme::Lcd => (Rgb(15, 56, 15), Rgb(155, 188, 15)),
+            Theme::Paper => (Rgb(0, 0, 0), Rgb(255, 255, 255)),
+        };
+        Palette {
+            foreground,
+            background,
+        }
+    }
+
+    /// The theme after this one (wrapping around after the last).
+    pub fn next(self) -> Theme {
+        let index = Self::ALL
+            .iter()
+            .position(|theme| *theme == self)
+            .unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+/// Settings overriding how a single game is run, which the settings file
+/// can hold for each game (keyed by the game's file name, or by its SHA-1 hash in hex).
+/// Options given on the command line take precedence over these.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RomSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variant: Option<Variant>,
+    // Quirks to enable (true) or disable (false).
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub quirks: HashMap<Quirk, bool>,
+    // How many cycles kinds of instructions take (1, unless given).
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub opcode_costs: HashMap<OpcodeClass, u64>,
+    // How many instructions to run per second.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theme: Option<Theme>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub foreground: Option<Rgb>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<Rgb>,
+}
+
+/// Settings that persist between runs (such as the last theme selected).
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Settings {
+    // The theme most recently selected, if any.
+    #[serde(default)]
+    pub theme: Option<Theme>,
+    // The directory to list games from when no game is given, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rom_dir: Option<String>,
+    // Overrides for individual games (keyed by file name or SHA-1 hash).
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub roms: HashMap<String, RomSettings>,
+    // The (absolute) paths of the games played most recently, the latest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recent: Vec<String>,
+}
+
+impl Settings {
+    /// The path of the settings file: `jchip8-rs/settings.json` inside
+    /// `$XDG_CONFIG_HOME` (or `~/.config`, or `%APPDATA%` on Windows), if any.
+    pub fn path() -> Option<PathBuf> {
+        let config_dir = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+            .or_else(|| env::var_os("APPDATA").map(PathBuf::from))?;
+        Some(config_dir.join(SETTINGS_DIR).join(SETTINGS_FILE))
+    }
+
+    /// Load the persisted settings, falling back to defaults
+    /// if there are none (or they can't be read).
+    pub fn load() -> Settings {
+        Self::path()
+            .and_then(|path| fs::read(path).ok())
+            .and_then(|contents| serde_json::from_slice(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// The overrides for the game at the given path, if any
+    /// (matched by its file name first, and then by its hash).
+    pub fn rom_settings(&self, game_path: &str) -> Option<&RomSettings> {
+        // (A game read from standard input can't be read again here, so it never matches.)
+        if self.roms.is_empty() || game_path == STDIN_PATH {
+            return None;
+        }
+
+        let file_name = Path::new(game_path).file_name()?.to_string_lossy();
+        self.roms.get(file_name.as_ref()).or_else(|| {
+            let contents = read_rom(game_path).ok()?;
+            self.roms.get(&rom_hash(&contents))
+        })
+    }
+
+    /// Remember a game as the one played most recently (by its absolute path,
+    /// so that it can be found again from anywhere), forgetting the oldest
+    /// if too many are remembered.
+    pub fn add_recent(&mut self, game_path: &str) {
+        let game_path = std::path::absolute(game_path).map_or_else(
+            |_| game_path.to_string(),
+            |path| path.to_string_lossy().into_owned(),
+        );
+        self.recent.retain(|recent| *recent != game_path);
+        self.recent.insert(0, game_path);
+        self.recent.truncate(MAX_RECENT_GAMES);
+    }
+
+    /// The games played most recently that still exist, the latest first.
+    pub fn recent_games(&self) -> Vec<&str> {
+        self.recent
+            .iter()
+            .map(String::as_str)
+            .filter(|game| rom_exists(game))
+            .collect()
+    }
+
+    /// Persist these settings (creating the settings directory if needed).
+    pub fn save(&self) -> Result<(), Error> {
+        let path = Self::path().ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                "No config directory to save settings in",
+            )
+        })?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let contents = serde_json::to_vec_pretty(self)
+            .map_err(|error| Error::new(ErrorKind::InvalidData, error))?;
+        fs::write(path, contents)
+    }
+}
+
+/// The built-in keyboard layouts for the keypad.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum KeyLayout {
+    /// Each keypad key on the keyboard key with the same name (0-9, A-F)
+    #[default]
+    Hex,
+    /// The keypad's 4x4 grid on the 1234/QWER/ASDF/ZXCV block
+    Qwerty,
+    /// Directions (2/4/6/8) on WASD, 5 on Space, and the rest nearby
+    Wasd,
+}
+
+impl KeyLayout {
+    /// The names of the keyboard keys bound to each keypad key, in keypad order.
+    fn names(self) -> [&'static str; 16] {
+        match self {
+            KeyLayout::Hex => [
+                "0", "1", "2", "3", "4", "5", "6", "7", "8", "9", "A", "B", "C", "D", "E", "F",
+            ],
+            KeyLayout::Qwerty => [
+                "X", "1", "2", "3", "Q", "W", "E", "A", "S", "D", "Z", "C", "4", "R", "F", "V",
+            ],
+            KeyLayout::Wasd => [
+                "X", "Q", "W", "E", "A", "Space", "D", "Z", "S", "C", "1", "2", "3", "4", "R", "F",
+            ],
+        }
+    }
+}
+
+/// The names of the host keys bound to each of the 16 keypad keys (0-F).
+/// Names are those used by SDL (for example, "1", "Q", "Space", or "Keypad 5").
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyBindings {
+    keys: [String; 16],
+}
+
+impl Default for KeyBindings {
+    fn default() -> KeyBindings {
+        KeyBindings::from(KeyLayout::default())
+    }
+}
+
+impl From<KeyLayout> for KeyBindings {
+    fn from(layout: KeyLayout) -> KeyBindings {
+        KeyBindings {
+            keys: layout.names().map(String::from),
+        }
+    }
+}
+
+impl KeyBindings {
+    /// Parse a single binding of the form `<keypad key>=<host key>` (for example, `C=4`).
+    pub fn parse_binding(binding: &str) -> Result<(u8, String), String> {
+        let (key, name) = binding
+            .split_once('=')
+            .ok_or_else(|| format!("'{binding}' is not of the form <keypad key>=<host key>"))?;
+        let key = Self::parse_key(key)?;
+        Ok((key, String::from(name)))
+    }
+
+    fn parse_key(key: &str) -> Result<u8, String> {
+        match u8::from_str_radix(key.trim(), 16) {
+            Ok(key) if key < 16 => Ok(key),
+            _ => Err(format!("'{key}' is not a keypad key (0-F)")),
+        }
+    }
+
+    /// Load bindings from a JSON file mapping keypad keys to host keys
+    /// (for example, `{"1": "1", "C": "4", "4": "Q"}`).
+    /// Keypad keys missing from the file keep their current bindings.
+    pub fn load_file(&mut self, path: &str) -> Result<(), Error> {
+        let contents = fs::read(path)?;
+        let bindings: HashMap<String, String> = serde_json::from_slice(&contents)
+            .map_err(|error| Error::new(ErrorKind::InvalidInput, error))?;
+
+        for (key, name) in bindings {
+            let key = Self::parse_key(&key)
+                .map_err(|error| Error::new(ErrorKind::InvalidInput, error))?;
+            self.bind(key, &name);
+        }
+        Ok(())
+    }
+
+    /// Bind a keypad key to a host key (replacing its previous binding).
+    pub fn bind(&mut self, key: u8, name: &str) {
+        self.keys[usize::from(key)] = String::from(name);
+    }
+
+    /// The names of the host keys bound to each keypad key, in keypad order.
+    pub fn names(&self) -> &[String; 16] {
+        &self.keys
+    }
+}
+
+/// The gamepad buttons bound to keypad keys (0-F).
+/// Buttons are named as in SDL (for example, "a", "start", or "dpup").
+#[derive(Clone, Debug, PartialEq)]
+pub struct GamepadBindings {
+    buttons: Vec<(String, u8)>,
+}
+
+impl Default for GamepadBindings {
+    fn default() -> GamepadBindings {
+        // The D-pad moves (as most games use 2/4/6/8 for directions),
+        // and the face buttons cover the most common action keys.
+        let buttons = [
+            ("dpup", 0x2),
+            ("dpleft", 0x4),
+            ("dpright", 0x6),
+            ("dpdown", 0x8),
+            ("a", 0x5),
+            ("b", 0x0),
+            ("x", 0x1),
+            ("y", 0x3),
+            ("back", 0xA),
+            ("start", 0xB),
+        ];
+        GamepadBindings {
+            buttons: buttons
+                .iter()
+                .map(|(button, key)| (String::from(*button), *key))
+                .collect(),
+        }
+    }
+}
+
+impl GamepadBindings {
+    /// Parse a single binding of the form `<button>=<keypad key>` (for example, `a=5`).
+    pub fn parse_binding(binding: &str) -> Result<(String, u8), String> {
+        let (button, key) = binding
+            .split_once('=')
+            .ok_or_else(|| format!("'{binding}' is not of the form <button>=<keypad key>"))?;
+        let key = KeyBindings::parse_key(key)?;
+        Ok((String::from(button), key))
+    }
+
+    /// Bind a gamepad button to a keypad key (replacing its previous binding).
+    pub fn bind(&mut self, button: &str, key: u8) {
+        self.buttons.retain(|(bound, _)| bound != button);
+        self.buttons.push((String::from(button), key));
+    }
+
+    /// The names of all bound buttons, and the keypad key each is bound to.
+    pub fn buttons(&self) -> &[(String, u8)] {
+        &self.buttons
+    }
+}
+
+/// How to reach the other player, for netplay.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NetplayPeer {
+    // Wait for the other player to connect, on the given address.
+    Host(String),
+    // Connect to the other player, at the given address.
+    Join(String),
+}
+
+/// A struct describing all user-selected options
+/// that control how the emulator is started and presented.
+#[derive(Clone, Default)]
+pub struct Config {
+    // How the emulator is presented.
+    pub frontend: Frontend,
+    // Path to the game to load, if any.
+    pub game_path: Option<String>,
+    // The title to show for the game, if not its path (such as a known game's real title).
+    pub game_title: Option<String>,
+    // Directory to list games from when no game is given, if not the current directory.
+    pub rom_dir: Option<String>,
+    // Path of a game state to load, if any.
+    pub load_state_path: Option<String>,
+    // Path to save game states to (or overwrite), if any.
+    pub save_state_path: Option<String>,
+    // Directory to keep default save states in, if not next to the game.
+    pub state_dir: Option<String>,
+    // Whether to save the game state when the user quits.
+    pub save_on_exit: bool,
+    // The encoding to write game states in, if not the one their path calls for.
+    pub state_format: Option<StateFormat>,
+    // Path to dump the emulated memory to (or overwrite), if any.
+    pub memory_dump_path: Option<String>,
+    // Whether memory dumps only hold the program region (instead of all memory).
+    pub memory_dump_program_only: bool,
+    // Path to write the game state to once running stops (or - for standard output), if any.
+    pub final_state_path: Option<String>,
+    // Path to write a crash dump to if the emulator panics, if any.
+    pub crash_dump_path: Option<String>,
+
+    // Path to write the screen to (as a PBM image) once running stops, if any.
+    pub screen_dump_path: Option<String>,
+    // How many frames to also write the screen after, if any
+    // (to numbered files next to the screen dump path).
+    pub screen_dump_interval: Option<u64>,
+
+    // Run limit components (for headless runs).
+    // How many cycles to run for at most, if limited.
+    pub max_cycles: Option<u64>,
+    // How many 60hz frames (of emulated time) to run for at most, if limited.
+    pub max_frames: Option<u64>,
+    // How many cycles to run for exactly (as fast as possible, headless), if fixed:
+    // unlike a run limit, reaching it is how the run is meant to end.
+    pub run_cycles: Option<u64>,
+    // How many instructions to run per second while explaining each one as it runs
+    // (to standard error), if explaining.
+    pub explain_rate: Option<u64>,
+    // Whether to stop once the game halts (jumps to itself,
+    // or waits for a key when running headless).
+    pub stop_on_halt: bool,
+    // Whether to print statistics about the run once it's over.
+    pub print_stats: bool,
+    // Whether to print the screen (as text) once the run is over.
+    pub print_screen: bool,
+    // Path to write how many times each instruction ran (as CSV) once the run is over, if any.
+    pub opcode_stats_path: Option<String>,
+
+    // Behavior components.
+    // (When unset, these come from a loaded state, or are left at their defaults.)
+    // The interpreter to emulate.
+    pub variant: Option<Variant>,
+    // Quirks to enable (true) or disable (false), in order.
+    pub quirks: Vec<(Quirk, bool)>,
+    // How many cycles kinds of instructions take, in order.
+    pub opcode_costs: Vec<(OpcodeClass, u64)>,
+    // How many instructions to run per second.
+    pub speed: Option<u64>,
+    // The seed for the random number generator.
+    pub seed: Option<u64>,
+    // Where to load the game and start running it, if not the usual 0x200.
+    pub start_address: Option<u16>,
+    // Whether the game is a raw image of all of memory (such as a memory dump),
+    // instead of a program.
+    pub memory_image: bool,
+    // An IPS patch to apply to the game before starting it, if any.
+    pub ips_path: Option<String>,
+
+    // Recording components.
+    // Path to record a movie (of all keypad input) to, if any.
+    pub record_movie_path: Option<String>,
+    // Path of a movie to play back, if any.
+    pub play_movie_path: Option<String>,
+    // Whether to verify that playing the movie back reproduces the run it recorded
+    // (stopping at the first cycle that doesn't, or once it finishes).
+    pub verify_movie: bool,
+    // Path to save GIF recordings to (or overwrite), if any.
+    pub gif_path: Option<String>,
+    // Path to save audio recordings to (or overwrite), if any.
+    pub wav_path: Option<String>,
+
+    // Netplay components.
+    // The other player to play with, if any.
+    pub netplay: Option<NetplayPeer>,
+    // How many frames to delay both players' input by (so that it can reach the other player in time).
+    pub netplay_delay: u32,
+
+    // Remote control components.
+    // The address to listen for remote control clients on, if any.
+    pub remote_address: Option<String>,
+    // The address to serve the debugger in the browser on, if any.
+    pub web_debugger_address: Option<String>,
+
+    // Audio components.
+    // The pitch of the buzzer (in hz).
+    pub beep_frequency: u32,
+    // How loud the buzzer is (as a percentage).
+    pub beep_volume: u32,
+    // The shape of the buzzer's wave.
+    pub beep_waveform: Waveform,
+
+    // Input components.
+    // Which host keys the keypad keys are bound to.
+    pub key_bindings: KeyBindings,
+    // Which keypad keys gamepad buttons are bound to.
+    pub gamepad_bindings: GamepadBindings,
+    // Whether to pause as soon as the first frame has been drawn.
+    pub start_paused: bool,
+    // Whether to keep running while the window is unfocused (instead of pausing).
+    pub keep_running_unfocused: bool,
+    // How many frames to delay keypad changes by (for testing poor input conditions).
+    pub input_latency: u32,
+    // The maximum number of extra frames to randomly delay keypad changes by.
+    pub input_jitter: u32,
+
+    // Display components.
+    // How many window pixels wide (and tall) each screen pixel starts out, if not the default.
+    pub scale: Option<u32>,
+    // How far to rotate the displayed image (clockwise).
+    pub rotation: Rotation,
+    // Which axis to mirror the displayed image across, if any.
+    pub flip: Option<Flip>,
+    // How many times larger the magnifier draws pixels.
+    pub zoom: u32,
+    // Whether to only scale the image by whole multiples when the window is resized.
+    pub integer_scaling: bool,
+    // Whether to synchronize drawing with the display's refresh.
+    pub vsync: bool,
+    // Whether to draw (and read input) on a thread of its own, apart from emulation.
+    pub render_thread: bool,
+    // How set pixels are drawn.
+    pub render_style: RenderStyle,
+    // How many frames cleared pixels take to fade out (0 to turn them off at once).
+    pub phosphor: u32,
+    // Whether scanlines are drawn over the screen (at first).
+    pub scanlines: bool,
+    // The theme selected (which the theme key cycles onward from).
+    pub theme: Theme,
+    // The colors the screen is drawn with
+    // (the theme's, unless the user picked their own).
+    pub palette: Palette,
+}
+
+impl Config {
+    /// Whether the game is read from standard input (instead of a file).
+    pub fn game_from_stdin(&self) -> bool {
+        self.game_path.as_deref() == Some(STDIN_PATH)
+    }
+
+    /// Whether a game waiting for a key has halted, since no key can ever be pressed
+    /// (when running headless).
+    pub fn key_wait_halts(&self) -> bool {
+        self.stop_on_halt && self.frontend == Frontend::Headless
+    }
+
+    fn default_path_base(&self) -> Option<&String> {
+        // The path that other default paths are derived from:
+        // the game's (or the loaded state's), unless the game has no file.
+        if self.game_from_stdin() {
+            return None;
+        }
+        self.game_path.as_ref().or(self.load_state_path.as_ref())
+    }
+
+    /// The path to save game states to: the one given by the user, if any.
+    /// Otherwise, a game's states default to `<game>.state` (next to the game,
+    /// or in the state directory), and a loaded state is saved back over itself.
+    pub fn save_state_path(&self) -> Option<String> {
+        if self.save_state_path.is_some() {
+            return self.save_state_path.clone();
+        }
+        if self.game_from_stdin() {
+            // (A game read from standard input has no file to keep states next to.)
+            return None;
+        }
+
+        if let Some(game) = &self.game_path {
+            let game = Path::new(game);
+            let file_name = format!("{}.{STATE_EXTENSION}", game.file_name()?.to_string_lossy());
+            let path = match &self.state_dir {
+                Some(dir) => Path::new(dir).join(file_name),
+                None => game.with_file_name(file_name),
+            };
+            Some(path.to_string_lossy().into_owned())
+        } else {
+            self.load_state_path.clone()
+        }
+    }
+
+    /// The path to load game states from while running: the loaded state, if any.
+    /// Otherwise, the state most recently saved (to the save state path).
+    pub fn load_state_path(&self) -> Option<String> {
+        self.load_state_path
+            .clone()
+            .or_else(|| self.save_state_path())
+    }
+
+    /// The path to dump the emulated memory to: the one given by the user, if any.
+    /// Otherwise, dumps default to `<game>.mem` (or `<state>.mem` for a loaded state).
+    pub fn memory_dump_path(&self) -> Option<String> {
+        if self.memory_dump_path.is_some() {
+            return self.memory_dump_path.clone();
+        }
+
+        let base = self.default_path_base()?;
+        Some(format!("{base}.{MEMORY_DUMP_EXTENSION}"))
+    }
+
+    /// The path to save GIF recordings to: the one given by the user, if any.
+    /// Otherwise, recordings default to `<game>.gif` (or `<state>.gif` for a loaded state).
+    pub fn gif_path(&self) -> Option<String> {
+        if self.gif_path.is_some() {
+            return self.gif_path.clone();
+        }
+
+        let base = self.default_path_base()?;
+        Some(format!("{base}.{GIF_EXTENSION}"))
+    }
+
+    /// The path to save audio recordings to: the one given by the user, if any.
+    /// Otherwise, recordings default to `<game>.wav` (or `<state>.wav` for a loaded state).
+    pub fn wav_path(&self) -> Option<String> {
+        if self.wav_path.is_some() {
+            return self.wav_path.clone();
+        }
+
+        let base = self.default_path_base()?;
+        Some(format!("{base}.{WAV_EXTENSION}"))
+    }
+
+    /// The path to write crash dumps to: the one given by the user, if any.
+    /// Otherwise, dumps default to `<game>.crash.json` (or `<state>.crash.json`
+    /// for a loaded state).
+    pub fn crash_dump_path(&self) -> Option<String> {
+        if self.crash_dump_path.is_some() {
+            return self.crash_dump_path.clone();
+        }
+
+        let base = self.default_path_base()?;
+        Some(format!("{base}.{CRASH_DUMP_EXTENSION}"))
+    }
+}
+
+/// Parse the address a game starts at: a hex address (such as `0x600`), or the name
+/// of a machine whose games start somewhere unusual (`eti-660`).
+pub fn parse_start_address(address: &str) -> Result<u16, String> {
+    if address.eq_ignore_ascii_case("eti-660") {
+        return Ok(ETI_660_START_ADDRESS);
+    }
+    let digits = address
+        .strip_prefix("0x")
+        .or_else(|| address.strip_prefix("0X"))
+        .unwrap_or(address);
+    match u16::from_str_radix(digits, 16) {
+        Ok(start) if (MIN_START_ADDRESS..=MAX_START_ADDRESS).contains(&start) => Ok(start),
+        _ => Err(format!(
+            "'{address}' is not a start address ({MIN_START_ADDRESS:#X}-{MAX_START_ADDRESS:#X}, or eti-660)"
+        )),
+    }
+}
+
+/// The SHA-1 hash of a game (in lowercase hex), which identifies it
+/// regardless of what its file is called.
+pub fn rom_hash(contents: &[u8]) -> String {
+    sha1_smol::Sha1::from(contents).digest().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_colors() {
+        // Colors are six hex digits (in either case), with or without a leading '#'.
+        assert_eq!("#33FF66".parse(), Ok(Rgb(0x33, 0xFF, 0x66)));
+        assert_eq!("0a0B0c".parse(), Ok(Rgb(0x0A, 0x0B, 0x0C)));
+        // (And they're written back the same way.)
+        assert_eq!(Rgb(0x33, 0xFF, 0x66).to_string(), "#33FF66");
+
+        // Anything longer or shorter isn't a color...
+        for hex in ["", "#", "#FFF", "#33FF6", "#33FF667", "##33FF66"] {
+            assert!(hex.parse::<Rgb>().is_err(), "{hex}");
+        }
+        // ...and neither is anything with digits that aren't hex.
+        for hex in ["#33FG66", "#+F+F+F", "#33 F66", "#33FF6é"] {
+            assert!(hex.parse::<Rgb>().is_err(), "{hex}");
+        }
+    }
+
+    #[test]
+    fn themes() {
+        // Themes cycle through every one of them, wrapping back around to the first.
+        let mut theme = Theme::default();
+        for _ in 0..Theme::ALL.len() {
+            theme = theme.next();
+        }
+        assert_eq!(theme, Theme::default());
+        assert_eq!(Theme::Paper.next(), Theme::Classic);
+
+        // They're named in lowercase in settings files, and given their colors there too.
+        let settings: RomSettings =
+            serde_json::from_str(r##"{"theme": "lcd", "foreground": "#0F380F"}"##).unwrap();
+        assert_eq!(settings.theme, Some(Theme::Lcd));
+        assert_eq!(settings.foreground, Some(Theme::Lcd.palette().foreground));
+        assert!(serde_json::from_str::<RomSettings>(r#"{"foreground": "green"}"#).is_err());
+    }
+
+    #[test]
+    fn key_bindings() {
+        // Bindings name a keypad key (in hex, either case) and the host key for it.
+        assert_eq!(
+            KeyBindings::parse_binding("C=4"),
+            Ok((0xC, String::from("4")))
+        );
+        assert_eq!(
+            KeyBindings::parse_binding("a=Keypad 5"),
+            Ok((0xA, String::from("Keypad 5")))
+        );
+
+        // Keypad keys past F (or not hex at all) aren't keypad keys...
+        for binding in ["10=Q", "G=Q", "=Q", "-1=Q"] {
+            assert!(KeyBindings::parse_binding(binding).is_err(), "{binding}");
+        }
+        // ...and bindings need both halves.
+        assert!(KeyBindings::parse_binding("C").is_err());
+        assert!(GamepadBindings::parse_binding("a").is_err());
+        assert!(GamepadBindings::parse_binding("a=G").is_err());
+
+        // Binding a keypad key again replaces its binding, leaving the rest of the layout.
+        let mut bindings = KeyBindings::from(KeyLayout::Qwerty);
+        bindings.bind(0xC, "Y");
+        bindings.bind(0xC, "T");
+        assert_eq!(bindings.names()[0xC], "T");
+        assert_eq!(bindings.names()[0xD], "R");
+        assert!(!bindings.names().contains(&String::from("Y")));
+    }
+
+    #[test]
+    fn key_binding_files() {
+        // Keymap files rebind only the keypad keys they name.
+        let path = env::temp_dir().join("jchip8-test-keymap.json");
+        fs::write(&path, r#"{"1": "Q", "c": "Space"}"#).unwrap();
+        let mut bindings = KeyBindings::default();
+        bindings.load_file(path.to_str().unwrap()).unwrap();
+        assert_eq!(bindings.names()[0x1], "Q");
+        assert_eq!(bindings.names()[0xC], "Space");
+        assert_eq!(bindings.names()[0x2], "2");
+
+        // Files naming anything but keypad keys are rejected.
+        fs::write(&path, r#"{"Z": "Q"}"#).unwrap();
+        let error = bindings.load_file(path.to_str().unwrap()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,71 @@
+use std::io::{Error, ErrorKind};
+use thiserror::Error;
+
+/// Everything that can go wrong while loading or running a game,
+/// for library users to match on.
+#[derive(Debug, Error)]
+pub enum Chip8Error {
+    /// The game doesn't fit in the memory after the start address.
+    #[error("The game is too large ({size} bytes, but only {max} fit in memory).")]
+    RomTooLarge { size: usize, max: usize },
+
+    /// A saved state couldn't be read as one.
+    #[error("Load state does not appear to be a valid saved state!")]
+    InvalidState(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    /// The game ran an instruction that the emulated interpreter doesn't have.
+    #[error("Unimplemented opcode {opcode:04X} at {pc:#05X}.")]
+    BadOpcode { opcode: u16, pc: u16 },
+
+    /// The game ran an instruction that reaches past the end of memory.
+    #[error(
+        "Opcode {opcode:04X} at {pc:#05X} reaches past the end of memory (at {address:#05X})."
+    )]
+    OutOfBounds {
+        opcode: u16,
+        pc: u16,
+        address: usize,
+    },
+
+    /// The game checked a key that the 16-key keypad doesn't have.
+    #[error("Opcode {opcode:04X} at {pc:#05X} checks key {key:#04X}, which isn't on the keypad.")]
+    BadKey { opcode: u16, pc: u16, key: u8 },
+
+    /// The game called a subroutine with all 16 stack entries in use.
+    #[error("Opcode {opcode:04X} at {pc:#05X} overflows the stack.")]
+    StackOverflow { opcode: u16, pc: u16 },
+
+    /// The game returned from a subroutine without having called one.
+    #[error("Opcode {opcode:04X} at {pc:#05X} returns with an empty stack.")]
+    StackUnderflow { opcode: u16, pc: u16 },
+
+    /// Playing a movie back didn't reproduce the run it recorded.
+    #[error("The replay diverged from the recording on cycle {cycle}: {detail}")]
+    ReplayDiverged { cycle: u64, detail: String },
+
+    /// SDL (the window, its renderer, or its events) couldn't be set up.
+    #[error("SDL failed: {0}")]
+    Sdl(String),
+
+    /// A window (of a frontend other than SDL's) couldn't be set up.
+    #[error("Creating the window failed: {0}")]
+    Window(String),
+
+    /// Reading or writing a file (or any other I/O) failed.
+    #[error(transparent)]
+    Io(#[from] Error),
+}
+
+impl From<Chip8Error> for Error {
+    fn from(error: Chip8Error) -> Error {
+        // (So that code already working in I/O errors can keep using them.)
+        let kind = match error {
+            Chip8Error::Io(error) => return error,
+            Chip8Error::RomTooLarge { .. } => ErrorKind::InvalidData,
+            Chip8Error::InvalidState(_) => ErrorKind::InvalidInput,
+            _ => ErrorKind::Other,
+        };
+        // (Keeping just the message, so that it's what gets shown.)
+        Error::new(kind, error.to_string())
+    }
+}
@@ -0,0 +1,94 @@
+use super::tone::Tone;
+#[cfg(feature = "recorder")]
+use super::WavRecorder;
+use crate::config::Waveform;
+use sdl2::audio::{AudioCallback, AudioDevice, AudioSpecDesired};
+use std::io::Error;
+
+// How many samples are played each second (and buffered at once).
+const SAMPLE_RATE: i32 = 44100;
+const BUFFER_SAMPLES: u16 = 1024;
+
+impl AudioCallback for Tone {
+    type Channel = f32;
+
+    fn callback(&mut self, samples: &mut [f32]) {
+        self.fill(samples);
+    }
+}
+
+/// The Chip8 buzzer: a tone (of the chosen waveform) played through SDL audio
+/// for as long as it's turned on (and silence otherwise).
+pub struct Buzzer {
+    device: AudioDevice<Tone>,
+    // How many samples the device plays each second.
+    sample_rate: u32,
+    // Whether the tone is currently playing.
+    playing: bool,
+    // How loud the tone is (as a percentage).
+    volume: u32,
+}
+
+impl Buzzer {
+    /// Open the default audio device (starting out silent), for a tone
+    /// of the given pitch (in hz), volume (as a percentage), and waveform.
+    pub fn new(
+        sdl: &sdl2::Sdl,
+        frequency: u32,
+        volume: u32,
+        waveform: Waveform,
+    ) -> Result<Buzzer, Error> {
+        let audio = sdl.audio().map_err(Error::other)?;
+        let desired = AudioSpecDesired {
+            freq: Some(SAMPLE_RATE),
+            channels: Some(1),
+            samples: Some(BUFFER_SAMPLES),
+        };
+        let device = audio
+            .open_playback(None, &desired, |spec| {
+                Tone::new(frequency, volume, waveform, spec.freq as u32)
+            })
+            .map_err(Error::other)?;
+        let sample_rate = device.spec().freq as u32;
+        device.resume();
+
+        Ok(Buzzer {
+            device,
+            sample_rate,
+            playing: false,
+            volume,
+        })
+    }
+
+    /// How loud the tone is (as a percentage).
+    pub fn volume(&self) -> u32 {
+        self.volume
+    }
+
+    /// Change how loud the tone is (as a percentage, up to 100).
+    pub fn set_volume(&mut self, volume: u32) {
+        self.volume = volume.min(100);
+        self.device.lock().volume = Tone::amplitude(self.volume);
+    }
+
+    /// Start or stop playing the tone (doing nothing if it already is, or isn't).
+    pub fn set_playing(&mut self, playing: bool) {
+        if playing == self.playing {
+            return;
+        }
+        self.playing = playing;
+        self.device.lock().playing = playing;
+    }
+
+    /// Start recording everything played (including silence), from now on.
+    #[cfg(feature = "recorder")]
+    pub fn start_recording(&mut self) {
+        self.device.lock().recording = Some(WavRecorder::new(self.sample_rate));
+    }
+
+    /// Stop recording, returning the recording (if recording).
+    #[cfg(feature = "recorder")]
+    pub fn stop_recording(&mut self) -> Option<WavRecorder> {
+        self.device.lock().recording.take()
+    }
+}
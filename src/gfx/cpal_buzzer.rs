@@ -0,0 +1,110 @@
+use super::tone::Tone;
+use crate::config::Waveform;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{Device, FromSample, SampleFormat, SizedSample, Stream, StreamConfig};
+use log::warn;
+use std::io::Error;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// The Chip8 buzzer, played through cpal (the host's own audio API) rather than SDL,
+/// for frontends that don't use SDL: the same tone as `Buzzer`, for as long
+/// as it's turned on (and silence otherwise).
+pub struct CpalBuzzer {
+    // The tone (shared with the stream, which plays it for as long as it's kept).
+    tone: Arc<Mutex<Tone>>,
+    _stream: Stream,
+    // Whether the tone is currently playing.
+    playing: bool,
+    // How loud the tone is (as a percentage).
+    volume: u32,
+}
+
+impl CpalBuzzer {
+    /// Open the default audio device (starting out silent), for a tone
+    /// of the given pitch (in hz), volume (as a percentage), and waveform.
+    pub fn new(frequency: u32, volume: u32, waveform: Waveform) -> Result<CpalBuzzer, Error> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or_else(|| Error::other("there's no audio output device"))?;
+        let supported = device.default_output_config().map_err(Error::other)?;
+        let config = supported.config();
+        let tone = Arc::new(Mutex::new(Tone::new(
+            frequency,
+            volume,
+            waveform,
+            config.sample_rate,
+        )));
+        let stream = match supported.sample_format() {
+            SampleFormat::F32 => Self::stream::<f32>(&device, &config, &tone),
+            SampleFormat::I16 => Self::stream::<i16>(&device, &config, &tone),
+            SampleFormat::U16 => Self::stream::<u16>(&device, &config, &tone),
+            SampleFormat::I32 => Self::stream::<i32>(&device, &config, &tone),
+            format => {
+                return Err(Error::other(format!(
+                    "the audio device's sample format ({format}) isn't supported"
+                )))
+            }
+        }
+        .map_err(Error::other)?;
+        stream.play().map_err(Error::other)?;
+
+        Ok(CpalBuzzer {
+            tone,
+            _stream: stream,
+            playing: false,
+            volume,
+        })
+    }
+
+    fn stream<T: SizedSample + FromSample<f32>>(
+        device: &Device,
+        config: &StreamConfig,
+        tone: &Arc<Mutex<Tone>>,
+    ) -> Result<Stream, cpal::BuildStreamError> {
+        // A stream playing the tone (on every channel) in the device's sample format.
+        let tone = Arc::clone(tone);
+        let channels = usize::from(config.channels);
+        let mut samples = Vec::new();
+        device.build_output_stream(
+            config,
+            move |data: &mut [T], _| {
+                samples.resize(data.len() / channels, 0.0);
+                if let Ok(mut tone) = tone.lock() {
+                    tone.fill(&mut samples);
+                }
+                for (frame, &sample) in data.chunks_mut(channels).zip(&samples) {
+                    frame.fill(T::from_sample(sample));
+                }
+            },
+            |error| warn!("Audio playback failed: {error}"),
+            None,
+        )
+    }
+
+    fn tone(&self) -> MutexGuard<'_, Tone> {
+        // (The stream never panics while holding the tone, but recover if it somehow did.)
+        self.tone
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// How loud the tone is (as a percentage).
+    pub fn volume(&self) -> u32 {
+        self.volume
+    }
+
+    /// Change how loud the tone is (as a percentage, up to 100).
+    pub fn set_volume(&mut self, volume: u32) {
+        self.volume = volume.min(100);
+        self.tone().volume = Tone::amplitude(self.volume);
+    }
+
+    /// Start or stop playing the tone (doing nothing if it already is, or isn't).
+    pub fn set_playing(&mut self, playing: bool) {
+        if playing == self.playing {
+            return;
+        }
+        self.playing = playing;
+        self.tone().playing = playing;
+    }
+}
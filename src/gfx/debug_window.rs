@@ -0,0 +1,138 @@
+use super::font::TextGrid;
+use super::MachineState;
+use sdl2::{pixels::Color, render::Canvas, video::Window, VideoSubsystem};
+use std::io::Error;
+use std::time::{Duration, Instant};
+
+const TITLE: &str = "Chip-8 Debugger";
+// Text layout constants.
+// How text is laid out, and how many characters fit in the window (columns, then lines).
+const GRID: TextGrid = TextGrid {
+    scale: 3,
+    margin: 4,
+};
+const TEXT_SIZE: (u32, u32) = (40, 28);
+// Where each part of the machine is shown (by line).
+const STACK_LINE: u32 = 7;
+const MEMORY_LINE: u32 = 11;
+// How much memory is shown (rows, and bytes per row).
+const MEMORY_ROWS: usize = 16;
+const MEMORY_ROW_LENGTH: usize = 8;
+// How often the window is redrawn (the machine changes far more often than that).
+const REDRAW_INTERVAL: Duration = Duration::from_millis(33);
+// Colors.
+const LABEL_COLOR: Color = Color::GRAY;
+const VALUE_COLOR: Color = Color::WHITE;
+const PC_COLOR: Color = Color::YELLOW;
+
+/// A secondary window showing the machine's registers, stack, and memory
+/// (around the PC) live, while the game runs in the main window.
+pub struct DebugWindow {
+    canvas: Canvas<Window>,
+    // When the window was last drawn, if ever.
+    last_drawn: Option<Instant>,
+}
+
+impl DebugWindow {
+    /// Open the window (which stays open until this is dropped).
+    pub fn new(video: &VideoSubsystem) -> Result<DebugWindow, Error> {
+        let (width, height) = GRID.window_size(TEXT_SIZE.0, TEXT_SIZE.1);
+        let window = video
+            .window(TITLE, width, height)
+            .build()
+            .map_err(|err| Error::other(err.to_string()))?;
+        let canvas = window
+            .into_canvas()
+            .build()
+            .map_err(|err| Error::other(err.to_string()))?;
+
+        Ok(DebugWindow {
+            canvas,
+            last_drawn: None,
+        })
+    }
+
+    /// The SDL ID of the window (which its events are tagged with).
+    pub fn id(&self) -> u32 {
+        self.canvas.window().id()
+    }
+
+    /// Show the machine's state, unless the window was drawn very recently.
+    pub fn draw(&mut self, state: &MachineState) {
+        let now = Instant::now();
+        if self
+            .last_drawn
+            .is_some_and(|last_drawn| now.duration_since(last_drawn) < REDRAW_INTERVAL)
+        {
+            return;
+        }
+        self.last_drawn = Some(now);
+
+        self.canvas.set_draw_color(Color::BLACK);
+        self.canvas.clear();
+
+        // Registers and timers.
+        self.draw_field("PC", &format!("{:04X}", state.pc), 0, 0);
+        self.draw_field("I", &format!("{:04X}", state.index_reg), 9, 0);
+        self.draw_field("DT", &format!("{:02X}", state.delay_timer), 17, 0);
+        self.draw_field("ST", &format!("{:02X}", state.sound_timer), 24, 0);
+        for (register, value) in state.registers.iter().enumerate() {
+            let (column, line) = (register as u32 % 4 * 10, register as u32 / 4 + 2);
+            self.draw_field(
+                &format!("V{register:X}"),
+                &format!("{value:02X}"),
+                column,
+                line,
+            );
+        }
+
+        // The stack (the oldest entries first).
+        self.draw_text("STACK", 0, STACK_LINE, LABEL_COLOR);
+        for (entry, address) in state.stack.iter().enumerate() {
+            let (column, line) = (entry as u32 % 8 * 5, entry as u32 / 8 + STACK_LINE + 1);
+            self.draw_text(&format!("{address:04X}"), column, line, VALUE_COLOR);
+        }
+
+        // Memory around the PC (with the instruction at the PC highlighted).
+        self.draw_text("MEMORY", 0, MEMORY_LINE, LABEL_COLOR);
+        let pc = usize::from(state.pc);
+        let start = (pc / MEMORY_ROW_LENGTH)
+            .saturating_sub(MEMORY_ROWS / 4)
+            .min((state.memory.len() / MEMORY_ROW_LENGTH).saturating_sub(MEMORY_ROWS))
+            * MEMORY_ROW_LENGTH;
+        let rows = state.memory[start..]
+            .chunks(MEMORY_ROW_LENGTH)
+            .take(MEMORY_ROWS);
+        for (row, bytes) in rows.enumerate() {
+            let address = start + row * MEMORY_ROW_LENGTH;
+            let line = row as u32 + MEMORY_LINE + 1;
+            self.draw_text(&format!("{address:04X}"), 0, line, LABEL_COLOR);
+            for (offset, byte) in bytes.iter().enumerate() {
+                let color = if (pc..pc + 2).contains(&(address + offset)) {
+                    PC_COLOR
+                } else {
+                    VALUE_COLOR
+                };
+                self.draw_text(&format!("{byte:02X}"), offset as u32 * 3 + 6, line, color);
+            }
+        }
+
+        self.canvas.present();
+    }
+
+    fn draw_field(&mut self, label: &str, value: &str, column: u32, line: u32) {
+        // Draw a label, followed by its value.
+        self.draw_text(label, column, line, LABEL_COLOR);
+        let column = column + label.chars().count() as u32 + 1;
+        self.draw_text(value, column, line, VALUE_COLOR);
+    }
+
+    fn draw_text(&mut self, text: &str, column: u32, line: u32, color: Color) {
+        // Draw text starting at the given character position.
+        let pixels = GRID.text(text, column, line);
+        self.canvas.set_draw_color(color);
+        self.canvas
+            .fill_rects(&pixels)
+            .expect("Failed to draw text!");
+    }
+}
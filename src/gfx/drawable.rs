@@ -2,6 +2,17 @@
 pub trait Drawable {
     fn clear_all_pixels(&mut self);
     fn xor_pixel(&mut self, x: u16, y: u16);
+    /// XOR one row of a sprite (`width` pixels wide, the leftmost in its highest bit)
+    /// onto row `y`, starting at column `x`, clipping it at the right edge
+    /// (or wrapping it around to the left, if `wrap`, given `x` is on the screen).
+    /// Returns whether any pixels were unset.
+    fn xor_row(&mut self, x: u32, y: u32, sprite: u16, width: u32, wrap: bool) -> bool;
+    /// Move every pixel down by `rows`, clearing the rows uncovered at the top.
+    fn scroll_down(&mut self, rows: u16);
+    /// Move every pixel left by `columns`, clearing the columns uncovered at the right.
+    fn scroll_left(&mut self, columns: u16);
+    /// Move every pixel right by `columns`, clearing the columns uncovered at the left.
+    fn scroll_right(&mut self, columns: u16);
 
     fn get_pixel(&self, x: u16, y: u16) -> bool;
     /// Determine if a given [x, y] coordinate is
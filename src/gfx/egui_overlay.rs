@@ -0,0 +1,408 @@
+use super::MachineState;
+use crate::arch::disassemble;
+use egui::epaint::{ImageDelta, Primitive};
+use egui::text::{LayoutJob, TextFormat};
+use egui::{Color32, ColorImage, Pos2, RawInput, Rect, RichText, TextStyle, TextureId, Vec2};
+use sdl2::event::Event;
+use sdl2::mouse::MouseButton;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::render::{BlendMode, Canvas, Texture};
+use sdl2::video::Window;
+use sdl2::{rect, sys};
+use std::collections::HashMap;
+use std::ffi::c_int;
+use std::time::{Duration, Instant};
+
+// How many instructions the disassembly shows before the PC, and after it.
+const DISASSEMBLY_BEFORE: u16 = 8;
+const DISASSEMBLY_AFTER: u16 = 24;
+// How many bytes each row of the memory view shows.
+const MEMORY_ROW_LENGTH: usize = 8;
+// How tall the disassembly and memory views are, at most (the rest scrolls).
+const VIEW_HEIGHT: f32 = 160.0;
+// How often the machine's state is taken again (like the debug window's redraws).
+const REFRESH_INTERVAL: Duration = Duration::from_millis(33);
+const PANEL_WIDTH: f32 = 220.0;
+// Colors.
+const LABEL_COLOR: Color32 = Color32::GRAY;
+const PC_COLOR: Color32 = Color32::YELLOW;
+const INDEX_COLOR: Color32 = Color32::LIGHT_BLUE;
+
+// The parts of the machine's state the panel shows
+// (copied out of it, since the panel is drawn after the machine moves on).
+#[derive(Default)]
+struct MachineView {
+    pc: u16,
+    index_reg: u16,
+    delay_timer: u8,
+    sound_timer: u8,
+    registers: [u8; 16],
+    stack: Vec<u16>,
+    // The instructions around the PC (by address).
+    disassembly: Vec<(u16, u16)>,
+    memory: Vec<u8>,
+}
+
+/// An egui panel drawn inside the main window (over the right side of the game),
+/// showing the machine's registers, timers, stack, and a disassembly around the PC.
+/// (Unlike the debug window, it doesn't need a second window; the disassembly
+/// can be scrolled with the mouse.) Below, a hex view of all of memory
+/// highlights the PC and the byte the index register points to.
+pub struct EguiOverlay {
+    context: egui::Context,
+    // When the overlay was opened (egui keeps time relative to it).
+    opened: Instant,
+    // Mouse input for egui, since the panel was last drawn.
+    events: Vec<egui::Event>,
+    // The textures egui asked for (its font atlas, mostly), kept as images
+    // so that they can be uploaded to the renderer on every draw.
+    images: HashMap<TextureId, ColorImage>,
+    machine: MachineView,
+    // Whether the memory view keeps scrolling to the PC as it moves.
+    follow_pc: bool,
+    // When the machine's state was last taken, if ever.
+    last_refreshed: Option<Instant>,
+}
+
+impl EguiOverlay {
+    pub fn new() -> EguiOverlay {
+        EguiOverlay {
+            context: egui::Context::default(),
+            opened: Instant::now(),
+            events: Vec::new(),
+            images: HashMap::new(),
+            machine: MachineView::default(),
+            follow_pc: true,
+            last_refreshed: None,
+        }
+    }
+
+    /// Follow the mouse in the given window, returning whether the panel
+    /// must be drawn again to show what it did.
+    pub fn handle_event(&mut self, event: &Event, window: u32) -> bool {
+        let event = match *event {
+            Event::MouseMotion {
+                window_id, x, y, ..
+            } if window_id == window => egui::Event::PointerMoved(Pos2::new(x as f32, y as f32)),
+            Event::MouseButtonDown {
+                window_id,
+                mouse_btn,
+                x,
+                y,
+                ..
+            }
+            | Event::MouseButtonUp {
+                window_id,
+                mouse_btn,
+                x,
+                y,
+                ..
+            } if window_id == window => {
+                let Some(button) = Self::button(mouse_btn) else {
+                    return false;
+                };
+                egui::Event::PointerButton {
+                    pos: Pos2::new(x as f32, y as f32),
+                    button,
+                    pressed: matches!(event, Event::MouseButtonDown { .. }),
+                    modifiers: egui::Modifiers::NONE,
+                }
+            }
+            Event::MouseWheel {
+                window_id,
+                precise_x,
+                precise_y,
+                ..
+            } if window_id == window => egui::Event::MouseWheel {
+                unit: egui::MouseWheelUnit::Line,
+                delta: Vec2::new(precise_x, precise_y),
+                modifiers: egui::Modifiers::NONE,
+            },
+            _ => return false,
+        };
+        self.events.push(event);
+        true
+    }
+
+    fn button(button: MouseButton) -> Option<egui::PointerButton> {
+        match button {
+            MouseButton::Left => Some(egui::PointerButton::Primary),
+            MouseButton::Right => Some(egui::PointerButton::Secondary),
+            MouseButton::Middle => Some(egui::PointerButton::Middle),
+            _ => None,
+        }
+    }
+
+    /// Take the machine's state to show, unless it was taken very recently,
+    /// returning whether it was (and so, whether the panel must be drawn again).
+    pub fn refresh(&mut self, state: &MachineState) -> bool {
+        let now = Instant::now();
+        if self
+            .last_refreshed
+            .is_some_and(|last_refreshed| now.duration_since(last_refreshed) < REFRESH_INTERVAL)
+        {
+            return false;
+        }
+        self.last_refreshed = Some(now);
+
+        let start = state
+            .pc
+            .saturating_sub(DISASSEMBLY_BEFORE * 2)
+            .max(state.pc % 2);
+        let disassembly = (start..state.pc.saturating_add(DISASSEMBLY_AFTER * 2))
+            .step_by(2)
+            .filter_map(|address| {
+                let bytes = state
+                    .memory
+                    .get(usize::from(address)..usize::from(address) + 2)?;
+                Some((address, u16::from_be_bytes([bytes[0], bytes[1]])))
+            })
+            .collect();
+        self.machine = MachineView {
+            pc: state.pc,
+            index_reg: state.index_reg,
+            delay_timer: state.delay_timer,
+            sound_timer: state.sound_timer,
+            registers: *state.registers,
+            stack: state.stack.to_vec(),
+            disassembly,
+            memory: state.memory.to_vec(),
+        };
+        true
+    }
+
+    /// Draw the panel over whatever is on the canvas (before it's presented).
+    pub fn draw(&mut self, canvas: &mut Canvas<Window>) -> Result<(), String> {
+        let (width, height) = canvas.output_size()?;
+        let input = RawInput {
+            screen_rect: Some(Rect::from_min_size(
+                Pos2::ZERO,
+                Vec2::new(width as f32, height as f32),
+            )),
+            time: Some(self.opened.elapsed().as_secs_f64()),
+            events: std::mem::take(&mut self.events),
+            ..RawInput::default()
+        };
+        let (machine, follow_pc) = (&self.machine, &mut self.follow_pc);
+        let output = self
+            .context
+            .run(input, |context| Self::show(context, machine, follow_pc));
+
+        for (id, delta) in output.textures_delta.set {
+            self.set_image(id, delta);
+        }
+        let primitives = self
+            .context
+            .tessellate(output.shapes, output.pixels_per_point);
+        let result = self.paint(canvas, primitives);
+        for id in output.textures_delta.free {
+            self.images.remove(&id);
+        }
+        result
+    }
+
+    fn show(context: &egui::Context, machine: &MachineView, follow_pc: &mut bool) {
+        // Lay out the panel itself.
+        egui::SidePanel::right("debugger")
+            .exact_width(PANEL_WIDTH)
+            .resizable(false)
+            .show(context, |ui| {
+                ui.style_mut().override_text_style = Some(egui::TextStyle::Monospace);
+                egui::Grid::new("timers").show(ui, |ui| {
+                    Self::field(ui, "PC", format!("{:04X}", machine.pc));
+                    Self::field(ui, "I", format!("{:04X}", machine.index_reg));
+                    ui.end_row();
+                    Self::field(ui, "DT", format!("{:02X}", machine.delay_timer));
+                    Self::field(ui, "ST", format!("{:02X}", machine.sound_timer));
+                    ui.end_row();
+                });
+                ui.separator();
+                egui::Grid::new("registers").show(ui, |ui| {
+                    for (register, value) in machine.registers.iter().enumerate() {
+                        Self::field(ui, &format!("V{register:X}"), format!("{value:02X}"));
+                        if register % 4 == 3 {
+                            ui.end_row();
+                        }
+                    }
+                });
+                ui.separator();
+                ui.label(RichText::new("STACK").color(LABEL_COLOR));
+                let stack: Vec<String> = machine
+                    .stack
+                    .iter()
+                    .map(|address| format!("{address:04X}"))
+                    .collect();
+                ui.label(stack.join(" "));
+                ui.separator();
+                ui.label(RichText::new("DISASSEMBLY").color(LABEL_COLOR));
+                egui::ScrollArea::vertical()
+                    .id_salt("disassembly")
+                    .max_height(VIEW_HEIGHT)
+                    .show(ui, |ui| {
+                        for &(address, opcode) in &machine.disassembly {
+                            let line =
+                                format!("{address:04X} {opcode:04X} {}", disassemble(opcode));
+                            let line = RichText::new(line);
+                            ui.label(if address == machine.pc {
+                                line.color(PC_COLOR)
+                            } else {
+                                line
+                            });
+                        }
+                    });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("MEMORY").color(LABEL_COLOR));
+                    ui.checkbox(follow_pc, "Follow PC");
+                });
+                Self::show_memory(ui, machine, *follow_pc);
+            });
+    }
+
+    fn show_memory(ui: &mut egui::Ui, machine: &MachineView, follow_pc: bool) {
+        // Show memory a row at a time (only laying out the rows in view),
+        // with the PC and the byte at the index register highlighted.
+        let row_height = ui.text_style_height(&TextStyle::Monospace);
+        let rows = machine.memory.len().div_ceil(MEMORY_ROW_LENGTH);
+        let mut scroll_area = egui::ScrollArea::vertical()
+            .id_salt("memory")
+            .max_height(VIEW_HEIGHT);
+        if follow_pc {
+            let row = usize::from(machine.pc) / MEMORY_ROW_LENGTH;
+            let spacing = ui.spacing().item_spacing.y;
+            scroll_area = scroll_area.vertical_scroll_offset(row as f32 * (row_height + spacing));
+        }
+        scroll_area.show_rows(ui, row_height, rows, |ui, rows| {
+            let font_id = TextStyle::Monospace.resolve(ui.style());
+            let format = |color| TextFormat::simple(font_id.clone(), color);
+            let (pc, index_reg) = (usize::from(machine.pc), usize::from(machine.index_reg));
+            for row in rows {
+                let start = row * MEMORY_ROW_LENGTH;
+                let mut line = LayoutJob::default();
+                line.append(&format!("{start:04X} "), 0.0, format(LABEL_COLOR));
+                let bytes = machine.memory[start..].iter().take(MEMORY_ROW_LENGTH);
+                for (address, byte) in (start..).zip(bytes) {
+                    let color = if (pc..pc + 2).contains(&address) {
+                        PC_COLOR
+                    } else if address == index_reg {
+                        INDEX_COLOR
+                    } else {
+                        ui.visuals().text_color()
+                    };
+                    line.append(&format!("{byte:02X} "), 0.0, format(color));
+                }
+                ui.label(line);
+            }
+        });
+    }
+
+    fn field(ui: &mut egui::Ui, label: &str, value: String) {
+        // Show a label, followed by its value.
+        ui.label(RichText::new(label).color(LABEL_COLOR));
+        ui.label(value);
+    }
+
+    fn set_image(&mut self, id: TextureId, delta: ImageDelta) {
+        // Create a texture, or replace part of one.
+        let egui::ImageData::Color(image) = delta.image;
+        let Some([x, y]) = delta.pos else {
+            self.images.insert(id, (*image).clone());
+            return;
+        };
+        let Some(texture) = self.images.get_mut(&id) else {
+            return;
+        };
+        let width = texture.size[0];
+        for (row, pixels) in image.pixels.chunks(image.size[0]).enumerate() {
+            let start = (y + row) * width + x;
+            texture.pixels[start..start + pixels.len()].copy_from_slice(pixels);
+        }
+    }
+
+    fn paint(
+        &self,
+        canvas: &mut Canvas<Window>,
+        primitives: Vec<egui::ClippedPrimitive>,
+    ) -> Result<(), String> {
+        // Upload the textures, then draw each mesh (clipped) with its texture.
+        // (SDL blends straight alpha, while egui's colors are premultiplied,
+        // so colors are converted along the way.)
+        let texture_creator = canvas.texture_creator();
+        let mut textures: HashMap<TextureId, Texture> = HashMap::new();
+        for (id, image) in &self.images {
+            let [width, height] = image.size;
+            let mut texture = texture_creator
+                .create_texture_static(PixelFormatEnum::RGBA32, width as u32, height as u32)
+                .map_err(|err| err.to_string())?;
+            let pixels: Vec<u8> = image
+                .pixels
+                .iter()
+                .flat_map(Color32::to_srgba_unmultiplied)
+                .collect();
+            texture
+                .update(None, &pixels, width * 4)
+                .map_err(|err| err.to_string())?;
+            texture.set_blend_mode(BlendMode::Blend);
+            textures.insert(*id, texture);
+        }
+
+        for primitive in primitives {
+            let Primitive::Mesh(mesh) = primitive.primitive else {
+                continue;
+            };
+            let Some(texture) = textures.get(&mesh.texture_id) else {
+                continue;
+            };
+            let clip = primitive.clip_rect;
+            canvas.set_clip_rect(rect::Rect::new(
+                clip.min.x as i32,
+                clip.min.y as i32,
+                clip.width().max(0.0) as u32,
+                clip.height().max(0.0) as u32,
+            ));
+            let vertices: Vec<sys::SDL_Vertex> = mesh
+                .vertices
+                .iter()
+                .map(|vertex| {
+                    let [r, g, b, a] = vertex.color.to_srgba_unmultiplied();
+                    sys::SDL_Vertex {
+                        position: sys::SDL_FPoint {
+                            x: vertex.pos.x,
+                            y: vertex.pos.y,
+                        },
+                        color: sys::SDL_Color { r, g, b, a },
+                        tex_coord: sys::SDL_FPoint {
+                            x: vertex.uv.x,
+                            y: vertex.uv.y,
+                        },
+                    }
+                })
+                .collect();
+            let indices: Vec<c_int> = mesh.indices.iter().map(|&index| index as c_int).collect();
+            // SAFETY: the renderer and texture outlive the call,
+            // and every index points into the vertices.
+            let result = unsafe {
+                sys::SDL_RenderGeometry(
+                    canvas.raw(),
+                    texture.raw(),
+                    vertices.as_ptr(),
+                    vertices.len() as c_int,
+                    indices.as_ptr(),
+                    indices.len() as c_int,
+                )
+            };
+            if result != 0 {
+                canvas.set_clip_rect(None);
+                return Err(sdl2::get_error());
+            }
+        }
+        canvas.set_clip_rect(None);
+        Ok(())
+    }
+}
+
+impl Default for EguiOverlay {
+    fn default() -> EguiOverlay {
+        EguiOverlay::new()
+    }
+}
@@ -0,0 +1,133 @@
+use sdl2::rect::Rect;
+
+// The characters that can be drawn as text, one row per byte (using the top 4 bits),
+// drawn like the Chip8 font (which the digits and A-F come straight from).
+// (Lowercase letters are drawn as uppercase ones.)
+const GLYPHS: [(char, [u8; 5]); 45] = [
+    ('0', [0xF0, 0x90, 0x90, 0x90, 0xF0]),
+    ('1', [0x20, 0x60, 0x20, 0x20, 0x70]),
+    ('2', [0xF0, 0x10, 0xF0, 0x80, 0xF0]),
+    ('3', [0xF0, 0x10, 0xF0, 0x10, 0xF0]),
+    ('4', [0x90, 0x90, 0xF0, 0x10, 0x10]),
+    ('5', [0xF0, 0x80, 0xF0, 0x10, 0xF0]),
+    ('6', [0xF0, 0x80, 0xF0, 0x90, 0xF0]),
+    ('7', [0xF0, 0x10, 0x20, 0x40, 0x40]),
+    ('8', [0xF0, 0x90, 0xF0, 0x90, 0xF0]),
+    ('9', [0xF0, 0x90, 0xF0, 0x10, 0xF0]),
+    ('A', [0xF0, 0x90, 0xF0, 0x90, 0x90]),
+    ('B', [0xE0, 0x90, 0xE0, 0x90, 0xE0]),
+    ('C', [0xF0, 0x80, 0x80, 0x80, 0xF0]),
+    ('D', [0xE0, 0x90, 0x90, 0x90, 0xE0]),
+    ('E', [0xF0, 0x80, 0xF0, 0x80, 0xF0]),
+    ('F', [0xF0, 0x80, 0xF0, 0x80, 0x80]),
+    ('G', [0xF0, 0x80, 0xB0, 0x90, 0xF0]),
+    ('H', [0x90, 0x90, 0xF0, 0x90, 0x90]),
+    ('I', [0x70, 0x20, 0x20, 0x20, 0x70]),
+    ('J', [0x10, 0x10, 0x10, 0x90, 0xF0]),
+    ('K', [0x90, 0xA0, 0xC0, 0xA0, 0x90]),
+    ('L', [0x80, 0x80, 0x80, 0x80, 0xF0]),
+    ('M', [0x90, 0xF0, 0xF0, 0x90, 0x90]),
+    ('N', [0x90, 0xD0, 0xB0, 0x90, 0x90]),
+    ('O', [0x60, 0x90, 0x90, 0x90, 0x60]),
+    ('P', [0xF0, 0x90, 0xF0, 0x80, 0x80]),
+    ('Q', [0x60, 0x90, 0x90, 0xB0, 0x70]),
+    ('R', [0xE0, 0x90, 0xE0, 0xA0, 0x90]),
+    ('S', [0xF0, 0x80, 0xF0, 0x10, 0xF0]),
+    ('T', [0x70, 0x20, 0x20, 0x20, 0x20]),
+    ('U', [0x90, 0x90, 0x90, 0x90, 0xF0]),
+    ('V', [0x90, 0x90, 0x90, 0x60, 0x60]),
+    ('W', [0x90, 0x90, 0xF0, 0xF0, 0x90]),
+    ('X', [0x90, 0x90, 0x60, 0x90, 0x90]),
+    ('Y', [0xA0, 0xA0, 0x40, 0x40, 0x40]),
+    ('Z', [0xF0, 0x10, 0x60, 0x80, 0xF0]),
+    ('.', [0x00, 0x00, 0x00, 0x00, 0x40]),
+    (',', [0x00, 0x00, 0x00, 0x40, 0x80]),
+    (':', [0x00, 0x40, 0x00, 0x40, 0x00]),
+    ('-', [0x00, 0x00, 0xF0, 0x00, 0x00]),
+    ('_', [0x00, 0x00, 0x00, 0x00, 0xF0]),
+    ('/', [0x10, 0x20, 0x40, 0x80, 0x80]),
+    ('(', [0x20, 0x40, 0x40, 0x40, 0x20]),
+    (')', [0x40, 0x20, 0x20, 0x20, 0x40]),
+    ('>', [0x40, 0x20, 0x10, 0x20, 0x40]),
+];
+
+/// The size of every glyph, in font pixels (width, then height).
+pub const GLYPH_SIZE: (u32, u32) = (4, 5);
+// How much room each character takes in a text grid (its glyph, plus a gap), in font pixels.
+const CHAR_SIZE: (u32, u32) = (GLYPH_SIZE.0 + 1, GLYPH_SIZE.1 + 2);
+
+fn glyph(character: char) -> Option<&'static [u8; 5]> {
+    // The glyph for a character (ignoring case), if it has one.
+    let character = character.to_ascii_uppercase();
+    GLYPHS
+        .iter()
+        .find(|(glyph, _)| *glyph == character)
+        .map(|(_, rows)| rows)
+}
+
+/// Every font pixel set in a character's glyph (column, then row).
+/// (Characters without a glyph are left blank, like a space.)
+pub fn pixels(character: char) -> impl Iterator<Item = (u32, u32)> {
+    glyph(character).into_iter().flat_map(|rows| {
+        rows.iter().enumerate().flat_map(|(y, row)| {
+            (0..GLYPH_SIZE.0)
+                .filter(move |x| row & (0x80 >> x) != 0)
+                .map(move |x| (x, y as u32))
+        })
+    })
+}
+
+/// A grid of characters filling a window: each font pixel is `scale` window pixels,
+/// with an empty border `margin` font pixels wide around the grid.
+#[derive(Clone, Copy)]
+pub struct TextGrid {
+    pub scale: u32,
+    pub margin: u32,
+}
+
+impl TextGrid {
+    /// The size of a window fitting the given number of columns and lines.
+    pub fn window_size(&self, columns: u32, lines: u32) -> (u32, u32) {
+        (
+            (columns * CHAR_SIZE.0 + self.margin * 2) * self.scale,
+            (lines * CHAR_SIZE.1 + self.margin * 2) * self.scale,
+        )
+    }
+
+    /// The area covered by `length` characters, starting at a given position.
+    pub fn cells(&self, column: u32, line: u32, length: u32) -> Rect {
+        let (left, top) = self.font_position(column, line);
+        Rect::new(
+            ((left - 1) * self.scale) as i32,
+            ((top - 1) * self.scale) as i32,
+            (length * CHAR_SIZE.0 + 1) * self.scale,
+            CHAR_SIZE.1 * self.scale,
+        )
+    }
+
+    /// Every window pixel covered by a line of text, starting at a given position.
+    pub fn text(&self, text: &str, column: u32, line: u32) -> Vec<Rect> {
+        text.chars()
+            .enumerate()
+            .flat_map(|(index, character)| {
+                let (left, top) = self.font_position(column + index as u32, line);
+                pixels(character).map(move |(x, y)| {
+                    Rect::new(
+                        ((left + x) * self.scale) as i32,
+                        ((top + y) * self.scale) as i32,
+                        self.scale,
+                        self.scale,
+                    )
+                })
+            })
+            .collect()
+    }
+
+    fn font_position(&self, column: u32, line: u32) -> (u32, u32) {
+        // Where a character's glyph starts, in font pixels.
+        (
+            column * CHAR_SIZE.0 + self.margin,
+            line * CHAR_SIZE.1 + self.margin,
+        )
+    }
+}
@@ -0,0 +1,91 @@
+use crate::config::GamepadBindings;
+use log::{debug, trace, warn};
+use sdl2::controller::{Button, GameController};
+use sdl2::event::Event;
+use sdl2::GameControllerSubsystem;
+use std::io::{Error, ErrorKind};
+
+/// A struct tracking the connected gamepads (SDL game controllers),
+/// and which keypad keys their buttons press.
+/// Gamepads can be connected and disconnected at any time.
+pub struct Gamepads {
+    // The SDL game controller subsystem, if it could be initialized
+    // (gamepads are optional, so we carry on without them if not).
+    subsystem: Option<GameControllerSubsystem>,
+    // The gamepads currently connected.
+    controllers: Vec<GameController>,
+    // The buttons bound to keypad keys.
+    layout: Vec<(Button, u8)>,
+}
+
+impl Gamepads {
+    pub fn new(sdl: &sdl2::Sdl, bindings: &GamepadBindings) -> Gamepads {
+        let subsystem = sdl
+            .game_controller()
+            .inspect_err(|error| warn!("Gamepads are unavailable: {error}"))
+            .ok();
+
+        Gamepads {
+            subsystem,
+            controllers: Vec::new(),
+            layout: Self::layout(bindings)
+                .unwrap_or_else(|error| panic!("Invalid gamepad bindings: {error}")),
+        }
+    }
+
+    /// Resolve the user's gamepad bindings into buttons,
+    /// returning an error naming the first button that doesn't exist.
+    pub fn layout(bindings: &GamepadBindings) -> Result<Vec<(Button, u8)>, Error> {
+        bindings
+            .buttons()
+            .iter()
+            .map(|(name, key)| match Button::from_string(name) {
+                Some(button) => Ok((button, *key)),
+                None => Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Unknown gamepad button name: '{name}'"),
+                )),
+            })
+            .collect()
+    }
+
+    /// Open newly connected gamepads and forget disconnected ones.
+    /// (SDL also reports gamepads that were connected before we started this way.)
+    pub fn handle_event(&mut self, event: &Event) {
+        match *event {
+            Event::ControllerDeviceAdded { which, .. } => {
+                let Some(subsystem) = &self.subsystem else {
+                    return;
+                };
+                match subsystem.open(which) {
+                    Ok(controller) => {
+                        debug!("Connected gamepad: {}", controller.name());
+                        self.controllers.push(controller);
+                    }
+                    Err(error) => {
+                        warn!("Failed to open gamepad: {error}");
+                    }
+                }
+            }
+            Event::ControllerDeviceRemoved { which, .. } => {
+                debug!("Disconnected gamepad {which}!");
+                self.controllers
+                    .retain(|controller| controller.instance_id() != which);
+            }
+            _ => (),
+        }
+    }
+
+    /// Press the keypad keys whose buttons are held on any gamepad
+    /// (keys that are already pressed stay pressed).
+    pub fn press_keys(&self, keyboard: &mut [bool]) {
+        for controller in &self.controllers {
+            for (button, key) in &self.layout {
+                if controller.button(*button) {
+                    trace!(button = button.string(), keypad_key = key; "{} was pressed!", button.string());
+                    keyboard[usize::from(*key)] = true;
+                }
+            }
+        }
+    }
+}
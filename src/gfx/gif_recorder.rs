@@ -0,0 +1,114 @@
+use super::{Drawable, Screen};
+use crate::config::Palette;
+use gif::{Encoder, EncodingError, Frame, Repeat};
+use std::fs;
+use std::io::Error;
+use std::time::{Duration, Instant};
+
+// How long one (60hz) frame lasts.
+const FRAME: Duration = Duration::from_nanos(16666667);
+// How many times larger than the screen's resolution recordings are.
+const GIF_SCALE: u32 = 4;
+
+/// Records the screen (once per frame) into an animated GIF,
+/// drawn with the palette in use when recording started.
+pub struct GifRecorder {
+    // The size of the recording (fixed, even if the screen's resolution changes).
+    width: u32,
+    height: u32,
+    // The encoded file so far.
+    encoder: Encoder<Vec<u8>>,
+    // The latest frame (as color indices), which is only written
+    // once it changes (so that it can be shown for as long as it lasted).
+    frame: Vec<u8>,
+    // When recording started, and when the latest frame was captured.
+    started: Instant,
+    last_capture: Instant,
+    // How long (in hundredths of a second, as GIF requires) written frames last in total.
+    written: u64,
+}
+
+impl GifRecorder {
+    /// Start recording, with the screen as it is `now` as the first frame.
+    pub fn new(screen: &Screen, palette: Palette, now: Instant) -> Result<GifRecorder, Error> {
+        let width = screen.res_width * GIF_SCALE;
+        let height = screen.res_height * GIF_SCALE;
+
+        // (With a global color table of two colors, and looping forever.)
+        let colors: Vec<u8> = [palette.background, palette.foreground]
+            .iter()
+            .flat_map(|color| [color.0, color.1, color.2])
+            .collect();
+        let mut encoder = Encoder::new(Vec::new(), width as u16, height as u16, &colors)
+            .map_err(encoding_error)?;
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .map_err(encoding_error)?;
+
+        let mut recorder = GifRecorder {
+            width,
+            height,
+            encoder,
+            frame: Vec::new(),
+            started: now,
+            last_capture: now,
+            written: 0,
+        };
+        recorder.frame = recorder.sample(screen);
+        Ok(recorder)
+    }
+
+    fn sample(&self, screen: &Screen) -> Vec<u8> {
+        // The color index of every pixel of the recording, row by row
+        // (scaling the screen to fit, in case its resolution changed).
+        let mut frame = Vec::with_capacity((self.width * self.height) as usize);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let screen_x = x * screen.res_width / self.width;
+                let screen_y = y * screen.res_height / self.height;
+                frame.push(u8::from(screen.get_pixel(screen_x as u16, screen_y as u16)));
+            }
+        }
+        frame
+    }
+
+    /// Capture the screen as it is `now`, if a frame has passed since the last capture.
+    pub fn capture(&mut self, screen: &Screen, now: Instant) -> Result<(), Error> {
+        if now.duration_since(self.last_capture) < FRAME {
+            return Ok(());
+        }
+        self.last_capture = now;
+
+        let frame = self.sample(screen);
+        if frame != self.frame {
+            self.write_frame(now)?;
+            self.frame = frame;
+        }
+        Ok(())
+    }
+
+    fn write_frame(&mut self, until: Instant) -> Result<(), Error> {
+        // Write the latest frame, lasting until the given time.
+        // (Durations are rounded, carrying over the difference, so the total stays accurate.)
+        let total = (until.duration_since(self.started).as_millis() as u64 + 5) / 10;
+        let delay = total.saturating_sub(self.written).min(u64::from(u16::MAX));
+        self.written += delay;
+
+        let mut frame =
+            Frame::from_indexed_pixels(self.width as u16, self.height as u16, &*self.frame, None);
+        frame.delay = delay as u16;
+        self.encoder.write_frame(&frame).map_err(encoding_error)
+    }
+
+    /// Stop recording `now`, and save the recording to a file.
+    pub fn save(mut self, path: &str, now: Instant) -> Result<(), Error> {
+        self.write_frame(now)?;
+        let data = self.encoder.into_inner().map_err(encoding_error)?;
+        fs::write(path, data)
+    }
+}
+
+fn encoding_error(error: EncodingError) -> Error {
+    // (Encoding into memory can't fail to write, so any failure is a bad frame.)
+    Error::other(error)
+}
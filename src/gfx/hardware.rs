@@ -1,80 +1,272 @@
-use super::{Drawable, Interactible, Screen, SetKeysResult};
-use sdl2::{event::Event, keyboard::Scancode, pixels::Color, rect::Rect};
-use std::io::Error;
+#[cfg(feature = "audio")]
+use super::Buzzer;
+#[cfg(feature = "debugger")]
+use super::DebugWindow;
+#[cfg(feature = "egui")]
+use super::EguiOverlay;
+#[cfg(feature = "recorder")]
+use super::GifRecorder;
+#[cfg(all(feature = "audio", feature = "recorder"))]
+use super::WavRecorder;
+use super::{
+    font, Drawable, Gamepads, HostKeyboard, InputDelay, Interactible, KeyPress, MachineState,
+    Phosphor, PlaybackSpeed, RenderFilter, Screen, SetKeysResult, Viewport,
+};
+use crate::config::{Config, KeyBindings, Palette, Settings, Theme};
+use crate::error::Chip8Error;
+use log::{debug, warn};
+use sdl2::event::{Event, WindowEvent};
+use sdl2::{keyboard::Scancode, pixels::Color, rect::Rect, render::BlendMode};
+use std::io::{Error, ErrorKind};
+use std::time::{Duration, Instant};
 
 // Keyboard layout constants.
-const KEYBOARD_LAYOUT: [Scancode; 16] = [
-    Scancode::Num0,
-    Scancode::Num1,
-    Scancode::Num2,
-    Scancode::Num3,
-    Scancode::Num4,
-    Scancode::Num5,
-    Scancode::Num6,
-    Scancode::Num7,
-    Scancode::Num8,
-    Scancode::Num9,
-    Scancode::A,
-    Scancode::B,
-    Scancode::C,
-    Scancode::D,
-    Scancode::E,
-    Scancode::F,
-];
+// (The keypad keys themselves are bound by the user; see `KeyBindings`.)
+const KEYPAD_SIZE: usize = 16;
 const KEY_QUIT: Scancode = Scancode::Escape;
 const KEY_PAUSE: Scancode = Scancode::P;
-const KEY_SAVE_STATE: Scancode = Scancode::S;
+// (Saving and loading are on function keys, which no built-in layout uses.)
+const KEY_SAVE_STATE: Scancode = Scancode::F5;
+const KEY_LOAD_STATE: Scancode = Scancode::F9;
+const KEY_DUMP_MEMORY: Scancode = Scancode::O;
+const KEY_ZOOM: Scancode = Scancode::M;
+const KEY_KEYPAD_OVERLAY: Scancode = Scancode::K;
+const KEY_THEME: Scancode = Scancode::T;
+const KEY_SCANLINES: Scancode = Scancode::N;
+const KEY_STATS: Scancode = Scancode::I;
+#[cfg(feature = "recorder")]
+const KEY_RECORD_GIF: Scancode = Scancode::G;
+#[cfg(all(feature = "audio", feature = "recorder"))]
+const KEY_RECORD_WAV: Scancode = Scancode::U;
+#[cfg(feature = "audio")]
+const KEY_VOLUME_UP: Scancode = Scancode::RightBracket;
+#[cfg(feature = "audio")]
+const KEY_VOLUME_DOWN: Scancode = Scancode::LeftBracket;
+#[cfg(feature = "debugger")]
+const KEY_DEBUG_WINDOW: Scancode = Scancode::F1;
+#[cfg(feature = "egui")]
+const KEY_DEBUG_OVERLAY: Scancode = Scancode::F2;
+const KEY_FAST_FORWARD: Scancode = Scancode::Tab;
+// Keys that change the speed (either key of each pair works).
+const KEYS_SPEED_UP: [Scancode; 2] = [Scancode::Equals, Scancode::KpPlus];
+const KEYS_SLOW_DOWN: [Scancode; 2] = [Scancode::Minus, Scancode::KpMinus];
+const KEY_SLOW_MOTION: Scancode = Scancode::LShift;
+// Keys that pan the magnifier (and which way they pan it, as seen in the window).
+const KEYS_PAN: [(Scancode, (i32, i32)); 4] = [
+    (Scancode::Left, (-1, 0)),
+    (Scancode::Right, (1, 0)),
+    (Scancode::Up, (0, -1)),
+    (Scancode::Down, (0, 1)),
+];
+// How the keypad overlay arranges keypad keys (as on the original hex keypad).
+const KEYPAD_GRID: [[usize; 4]; 4] = [
+    [0x1, 0x2, 0x3, 0xC],
+    [0x4, 0x5, 0x6, 0xD],
+    [0x7, 0x8, 0x9, 0xE],
+    [0xA, 0x0, 0xB, 0xF],
+];
+// On-screen display constants.
+// How long a message stays on screen.
+const OSD_DURATION: Duration = Duration::from_secs(2);
+// How much each press of a volume key changes the volume (as a percentage).
+#[cfg(feature = "audio")]
+const VOLUME_STEP: u32 = 10;
+// Scanline constants.
+// How far apart scanlines are, in window pixels (each is one window pixel tall).
+const SCANLINE_SPACING: usize = 3;
+// How dark scanlines are, out of 255 (fully black).
+const SCANLINE_ALPHA: u8 = 96;
 const NO_GAME_LOADED: &str = "No game loaded";
 
+// What the user did since input was last read.
+#[derive(Default)]
+struct Presses {
+    // Whether the user asked to quit.
+    quit: bool,
+    // The keypad keys pressed (even if they've been released again since).
+    keypad: [bool; KEYPAD_SIZE],
+    // The other keys pressed, in order.
+    hotkeys: Vec<Scancode>,
+}
+
 /// A struct describing the interactible aspects of an emulated device
 /// and the machinery required to operate them.
 pub struct Hardware {
-    // Whether or not debug mode is enabled (if so, verbose logging is emitted).
-    pub debug: bool,
     // The title of this device (which may be used in display-related components).
     title: String,
     // SDL components.
+    // (The context keeps SDL running for as long as the hardware is around,
+    // and opens the debug window.)
+    #[cfg_attr(not(feature = "debugger"), allow(dead_code))]
     sdl: sdl2::Sdl,
     canvas: sdl2::render::Canvas<sdl2::video::Window>,
-    // The event pump is wrapped in an optional so that it does not
-    // need to be initialized at struct creation time.
-    // This is helpful for testing and also borrow-checking.
+    // How screen pixels are placed inside of the window,
+    // and whether the screen must be drawn again (for example, because the window
+    // was resized) before the game next draws it.
+    viewport: Viewport,
+    needs_redraw: bool,
+    // How screen pixels are drawn once placed.
+    filter: Box<dyn RenderFilter>,
+    // How cleared pixels fade out (if they do).
+    phosphor: Phosphor,
+    // Whether scanlines are drawn over the screen.
+    scanlines: bool,
+    // The colors the screen is drawn with, and the theme they were picked from
+    // (and whether it was switched to while running, so needs remembering).
+    palette: Palette,
+    theme: Theme,
+    theme_changed: bool,
+    // The buzzer (if an audio device could be opened).
+    #[cfg(feature = "audio")]
+    buzzer: Option<Buzzer>,
+    // Whether to pause once the first frame has been drawn,
+    // and whether the screen has been drawn yet.
+    start_paused: bool,
+    drawn: bool,
+    // The event pump is wrapped in an optional so that
+    // it can be borrowed apart from the rest of the hardware.
     events: Option<sdl2::EventPump>,
+    // Which host keys are held (including those bound to each keypad key).
+    host_keyboard: HostKeyboard,
+    // Connected gamepads, which can press keypad keys too.
+    gamepads: Gamepads,
     // An array of keyboard keys, true for each key if currently pressed
-    // (this remains true while the key is held down).
-    keyboard: [bool; KEYBOARD_LAYOUT.len()],
+    // (this remains true while the key is held down, and for one check
+    // after a key was tapped in between checks).
+    keyboard: [bool; KEYPAD_SIZE],
+    // An optional (diagnostic) delay applied to keyboard changes.
+    input_delay: InputDelay<KEYPAD_SIZE>,
+    // Whether the keypad overlay is shown.
+    keypad_overlay: bool,
+    // Whether to keep running while the window is unfocused (instead of pausing),
+    // and whether the window has lost focus since we last checked.
+    keep_running_unfocused: bool,
+    focus_lost: bool,
+    // The number shown on screen (the speed or volume, after it changes)
+    // and when it disappears, if any.
+    osd: Option<(u64, Instant)>,
+    // Whether to show how fast emulation is running, and the latest measurements
+    // (frames, then instructions per second), if any.
+    stats_overlay: bool,
+    stats: Option<(u64, u64)>,
+    // How fast emulation should run
+    // (following the fast-forward or slow-motion key while it's held).
+    playback_speed: PlaybackSpeed,
+    // The GIF being recorded (if any), and where recordings are saved.
+    #[cfg(feature = "recorder")]
+    gif: Option<GifRecorder>,
+    #[cfg(feature = "recorder")]
+    gif_path: Option<String>,
+    // Where audio recordings are saved (the recording itself is kept by the buzzer).
+    #[cfg(all(feature = "audio", feature = "recorder"))]
+    wav_path: Option<String>,
+    // The debug window (if open).
+    #[cfg(feature = "debugger")]
+    debug_window: Option<DebugWindow>,
+    // The debug overlay (if shown).
+    #[cfg(feature = "egui")]
+    debug_overlay: Option<EguiOverlay>,
 }
 
 impl Hardware {
-    pub fn new(screen: &Screen, debug: bool, title: &str) -> Hardware {
-        // We allow SDL initialization actions to fail with panics
-        // as that likely indicates a problem with SDL setup
-        // or misuse here!
-        let sdl = sdl2::init().expect("SDL initialization failed.");
-        let window = sdl
+    /// Open the window (and set up input and sound), returning an error
+    /// if SDL can't be set up (which likely indicates a problem with SDL itself).
+    pub fn new(screen: &Screen, config: &Config, title: &str) -> Result<Hardware, Chip8Error> {
+        let viewport = Viewport::new(screen, config);
+        let (width, height) = viewport.window_size();
+        let sdl = sdl2::init().map_err(Chip8Error::Sdl)?;
+        let mut window = sdl
             .video()
-            .expect("SDL video initialization failed.")
-            .window(&title, screen.width, screen.height)
+            .map_err(Chip8Error::Sdl)?
+            .window(title, width, height)
             .position_centered()
+            .resizable()
             .build()
-            .unwrap_or_else(|_| {
-                panic!(
-                    "SDL window creation ({} x {}) failed.",
-                    screen.width, screen.height
-                )
-            });
+            .map_err(|err| {
+                Chip8Error::Sdl(format!(
+                    "window creation ({width} x {height}) failed: {err}"
+                ))
+            })?;
+        // (Don't let the window shrink below one window pixel per screen pixel.)
+        let (min_width, min_height) = viewport.minimum_window_size();
+        window
+            .set_minimum_size(min_width, min_height)
+            .map_err(|err| Chip8Error::Sdl(err.to_string()))?;
+        // (The event pump is a singleton, so it's created once, here.)
+        let events = sdl.event_pump().map_err(Chip8Error::Sdl)?;
+
+        let gamepads = Gamepads::new(&sdl, &config.gamepad_bindings);
+        // (Games are still playable without sound, so a missing audio device isn't fatal.)
+        #[cfg(feature = "audio")]
+        let buzzer = Buzzer::new(
+            &sdl,
+            config.beep_frequency,
+            config.beep_volume,
+            config.beep_waveform,
+        )
+        .inspect_err(|err| warn!("Failed to open audio device: {err}"))
+        .ok();
+        let mut canvas = window.into_canvas();
+        if config.vsync {
+            canvas = canvas.present_vsync();
+        }
 
-        Hardware {
-            debug,
+        Ok(Hardware {
             title: String::from(title),
             sdl,
-            canvas: window
-                .into_canvas()
+            canvas: canvas
                 .build()
-                .expect("Canvas initialization failed."),
-            events: None,
-            keyboard: [false; KEYBOARD_LAYOUT.len()],
+                .map_err(|err| Chip8Error::Sdl(err.to_string()))?,
+            viewport,
+            needs_redraw: false,
+            filter: config.render_style.filter(),
+            phosphor: Phosphor::new(config.phosphor),
+            scanlines: config.scanlines,
+            palette: config.palette,
+            theme: config.theme,
+            theme_changed: false,
+            #[cfg(feature = "audio")]
+            buzzer,
+            start_paused: config.start_paused,
+            drawn: false,
+            events: Some(events),
+            host_keyboard: HostKeyboard::new(Self::layout(&config.key_bindings)?),
+            gamepads,
+            keyboard: [false; KEYPAD_SIZE],
+            input_delay: InputDelay::new(config.input_latency, config.input_jitter),
+            keypad_overlay: false,
+            keep_running_unfocused: config.keep_running_unfocused,
+            focus_lost: false,
+            osd: None,
+            stats_overlay: false,
+            stats: None,
+            playback_speed: PlaybackSpeed::Normal,
+            #[cfg(feature = "recorder")]
+            gif: None,
+            #[cfg(feature = "recorder")]
+            gif_path: config.gif_path(),
+            #[cfg(all(feature = "audio", feature = "recorder"))]
+            wav_path: config.wav_path(),
+            #[cfg(feature = "debugger")]
+            debug_window: None,
+            #[cfg(feature = "egui")]
+            debug_overlay: None,
+        })
+    }
+
+    /// Resolve the user's key bindings into host keys,
+    /// returning an error naming the first host key that doesn't exist.
+    pub fn layout(key_bindings: &KeyBindings) -> Result<[Scancode; KEYPAD_SIZE], Error> {
+        let mut layout = [Scancode::Num0; KEYPAD_SIZE];
+        for (scancode, name) in layout.iter_mut().zip(key_bindings.names()) {
+            *scancode = Scancode::from_name(name).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Unknown key name: '{name}'"),
+                )
+            })?;
         }
+        Ok(layout)
     }
 
     fn draw_rect(&mut self, rect: Rect) {
@@ -84,15 +276,14 @@ impl Hardware {
             .expect("Failed to draw rectangle!");
     }
 
-    fn handle_pause(&mut self, screen: &Screen) -> bool {
-        // The pause key has been pressed, so we must
-        // draw the pause icon on the screen
+    fn handle_pause(&mut self, screen: &Screen, key_pressed: bool) -> bool {
+        // The pause key has been pressed (or we were asked to start paused),
+        // so we must draw the pause icon on the screen
         // and wait until we either quit or resume.
-        if self.debug {
-            println!("Pausing!");
-        }
+        debug!("Pausing!");
 
         self.draw_pause(screen);
+        self.silence_buzzer();
 
         let Some(event_pump) = &mut self.events else {
             // If the event pump is gone, we're already quitting,
@@ -114,9 +305,13 @@ impl Hardware {
         //   Once that's delivered, a KeyDown event followed by a KeyUp event
         //   for the pause key will unpause the emulation.
         //   Note that we can still quit while this is all happening.
+        //   (When we weren't paused by the key, there's no press to wait out.)
         let mut key_raised = false;
-        let mut key_released = false;
+        let mut key_released = !key_pressed;
+        let main_window = self.canvas.window().id();
         for event in event_pump.wait_iter() {
+            // (Keys released while paused are still followed, so that none are left held.)
+            self.host_keyboard.handle_event(&event);
             match event {
                 // (a)
                 Event::Quit { .. }
@@ -124,18 +319,24 @@ impl Hardware {
                     scancode: Some(KEY_QUIT),
                     ..
                 } => {
-                    if self.debug {
-                        println!("Quitting!");
-                    }
+                    debug!("Quitting!");
+                    return false;
+                }
+                // (With the debug window open, closing the main window doesn't send
+                // the Quit event, so that's checked for separately.)
+                Event::Window {
+                    window_id,
+                    win_event: WindowEvent::Close,
+                    ..
+                } if window_id == main_window => {
+                    debug!("Quitting!");
                     return false;
                 }
                 Event::KeyDown {
                     scancode: Some(KEY_PAUSE),
                     ..
                 } if key_released => {
-                    if self.debug {
-                        println!("Saw Pause Keydown!");
-                    }
+                    debug!("Saw Pause Keydown!");
                     key_raised = true;
                 }
                 Event::KeyUp {
@@ -144,97 +345,512 @@ impl Hardware {
                 } => {
                     // (b)
                     if key_raised {
-                        if self.debug {
-                            println!("Unpausing!");
-                        }
+                        debug!("Unpausing!");
                         // Clear "Pause" icon here.
 
                         break;
                     } else {
-                        if self.debug {
-                            println!("First key up!");
-                        }
+                        debug!("First key up!");
 
                         // Else, this is the key up from the actual pause press.
                         key_released = true;
                     }
                 }
-                _ => (),
+                _ => self.gamepads.handle_event(&event),
             }
         }
 
         // We've unpaused, so it's time to re-draw the screen and resume.
-        self.update_display(&screen);
+        self.update_display(screen);
         true
     }
 
-    pub fn handle_quit(&mut self) -> bool {
-        // Check for quit (note that unlike handle_pause()
-        // the quit key has not necessarily been pressed).
-
+    fn handle_events(&mut self) -> Presses {
+        // Go through every event since we last checked, following keys
+        // as they're pressed and released, and collecting what the user asked for.
+        let mut presses = Presses::default();
         let Some(event_pump) = &mut self.events else {
             // If the event pump is gone, we're quitting by definition.
+            presses.quit = true;
+            return presses;
+        };
+
+        // (Gamepads connecting or disconnecting, the window
+        // losing focus, and the debug window closing, are also handled here.)
+        let main_window = self.canvas.window().id();
+        for event in event_pump.poll_iter() {
+            // (The debug overlay follows the mouse, on top of anything else.)
+            #[cfg(feature = "egui")]
+            if let Some(debug_overlay) = &mut self.debug_overlay {
+                self.needs_redraw |= debug_overlay.handle_event(&event, main_window);
+            }
+            match event {
+                // Quitting can happen via either the quit key being pressed
+                // or the SDL quit event being sent through the event pump.
+                Event::Quit { .. }
+                | Event::KeyDown {
+                    scancode: Some(KEY_QUIT),
+                    ..
+                } => {
+                    debug!("Quitting!");
+                    presses.quit = true;
+                }
+                // (With the debug window open, closing either window
+                // doesn't send the Quit event, so we check which one closed.)
+                Event::Window {
+                    window_id,
+                    win_event: WindowEvent::Close,
+                    ..
+                } => {
+                    if window_id == main_window {
+                        debug!("Quitting!");
+                        presses.quit = true;
+                    }
+                    #[cfg(feature = "debugger")]
+                    if self
+                        .debug_window
+                        .as_ref()
+                        .is_some_and(|debug_window| debug_window.id() == window_id)
+                    {
+                        self.debug_window = None;
+                    }
+                }
+                Event::Window {
+                    win_event: WindowEvent::FocusLost,
+                    ..
+                } => self.focus_lost = true,
+                Event::Window {
+                    win_event: WindowEvent::FocusGained,
+                    ..
+                } => self.focus_lost = false,
+                Event::Window {
+                    window_id,
+                    win_event: WindowEvent::SizeChanged(width, height),
+                    ..
+                } if window_id == main_window => {
+                    self.viewport.resize(width as u32, height as u32);
+                    self.needs_redraw = true;
+                }
+                _ => match self.host_keyboard.handle_event(&event) {
+                    Some(KeyPress::Keypad(key)) => presses.keypad[key] = true,
+                    Some(KeyPress::Hotkey(hotkey)) => presses.hotkeys.push(hotkey),
+                    None => self.gamepads.handle_event(&event),
+                },
+            }
+        }
+
+        presses
+    }
+
+    fn handle_focus_loss(&mut self, screen: &Screen) -> bool {
+        // The window has lost focus, so (unless told otherwise) we pause
+        // until it regains focus or we quit, just like handle_pause().
+        self.focus_lost = false;
+        if self.keep_running_unfocused {
+            return true;
+        }
+        debug!("Pausing while unfocused!");
+
+        self.draw_pause(screen);
+        self.silence_buzzer();
+
+        let Some(event_pump) = &mut self.events else {
+            // If the event pump is gone, we're already quitting.
             return false;
         };
-        let keyboard_state = event_pump.keyboard_state();
 
-        // Quitting can happen via either (a) the quit key being pressed
-        // or (b) the SDL quit event being sent through the event pump.
+        let main_window = self.canvas.window().id();
+        for event in event_pump.wait_iter() {
+            self.host_keyboard.handle_event(&event);
+            match event {
+                Event::Quit { .. }
+                | Event::KeyDown {
+                    scancode: Some(KEY_QUIT),
+                    ..
+                } => {
+                    debug!("Quitting!");
+                    return false;
+                }
+                Event::Window {
+                    window_id,
+                    win_event: WindowEvent::Close,
+                    ..
+                } if window_id == main_window => {
+                    debug!("Quitting!");
+                    return false;
+                }
+                Event::Window {
+                    win_event: WindowEvent::FocusGained,
+                    ..
+                } => {
+                    debug!("Unpausing now that we're focused!");
+                    break;
+                }
+                _ => self.gamepads.handle_event(&event),
+            }
+        }
+
+        // We've unpaused, so it's time to re-draw the screen and resume.
+        self.update_display(screen);
+        true
+    }
+
+    fn handle_zoom(&mut self, pressed: [bool; KEYS_PAN.len() + 1], screen: &Screen) {
+        // Act on magnifier keys that were pressed since we last checked
+        // (the zoom key, then each pan key).
+        // These only change how the screen is presented (never the game),
+        // so we redraw right away instead of waiting for the game to draw.
+        let mut changed = false;
 
-        // (a)
-        if keyboard_state.is_scancode_pressed(KEY_QUIT) {
-            if self.debug {
-                println!("Quitting due to escape key!");
+        if pressed[0] {
+            debug!("Toggling magnifier!");
+            self.viewport.toggle_zoom();
+            changed = true;
+        }
+
+        if self.viewport.is_zoomed() {
+            for (index, (_, (x_steps, y_steps))) in KEYS_PAN.iter().enumerate() {
+                if pressed[index + 1] {
+                    let (x_steps, y_steps) = self.viewport.screen_direction(*x_steps, *y_steps);
+                    self.viewport.pan(x_steps, y_steps);
+                    changed = true;
+                }
             }
-            return false;
         }
 
-        // (b)
-        for event in event_pump.poll_iter() {
-            if let Event::Quit { .. } = event {
-                if self.debug {
-                    println!("Quitting!");
+        if changed {
+            self.update_display(screen);
+        }
+    }
+
+    fn handle_keypad_overlay(
+        &mut self,
+        pressed: bool,
+        previous_keyboard: [bool; KEYPAD_SIZE],
+        screen: &Screen,
+    ) {
+        // Toggle the keypad overlay if its key was pressed since we last checked,
+        // and redraw right away if it changed (or shows keys that changed).
+        if pressed {
+            debug!("Toggling keypad overlay!");
+            self.keypad_overlay = !self.keypad_overlay;
+        }
+
+        if pressed || (self.keypad_overlay && previous_keyboard != self.keyboard) {
+            self.update_display(screen);
+        }
+    }
+
+    fn handle_theme(&mut self, pressed: bool, screen: &Screen) {
+        // Switch to the next theme if its key was pressed since we last checked
+        // (remembering it for the next run once we're done, see `save_theme()`).
+        if !pressed {
+            return;
+        }
+
+        self.theme = self.theme.next();
+        self.palette = self.theme.palette();
+        self.theme_changed = true;
+        debug!("Switching to the {:?} theme!", self.theme);
+        self.update_display(screen);
+    }
+
+    fn save_theme(&mut self) {
+        // Remember the theme switched to (if any) for the next run,
+        // writing the settings once when we're done rather than on every switch.
+        if !self.theme_changed {
+            return;
+        }
+        let mut settings = Settings::load();
+        settings.theme = Some(self.theme);
+        if let Err(err) = settings.save() {
+            warn!("Failed to save settings: {err}");
+        }
+        self.theme_changed = false;
+    }
+
+    fn draw_phosphor(&mut self, screen: &Screen) {
+        // Draw cleared pixels that are still fading out,
+        // somewhere between the foreground and background colors.
+        self.phosphor.update(screen, Instant::now());
+        let fading: Vec<(Rect, f64)> = self
+            .phosphor
+            .fading(screen)
+            .filter_map(|(x, y, glow)| Some((self.viewport.pixel_rect(x, y)?, glow)))
+            .collect();
+
+        let Palette {
+            foreground,
+            background,
+        } = self.palette;
+        let blend = |from: u8, to: u8, amount: f64| {
+            (f64::from(from) + (f64::from(to) - f64::from(from)) * amount) as u8
+        };
+        for (rect, glow) in fading {
+            self.canvas.set_draw_color(Color::RGB(
+                blend(background.0, foreground.0, glow),
+                blend(background.1, foreground.1, glow),
+                blend(background.2, foreground.2, glow),
+            ));
+            self.draw_rect(rect);
+        }
+    }
+
+    fn handle_scanlines(&mut self, pressed: bool, screen: &Screen) {
+        // Toggle scanlines if their key was pressed since we last checked.
+        if pressed {
+            debug!("Toggling scanlines!");
+            self.scanlines = !self.scanlines;
+            self.update_display(screen);
+        }
+    }
+
+    fn draw_scanlines(&mut self) {
+        // Darken every few rows of the window, like the gaps between a CRT's scanlines.
+        // (These follow the window rather than the screen, just like a real monitor.)
+        let (width, height) = self
+            .canvas
+            .output_size()
+            .expect("Failed to get window size!");
+        let lines: Vec<Rect> = (0..height)
+            .step_by(SCANLINE_SPACING)
+            .map(|y| Rect::new(0, y as i32, width, 1))
+            .collect();
+
+        self.canvas.set_blend_mode(BlendMode::Blend);
+        self.canvas
+            .set_draw_color(Color::RGBA(0, 0, 0, SCANLINE_ALPHA));
+        self.canvas
+            .fill_rects(&lines)
+            .expect("Failed to draw scanlines!");
+        self.canvas.set_blend_mode(BlendMode::None);
+    }
+
+    #[cfg(feature = "recorder")]
+    fn handle_gif(&mut self, pressed: bool, screen: &Screen) {
+        // Start or stop recording if the recording key was pressed since we last checked,
+        // and otherwise keep capturing frames while recording.
+        let now = Instant::now();
+        if !pressed {
+            if let Some(gif) = &mut self.gif {
+                if let Err(error) = gif.capture(screen, now) {
+                    warn!("Failed to record GIF: {error}");
+                    self.gif = None;
                 }
-                return false;
             }
+            return;
         }
 
-        // We're not quitting.
-        true
+        if self.gif.is_some() {
+            self.save_gif(now);
+        } else {
+            debug!("Recording a GIF!");
+            self.gif = GifRecorder::new(screen, self.palette, now)
+                .inspect_err(|error| warn!("Failed to start recording a GIF: {error}"))
+                .ok();
+        }
+    }
+
+    #[cfg(feature = "recorder")]
+    fn save_gif(&mut self, now: Instant) {
+        // Stop recording (if we are), and save the recording.
+        let (Some(gif), Some(path)) = (self.gif.take(), &self.gif_path) else {
+            return;
+        };
+        debug!("Saving GIF to {path}!");
+        if let Err(error) = gif.save(path, now) {
+            warn!("Failed to save GIF: {error}");
+        }
+    }
+
+    #[cfg(all(feature = "audio", feature = "recorder"))]
+    fn handle_wav(&mut self, pressed: bool) {
+        // Start or stop recording audio if its key was pressed since we last checked.
+        if !pressed {
+            return;
+        }
+        let Some(buzzer) = &mut self.buzzer else {
+            warn!("Can't record audio without an audio device.");
+            return;
+        };
+
+        match buzzer.stop_recording() {
+            Some(wav) => self.save_wav(wav),
+            None => {
+                debug!("Recording audio!");
+                buzzer.start_recording();
+            }
+        }
+    }
+
+    #[cfg(all(feature = "audio", feature = "recorder"))]
+    fn save_wav(&self, wav: WavRecorder) {
+        // Save an audio recording.
+        let Some(path) = &self.wav_path else {
+            return;
+        };
+        debug!("Saving WAV to {path}!");
+        if let Err(error) = wav.save(path) {
+            warn!("Failed to save WAV: {error}");
+        }
+    }
+
+    fn handle_stats(&mut self, pressed: bool, screen: &Screen) {
+        // Toggle the stats overlay if its key was pressed since we last checked.
+        if pressed {
+            debug!("Toggling stats overlay!");
+            self.stats_overlay = !self.stats_overlay;
+            self.update_display(screen);
+        }
+    }
+
+    #[cfg(feature = "audio")]
+    fn handle_volume(&mut self, pressed: [bool; 2], screen: &Screen) {
+        // Turn the buzzer up (or down) if a volume key was pressed since we last checked,
+        // and show the new volume on screen.
+        let Some(buzzer) = &mut self.buzzer else {
+            return;
+        };
+        let volume = if pressed[0] {
+            buzzer.volume().saturating_add(VOLUME_STEP)
+        } else if pressed[1] {
+            buzzer.volume().saturating_sub(VOLUME_STEP)
+        } else {
+            return;
+        };
+
+        buzzer.set_volume(volume);
+        let volume = buzzer.volume();
+        debug!("Setting volume to {volume}%!");
+        self.osd = Some((u64::from(volume), Instant::now() + OSD_DURATION));
+        self.update_display(screen);
+    }
+
+    #[cfg(feature = "debugger")]
+    fn handle_debug_window(&mut self, pressed: bool) {
+        // Open or close the debug window if its key was pressed since we last checked.
+        if !pressed {
+            return;
+        }
+
+        if self.debug_window.take().is_some() {
+            debug!("Closing debug window!");
+            return;
+        }
+        debug!("Opening debug window!");
+        let debug_window = self
+            .sdl
+            .video()
+            .map_err(Error::other)
+            .and_then(|video| DebugWindow::new(&video));
+        match debug_window {
+            Ok(debug_window) => self.debug_window = Some(debug_window),
+            Err(err) => {
+                warn!("Failed to open debug window: {err}");
+            }
+        }
+    }
+
+    #[cfg(feature = "egui")]
+    fn handle_debug_overlay(&mut self, pressed: bool, screen: &Screen) {
+        // Show or hide the debug overlay if its key was pressed since we last checked.
+        if !pressed {
+            return;
+        }
+
+        if self.debug_overlay.take().is_some() {
+            debug!("Hiding debug overlay!");
+        } else {
+            debug!("Showing debug overlay!");
+            self.debug_overlay = Some(EguiOverlay::new());
+        }
+        self.update_display(screen);
+    }
+
+    fn draw_thumbnail(&mut self, screen: &Screen) {
+        // While magnified, draw a small copy of the full screen in a corner,
+        // outlining the magnified region, so the player doesn't get lost.
+        self.canvas.set_draw_color(Color::GRAY);
+        self.draw_rect(self.viewport.thumbnail());
+
+        self.canvas
+            .set_draw_color(Color::from(self.palette.foreground));
+        for (setx, sety) in screen {
+            let rect = self.viewport.thumbnail_pixel_rect(setx as u32, sety as u32);
+            self.draw_rect(rect);
+        }
+
+        self.canvas.set_draw_color(Color::YELLOW);
+        self.canvas
+            .draw_rect(self.viewport.thumbnail_zoom_region())
+            .expect("Failed to draw rectangle!");
+    }
+
+    fn draw_keypad_overlay(&mut self) {
+        // Draw the keypad in a corner, lighting up the keys that are pressed.
+        for (row, keys) in KEYPAD_GRID.iter().enumerate() {
+            for (column, key) in keys.iter().enumerate() {
+                let rect = self.viewport.keypad_cell(row as u32, column as u32);
+                if self.keyboard[*key] {
+                    self.canvas.set_draw_color(Color::YELLOW);
+                    self.draw_rect(rect);
+                } else {
+                    self.canvas.set_draw_color(Color::GRAY);
+                    self.canvas
+                        .draw_rect(rect)
+                        .expect("Failed to draw rectangle!");
+                }
+            }
+        }
+    }
+
+    fn draw_osd(&mut self, text: &str, line: u32, color: Color) {
+        // Draw a line of text in the top right corner of the window.
+        self.canvas.set_draw_color(color);
+        let length = text.chars().count() as u32;
+        for (index, character) in text.chars().enumerate() {
+            for (x, y) in font::pixels(character) {
+                let rect =
+                    self.viewport
+                        .osd_pixel(x, y, index as u32, length, line, font::GLYPH_SIZE);
+                self.draw_rect(rect);
+            }
+        }
+    }
+
+    fn silence_buzzer(&mut self) {
+        // Stop the buzzer while paused
+        // (the game turns it back on once it's running again, if needed).
+        #[cfg(feature = "audio")]
+        self.set_buzzer(false);
     }
 
     fn draw_pause(&mut self, screen: &Screen) {
         // We want to draw a pause icon in the middle of the screen.
 
-        // First, clear the screen (draw it entirely black).
-        self.canvas.set_draw_color(Color::BLACK);
+        // First, clear the screen (draw it entirely in the background color).
+        self.canvas
+            .set_draw_color(Color::from(self.palette.background));
         self.canvas.clear();
 
-        // Next, draw the left hand side bar of the pause icon in white.
-        self.canvas.set_draw_color(Color::WHITE);
+        // Next, draw the left hand side bar of the pause icon in the foreground color.
+        // (The icon is laid out in screen pixels, so that it follows
+        // the same orientation as the game itself.)
+        self.canvas
+            .set_draw_color(Color::from(self.palette.foreground));
         let xcoord = (screen.res_width / 2) - (screen.res_width / 12); // Roughly lhs of middle of screen.
         let ycoord = screen.res_height / 3; // Roughly top of middle of screen.
-        let height = screen.height / 3;
-        // If the coordinate is in bounds, it should be safe to convert to / from 32-bit types.
-        if screen.in_bounds(xcoord, ycoord) {
-            let rect = Rect::new(
-                (xcoord * screen.x_display_scale) as i32,
-                (ycoord * screen.y_display_scale) as i32,
-                screen.x_display_scale,
-                height,
-            );
+        let height = screen.res_height / 3;
+        if screen.in_bounds(xcoord, ycoord + height - 1) {
+            let rect = self.viewport.rect(xcoord, ycoord, 1, height);
             self.draw_rect(rect);
         }
 
-        // Now, draw the right hand side bar of the pause icon in white.
+        // Now, draw the right hand side bar of the pause icon.
         let xcoord = (screen.res_width / 2) + (screen.res_width / 12); // Roughly rhs of middle of screen.
-        if screen.in_bounds(xcoord, ycoord) {
-            let rect = Rect::new(
-                (xcoord * screen.x_display_scale) as i32,
-                (ycoord * screen.y_display_scale) as i32,
-                screen.x_display_scale,
-                height, // Same height as other drawn rectangle.
-            );
+        if screen.in_bounds(xcoord, ycoord + height - 1) {
+            // Same height as other drawn rectangle.
+            let rect = self.viewport.rect(xcoord, ycoord, 1, height);
             self.draw_rect(rect);
         }
 
@@ -244,15 +860,7 @@ impl Hardware {
 
 impl Interactible for Hardware {
     fn init(&mut self) {
-        // Another reason we construct the event pump here:
-        // it is a singleton, so we cannot reference the event pump
-        // *inside* of self.sdl inside of the constructor.
-        // (It's being moved there.)
-        self.events = Some(
-            self.sdl
-                .event_pump()
-                .expect("SDL event pump initialization failed."),
-        );
+        // (Nothing to do, everything was set up along with the window.)
     }
 
     fn set_title(&mut self, title: &str) -> Result<(), Error> {
@@ -267,27 +875,63 @@ impl Interactible for Hardware {
     }
 
     fn update_display(&mut self, screen: &Screen) {
-        // First, re-draw the entire canvas with black.
-        self.canvas.set_draw_color(Color::BLACK);
+        self.drawn = true;
+        if self.viewport.resolution() != (screen.res_width, screen.res_height) {
+            // The game switched resolutions, so lay the new one out in the window
+            // (which mustn't shrink below one window pixel per screen pixel).
+            self.viewport.set_resolution(screen);
+            let (min_width, min_height) = self.viewport.minimum_window_size();
+            if let Err(err) = self
+                .canvas
+                .window_mut()
+                .set_minimum_size(min_width, min_height)
+            {
+                warn!("Failed to set the minimum window size: {err}");
+            }
+        }
+
+        // First, re-draw the entire canvas with the background color.
+        self.canvas
+            .set_draw_color(Color::from(self.palette.background));
         self.canvas.clear();
 
-        // Next, draw the set pixels with white.
-        self.canvas.set_draw_color(Color::WHITE);
+        // Next, iterate over only the set pixels, and create an SDL Rect instance
+        // for each one, and have our filter draw them. (They will not visibly appear
+        // until the canvas itself is made visible).
+        // (The viewport takes care of any rotation, flipping, or magnification.)
+        if self.phosphor.is_enabled() {
+            self.draw_phosphor(screen);
+        }
+        let pixels: Vec<Rect> = screen
+            .into_iter()
+            .filter_map(|(setx, sety)| self.viewport.pixel_rect(setx as u32, sety as u32))
+            .collect();
+        self.filter
+            .draw(&mut self.canvas, &pixels, self.palette.foreground.into())
+            .expect("Failed to draw pixels!");
 
-        // Iterate over only the set pixels, and create an SDL Rect instance
-        // for each one, and draw it. (It will not visibly appear until
-        // the canvas itself is made visible).
-        for (setx, sety) in screen {
-            let xcoord = ((setx as u32) * screen.x_display_scale) as i32;
-            let ycoord = ((sety as u32) * screen.y_display_scale) as i32;
-
-            let rect = Rect::new(
-                xcoord,
-                ycoord,
-                screen.x_display_scale,
-                screen.y_display_scale,
-            );
-            self.draw_rect(rect);
+        if self.scanlines {
+            self.draw_scanlines();
+        }
+        if self.viewport.is_zoomed() {
+            self.draw_thumbnail(screen);
+        }
+        if self.keypad_overlay {
+            self.draw_keypad_overlay();
+        }
+        if let Some((number, _)) = self.osd {
+            self.draw_osd(&number.to_string(), 0, Color::GREEN);
+        }
+        if let Some((frames, instructions)) = self.stats.filter(|_| self.stats_overlay) {
+            // (Below the speed, which may be shown at the same time.)
+            self.draw_osd(&format!("{frames} FPS"), 1, Color::CYAN);
+            self.draw_osd(&format!("{instructions} IPS"), 2, Color::CYAN);
+        }
+        #[cfg(feature = "egui")]
+        if let Some(debug_overlay) = &mut self.debug_overlay {
+            if let Err(err) = debug_overlay.draw(&mut self.canvas) {
+                warn!("Failed to draw debug overlay: {err}");
+            }
         }
 
         // Make the canvas visible.
@@ -295,78 +939,112 @@ impl Interactible for Hardware {
     }
 
     fn set_keys(&mut self, screen: &Screen) -> SetKeysResult {
-        let Some(event_pump) = &mut self.events else {
-            // If the event pump is gone, we're already quitting,
-            // so don't process any keys this cycle (and exit!).
+        // Catch up on everything that happened since we last checked.
+        let presses = self.handle_events();
+        if presses.quit {
             return SetKeysResult::ShouldExit;
-        };
+        }
+        let pressed = |hotkey: Scancode| presses.hotkeys.contains(&hotkey);
 
-        // Check for keyboard input, and update our internal state
-        // for each key. (Because we do not have any perpetual listeners
-        // on the event pump, we must query each key's state individually.)
-        let keyboard_state = event_pump.keyboard_state();
-        for (index, key) in KEYBOARD_LAYOUT.iter().enumerate() {
-            if keyboard_state.is_scancode_pressed(*key) {
-                if self.debug {
-                    println!("{} was pressed!", *key);
-                }
-                self.keyboard[index] = true;
-            } else {
-                self.keyboard[index] = false;
-            }
+        // The game sees the keypad keys that are held, along with any that were
+        // pressed since we last checked (however briefly), and any held on gamepads.
+        let previous_keyboard = self.keyboard;
+        let mut keyboard = self.host_keyboard.keypad();
+        for (key, pressed) in keyboard.iter_mut().zip(presses.keypad) {
+            *key |= pressed;
         }
+        self.gamepads.press_keys(&mut keyboard);
+        // If we're simulating poor input conditions,
+        // the game only sees keyboard changes once they're due.
+        if self.input_delay.is_enabled() {
+            keyboard = self.input_delay.delay(keyboard, Instant::now());
+        }
+        self.keyboard = keyboard;
 
-        // Now that regular keys have been processed,
-        // check what action we will return to our caller.
-        //
-        // There are three actions:
-        // If a quit key has been pressed, we will ask our caller
-        // to start exiting. Otherwise, our caller will continue
-        // executing - but it may be asked to save its state out to disk.
-        //
-        // Processing this is slightly complicated, as we also
-        // handle pausing from this code as well first.
-        // We are guaranteed that when we run we are not paused,
-        // and we guarantee that we return unpaused - so
-        // any waiting is handled by pause-code invoked here.
-        // Note that quitting while paused is also supported,
-        // so both methods may instruct us to quit.
-        //
-        // Since pause-handling and quit-handling code both take control
-        // of the event pump, we must check if a save state was requested
-        // before we handle pausing (we don't support saving states during pauses).
-        // If either pause-handling code and quit-handling code
-        // told us to quit, we won't act on the save state request.
-        //
-        // Finally, it's important that quit-handling code runs last,
-        // as it cycles through the event pump and ensures we don't get
-        // duplicate results about any key presses (including pause / save state)
-        // the next time we're here.
-        let mut caller_action = SetKeysResult::ShouldContinue;
-
-        // Check if the save state key was pressed.
-        // If so, we'll return to our caller that it was pressed
-        // *only* if we're not pausing or quitting.
-        if keyboard_state.is_scancode_pressed(KEY_SAVE_STATE) {
-            if self.debug {
-                println!("Saving state!");
+        // (The fast-forward and slow-motion keys only last while they're held.)
+        self.playback_speed = if self.host_keyboard.is_held(KEY_FAST_FORWARD) {
+            PlaybackSpeed::FastForward
+        } else if self.host_keyboard.is_held(KEY_SLOW_MOTION) {
+            PlaybackSpeed::SlowMotion
+        } else {
+            PlaybackSpeed::Normal
+        };
+
+        // Pause if the pause key was pressed, once the first frame has been drawn
+        // when starting paused, or while the window is unfocused.
+        // We guarantee that we return unpaused, unless the user quit while paused.
+        if pressed(KEY_PAUSE) && !self.handle_pause(screen, self.host_keyboard.is_held(KEY_PAUSE)) {
+            return SetKeysResult::ShouldExit;
+        }
+        if self.start_paused && self.drawn {
+            self.start_paused = false;
+            if !self.handle_pause(screen, false) {
+                return SetKeysResult::ShouldExit;
             }
-            caller_action = SetKeysResult::ShouldSaveState;
         }
-
-        // Check if we need to pause (and if so, if we quit during the pause).
-        // (We don't allow saving states while paused, so we'll ignore
-        // any key presses above for saving states.)
-        if keyboard_state.is_scancode_pressed(KEY_PAUSE) && !self.handle_pause(&screen) {
+        if self.focus_lost && !self.handle_focus_loss(screen) {
             return SetKeysResult::ShouldExit;
         }
 
-        // Check if we need to quit - if not,
-        // we'll continue (and save state if we saw the key press above).
-        match self.handle_quit() {
-            false => SetKeysResult::ShouldExit,
-            true => caller_action,
+        // Magnifier and overlay keys don't interact with the game,
+        // so they're handled here.
+        let mut zoom_keys = [pressed(KEY_ZOOM); KEYS_PAN.len() + 1];
+        for (index, (key, _)) in KEYS_PAN.iter().enumerate() {
+            zoom_keys[index + 1] = pressed(*key);
+        }
+        self.handle_zoom(zoom_keys, screen);
+        self.handle_keypad_overlay(pressed(KEY_KEYPAD_OVERLAY), previous_keyboard, screen);
+        self.handle_theme(pressed(KEY_THEME), screen);
+        self.handle_scanlines(pressed(KEY_SCANLINES), screen);
+        self.handle_stats(pressed(KEY_STATS), screen);
+        #[cfg(feature = "recorder")]
+        self.handle_gif(pressed(KEY_RECORD_GIF), screen);
+        #[cfg(all(feature = "audio", feature = "recorder"))]
+        self.handle_wav(pressed(KEY_RECORD_WAV));
+        #[cfg(feature = "debugger")]
+        self.handle_debug_window(pressed(KEY_DEBUG_WINDOW));
+        #[cfg(feature = "egui")]
+        self.handle_debug_overlay(pressed(KEY_DEBUG_OVERLAY), screen);
+        #[cfg(feature = "audio")]
+        self.handle_volume([pressed(KEY_VOLUME_UP), pressed(KEY_VOLUME_DOWN)], screen);
+
+        // Clear the on-screen display once it has been shown for long enough,
+        // and keep fading out cleared pixels (even if the game has stopped drawing).
+        let now = Instant::now();
+        if self.osd.is_some_and(|(_, until)| now >= until) {
+            self.osd = None;
+            self.update_display(screen);
+        } else if self.phosphor.is_enabled() && self.phosphor.needs_redraw(screen, now) {
+            self.update_display(screen);
+        }
+        // Redraw right away if needed (so that, for example, the image fits a resized window).
+        if std::mem::take(&mut self.needs_redraw) {
+            self.update_display(screen);
         }
+
+        // Anything else the user asked for is left to our caller
+        // (the first of those keys pressed, if there were several).
+        presses
+            .hotkeys
+            .iter()
+            .find_map(|hotkey| match *hotkey {
+                KEY_SAVE_STATE => {
+                    debug!("Saving state!");
+                    Some(SetKeysResult::ShouldSaveState)
+                }
+                KEY_LOAD_STATE => {
+                    debug!("Loading state!");
+                    Some(SetKeysResult::ShouldLoadState)
+                }
+                KEY_DUMP_MEMORY => {
+                    debug!("Dumping memory!");
+                    Some(SetKeysResult::ShouldDumpMemory)
+                }
+                hotkey if KEYS_SPEED_UP.contains(&hotkey) => Some(SetKeysResult::ShouldSpeedUp),
+                hotkey if KEYS_SLOW_DOWN.contains(&hotkey) => Some(SetKeysResult::ShouldSlowDown),
+                _ => None,
+            })
+            .unwrap_or(SetKeysResult::ShouldContinue)
     }
 
     fn get_keys(&self) -> &[bool] {
@@ -377,11 +1055,105 @@ impl Interactible for Hardware {
     fn key_is_pressed(&self, key: u8) -> bool {
         self.keyboard[key as usize]
     }
+
+    fn playback_speed(&self) -> PlaybackSpeed {
+        self.playback_speed
+    }
+
+    fn show_speed(&mut self, instructions_per_frame: u64) {
+        debug!("Running {instructions_per_frame} instructions per frame!");
+        self.osd = Some((instructions_per_frame, Instant::now() + OSD_DURATION));
+    }
+
+    fn show_stats(&mut self, frames_per_second: u64, instructions_per_second: u64) {
+        self.stats = Some((frames_per_second, instructions_per_second));
+        if self.stats_overlay {
+            self.needs_redraw = true;
+        }
+    }
+
+    fn set_buzzer(&mut self, _playing: bool) {
+        #[cfg(feature = "audio")]
+        if let Some(buzzer) = &mut self.buzzer {
+            buzzer.set_playing(_playing);
+        }
+    }
+
+    fn show_machine_state(&mut self, _state: &MachineState) {
+        #[cfg(feature = "debugger")]
+        if let Some(debug_window) = &mut self.debug_window {
+            debug_window.draw(_state);
+        }
+        #[cfg(feature = "egui")]
+        if let Some(debug_overlay) = &mut self.debug_overlay {
+            self.needs_redraw |= debug_overlay.refresh(_state);
+        }
+    }
+}
+
+impl Drop for Hardware {
+    fn drop(&mut self) {
+        self.save_theme();
+        // Don't lose a recording that's still going when we quit.
+        #[cfg(feature = "recorder")]
+        self.save_gif(Instant::now());
+        #[cfg(all(feature = "audio", feature = "recorder"))]
+        if let Some(wav) = self.buzzer.as_mut().and_then(Buzzer::stop_recording) {
+            self.save_wav(wav);
+        }
+    }
 }
 
 impl Default for Hardware {
     fn default() -> Hardware {
+        // (Defaults can't fail, so failing to set up SDL panics here;
+        // use `Hardware::new()` to handle that instead.)
         let screen = Screen::default();
-        Hardware::new(&screen, false, NO_GAME_LOADED)
+        Hardware::new(&screen, &Config::default(), NO_GAME_LOADED).expect("SDL setup failed.")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::KeyLayout;
+    use clap::ValueEnum;
+
+    #[test]
+    fn layouts_leave_hotkeys_free() {
+        // Keys bound to keypad keys only act as keypad keys,
+        // so no built-in layout may take a hotkey's key.
+        let mut hotkeys = vec![
+            KEY_QUIT,
+            KEY_PAUSE,
+            KEY_SAVE_STATE,
+            KEY_LOAD_STATE,
+            KEY_DUMP_MEMORY,
+            KEY_ZOOM,
+            KEY_KEYPAD_OVERLAY,
+            KEY_THEME,
+            KEY_SCANLINES,
+            KEY_STATS,
+            KEY_FAST_FORWARD,
+            KEY_SLOW_MOTION,
+        ];
+        hotkeys.extend(KEYS_SPEED_UP.iter().chain(&KEYS_SLOW_DOWN));
+        hotkeys.extend(KEYS_PAN.iter().map(|(key, _)| key));
+        #[cfg(feature = "recorder")]
+        hotkeys.push(KEY_RECORD_GIF);
+        #[cfg(all(feature = "audio", feature = "recorder"))]
+        hotkeys.push(KEY_RECORD_WAV);
+        #[cfg(feature = "audio")]
+        hotkeys.extend([KEY_VOLUME_UP, KEY_VOLUME_DOWN]);
+
+        for &layout in KeyLayout::value_variants() {
+            let keys = Hardware::layout(&KeyBindings::from(layout)).unwrap();
+            for hotkey in &hotkeys {
+                assert!(
+                    !keys.contains(hotkey),
+                    "{layout:?} binds {hotkey:?}, hiding its hotkey"
+                );
+            }
+        }
     }
 }
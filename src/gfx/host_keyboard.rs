@@ -0,0 +1,84 @@
+use log::trace;
+use sdl2::event::Event;
+use sdl2::keyboard::Scancode;
+use std::collections::HashSet;
+
+/// A key pressed on the host keyboard, as reported by `HostKeyboard::handle_event()`.
+pub enum KeyPress {
+    // A key bound to a keypad key (given by its index).
+    Keypad(usize),
+    // Any other key (which may be a hotkey).
+    Hotkey(Scancode),
+}
+
+/// A struct tracking which host keys are held down, from their key events
+/// (so that no press is missed, however briefly the key is held).
+/// Keys bound to keypad keys are tracked as those keypad keys,
+/// and are never treated as hotkeys.
+pub struct HostKeyboard {
+    // The host keys bound to each keypad key.
+    layout: [Scancode; 16],
+    // Which keypad keys are held.
+    keypad: [bool; 16],
+    // Which other keys are held.
+    hotkeys: HashSet<Scancode>,
+}
+
+impl HostKeyboard {
+    pub fn new(layout: [Scancode; 16]) -> HostKeyboard {
+        HostKeyboard {
+            layout,
+            keypad: [false; 16],
+            hotkeys: HashSet::new(),
+        }
+    }
+
+    /// Follow a key being pressed or released,
+    /// returning the key if it was just pressed (key repeats don't count).
+    pub fn handle_event(&mut self, event: &Event) -> Option<KeyPress> {
+        match *event {
+            Event::KeyDown {
+                scancode: Some(scancode),
+                repeat: false,
+                ..
+            } => match self.keypad_key(scancode) {
+                Some(key) => {
+                    trace!(key:% = scancode, keypad_key = key; "{} was pressed!", scancode);
+                    self.keypad[key] = true;
+                    Some(KeyPress::Keypad(key))
+                }
+                None => {
+                    self.hotkeys.insert(scancode);
+                    Some(KeyPress::Hotkey(scancode))
+                }
+            },
+            Event::KeyUp {
+                scancode: Some(scancode),
+                ..
+            } => {
+                match self.keypad_key(scancode) {
+                    Some(key) => self.keypad[key] = false,
+                    None => {
+                        self.hotkeys.remove(&scancode);
+                    }
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Which keypad keys are held.
+    pub fn keypad(&self) -> [bool; 16] {
+        self.keypad
+    }
+
+    /// Whether a key that isn't bound to a keypad key is held.
+    pub fn is_held(&self, hotkey: Scancode) -> bool {
+        self.hotkeys.contains(&hotkey)
+    }
+
+    fn keypad_key(&self, scancode: Scancode) -> Option<usize> {
+        self.layout.iter().position(|key| *key == scancode)
+    }
+}
@@ -0,0 +1,132 @@
+use rand::Rng;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// How long one (60hz) frame lasts.
+const FRAME: Duration = Duration::from_nanos(16666667);
+
+/// A diagnostic delay queue for keypad input.
+/// Changes to the keypad are held back for a fixed latency
+/// (plus a random amount of jitter) before the emulated program sees them,
+/// to simulate playing under poor input conditions.
+pub struct InputDelay<const N: usize> {
+    // The fixed delay applied to every change.
+    latency: Duration,
+    // The maximum extra (random) delay applied to every change.
+    jitter: Duration,
+    // Changes that have not been delivered yet (and when they're due),
+    // oldest first.
+    pending: VecDeque<(Instant, [bool; N])>,
+    // The most recent keypad state that was queued.
+    queued: [bool; N],
+    // The keypad state currently seen by the emulated program.
+    delivered: [bool; N],
+}
+
+impl<const N: usize> InputDelay<N> {
+    /// Create a new delay queue, with latency and jitter given in frames.
+    pub fn new(latency_frames: u32, jitter_frames: u32) -> InputDelay<N> {
+        InputDelay {
+            latency: FRAME * latency_frames,
+            jitter: FRAME * jitter_frames,
+            pending: VecDeque::new(),
+            queued: [false; N],
+            delivered: [false; N],
+        }
+    }
+
+    /// Whether or not any delay is applied at all.
+    pub fn is_enabled(&self) -> bool {
+        !self.latency.is_zero() || !self.jitter.is_zero()
+    }
+
+    /// Queue the current (real) keypad state, and return
+    /// the (delayed) state the emulated program should see at `now`.
+    pub fn delay(&mut self, keys: [bool; N], now: Instant) -> [bool; N] {
+        if keys != self.queued {
+            let jitter = if self.jitter.is_zero() {
+                Duration::ZERO
+            } else {
+                rand::thread_rng().gen_range(Duration::ZERO..=self.jitter)
+            };
+
+            // Changes are never delivered out of order,
+            // even if jitter would make a newer change due first.
+            let mut due = now + self.latency + jitter;
+            if let Some((last_due, _)) = self.pending.back() {
+                due = due.max(*last_due);
+            }
+
+            self.pending.push_back((due, keys));
+            self.queued = keys;
+        }
+
+        while let Some((due, _)) = self.pending.front() {
+            if *due > now {
+                break;
+            }
+            if let Some((_, keys)) = self.pending.pop_front() {
+                self.delivered = keys;
+            }
+        }
+
+        self.delivered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Keypad states numbering each change (so their order can be read back).
+    fn keys(number: u8) -> [bool; 8] {
+        std::array::from_fn(|bit| number & (1 << bit) != 0)
+    }
+
+    fn number(keys: [bool; 8]) -> u8 {
+        (0..8).filter(|&bit| keys[bit]).map(|bit| 1 << bit).sum()
+    }
+
+    #[test]
+    fn changes_come_out_in_order() {
+        let mut delay = InputDelay::new(2, 0);
+        let start = Instant::now();
+        assert_eq!(delay.delay(keys(1), start), keys(0));
+        assert_eq!(delay.delay(keys(2), start + FRAME), keys(0));
+        assert_eq!(delay.delay(keys(2), start + FRAME * 2), keys(1));
+        assert_eq!(delay.delay(keys(2), start + FRAME * 3), keys(2));
+    }
+
+    #[test]
+    fn changes_are_held_back_for_the_latency() {
+        let mut delay = InputDelay::new(3, 0);
+        assert!(delay.is_enabled());
+        let start = Instant::now();
+        assert_eq!(delay.delay(keys(1), start), keys(0));
+        let due = start + FRAME * 3;
+        assert_eq!(delay.delay(keys(1), due - Duration::from_nanos(1)), keys(0));
+        assert_eq!(delay.delay(keys(1), due), keys(1));
+
+        // (Without latency or jitter, changes come straight through.)
+        let mut delay = InputDelay::new(0, 0);
+        assert!(!delay.is_enabled());
+        assert_eq!(delay.delay(keys(1), start), keys(1));
+    }
+
+    #[test]
+    fn jitter_never_reorders_changes() {
+        for _ in 0..100 {
+            let mut delay = InputDelay::new(1, 10);
+            let start = Instant::now();
+            let mut seen = 0;
+            for step in 0..200 {
+                // A new change every frame for a while, then just waiting for the rest.
+                let change = (step + 1).min(50);
+                let delivered = number(delay.delay(keys(change), start + FRAME * u32::from(step)));
+                assert!(delivered >= seen, "{delivered} came out after {seen}");
+                seen = delivered;
+            }
+            assert_eq!(seen, 50);
+        }
+    }
+}
@@ -13,6 +13,43 @@ pub enum SetKeysResult {
     // but should attempt to save its current
     // state to disk first.
     ShouldSaveState,
+    // Caller should continue execution,
+    // but should attempt to replace its current
+    // state with one loaded from disk first.
+    ShouldLoadState,
+    // Caller should continue execution,
+    // but should attempt to dump its memory
+    // to disk first.
+    ShouldDumpMemory,
+    // Caller should continue execution,
+    // but should run more (or fewer) instructions per frame.
+    ShouldSpeedUp,
+    ShouldSlowDown,
+}
+
+/// An enum describing how fast the user wants emulation to run
+/// (as of the last time keyboard input was processed).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlaybackSpeed {
+    // Run at the emulated program's speed.
+    Normal,
+    // Run several times faster than normal.
+    FastForward,
+    // Run several times slower than normal.
+    SlowMotion,
+}
+
+/// The emulated machine's state (as of the latest cycle),
+/// for hardware that can show it while the game runs.
+pub struct MachineState<'a> {
+    pub memory: &'a [u8],
+    pub registers: &'a [u8; 16],
+    pub index_reg: u16,
+    pub pc: u16,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    // Only the entries in use (the oldest first).
+    pub stack: &'a [u16],
 }
 
 /// A trait that describes the interactible aspects of an emulated device
@@ -28,4 +65,21 @@ pub trait Interactible {
     fn set_keys(&mut self, screen: &Screen) -> SetKeysResult;
     fn get_keys(&self) -> &[bool]; // True if pressed.
     fn key_is_pressed(&self, key: u8) -> bool; // True if pressed.
+    fn playback_speed(&self) -> PlaybackSpeed;
+
+    /// Briefly show how many instructions run per frame
+    /// (after the user has changed it).
+    fn show_speed(&mut self, instructions_per_frame: u64);
+
+    /// Report how fast emulation is actually running (measured over the last second),
+    /// for the hardware to show if the user asked for it.
+    fn show_stats(&mut self, frames_per_second: u64, instructions_per_second: u64);
+
+    /// Start or stop sounding the buzzer
+    /// (which should keep sounding until told to stop).
+    fn set_buzzer(&mut self, playing: bool);
+
+    /// Report the machine's state (after every cycle),
+    /// for the hardware to show if the user asked for it.
+    fn show_machine_state(&mut self, state: &MachineState);
 }
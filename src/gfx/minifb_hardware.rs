@@ -0,0 +1,304 @@
+use super::interactible::{Interactible, MachineState, PlaybackSpeed, SetKeysResult};
+use super::screen::Screen;
+use super::window_controls::{Hotkey, KeyNames, WindowControls};
+#[cfg(feature = "cpal")]
+use super::CpalBuzzer;
+use super::Drawable;
+use crate::config::{Config, Palette, Rgb};
+use crate::error::Chip8Error;
+use log::warn;
+use minifb::{Key, KeyRepeat, ScaleMode, Window, WindowOptions};
+use std::io::Error;
+use std::thread;
+use std::time::Duration;
+
+// Keyboard layout constants.
+const KEYPAD_SIZE: usize = 16;
+const KEY_NAMES: KeyNames<Key> = KeyNames {
+    letters: [
+        Key::A,
+        Key::B,
+        Key::C,
+        Key::D,
+        Key::E,
+        Key::F,
+        Key::G,
+        Key::H,
+        Key::I,
+        Key::J,
+        Key::K,
+        Key::L,
+        Key::M,
+        Key::N,
+        Key::O,
+        Key::P,
+        Key::Q,
+        Key::R,
+        Key::S,
+        Key::T,
+        Key::U,
+        Key::V,
+        Key::W,
+        Key::X,
+        Key::Y,
+        Key::Z,
+    ],
+    digits: [
+        Key::Key0,
+        Key::Key1,
+        Key::Key2,
+        Key::Key3,
+        Key::Key4,
+        Key::Key5,
+        Key::Key6,
+        Key::Key7,
+        Key::Key8,
+        Key::Key9,
+    ],
+    named: [
+        Key::Space,
+        Key::Enter,
+        Key::Up,
+        Key::Down,
+        Key::Left,
+        Key::Right,
+    ],
+};
+// How often the window is checked on while paused.
+const PAUSED_POLL_INTERVAL: Duration = Duration::from_millis(16);
+
+/// Hardware that draws the screen in a window with minifb (a much lighter
+/// dependency than SDL, which only needs the system's windowing libraries at runtime)
+/// and reads keys from it. There are no gamepads or debugging windows (or sound,
+/// without the `cpal` feature); the title bar shows what the SDL frontend's overlays would.
+pub struct MinifbHardware {
+    window: Window,
+    // The title bar, and how hotkeys are acted on.
+    controls: WindowControls,
+    // The colors the screen is drawn with (as minifb wants them), and the screen,
+    // as drawn last.
+    foreground: u32,
+    background: u32,
+    buffer: Vec<u32>,
+    // The keys bound to each keypad key, if any.
+    layout: [Option<Key>; KEYPAD_SIZE],
+    keyboard: [bool; KEYPAD_SIZE],
+    // The buzzer (if an audio device could be opened).
+    #[cfg(feature = "cpal")]
+    buzzer: Option<CpalBuzzer>,
+}
+
+impl MinifbHardware {
+    pub fn new(
+        screen: &Screen,
+        config: &Config,
+        title: &str,
+    ) -> Result<MinifbHardware, Chip8Error> {
+        let (width, height) = (screen.width as usize, screen.height as usize);
+        let options = WindowOptions {
+            resize: true,
+            scale_mode: ScaleMode::AspectRatioStretch,
+            ..WindowOptions::default()
+        };
+        let mut window = Window::new(title, width, height, options).map_err(|err| {
+            Chip8Error::Window(format!(
+                "window creation ({width} x {height}) failed: {err}"
+            ))
+        })?;
+        // (Frames are paced by the emulator, not by the window.)
+        window.set_target_fps(0);
+
+        let mut layout = [None; KEYPAD_SIZE];
+        for (key, name) in layout.iter_mut().zip(config.key_bindings.names()) {
+            *key = KEY_NAMES.key(name);
+            if key.is_none() {
+                warn!("The minifb frontend can't bind '{name}', so it's left unbound.");
+            }
+        }
+
+        // (Games are still playable without sound, so a missing audio device isn't fatal.)
+        #[cfg(feature = "cpal")]
+        let buzzer = CpalBuzzer::new(
+            config.beep_frequency,
+            config.beep_volume,
+            config.beep_waveform,
+        )
+        .inspect_err(|err| warn!("Failed to open audio device: {err}"))
+        .ok();
+        let Palette {
+            foreground,
+            background,
+        } = config.palette;
+        Ok(MinifbHardware {
+            window,
+            controls: WindowControls::new(title, config.start_paused),
+            foreground: Self::color(foreground),
+            background: Self::color(background),
+            buffer: Vec::new(),
+            layout,
+            keyboard: [false; KEYPAD_SIZE],
+            #[cfg(feature = "cpal")]
+            buzzer,
+        })
+    }
+
+    fn hotkeys(hotkey: Hotkey) -> &'static [Key] {
+        // The keys for each hotkey (as in the SDL frontend).
+        match hotkey {
+            Hotkey::Quit => &[Key::Escape],
+            Hotkey::Pause => &[Key::P],
+            Hotkey::SaveState => &[Key::F5],
+            Hotkey::LoadState => &[Key::F9],
+            Hotkey::DumpMemory => &[Key::O],
+            Hotkey::Stats => &[Key::I],
+            Hotkey::SpeedUp => &[Key::Equal, Key::NumPadPlus],
+            Hotkey::SlowDown => &[Key::Minus, Key::NumPadMinus],
+            Hotkey::FastForward => &[Key::Tab],
+            Hotkey::SlowMotion => &[Key::LeftShift],
+        }
+    }
+
+    fn hotkey(
+        window: &Window,
+        layout: &[Option<Key>],
+        hotkey: Hotkey,
+        repeat: Option<KeyRepeat>,
+    ) -> bool {
+        // Whether a hotkey was pressed since the window was last updated (or is held,
+        // without `repeat`). Hotkeys the user has bound to keypad keys are left to the keypad.
+        Self::hotkeys(hotkey).iter().any(|&key| {
+            !layout.contains(&Some(key))
+                && match repeat {
+                    Some(repeat) => window.is_key_pressed(key, repeat),
+                    None => window.is_key_down(key),
+                }
+        })
+    }
+
+    fn color(Rgb(red, green, blue): Rgb) -> u32 {
+        // A color as minifb draws it (0RGB).
+        u32::from_be_bytes([0, red, green, blue])
+    }
+
+    fn refresh_title(&mut self) {
+        if let Some(title) = self.controls.changed_title() {
+            self.window.set_title(&title);
+        }
+    }
+
+    fn wait_while_paused(&mut self) -> bool {
+        // Keep the window responsive (showing the screen as it was) until unpaused,
+        // returning false if the user quit instead.
+        self.window.set_title(&self.controls.title(true));
+        loop {
+            thread::sleep(PAUSED_POLL_INTERVAL);
+            self.window.update();
+            if !self.window.is_open()
+                || Self::hotkey(&self.window, &self.layout, Hotkey::Quit, None)
+            {
+                return false;
+            }
+            if Self::hotkey(
+                &self.window,
+                &self.layout,
+                Hotkey::Pause,
+                Some(KeyRepeat::No),
+            ) {
+                self.window.set_title(&self.controls.title(false));
+                return true;
+            }
+        }
+    }
+}
+
+impl Interactible for MinifbHardware {
+    fn init(&mut self) {}
+
+    fn set_title(&mut self, title: &str) -> Result<(), Error> {
+        self.controls.set_title(title);
+        self.refresh_title();
+        Ok(())
+    }
+
+    fn update_display(&mut self, screen: &Screen) {
+        // Draw a buffer pixel per screen pixel, and let minifb scale it to the window.
+        self.controls.drawn();
+        let (width, height) = (screen.res_width as usize, screen.res_height as usize);
+        self.buffer.clear();
+        for y in 0..height {
+            for x in 0..width {
+                self.buffer.push(if screen.get_pixel(x as u16, y as u16) {
+                    self.foreground
+                } else {
+                    self.background
+                });
+            }
+        }
+        if let Err(error) = self.window.update_with_buffer(&self.buffer, width, height) {
+            warn!("Failed to draw the screen: {error}");
+        }
+    }
+
+    fn set_keys(&mut self, screen: &Screen) -> SetKeysResult {
+        // (Drawing the screen also reads input, so only read it here in between.)
+        self.window.update();
+        if !self.window.is_open() {
+            return SetKeysResult::ShouldExit;
+        }
+
+        for (pressed, key) in self.keyboard.iter_mut().zip(&self.layout) {
+            *pressed = key.is_some_and(|key| self.window.is_key_down(key));
+        }
+        let (window, layout) = (&self.window, &self.layout);
+        let (mut caller_action, pause) = self
+            .controls
+            .action(|hotkey| Self::hotkey(window, layout, hotkey, Some(KeyRepeat::No)));
+        if pause {
+            self.update_display(screen);
+            // (The buzzer stays quiet while paused.)
+            self.set_buzzer(false);
+            if !self.wait_while_paused() {
+                return SetKeysResult::ShouldExit;
+            }
+            // (We don't allow saving states while paused, just like `Hardware`.)
+            caller_action = SetKeysResult::ShouldContinue;
+        }
+        self.refresh_title();
+        caller_action
+    }
+
+    fn get_keys(&self) -> &[bool] {
+        &self.keyboard
+    }
+
+    fn key_is_pressed(&self, key: u8) -> bool {
+        self.keyboard[key as usize]
+    }
+
+    fn playback_speed(&self) -> PlaybackSpeed {
+        WindowControls::playback_speed(|hotkey| {
+            Self::hotkey(&self.window, &self.layout, hotkey, None)
+        })
+    }
+
+    fn show_speed(&mut self, instructions_per_frame: u64) {
+        self.controls.show_speed(instructions_per_frame);
+        self.refresh_title();
+    }
+
+    fn show_stats(&mut self, frames_per_second: u64, instructions_per_second: u64) {
+        self.controls
+            .show_stats(frames_per_second, instructions_per_second);
+        self.refresh_title();
+    }
+
+    // (There's no sound without cpal.)
+    fn set_buzzer(&mut self, _playing: bool) {
+        #[cfg(feature = "cpal")]
+        if let Some(buzzer) = &mut self.buzzer {
+            buzzer.set_playing(_playing);
+        }
+    }
+
+    fn show_machine_state(&mut self, _state: &MachineState) {}
+}
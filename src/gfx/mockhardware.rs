@@ -1,22 +1,15 @@
-use super::interactible::{Interactible, SetKeysResult};
+use super::interactible::{Interactible, MachineState, PlaybackSpeed, SetKeysResult};
 use super::screen::Screen;
 
 #[derive(Default)]
 /// A placeholder struct for Hardware that is useful during testing
 /// when we cannot call any SDL methods (since our test runner
 /// may not run our tests on the main thread, which SDL strictly requires).
+/// Tests plug it in with `Chip8::tester()`.
 pub struct MockHardware {
-    pub debug: bool,
-    keyboard: [bool; 1],
-}
-
-impl MockHardware {
-    pub fn new(_screen: &Screen, debug: bool, _title: &str) -> MockHardware {
-        MockHardware {
-            debug,
-            keyboard: [false; 1],
-        }
-    }
+    // Sized like a real keypad so that instructions which
+    // derive offsets from the key count behave the same.
+    keyboard: [bool; 16],
 }
 
 impl Interactible for MockHardware {
@@ -39,4 +32,16 @@ impl Interactible for MockHardware {
     fn key_is_pressed(&self, _key: u8) -> bool {
         false
     }
+
+    fn playback_speed(&self) -> PlaybackSpeed {
+        PlaybackSpeed::Normal
+    }
+
+    fn show_speed(&mut self, _instructions_per_frame: u64) {}
+
+    fn show_stats(&mut self, _frames_per_second: u64, _instructions_per_second: u64) {}
+
+    fn set_buzzer(&mut self, _playing: bool) {}
+
+    fn show_machine_state(&mut self, _state: &MachineState) {}
 }
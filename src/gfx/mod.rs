@@ -1,13 +1,99 @@
+#[cfg(all(feature = "sdl", feature = "audio"))]
+mod buzzer;
+#[cfg(feature = "cpal")]
+mod cpal_buzzer;
+#[cfg(all(feature = "sdl", feature = "debugger"))]
+mod debug_window;
 mod drawable;
+#[cfg(feature = "egui")]
+mod egui_overlay;
+#[cfg(feature = "sdl")]
+mod font;
+#[cfg(feature = "sdl")]
+mod gamepads;
+#[cfg(feature = "recorder")]
+mod gif_recorder;
+#[cfg(feature = "sdl")]
 mod hardware;
+#[cfg(feature = "sdl")]
+mod host_keyboard;
+mod input_delay;
 mod interactible;
+#[cfg(feature = "minifb")]
+mod minifb_hardware;
 #[cfg(test)]
 mod mockhardware;
+#[cfg(feature = "netplay")]
+mod netplay;
+mod null_hardware;
+mod phosphor;
+#[cfg(feature = "pixels")]
+mod pixels_hardware;
+#[cfg(feature = "sdl")]
+mod render_filter;
+#[cfg(feature = "sdl")]
+mod render_thread;
+#[cfg(feature = "sdl")]
+mod rom_browser;
 mod screen;
+#[cfg(feature = "tui")]
+mod terminal_hardware;
+#[cfg(any(all(feature = "sdl", feature = "audio"), feature = "cpal"))]
+mod tone;
+#[cfg(feature = "sdl")]
+mod viewport;
+#[cfg(feature = "recorder")]
+mod wav_recorder;
+#[cfg(feature = "wgpu")]
+mod wgpu_hardware;
+#[cfg(any(feature = "minifb", feature = "pixels", feature = "wgpu"))]
+mod window_controls;
+#[cfg(any(feature = "pixels", feature = "wgpu"))]
+mod winit_hardware;
 
+#[cfg(all(feature = "sdl", feature = "audio"))]
+pub use buzzer::Buzzer;
+#[cfg(feature = "cpal")]
+pub use cpal_buzzer::CpalBuzzer;
+#[cfg(all(feature = "sdl", feature = "debugger"))]
+pub use debug_window::DebugWindow;
 pub use drawable::Drawable;
+#[cfg(feature = "egui")]
+pub use egui_overlay::EguiOverlay;
+#[cfg(feature = "sdl")]
+pub use gamepads::Gamepads;
+#[cfg(feature = "recorder")]
+pub use gif_recorder::GifRecorder;
+#[cfg(feature = "sdl")]
 pub use hardware::Hardware;
-pub use interactible::{Interactible, SetKeysResult};
+#[cfg(feature = "sdl")]
+pub use host_keyboard::{HostKeyboard, KeyPress};
+pub use input_delay::InputDelay;
+pub use interactible::{Interactible, MachineState, PlaybackSpeed, SetKeysResult};
+#[cfg(feature = "minifb")]
+pub use minifb_hardware::MinifbHardware;
 #[cfg(test)]
 pub use mockhardware::MockHardware;
+#[cfg(feature = "netplay")]
+pub use netplay::{Netplay, NetplaySettings};
+pub use null_hardware::NullHardware;
+pub use phosphor::Phosphor;
+#[cfg(feature = "pixels")]
+pub use pixels_hardware::{PixelsHardware, PixelsRenderer};
+#[cfg(feature = "sdl")]
+pub use render_filter::{DepthFilter, FlatFilter, RenderFilter};
+#[cfg(feature = "sdl")]
+pub use render_thread::RenderThread;
+#[cfg(feature = "sdl")]
+pub use rom_browser::RomBrowser;
 pub use screen::{Screen, ScreenIterator};
+#[cfg(feature = "tui")]
+pub use terminal_hardware::TerminalHardware;
+#[cfg(feature = "sdl")]
+pub use viewport::Viewport;
+#[cfg(feature = "recorder")]
+pub use wav_recorder::WavRecorder;
+#[cfg(feature = "wgpu")]
+pub use wgpu_hardware::{WgpuHardware, WgpuRenderer};
+#[cfg(any(feature = "pixels", feature = "wgpu"))]
+pub use winit_hardware::{WindowRenderer, WinitHardware};
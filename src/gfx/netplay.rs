@@ -0,0 +1,287 @@
+use super::{Interactible, MachineState, PlaybackSpeed, Screen, SetKeysResult};
+use crate::arch::{OpcodeCosts, Quirks, Variant};
+use crate::config::NetplayPeer;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Error, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+// The version of the protocol spoken between players
+// (both must speak the same one).
+const PROTOCOL_VERSION: u32 = 1;
+// The kinds of messages sent each frame.
+const INPUT_MESSAGE: u8 = 0;
+const QUIT_MESSAGE: u8 = 1;
+
+/// The settings both players must run with to stay in sync,
+/// exchanged when connecting (the host's are the ones used).
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct NetplaySettings {
+    // The hash of the loaded machine's memory (so, of the game).
+    pub rom_hash: String,
+    // The seed for the random number generator.
+    pub seed: u64,
+    // How long each instruction takes (in nanoseconds).
+    pub cycle_rate: u64,
+    // The interpreter being emulated, its quirks, and how many cycles its instructions take.
+    pub variant: Variant,
+    pub quirks: Quirks,
+    #[serde(default)]
+    pub opcode_costs: OpcodeCosts,
+    // How many frames each player's input is delayed by before the game sees it.
+    pub delay: u32,
+}
+
+// What each player sends the other when connecting.
+#[derive(Deserialize, Serialize)]
+struct Hello {
+    version: u32,
+    settings: NetplaySettings,
+}
+
+/// Hardware (wrapping the local player's) that plays in lockstep with another player
+/// over TCP: every frame, each player sends the other their keypad, and the game
+/// sees both keypads combined (so each player presses their own side's keys).
+/// Since both emulators start from the same settings and see the same input
+/// on the same frame, they run identically.
+///
+/// Input is delayed by a few frames before the game sees it, giving it time to
+/// reach the other player, who waits for it if it's late. (Hotkeys that would
+/// change the machine or its speed, like loading states, are ignored.)
+pub struct Netplay {
+    // The local player's hardware.
+    hardware: Box<dyn Interactible>,
+    // How messages are read from and sent to the other player.
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    // Each player's keypads not yet seen by the game, oldest first.
+    local_inputs: VecDeque<[bool; 16]>,
+    remote_inputs: VecDeque<[bool; 16]>,
+    // The combined keypad seen by the game.
+    keypad: [bool; 16],
+}
+
+impl Netplay {
+    /// Connect to the other player (or wait for them to connect, when hosting),
+    /// and agree on the settings to run with: the host's.
+    /// Returns the settings agreed on, or an error if connecting failed
+    /// or the players are running different games.
+    pub fn connect(
+        hardware: Box<dyn Interactible>,
+        peer: &NetplayPeer,
+        settings: NetplaySettings,
+    ) -> Result<(Netplay, NetplaySettings), Error> {
+        let stream = match peer {
+            NetplayPeer::Host(address) => {
+                let listener = TcpListener::bind(address)?;
+                info!(
+                    "Waiting for the other player to connect on {}...",
+                    listener.local_addr()?
+                );
+                let (stream, address) = listener.accept()?;
+                info!("{address} connected!");
+                stream
+            }
+            NetplayPeer::Join(address) => {
+                let stream = TcpStream::connect(address)?;
+                info!("Connected to {address}!");
+                stream
+            }
+        };
+        Self::from_stream(
+            hardware,
+            stream,
+            matches!(peer, NetplayPeer::Host(_)),
+            settings,
+        )
+    }
+
+    /// Like `connect()`, but with the other player already connected.
+    pub fn from_stream(
+        hardware: Box<dyn Interactible>,
+        stream: TcpStream,
+        hosting: bool,
+        settings: NetplaySettings,
+    ) -> Result<(Netplay, NetplaySettings), Error> {
+        // (Frames are tiny, and each is needed as soon as possible.)
+        stream.set_nodelay(true)?;
+        let mut writer = stream.try_clone()?;
+        let mut reader = BufReader::new(stream);
+
+        // Each player sends the other their settings (one line of JSON) first.
+        let hello = Hello {
+            version: PROTOCOL_VERSION,
+            settings,
+        };
+        writeln!(writer, "{}", serde_json::to_string(&hello)?)?;
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let peer_hello: Hello = serde_json::from_str(&line)
+            .map_err(|_| Error::new(ErrorKind::InvalidData, "The other player isn't playing."))?;
+        if peer_hello.version != PROTOCOL_VERSION {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "The other player is running a different version of the emulator.",
+            ));
+        }
+        if peer_hello.settings.rom_hash != hello.settings.rom_hash {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "The other player is playing a different game.",
+            ));
+        }
+        let settings = if hosting {
+            hello.settings
+        } else {
+            peer_hello.settings
+        };
+
+        // Both players start out having "pressed" nothing for the delayed frames.
+        let delayed = VecDeque::from(vec![[false; 16]; settings.delay as usize]);
+        let netplay = Netplay {
+            hardware,
+            reader,
+            writer,
+            local_inputs: delayed.clone(),
+            remote_inputs: delayed,
+            keypad: [false; 16],
+        };
+        Ok((netplay, settings))
+    }
+
+    fn send(&mut self, kind: u8, keypad: &[bool; 16]) -> Result<(), Error> {
+        let bits = to_bits(keypad).to_le_bytes();
+        self.writer.write_all(&[kind, bits[0], bits[1]])
+    }
+
+    fn receive(&mut self) -> Result<Option<[bool; 16]>, Error> {
+        // Read the other player's keypad for their next frame
+        // (or None if they quit).
+        let mut message = [0; 3];
+        self.reader.read_exact(&mut message)?;
+        match message[0] {
+            INPUT_MESSAGE => Ok(Some(from_bits(u16::from_le_bytes([
+                message[1], message[2],
+            ])))),
+            QUIT_MESSAGE => Ok(None),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                "The other player sent a message that isn't understood.",
+            )),
+        }
+    }
+
+    fn exchange_input(&mut self) -> Result<bool, Error> {
+        // Send the local player's keypad for this frame, and (once the other player's
+        // keypad for the frame the game is on arrives) combine both players' keypads.
+        // Returns false if the other player quit.
+        let mut local = [false; 16];
+        local.copy_from_slice(self.hardware.get_keys());
+        self.send(INPUT_MESSAGE, &local)?;
+        self.local_inputs.push_back(local);
+        if self.remote_inputs.is_empty() {
+            match self.receive()? {
+                Some(remote) => self.remote_inputs.push_back(remote),
+                None => return Ok(false),
+            }
+        }
+
+        let (local, remote) = (
+            self.local_inputs.pop_front().unwrap_or_default(),
+            self.remote_inputs.pop_front().unwrap_or_default(),
+        );
+        for (key, (local, remote)) in self.keypad.iter_mut().zip(local.iter().zip(remote)) {
+            *key = *local || remote;
+        }
+        Ok(true)
+    }
+}
+
+fn to_bits(keypad: &[bool; 16]) -> u16 {
+    keypad.iter().enumerate().fold(0, |bits, (key, pressed)| {
+        bits | (u16::from(*pressed) << key)
+    })
+}
+
+fn from_bits(bits: u16) -> [bool; 16] {
+    let mut keypad = [false; 16];
+    for (key, pressed) in keypad.iter_mut().enumerate() {
+        *pressed = bits & (1 << key) != 0;
+    }
+    keypad
+}
+
+impl Interactible for Netplay {
+    fn init(&mut self) {
+        self.hardware.init();
+    }
+
+    fn set_title(&mut self, title: &str) -> Result<(), Error> {
+        self.hardware.set_title(title)
+    }
+
+    fn update_display(&mut self, screen: &Screen) {
+        self.hardware.update_display(screen);
+    }
+
+    fn set_keys(&mut self, screen: &Screen) -> SetKeysResult {
+        let result = match self.hardware.set_keys(screen) {
+            SetKeysResult::ShouldExit => {
+                // Let the other player know (if they're still there) before leaving.
+                let _ = self.send(QUIT_MESSAGE, &[false; 16]);
+                return SetKeysResult::ShouldExit;
+            }
+            SetKeysResult::ShouldLoadState
+            | SetKeysResult::ShouldSpeedUp
+            | SetKeysResult::ShouldSlowDown => {
+                // (The other player's machine wouldn't follow along.)
+                warn!("Loading states and changing the speed are disabled during netplay.");
+                SetKeysResult::ShouldContinue
+            }
+            result => result,
+        };
+
+        match self.exchange_input() {
+            Ok(true) => result,
+            Ok(false) => {
+                info!("The other player quit.");
+                SetKeysResult::ShouldExit
+            }
+            Err(err) => {
+                warn!("Lost the connection to the other player: {err}");
+                SetKeysResult::ShouldExit
+            }
+        }
+    }
+
+    fn get_keys(&self) -> &[bool] {
+        &self.keypad
+    }
+
+    fn key_is_pressed(&self, key: u8) -> bool {
+        self.keypad[key as usize]
+    }
+
+    fn playback_speed(&self) -> PlaybackSpeed {
+        // (Neither player can run ahead of the other anyway.)
+        PlaybackSpeed::Normal
+    }
+
+    fn show_speed(&mut self, instructions_per_frame: u64) {
+        self.hardware.show_speed(instructions_per_frame);
+    }
+
+    fn show_stats(&mut self, frames_per_second: u64, instructions_per_second: u64) {
+        self.hardware
+            .show_stats(frames_per_second, instructions_per_second);
+    }
+
+    fn set_buzzer(&mut self, playing: bool) {
+        self.hardware.set_buzzer(playing);
+    }
+
+    fn show_machine_state(&mut self, state: &MachineState) {
+        self.hardware.show_machine_state(state);
+    }
+}
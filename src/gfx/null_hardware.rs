@@ -0,0 +1,58 @@
+use super::interactible::{Interactible, MachineState, PlaybackSpeed, SetKeysResult};
+use super::screen::Screen;
+use crate::config::Config;
+use log::debug;
+
+/// Hardware with no window, no sound, and no input, for running headless
+/// (on servers, in CI, or for scripted analysis). Unlike `Hardware`,
+/// it never touches SDL, so it works wherever the emulator itself does.
+/// Emulation still runs at its usual speed, until the program exits.
+pub struct NullHardware {
+    // No keys are ever pressed
+    // (sized like a real keypad, just like `MockHardware`).
+    keyboard: [bool; 16],
+}
+
+impl NullHardware {
+    pub fn new(_config: &Config) -> NullHardware {
+        NullHardware {
+            keyboard: [false; 16],
+        }
+    }
+}
+
+impl Interactible for NullHardware {
+    fn init(&mut self) {
+        debug!("Running headless!");
+    }
+
+    fn set_title(&mut self, _title: &str) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+
+    fn update_display(&mut self, _screen: &Screen) {}
+
+    fn set_keys(&mut self, _screen: &Screen) -> SetKeysResult {
+        SetKeysResult::ShouldContinue
+    }
+
+    fn get_keys(&self) -> &[bool] {
+        &self.keyboard
+    }
+
+    fn key_is_pressed(&self, _key: u8) -> bool {
+        false
+    }
+
+    fn playback_speed(&self) -> PlaybackSpeed {
+        PlaybackSpeed::Normal
+    }
+
+    fn show_speed(&mut self, _instructions_per_frame: u64) {}
+
+    fn show_stats(&mut self, _frames_per_second: u64, _instructions_per_second: u64) {}
+
+    fn set_buzzer(&mut self, _playing: bool) {}
+
+    fn show_machine_state(&mut self, _state: &MachineState) {}
+}
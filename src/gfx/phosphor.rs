@@ -0,0 +1,84 @@
+use super::{Drawable, Screen};
+use std::time::{Duration, Instant};
+
+// How long one (60hz) frame lasts.
+const FRAME: Duration = Duration::from_nanos(16666667);
+
+/// A presentation-only afterglow for screen pixels.
+/// Pixels that are cleared fade out over a few frames instead of
+/// turning off at once, which hides the flicker of games that
+/// erase and redraw their sprites (with XOR) every frame.
+pub struct Phosphor {
+    // How many frames a cleared pixel takes to fade out (0 if disabled).
+    frames: u32,
+    // The resolution of the screen last seen.
+    res_width: u32,
+    res_height: u32,
+    // How many frames each pixel has left to glow for (column by column).
+    glow: Vec<u32>,
+    // When pixels last faded.
+    last_fade: Instant,
+}
+
+impl Phosphor {
+    /// Create a new afterglow, lasting the given number of frames.
+    pub fn new(frames: u32) -> Phosphor {
+        Phosphor {
+            frames,
+            res_width: 0,
+            res_height: 0,
+            glow: Vec::new(),
+            last_fade: Instant::now(),
+        }
+    }
+
+    /// Whether or not cleared pixels fade out at all.
+    pub fn is_enabled(&self) -> bool {
+        self.frames > 0
+    }
+
+    /// Catch up with the screen as of `now`: set pixels glow fully,
+    /// and every other pixel fades by the number of frames since the last fade.
+    pub fn update(&mut self, screen: &Screen, now: Instant) {
+        if (screen.res_width, screen.res_height) != (self.res_width, self.res_height) {
+            // (The resolution changed, so there's nothing left to fade.)
+            self.res_width = screen.res_width;
+            self.res_height = screen.res_height;
+            self.glow = vec![0; (self.res_width * self.res_height) as usize];
+        }
+
+        let frames = (now.duration_since(self.last_fade).as_nanos() / FRAME.as_nanos()) as u32;
+        if frames > 0 {
+            for glow in &mut self.glow {
+                *glow = glow.saturating_sub(frames);
+            }
+            self.last_fade += FRAME * frames;
+        }
+
+        for (x, y) in screen {
+            self.glow[x * self.res_height as usize + y] = self.frames;
+        }
+    }
+
+    /// Whether any cleared pixel is still fading out, and a frame has passed
+    /// since the last fade (so that the screen should be drawn again).
+    pub fn needs_redraw(&self, screen: &Screen, now: Instant) -> bool {
+        now.duration_since(self.last_fade) >= FRAME && self.fading(screen).next().is_some()
+    }
+
+    /// Every cleared pixel that's still fading out,
+    /// along with how brightly it still glows (from 0 to 1).
+    pub fn fading<'a>(&'a self, screen: &'a Screen) -> impl Iterator<Item = (u32, u32, f64)> + 'a {
+        let frames = f64::from(self.frames + 1);
+        self.glow
+            .iter()
+            .enumerate()
+            .filter(|(_, glow)| **glow > 0)
+            .map(move |(index, glow)| {
+                let x = index as u32 / self.res_height;
+                let y = index as u32 % self.res_height;
+                (x, y, f64::from(*glow) / frames)
+            })
+            .filter(|(x, y, _)| !screen.get_pixel(*x as u16, *y as u16))
+    }
+}
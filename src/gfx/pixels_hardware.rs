@@ -0,0 +1,80 @@
+use super::screen::Screen;
+use super::winit_hardware::{WindowRenderer, WinitHardware};
+use super::Drawable;
+use crate::config::{Config, Rgb};
+use crate::error::Chip8Error;
+use log::warn;
+use pixels::{Pixels, SurfaceTexture};
+use winit::window::Window;
+
+/// Hardware that draws the screen in a winit window with pixels
+/// (which scales it on the GPU, through wgpu, keeping its pixels square).
+pub type PixelsHardware = WinitHardware<PixelsRenderer>;
+
+/// Draws the screen into a window with pixels (see `PixelsHardware`).
+pub struct PixelsRenderer {
+    pixels: Pixels,
+    // The colors the screen is drawn with (as pixels wants them),
+    // and the resolution of the screen as drawn last.
+    foreground: [u8; 4],
+    background: [u8; 4],
+    resolution: (u32, u32),
+}
+
+impl PixelsRenderer {
+    fn color(Rgb(red, green, blue): Rgb) -> [u8; 4] {
+        // A color as pixels draws it (RGBA).
+        [red, green, blue, 0xFF]
+    }
+
+    fn render(&self) {
+        if let Err(error) = self.pixels.render() {
+            warn!("Failed to draw the screen: {error}");
+        }
+    }
+}
+
+impl WindowRenderer for PixelsRenderer {
+    fn new(window: &Window, screen: &Screen, config: &Config) -> Result<Self, Chip8Error> {
+        let size = window.inner_size();
+        let surface = SurfaceTexture::new(size.width, size.height, window);
+        let pixels = Pixels::new(screen.res_width, screen.res_height, surface)
+            .map_err(|err| Chip8Error::Window(format!("setting up drawing failed: {err}")))?;
+        Ok(PixelsRenderer {
+            pixels,
+            foreground: Self::color(config.palette.foreground),
+            background: Self::color(config.palette.background),
+            resolution: (screen.res_width, screen.res_height),
+        })
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        if let Err(error) = self.pixels.resize_surface(width, height) {
+            warn!("Failed to resize the window's surface: {error}");
+        }
+        self.render();
+    }
+
+    fn draw(&mut self, screen: &Screen) {
+        // Draw a pixel per screen pixel, and let pixels scale it to the window.
+        let resolution = (screen.res_width, screen.res_height);
+        if resolution != self.resolution {
+            if let Err(error) = self.pixels.resize_buffer(resolution.0, resolution.1) {
+                warn!("Failed to change the screen's resolution: {error}");
+                return;
+            }
+            self.resolution = resolution;
+        }
+        let width = screen.res_width as usize;
+        let (foreground, background) = (self.foreground, self.background);
+        for (index, pixel) in self.pixels.frame_mut().chunks_exact_mut(4).enumerate() {
+            let (x, y) = ((index % width) as u16, (index / width) as u16);
+            pixel.copy_from_slice(if screen.get_pixel(x, y) {
+                &foreground
+            } else {
+                &background
+            });
+        }
+        self.render();
+    }
+}
@@ -0,0 +1,112 @@
+use crate::config::{RenderStyle, Rgb};
+use sdl2::{pixels::Color, rect::Rect, render::WindowCanvas};
+use std::ops::RangeInclusive;
+
+// Depth constants.
+// How far blocks are extruded, as a fraction of a pixel's size.
+const DEPTH_FRACTION: u32 = 3;
+// How bright the sides and shadows of blocks are, out of 255 (the face's brightness).
+const DEPTH_SIDE: u32 = 150;
+const DEPTH_SHADOW: u32 = 60;
+
+impl From<Rgb> for Color {
+    fn from(Rgb(r, g, b): Rgb) -> Color {
+        Color::RGB(r, g, b)
+    }
+}
+
+/// A trait describing how the set pixels of a screen are drawn onto a canvas.
+/// Filters are purely presentational, and never change the screen itself.
+pub trait RenderFilter {
+    /// Draw all set pixels (in the given color),
+    /// given as the window-space rectangles they occupy.
+    /// The canvas has already been cleared.
+    fn draw(
+        &mut self,
+        canvas: &mut WindowCanvas,
+        pixels: &[Rect],
+        color: Color,
+    ) -> Result<(), String>;
+}
+
+impl RenderStyle {
+    pub fn filter(self) -> Box<dyn RenderFilter> {
+        match self {
+            RenderStyle::Flat => Box::new(FlatFilter),
+            RenderStyle::Depth => Box::new(DepthFilter),
+        }
+    }
+}
+
+/// Draws every pixel as a plain rectangle.
+pub struct FlatFilter;
+
+impl RenderFilter for FlatFilter {
+    fn draw(
+        &mut self,
+        canvas: &mut WindowCanvas,
+        pixels: &[Rect],
+        color: Color,
+    ) -> Result<(), String> {
+        canvas.set_draw_color(color);
+        canvas.fill_rects(pixels)
+    }
+}
+
+/// Draws every pixel as a block sticking out of the screen,
+/// with its sides and shadow trailing off to the bottom right.
+pub struct DepthFilter;
+
+impl DepthFilter {
+    fn shade(color: Color, brightness: u32) -> Color {
+        // A darker version of a color (with full brightness being 255).
+        let darken = |component: u8| (u32::from(component) * brightness / 255) as u8;
+        Color::RGB(darken(color.r), darken(color.g), darken(color.b))
+    }
+
+    fn offset(pixels: &[Rect], steps: RangeInclusive<i32>) -> Vec<Rect> {
+        // Copies of every pixel, pushed towards the bottom right
+        // by each of the given number of steps.
+        pixels
+            .iter()
+            .flat_map(|rect| {
+                steps.clone().map(|step| {
+                    Rect::new(
+                        rect.x() + step,
+                        rect.y() + step,
+                        rect.width(),
+                        rect.height(),
+                    )
+                })
+            })
+            .collect()
+    }
+}
+
+impl RenderFilter for DepthFilter {
+    fn draw(
+        &mut self,
+        canvas: &mut WindowCanvas,
+        pixels: &[Rect],
+        color: Color,
+    ) -> Result<(), String> {
+        let Some(first) = pixels.first() else {
+            return Ok(());
+        };
+        let depth = (first.width().min(first.height()) / DEPTH_FRACTION).max(1) as i32;
+
+        // Each layer is drawn for every pixel before the next layer,
+        // so that blocks never cover up their neighbors' faces.
+        // First, the shadows (furthest away)...
+        canvas.set_draw_color(Self::shade(color, DEPTH_SHADOW));
+        canvas.fill_rects(&Self::offset(pixels, (depth + 1)..=(depth * 2)))?;
+
+        // ...then the sides of each block...
+        canvas.set_draw_color(Self::shade(color, DEPTH_SIDE));
+        canvas.fill_rects(&Self::offset(pixels, 1..=depth))?;
+
+        // ...and finally the faces.
+        canvas.set_draw_color(color);
+        canvas.fill_rects(pixels)
+    }
+}
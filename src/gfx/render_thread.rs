@@ -0,0 +1,309 @@
+use super::{Hardware, Interactible, MachineState, PlaybackSpeed, Screen, SetKeysResult};
+use crate::config::Config;
+use crate::error::Chip8Error;
+use log::{debug, warn};
+use std::collections::VecDeque;
+use std::io::Error;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+// How often the render thread reads input when it has nothing to draw.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+// How long emulation keeps running without hearing from the render thread
+// (which stops reading input while the game is paused, or its window unfocused)
+// before it waits for it.
+const STALL_TIMEOUT: Duration = Duration::from_millis(50);
+// How often the machine's state is sent to the render thread
+// (the debug window doesn't redraw any more often than this anyway).
+const MACHINE_STATE_INTERVAL: Duration = Duration::from_millis(33);
+
+// What the emulator asks of the hardware on the render thread.
+enum Request {
+    // A new frame is waiting in the back buffer.
+    Frame,
+    Init,
+    Title(String),
+    Speed(u64),
+    Stats(u64, u64),
+    Buzzer(bool),
+    MachineState(OwnedMachineState),
+}
+
+// What the hardware on the render thread reports back
+// (after each time it reads input).
+struct Input {
+    keyboard: [bool; 16],
+    playback_speed: PlaybackSpeed,
+    result: SetKeysResult,
+}
+
+// A copy of `MachineState` that can be sent across threads.
+struct OwnedMachineState {
+    memory: Vec<u8>,
+    registers: [u8; 16],
+    index_reg: u16,
+    pc: u16,
+    delay_timer: u8,
+    sound_timer: u8,
+    stack: Vec<u16>,
+}
+
+/// SDL hardware (see `Hardware`) running on a thread of its own,
+/// so that slow drawing never holds up the emulated program.
+///
+/// The emulator hands frames over through a double buffer: only the latest frame
+/// waits to be drawn (replacing any the render thread hasn't gotten to),
+/// and drawing happens on the render thread while the emulator keeps running.
+/// Input is read on the render thread too, and sent back after every read.
+///
+/// Everything SDL touches (initialization, the window, and its events)
+/// stays on the render thread, as SDL requires. (On macOS, SDL must
+/// run on the main thread instead, so this isn't supported there.)
+pub struct RenderThread {
+    // The latest frame not yet drawn, if any (the back buffer),
+    // and how requests reach the render thread.
+    back_buffer: Arc<Mutex<Option<Screen>>>,
+    requests: Option<Sender<Request>>,
+    // How input comes back, and when it last did.
+    input: Receiver<Input>,
+    last_input: Instant,
+    // Results of reading input that are still waiting to be acted upon.
+    results: VecDeque<SetKeysResult>,
+    // The keypad and playback speed, as of the latest input.
+    keyboard: [bool; 16],
+    playback_speed: PlaybackSpeed,
+    // When the machine's state was last sent, if ever.
+    machine_state_sent: Option<Instant>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl RenderThread {
+    /// Start the render thread, and open the window (and set up input and sound)
+    /// on it, returning an error if SDL can't be set up.
+    pub fn new(screen: &Screen, config: &Config, title: &str) -> Result<RenderThread, Chip8Error> {
+        let back_buffer = Arc::new(Mutex::new(None));
+        let (request_sender, requests) = mpsc::channel();
+        let (input_sender, input) = mpsc::channel();
+        let (ready_sender, ready) = mpsc::channel();
+
+        let thread = {
+            let back_buffer = Arc::clone(&back_buffer);
+            let (screen, config, title) = (screen.clone(), config.clone(), String::from(title));
+            thread::Builder::new()
+                .name(String::from("render"))
+                .spawn(move || {
+                    match Hardware::new(&screen, &config, &title) {
+                        Ok(hardware) => {
+                            // (The emulator is waiting, so this can't fail.)
+                            let _ = ready_sender.send(Ok(()));
+                            render(hardware, screen, &back_buffer, &requests, &input_sender);
+                        }
+                        Err(err) => {
+                            let _ = ready_sender.send(Err(err));
+                        }
+                    }
+                })?
+        };
+        ready
+            .recv()
+            .map_err(|_| Chip8Error::Sdl(String::from("The render thread stopped.")))??;
+
+        Ok(RenderThread {
+            back_buffer,
+            requests: Some(request_sender),
+            input,
+            last_input: Instant::now(),
+            results: VecDeque::new(),
+            keyboard: [false; 16],
+            playback_speed: PlaybackSpeed::Normal,
+            machine_state_sent: None,
+            thread: Some(thread),
+        })
+    }
+
+    fn request(&self, request: Request) {
+        // Send a request to the render thread
+        // (which only stops once the user quits, so any failure can be ignored).
+        if let Some(requests) = &self.requests {
+            let _ = requests.send(request);
+        }
+    }
+
+    fn receive_input(&mut self, input: Input) {
+        self.keyboard = input.keyboard;
+        self.playback_speed = input.playback_speed;
+        if !matches!(input.result, SetKeysResult::ShouldContinue) {
+            self.results.push_back(input.result);
+        }
+        self.last_input = Instant::now();
+    }
+}
+
+fn render(
+    mut hardware: Hardware,
+    mut front_buffer: Screen,
+    back_buffer: &Mutex<Option<Screen>>,
+    requests: &Receiver<Request>,
+    input: &Sender<Input>,
+) {
+    // Carry out the emulator's requests, drawing each new frame,
+    // and read input in between (sending it back), until the emulator is gone.
+    let mut exited = false;
+    loop {
+        match requests.recv_timeout(POLL_INTERVAL) {
+            Ok(Request::Frame) => {
+                let frame = back_buffer
+                    .lock()
+                    .unwrap_or_else(|err| err.into_inner())
+                    .take();
+                if let Some(frame) = frame {
+                    front_buffer = frame;
+                    hardware.update_display(&front_buffer);
+                }
+            }
+            Ok(Request::Init) => hardware.init(),
+            Ok(Request::Title(title)) => {
+                if let Err(err) = hardware.set_title(&title) {
+                    warn!("Failed to set the window title: {err}");
+                }
+            }
+            Ok(Request::Speed(instructions_per_frame)) => {
+                hardware.show_speed(instructions_per_frame)
+            }
+            Ok(Request::Stats(frames, instructions)) => hardware.show_stats(frames, instructions),
+            Ok(Request::Buzzer(playing)) => hardware.set_buzzer(playing),
+            Ok(Request::MachineState(state)) => hardware.show_machine_state(&MachineState {
+                memory: &state.memory,
+                registers: &state.registers,
+                index_reg: state.index_reg,
+                pc: state.pc,
+                delay_timer: state.delay_timer,
+                sound_timer: state.sound_timer,
+                stack: &state.stack,
+            }),
+            Err(RecvTimeoutError::Timeout) => (),
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        // (Once the user quits, there's no more input to read.)
+        if exited {
+            continue;
+        }
+        let result = hardware.set_keys(&front_buffer);
+        exited = matches!(result, SetKeysResult::ShouldExit);
+        let mut keyboard = [false; 16];
+        keyboard.copy_from_slice(hardware.get_keys());
+        let sent = input.send(Input {
+            keyboard,
+            playback_speed: hardware.playback_speed(),
+            result,
+        });
+        if sent.is_err() {
+            break;
+        }
+    }
+    debug!("Render thread stopped.");
+}
+
+impl Interactible for RenderThread {
+    fn init(&mut self) {
+        self.request(Request::Init);
+    }
+
+    fn set_title(&mut self, title: &str) -> Result<(), Error> {
+        // (Failures are reported on the render thread, where the window is.)
+        self.request(Request::Title(String::from(title)));
+        Ok(())
+    }
+
+    fn update_display(&mut self, screen: &Screen) {
+        // Replace the frame waiting to be drawn (if any),
+        // only letting the render thread know if none was waiting.
+        let mut back_buffer = self
+            .back_buffer
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        if back_buffer.replace(screen.clone()).is_none() {
+            drop(back_buffer);
+            self.request(Request::Frame);
+        }
+    }
+
+    fn set_keys(&mut self, _screen: &Screen) -> SetKeysResult {
+        // Take in whatever input arrived since we last checked,
+        // waiting for more if the render thread has gone quiet (for example, while paused).
+        while let Ok(input) = self.input.try_recv() {
+            self.receive_input(input);
+        }
+        while self.results.is_empty() && self.last_input.elapsed() >= STALL_TIMEOUT {
+            match self.input.recv() {
+                Ok(input) => self.receive_input(input),
+                // (The render thread stopped, so the window is gone.)
+                Err(_) => return SetKeysResult::ShouldExit,
+            }
+        }
+        self.results
+            .pop_front()
+            .unwrap_or(SetKeysResult::ShouldContinue)
+    }
+
+    fn get_keys(&self) -> &[bool] {
+        &self.keyboard
+    }
+
+    fn key_is_pressed(&self, key: u8) -> bool {
+        self.keyboard[key as usize]
+    }
+
+    fn playback_speed(&self) -> PlaybackSpeed {
+        self.playback_speed
+    }
+
+    fn show_speed(&mut self, instructions_per_frame: u64) {
+        self.request(Request::Speed(instructions_per_frame));
+    }
+
+    fn show_stats(&mut self, frames_per_second: u64, instructions_per_second: u64) {
+        self.request(Request::Stats(frames_per_second, instructions_per_second));
+    }
+
+    fn set_buzzer(&mut self, playing: bool) {
+        self.request(Request::Buzzer(playing));
+    }
+
+    fn show_machine_state(&mut self, state: &MachineState) {
+        // (This is reported after every cycle, so only a copy every so often is sent.)
+        let now = Instant::now();
+        if self
+            .machine_state_sent
+            .is_some_and(|sent| now.duration_since(sent) < MACHINE_STATE_INTERVAL)
+        {
+            return;
+        }
+        self.machine_state_sent = Some(now);
+        self.request(Request::MachineState(OwnedMachineState {
+            memory: state.memory.to_vec(),
+            registers: *state.registers,
+            index_reg: state.index_reg,
+            pc: state.pc,
+            delay_timer: state.delay_timer,
+            sound_timer: state.sound_timer,
+            stack: state.stack.to_vec(),
+        }));
+    }
+}
+
+impl Drop for RenderThread {
+    fn drop(&mut self) {
+        // Stop the render thread, and wait for it to close the window
+        // (so that anything the hardware saves when dropped is saved).
+        self.requests = None;
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                warn!("The render thread panicked.");
+            }
+        }
+    }
+}
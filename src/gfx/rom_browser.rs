@@ -0,0 +1,231 @@
+use super::font::TextGrid;
+use super::Hardware;
+use crate::config::{Config, Palette, Settings};
+use log::debug;
+use sdl2::event::Event;
+use sdl2::{keyboard::Scancode, pixels::Color, render::Canvas, video::Window};
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
+
+const TITLE: &str = "Chip-8 Emulator";
+// The extension games are listed by (ignoring case).
+const GAME_EXTENSION: &str = "ch8";
+// Text layout constants.
+// How text is laid out, and how many characters fit in the window (columns, then lines).
+const GRID: TextGrid = TextGrid {
+    scale: 3,
+    margin: 6,
+};
+const TEXT_SIZE: (u32, u32) = (40, 21);
+// How many games are listed at once (below the heading, and above the help line).
+const LIST_LINES: usize = 16;
+const LIST_LINE: u32 = 2;
+const HEADING: &str = "SELECT A GAME";
+const RECENT_HEADING: &str = "RECENT GAMES";
+const HELP: &str = "2/8: MOVE  4/6: PAGE  5: PLAY";
+// Keyboard layout constants.
+// The keypad keys that move the selection (and how far), and the one that picks a game.
+// (The arrow keys and Enter work too.)
+const KEYPAD_MOVES: [(usize, isize); 4] = [
+    (0x2, -1),
+    (0x8, 1),
+    (0x4, -(LIST_LINES as isize)),
+    (0x6, LIST_LINES as isize),
+];
+const KEYPAD_SELECT: usize = 0x5;
+const KEYS_MOVE: [(Scancode, isize); 4] = [
+    (Scancode::Up, -1),
+    (Scancode::Down, 1),
+    (Scancode::PageUp, -(LIST_LINES as isize)),
+    (Scancode::PageDown, LIST_LINES as isize),
+];
+const KEYS_SELECT: [Scancode; 2] = [Scancode::Return, Scancode::KpEnter];
+const KEY_QUIT: Scancode = Scancode::Escape;
+
+/// A window listing the games in a directory, for picking one to play
+/// with the keypad (so that no terminal is needed to start a game).
+pub struct RomBrowser {
+    // The games to pick from, and what they are.
+    games: Vec<PathBuf>,
+    heading: &'static str,
+    // Which game is selected, and which is listed first.
+    selected: usize,
+    scroll: usize,
+    // The host keys bound to each keypad key.
+    layout: [Scancode; 16],
+    palette: Palette,
+}
+
+impl RomBrowser {
+    /// List the games in the configured ROM directory (or the current directory),
+    /// returning an error if there are none.
+    pub fn new(config: &Config) -> Result<RomBrowser, Error> {
+        let dir = config.rom_dir.as_deref().unwrap_or(".");
+        let mut games: Vec<PathBuf> = fs::read_dir(dir)?
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| {
+                path.is_file()
+                    && path
+                        .extension()
+                        .is_some_and(|extension| extension.eq_ignore_ascii_case(GAME_EXTENSION))
+            })
+            .collect();
+        if games.is_empty() {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("No games (.{GAME_EXTENSION} files) found in '{dir}'. Please check usage with '-h'."),
+            ));
+        }
+        games.sort();
+        Self::with_games(config, games, HEADING)
+    }
+
+    /// List the games played most recently (that still exist), the latest first,
+    /// returning an error if there are none.
+    pub fn recent(config: &Config) -> Result<RomBrowser, Error> {
+        let games: Vec<PathBuf> = Settings::load()
+            .recent_games()
+            .into_iter()
+            .map(PathBuf::from)
+            .collect();
+        if games.is_empty() {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                "No games have been played recently. Please check usage with '-h'.",
+            ));
+        }
+        Self::with_games(config, games, RECENT_HEADING)
+    }
+
+    fn with_games(
+        config: &Config,
+        games: Vec<PathBuf>,
+        heading: &'static str,
+    ) -> Result<RomBrowser, Error> {
+        Ok(RomBrowser {
+            games,
+            heading,
+            selected: 0,
+            scroll: 0,
+            layout: Hardware::layout(&config.key_bindings)?,
+            palette: config.palette,
+        })
+    }
+
+    /// Open the browser window, and wait for the user to pick a game.
+    /// Returns the path of the game picked, or None if the user quit instead.
+    pub fn choose(mut self) -> Option<String> {
+        // As in `Hardware`, failing to set up SDL indicates a problem with SDL itself.
+        let sdl = sdl2::init().expect("SDL initialization failed.");
+        let (width, height) = GRID.window_size(TEXT_SIZE.0, TEXT_SIZE.1);
+        let window = sdl
+            .video()
+            .expect("SDL video initialization failed.")
+            .window(TITLE, width, height)
+            .position_centered()
+            .build()
+            .unwrap_or_else(|_| panic!("SDL window creation ({} x {}) failed.", width, height));
+        let mut canvas = window
+            .into_canvas()
+            .build()
+            .expect("Canvas initialization failed.");
+        let mut events = sdl
+            .event_pump()
+            .expect("SDL event pump initialization failed.");
+
+        self.draw(&mut canvas);
+        for event in events.wait_iter() {
+            let scancode = match event {
+                Event::Quit { .. } => return None,
+                Event::KeyDown {
+                    scancode: Some(scancode),
+                    ..
+                } => scancode,
+                _ => continue,
+            };
+
+            let keypad_key = self.layout.iter().position(|key| *key == scancode);
+            let step = KEYPAD_MOVES
+                .iter()
+                .find(|(key, _)| keypad_key == Some(*key))
+                .map(|(_, step)| *step)
+                .or_else(|| {
+                    KEYS_MOVE
+                        .iter()
+                        .find(|(key, _)| *key == scancode)
+                        .map(|(_, step)| *step)
+                });
+            if let Some(step) = step {
+                self.select(step);
+                self.draw(&mut canvas);
+            } else if keypad_key == Some(KEYPAD_SELECT) || KEYS_SELECT.contains(&scancode) {
+                let game = self.games[self.selected].to_string_lossy().into_owned();
+                debug!("Playing {game}!");
+                return Some(game);
+            } else if scancode == KEY_QUIT {
+                return None;
+            }
+        }
+        None
+    }
+
+    fn select(&mut self, step: isize) {
+        // Move the selection (stopping at either end of the list),
+        // scrolling the list to keep it in view.
+        self.selected = self
+            .selected
+            .saturating_add_signed(step)
+            .min(self.games.len() - 1);
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + LIST_LINES {
+            self.scroll = self.selected + 1 - LIST_LINES;
+        }
+    }
+
+    fn draw(&self, canvas: &mut Canvas<Window>) {
+        // Draw the heading, the visible part of the list
+        // (with the selected game highlighted), and how to use it.
+        let foreground = Color::from(self.palette.foreground);
+        let background = Color::from(self.palette.background);
+        canvas.set_draw_color(background);
+        canvas.clear();
+
+        let mut text = vec![(self.heading.to_string(), 0, foreground)];
+        let listed = self
+            .games
+            .iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(LIST_LINES);
+        for (line, (index, game)) in listed.enumerate() {
+            let name: String = game
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .chars()
+                .take(TEXT_SIZE.0 as usize)
+                .collect();
+            let line = line as u32 + LIST_LINE;
+            if index == self.selected {
+                canvas.set_draw_color(foreground);
+                canvas
+                    .fill_rect(GRID.cells(0, line, TEXT_SIZE.0))
+                    .expect("Failed to draw rectangle!");
+                text.push((name, line, background));
+            } else {
+                text.push((name, line, foreground));
+            }
+        }
+        text.push((HELP.to_string(), TEXT_SIZE.1 - 1, Color::GRAY));
+
+        for (text, line, color) in text {
+            canvas.set_draw_color(color);
+            canvas
+                .fill_rects(&GRID.text(&text, 0, line))
+                .expect("Failed to draw text!");
+        }
+        canvas.present();
+    }
+}
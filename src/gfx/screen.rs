@@ -1,10 +1,16 @@
 use super::Drawable;
 use serde::{Deserialize, Serialize};
-use serde_with::serde_as;
 use std::fmt;
+use std::iter::Enumerate;
+use std::slice::Iter;
 
-#[serde_as]
-#[derive(Serialize, Deserialize)]
+// The widest a screen can be (one bit per pixel in each row).
+const MAX_RES_WIDTH: u32 = u128::BITS;
+// The bit holding the leftmost pixel of a row.
+const LEFTMOST_PIXEL: u128 = 1 << (u128::BITS - 1);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "SavedScreen")]
 /// A struct describing a displayable two-dimensional device
 /// with individual pixels that are either on or off.
 /// It can be queried by pixel or iterated over
@@ -23,39 +29,103 @@ pub struct Screen {
     pub x_display_scale: u32,
     // The ratio height / res_height.
     pub y_display_scale: u32,
-    // The actual pixel values.
+    // The actual pixel values, one row per bit-packed integer (the top row first),
+    // with the leftmost pixel in the highest bit (so that sprite rows are drawn at once).
+    rows: Vec<u128>,
+}
+
+#[derive(Deserialize)]
+struct SavedScreen {
+    // A screen as saved in a state (see `Screen` for the fields),
+    // which may hold its pixels the way they were stored before they were bit-packed
+    // (as a vector of columns, each a vector of pixels).
+    width: u32,
+    height: u32,
+    res_width: u32,
+    res_height: u32,
+    x_display_scale: u32,
+    y_display_scale: u32,
+    #[serde(default)]
+    rows: Vec<u128>,
+    #[serde(default)]
     pixels: Vec<Vec<bool>>,
 }
 
-/// Iterator for a Screen that only returns pixels that are set.
+impl TryFrom<SavedScreen> for Screen {
+    type Error = String;
+
+    fn try_from(saved: SavedScreen) -> Result<Screen, String> {
+        // (Checked like `Screen::new()` checks them, so that a bad state fails to load
+        // rather than making drawing on the screen panic later.)
+        if saved.width == 0 || saved.height == 0 || saved.res_width == 0 || saved.res_height == 0 {
+            return Err(String::from("the screen has no size"));
+        }
+        if saved.res_width > MAX_RES_WIDTH {
+            return Err(format!(
+                "the screen is {} pixels wide (at most {MAX_RES_WIDTH} fit)",
+                saved.res_width
+            ));
+        }
+        if saved.x_display_scale == 0 || saved.y_display_scale == 0 {
+            return Err(String::from("the screen's pixels have no size"));
+        }
+        let mut screen = Screen {
+            width: saved.width,
+            height: saved.height,
+            res_width: saved.res_width,
+            res_height: saved.res_height,
+            x_display_scale: saved.x_display_scale,
+            y_display_scale: saved.y_display_scale,
+            rows: saved.rows,
+        };
+        if screen.rows.is_empty() {
+            if saved.pixels.len() > saved.res_width as usize
+                || saved
+                    .pixels
+                    .iter()
+                    .any(|column| column.len() > saved.res_height as usize)
+            {
+                return Err(String::from("the screen's pixels don't fit in it"));
+            }
+            screen.rows = vec![0; saved.res_height as usize];
+            for (x, column) in saved.pixels.iter().enumerate() {
+                for (y, _) in column.iter().enumerate().filter(|(_, pixel)| **pixel) {
+                    screen.xor_pixel(x as u16, y as u16);
+                }
+            }
+        }
+        if screen.rows.len() != screen.res_height as usize {
+            return Err(format!(
+                "the screen has {} rows, but is {} pixels tall",
+                screen.rows.len(),
+                screen.res_height
+            ));
+        }
+        Ok(screen)
+    }
+}
+
+/// Iterator for a Screen that only returns pixels that are set
+/// (row by row, from left to right).
 pub struct ScreenIterator<'a> {
-    screen: &'a Screen,
-    // Keep track of the last (X, Y) pixel we saw that was set.
-    curr: (usize, usize),
+    // The rows not reached yet, and the current row
+    // (with only the pixels that haven't been returned yet still set).
+    rows: Enumerate<Iter<'a, u128>>,
+    row: (usize, u128),
 }
 
 impl Iterator for ScreenIterator<'_> {
     type Item = (usize, usize);
 
     fn next(&mut self) -> Option<Self::Item> {
-        // Iterate only the vectors starting with our current X coordinate.
-        for (xindex, xarr) in self.screen.pixels[self.curr.0..].iter().enumerate() {
-            // Iterate all Y values, even those we've already seen in the current X vector
-            // (we do this to keep updating our saved Y simple).
-            for (yindex, pixel) in xarr.iter().enumerate() {
-                // Since we sliced above, xindex is the start from the slice, not the entire vector.
-                let real_xindex = xindex + self.curr.0;
-
-                if *pixel && ((xindex > 0) || (xindex == 0 && yindex > self.curr.1)) {
-                    // If we see a pixel past the last Y we saw in the first vector,
-                    // or a pixel in *ANY* vector past the first, it's new. Save it and return it.
-                    self.curr = (real_xindex, yindex);
-                    return Some(self.curr);
-                }
-            }
+        while self.row.1 == 0 {
+            let (y, row) = self.rows.next()?;
+            self.row = (y, *row);
         }
-
-        None
+        let (y, row) = self.row;
+        let x = row.leading_zeros();
+        self.row.1 &= !(LEFTMOST_PIXEL >> x);
+        Some((x as usize, y))
     }
 }
 
@@ -67,8 +137,8 @@ impl<'a> IntoIterator for &'a Screen {
 
     fn into_iter(self) -> ScreenIterator<'a> {
         ScreenIterator {
-            screen: self,
-            curr: (0, 0),
+            rows: self.rows.iter().enumerate(),
+            row: (0, 0),
         }
     }
 }
@@ -81,6 +151,9 @@ impl Screen {
                 "Zero screen resolution provided: w{width} h{height} rw{res_width} rh{res_height}"
             );
         }
+        if res_width > MAX_RES_WIDTH {
+            panic!("Screen resolution too wide: rw{res_width} (at most {MAX_RES_WIDTH})");
+        }
         let x_display_scale = width / res_width;
         let y_display_scale = height / res_height;
         if x_display_scale == 0 {
@@ -100,26 +173,90 @@ impl Screen {
             res_height,
             x_display_scale,
             y_display_scale,
-            pixels: vec![vec![false; res_height as usize]; res_width as usize],
+            rows: vec![0; res_height as usize],
         }
     }
+
+    /// Change the resolution (as SCHIP programs switch between two), clearing every pixel.
+    /// The display size stays the same, with the display scales re-derived to fill it
+    /// (unless it's too small for the new resolution, in which case it grows to fit).
+    pub fn set_resolution(&mut self, res_width: u32, res_height: u32) {
+        if res_width == 0 || res_height == 0 {
+            panic!("Zero screen resolution provided: rw{res_width} rh{res_height}");
+        } else if res_width > MAX_RES_WIDTH {
+            panic!("Screen resolution too wide: rw{res_width} (at most {MAX_RES_WIDTH})");
+        }
+        self.width = self.width.max(res_width);
+        self.height = self.height.max(res_height);
+        self.res_width = res_width;
+        self.res_height = res_height;
+        self.x_display_scale = self.width / res_width;
+        self.y_display_scale = self.height / res_height;
+        self.rows = vec![0; res_height as usize];
+    }
+
+    /// Change the display size to `scale` times the resolution
+    /// (keeping every pixel square).
+    pub fn set_scale(&mut self, scale: u32) {
+        if scale == 0 {
+            panic!("Zero screen scale provided");
+        }
+        self.width = self.res_width * scale;
+        self.height = self.res_height * scale;
+        self.x_display_scale = scale;
+        self.y_display_scale = scale;
+    }
 }
 
 impl Drawable for Screen {
     // Setters.
     fn clear_all_pixels(&mut self) {
-        self.pixels.iter_mut().for_each(|x| x.fill(false));
+        self.rows.fill(0);
     }
 
     fn xor_pixel(&mut self, x: u16, y: u16) {
-        let x_us = x as usize;
-        let y_us = y as usize;
-        self.pixels[x_us][y_us] = self.pixels[x_us][y_us] != true;
+        self.rows[y as usize] ^= LEFTMOST_PIXEL >> x;
+    }
+
+    fn xor_row(&mut self, x: u32, y: u32, sprite: u16, width: u32, wrap: bool) -> bool {
+        // Line the sprite up with the row (from the leftmost pixel), then move it into place.
+        let sprite = u128::from(sprite) << (u128::BITS - width);
+        let mut mask = sprite.checked_shr(x).unwrap_or(0);
+        if wrap {
+            // (Pixels past the right edge come back around at the left.)
+            mask |= sprite.checked_shl(self.res_width - x).unwrap_or(0);
+        }
+        mask &= self.row_mask();
+
+        let row = &mut self.rows[y as usize];
+        let unset = *row & mask != 0;
+        *row ^= mask;
+        unset
+    }
+
+    fn scroll_down(&mut self, rows: u16) {
+        let rows = usize::from(rows).min(self.rows.len());
+        self.rows.rotate_right(rows);
+        self.rows[..rows].fill(0);
+    }
+
+    fn scroll_left(&mut self, columns: u16) {
+        let row_mask = self.row_mask();
+        for row in self.rows.iter_mut() {
+            *row = row.checked_shl(u32::from(columns)).unwrap_or(0) & row_mask;
+        }
+    }
+
+    fn scroll_right(&mut self, columns: u16) {
+        let row_mask = self.row_mask();
+        for row in self.rows.iter_mut() {
+            *row = row.checked_shr(u32::from(columns)).unwrap_or(0) & row_mask;
+        }
     }
 
     // Getters.
     fn get_pixel(&self, x: u16, y: u16) -> bool {
-        self.pixels[x as usize][y as usize]
+        self.rows[y as usize] & (LEFTMOST_PIXEL >> x) != 0
     }
 
     // Info.
@@ -128,9 +265,67 @@ impl Drawable for Screen {
     }
 }
 
-// Mostly useful for debugging.
+impl Screen {
+    fn row_mask(&self) -> u128 {
+        // The bits of each row that hold pixels (the rest are always clear).
+        u128::MAX << (u128::BITS - self.res_width)
+    }
+
+    /// The pixels as a packed bitmap: one bit per pixel (set for every set pixel), row by row
+    /// from the top, with the leftmost pixel of each row in the highest bit of its first byte.
+    /// Each row starts on a new byte (so it takes `res_width / 8` bytes, rounded up).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let row_bytes = self.res_width.div_ceil(8) as usize;
+        self.rows
+            .iter()
+            .flat_map(|row| row.to_be_bytes().into_iter().take(row_bytes))
+            .collect()
+    }
+
+    /// The pixels as text, for a quick look at the screen (in a terminal, a log,
+    /// or a failing test): one line per row, with a full block for every set pixel
+    /// and a dot for every clear one.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for y in 0..self.res_height as u16 {
+            text.extend((0..self.res_width as u16).map(|x| {
+                if self.get_pixel(x, y) {
+                    '█'
+                } else {
+                    '.'
+                }
+            }));
+            text.push('\n');
+        }
+        text
+    }
+
+    /// The pixels as a plain (text) PBM image: a header, then one line per row,
+    /// with 1 for every set pixel and 0 for every clear one.
+    /// (The output only depends on the pixels, so it's easy to compare between runs.)
+    pub fn to_pbm(&self) -> String {
+        let mut pbm = format!("P1\n{} {}\n", self.res_width, self.res_height);
+        for y in 0..self.res_height as u16 {
+            pbm.extend((0..self.res_width as u16).map(|x| {
+                if self.get_pixel(x, y) {
+                    '1'
+                } else {
+                    '0'
+                }
+            }));
+            pbm.push('\n');
+        }
+        pbm
+    }
+}
+
+// Mostly useful for debugging
+// (with `{:#}`, the pixels themselves are shown, as in `to_text()`).
 impl fmt::Display for Screen {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            return f.write_str(&self.to_text());
+        }
         write!(
             f,
             "W: {} H: {} SW: {} SH: {} XS: {} YS: {}",
@@ -146,7 +341,7 @@ impl fmt::Display for Screen {
 
 impl Default for Screen {
     fn default() -> Screen {
-        // For now, our default window size.
-        Screen::new(640, 480, 64, 32)
+        // Our default window size (with square pixels, 10 window pixels wide).
+        Screen::new(640, 320, 64, 32)
     }
 }
@@ -0,0 +1,345 @@
+use super::interactible::{Interactible, MachineState, PlaybackSpeed, SetKeysResult};
+use super::screen::Screen;
+use super::Drawable;
+use crate::config::{Config, Palette, Rgb};
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::{Color, Colors, Print, ResetColor, SetColors};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use log::warn;
+use std::io::{self, Error, Write};
+use std::time::{Duration, Instant};
+
+// Keyboard layout constants.
+// (Keys are read as the characters they type, so hotkeys are characters too,
+// with function keys past ASCII, where no typed character can be.)
+const KEYPAD_SIZE: usize = 16;
+const FUNCTION_KEYS: u8 = 0x80;
+const KEYS_QUIT: [u8; 2] = [0x1B, 0x03]; // Escape, or Ctrl-C.
+const KEY_PAUSE: u8 = b'p';
+const KEY_SAVE_STATE: u8 = FUNCTION_KEYS + 5; // F5
+const KEY_LOAD_STATE: u8 = FUNCTION_KEYS + 9; // F9
+const KEY_DUMP_MEMORY: u8 = b'o';
+const KEYS_SPEED_UP: [u8; 2] = [b'=', b'+'];
+const KEY_SLOW_DOWN: u8 = b'-';
+const KEY_STATS: u8 = b'i';
+// Terminals don't report key releases, so a key counts as held
+// for this long after each character it sends
+// (long enough to bridge the gap until the terminal starts repeating it).
+const KEY_HOLD: Duration = Duration::from_millis(200);
+const BELL: char = '\x07';
+// How long the speed is shown for after it changes.
+const STATUS_DURATION: Duration = Duration::from_secs(2);
+
+/// Hardware that draws the screen in the terminal (two screen pixels
+/// per character, using Unicode block characters) and reads keys from stdin.
+/// Useful over SSH and on machines without SDL.
+///
+/// The terminal is put into raw mode (and switched to its alternate screen,
+/// with crossterm) while running, and restored once the hardware is dropped.
+pub struct TerminalHardware {
+    // The title of this device (shown below the screen).
+    title: String,
+    // The colors the screen is drawn with.
+    palette: Palette,
+    // The characters bound to each keypad key, if any
+    // (only keys that type a single character can be bound).
+    layout: [Option<u8>; KEYPAD_SIZE],
+    // Whether the terminal has been set up for drawing and reading keys
+    // (and so needs restoring).
+    active: bool,
+    // Until when each keypad key counts as held.
+    held_until: [Option<Instant>; KEYPAD_SIZE],
+    keyboard: [bool; KEYPAD_SIZE],
+    // Whether the emulation is paused, whether to pause once the first frame
+    // has been drawn, and whether the screen has been drawn yet.
+    paused: bool,
+    start_paused: bool,
+    drawn: bool,
+    // The speed to show below the screen (and until when), if any.
+    status: Option<(u64, Instant)>,
+    // Whether to show how fast emulation is running below the screen,
+    // and the latest measurements (frames, then instructions per second), if any.
+    show_stats: bool,
+    stats: Option<(u64, u64)>,
+    // Whether the buzzer is sounding.
+    buzzing: bool,
+}
+
+impl TerminalHardware {
+    pub fn new(config: &Config, title: &str) -> TerminalHardware {
+        let mut layout = [None; KEYPAD_SIZE];
+        for (key, name) in layout.iter_mut().zip(config.key_bindings.names()) {
+            *key = Self::character(name);
+        }
+
+        TerminalHardware {
+            title: String::from(title),
+            palette: config.palette,
+            layout,
+            active: false,
+            held_until: [None; KEYPAD_SIZE],
+            keyboard: [false; KEYPAD_SIZE],
+            paused: false,
+            start_paused: config.start_paused,
+            drawn: false,
+            status: None,
+            show_stats: false,
+            stats: None,
+            buzzing: false,
+        }
+    }
+
+    fn character(name: &str) -> Option<u8> {
+        // The character a key (named as in SDL) types, if it types exactly one.
+        match name {
+            "Space" => Some(b' '),
+            _ if name.len() == 1 && name.is_ascii() => {
+                Some(name.as_bytes()[0].to_ascii_lowercase())
+            }
+            _ => None,
+        }
+    }
+
+    fn color(Rgb(r, g, b): Rgb) -> Color {
+        Color::Rgb { r, g, b }
+    }
+
+    fn typed(event: &Event) -> Option<u8> {
+        // The character a key event typed, with Escape and Ctrl-C as their control
+        // characters, and function keys past ASCII (key releases, which only some
+        // terminals report, type nothing).
+        let Event::Key(key) = event else {
+            return None;
+        };
+        if key.kind == KeyEventKind::Release {
+            return None;
+        }
+        match key.code {
+            KeyCode::Esc => Some(0x1B),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Some(0x03),
+            KeyCode::Char(character) if character.is_ascii() => Some(character as u8),
+            KeyCode::F(number) => FUNCTION_KEYS.checked_add(number),
+            _ => None,
+        }
+    }
+
+    fn read_characters(&self, wait: bool) -> Result<Vec<u8>, Error> {
+        // Every character typed since we last checked (waiting for one, if asked to).
+        let mut characters = Vec::new();
+        if !self.active {
+            return Ok(characters);
+        }
+        while wait && characters.is_empty() {
+            characters.extend(Self::typed(&event::read()?));
+        }
+        while event::poll(Duration::ZERO)? {
+            characters.extend(Self::typed(&event::read()?));
+        }
+        Ok(characters)
+    }
+
+    fn draw(&self, screen: &Screen) -> Result<(), Error> {
+        // Draw two rows of screen pixels per row of characters,
+        // with half (or full) blocks for set pixels.
+        let Palette {
+            foreground,
+            background,
+        } = self.palette;
+        let colors = Colors::new(Self::color(foreground), Self::color(background));
+        let mut stdout = io::stdout().lock();
+        queue!(stdout, MoveTo(0, 0))?;
+        for y in (0..screen.res_height).step_by(2) {
+            let row: String = (0..screen.res_width)
+                .map(|x| {
+                    let top = screen.get_pixel(x as u16, y as u16);
+                    let bottom =
+                        y + 1 < screen.res_height && screen.get_pixel(x as u16, y as u16 + 1);
+                    match (top, bottom) {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' ',
+                    }
+                })
+                .collect();
+            queue!(
+                stdout,
+                SetColors(colors),
+                Print(row),
+                ResetColor,
+                Print("\r\n")
+            )?;
+        }
+
+        // Below the screen, show the title (and any status).
+        let mut status = self.title.clone();
+        if self.paused {
+            status.push_str(" (paused)");
+        } else if let Some((instructions_per_frame, _)) = self.status {
+            status.push_str(&format!(
+                " ({instructions_per_frame} instructions per frame)"
+            ));
+        }
+        if let Some((frames, instructions)) = self.stats.filter(|_| self.show_stats) {
+            status.push_str(&format!(" [{frames} FPS, {instructions} IPS]"));
+        }
+        queue!(stdout, Print(status), Clear(ClearType::UntilNewLine))?;
+        stdout.flush()
+    }
+}
+
+impl Interactible for TerminalHardware {
+    fn init(&mut self) {
+        // Switch the terminal into raw mode (so that keys arrive as soon as
+        // they're typed), drawing on its alternate screen.
+        let entered = terminal::enable_raw_mode().and_then(|_| {
+            execute!(
+                io::stdout(),
+                EnterAlternateScreen,
+                Hide,
+                Clear(ClearType::All)
+            )
+        });
+        if let Err(error) = entered {
+            warn!("Failed to set up the terminal: {error}");
+        }
+        self.active = true;
+    }
+
+    fn set_title(&mut self, title: &str) -> Result<(), Error> {
+        self.title = String::from(title);
+        Ok(())
+    }
+
+    fn update_display(&mut self, screen: &Screen) {
+        self.drawn = true;
+        if let Err(error) = self.draw(screen) {
+            warn!("Failed to draw to the terminal: {error}");
+        }
+    }
+
+    fn set_keys(&mut self, screen: &Screen) -> SetKeysResult {
+        let now = Instant::now();
+        let mut caller_action = SetKeysResult::ShouldContinue;
+        let mut pause_toggled = false;
+        let characters = match self.read_characters(false) {
+            Ok(characters) => characters,
+            Err(error) => {
+                warn!("Failed to read from the terminal: {error}");
+                return SetKeysResult::ShouldExit;
+            }
+        };
+        for character in characters {
+            let character = character.to_ascii_lowercase();
+            // (Hotkeys the user has bound to keypad keys are left to the keypad.)
+            if let Some(key) = self.layout.iter().position(|key| *key == Some(character)) {
+                self.held_until[key] = Some(now + KEY_HOLD);
+            } else if KEYS_QUIT.contains(&character) {
+                return SetKeysResult::ShouldExit;
+            } else if character == KEY_PAUSE {
+                pause_toggled = !pause_toggled;
+            } else if character == KEY_SAVE_STATE {
+                caller_action = SetKeysResult::ShouldSaveState;
+            } else if character == KEY_LOAD_STATE {
+                caller_action = SetKeysResult::ShouldLoadState;
+            } else if character == KEY_DUMP_MEMORY {
+                caller_action = SetKeysResult::ShouldDumpMemory;
+            } else if KEYS_SPEED_UP.contains(&character) {
+                caller_action = SetKeysResult::ShouldSpeedUp;
+            } else if character == KEY_SLOW_DOWN {
+                caller_action = SetKeysResult::ShouldSlowDown;
+            } else if character == KEY_STATS {
+                self.show_stats = !self.show_stats;
+                self.update_display(screen);
+            }
+        }
+
+        for (pressed, held_until) in self.keyboard.iter_mut().zip(&self.held_until) {
+            *pressed = held_until.is_some_and(|until| now < until);
+        }
+
+        if self.start_paused && self.drawn {
+            self.start_paused = false;
+            pause_toggled = true;
+        }
+        if pause_toggled {
+            // Wait (drawing the paused screen) until unpaused or quit.
+            self.paused = true;
+            self.update_display(screen);
+            while self.paused {
+                let characters = match self.read_characters(true) {
+                    Ok(characters) => characters,
+                    Err(error) => {
+                        warn!("Failed to read from the terminal: {error}");
+                        return SetKeysResult::ShouldExit;
+                    }
+                };
+                for character in characters {
+                    if KEYS_QUIT.contains(&character) {
+                        return SetKeysResult::ShouldExit;
+                    } else if character.to_ascii_lowercase() == KEY_PAUSE {
+                        self.paused = false;
+                    }
+                }
+            }
+            self.update_display(screen);
+            // (We don't allow saving states while paused, just like `Hardware`.)
+            caller_action = SetKeysResult::ShouldContinue;
+        }
+
+        // Clear the status once it has been shown for long enough.
+        if self.status.is_some_and(|(_, until)| now >= until) {
+            self.status = None;
+            self.update_display(screen);
+        }
+        caller_action
+    }
+
+    fn get_keys(&self) -> &[bool] {
+        &self.keyboard
+    }
+
+    fn key_is_pressed(&self, key: u8) -> bool {
+        self.keyboard[key as usize]
+    }
+
+    fn playback_speed(&self) -> PlaybackSpeed {
+        PlaybackSpeed::Normal
+    }
+
+    fn show_speed(&mut self, instructions_per_frame: u64) {
+        self.status = Some((instructions_per_frame, Instant::now() + STATUS_DURATION));
+    }
+
+    fn show_stats(&mut self, frames_per_second: u64, instructions_per_second: u64) {
+        self.stats = Some((frames_per_second, instructions_per_second));
+    }
+
+    fn set_buzzer(&mut self, playing: bool) {
+        // Terminals can only ring their bell, so ring it whenever the buzzer starts.
+        if playing && !self.buzzing {
+            if let Err(error) = execute!(io::stdout(), Print(BELL)) {
+                warn!("Failed to ring the terminal's bell: {error}");
+            }
+        }
+        self.buzzing = playing;
+    }
+
+    fn show_machine_state(&mut self, _state: &MachineState) {}
+}
+
+impl Drop for TerminalHardware {
+    fn drop(&mut self) {
+        // Put the terminal back the way we found it.
+        if !self.active {
+            return;
+        }
+        let left = execute!(io::stdout(), ResetColor, Show, LeaveAlternateScreen)
+            .and_then(|_| terminal::disable_raw_mode());
+        if let Err(error) = left {
+            warn!("Failed to restore the terminal: {error}");
+        }
+    }
+}
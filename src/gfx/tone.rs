@@ -0,0 +1,97 @@
+#[cfg(feature = "recorder")]
+use super::WavRecorder;
+use crate::config::Waveform;
+use std::f32::consts::TAU;
+
+// Where noise starts (any nonzero value works).
+const NOISE_SEED: u32 = 0x2545_F491;
+
+/// The buzzer's tone, as generated for an audio device (whichever plays it).
+pub struct Tone {
+    // Whether the tone is sounding (the device keeps playing silence while it isn't,
+    // so that recordings keep time).
+    pub playing: bool,
+    // The shape of the wave.
+    waveform: Waveform,
+    // How far through one cycle of the wave each sample moves (from 0 to 1),
+    // and how far through the current cycle we are.
+    phase_step: f32,
+    phase: f32,
+    // How loud the tone is (from 0 to 1).
+    pub volume: f32,
+    // The state of the noise generator (a xorshift, which is plenty for static),
+    // and the level it picked for the current cycle (from -1 to 1).
+    noise_state: u32,
+    noise_level: f32,
+    // The recording of everything played (if recording).
+    #[cfg(feature = "recorder")]
+    pub recording: Option<WavRecorder>,
+}
+
+impl Tone {
+    /// A silent tone of the given pitch (in hz), volume (as a percentage), and waveform,
+    /// for a device playing this many samples each second.
+    pub fn new(frequency: u32, volume: u32, waveform: Waveform, sample_rate: u32) -> Tone {
+        Tone {
+            playing: false,
+            waveform,
+            phase_step: frequency as f32 / sample_rate as f32,
+            phase: 0.0,
+            volume: Self::amplitude(volume),
+            noise_state: NOISE_SEED,
+            noise_level: 1.0,
+            #[cfg(feature = "recorder")]
+            recording: None,
+        }
+    }
+
+    /// The amplitude of the wave at the given volume (as a percentage; at most 1).
+    pub fn amplitude(volume: u32) -> f32 {
+        volume.min(100) as f32 / 100.0
+    }
+
+    /// Fill the samples with the tone (or silence, while it isn't playing).
+    pub fn fill(&mut self, samples: &mut [f32]) {
+        for sample in samples.iter_mut() {
+            if !self.playing {
+                *sample = 0.0;
+                continue;
+            }
+            *sample = self.level() * self.volume;
+            self.phase += self.phase_step;
+            if self.phase >= 1.0 {
+                // (Noise picks a new level once per cycle, so that it keeps the tone's pitch.)
+                self.phase -= 1.0;
+                self.noise_level = self.next_noise_level();
+            }
+        }
+        #[cfg(feature = "recorder")]
+        if let Some(recording) = &mut self.recording {
+            recording.capture(samples);
+        }
+    }
+
+    fn level(&self) -> f32 {
+        // The wave's level at the current phase (from -1 to 1).
+        match self.waveform {
+            Waveform::Square => {
+                if self.phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Waveform::Triangle => 1.0 - 4.0 * (self.phase - 0.5).abs(),
+            Waveform::Sine => (self.phase * TAU).sin(),
+            Waveform::Noise => self.noise_level,
+        }
+    }
+
+    fn next_noise_level(&mut self) -> f32 {
+        // Step the noise generator, returning a new level (from -1 to 1).
+        self.noise_state ^= self.noise_state << 13;
+        self.noise_state ^= self.noise_state >> 17;
+        self.noise_state ^= self.noise_state << 5;
+        self.noise_state as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+}
@@ -0,0 +1,487 @@
+use super::Screen;
+use crate::config::{Config, Flip, Rotation};
+use sdl2::rect::Rect;
+
+// Magnifier constants.
+const MIN_ZOOM: u32 = 2;
+const MAX_ZOOM: u32 = 8;
+// The thumbnail of the full screen is this many times smaller than the window.
+const THUMBNAIL_SHRINK: u32 = 4;
+// The keypad overlay's cells are this many times smaller than the window.
+const KEYPAD_CELL_SHRINK: u32 = 20;
+// The space between (and around) keypad overlay cells, in window pixels.
+const KEYPAD_CELL_GAP: u32 = 2;
+// The on-screen display's text pixels are this many times smaller than the window.
+const OSD_PIXEL_SHRINK: u32 = 80;
+// How far one press of a panning key moves the magnified region,
+// as a fraction of the region's size.
+const PAN_FRACTION: u32 = 4;
+
+/// A struct describing how the pixels of a `Screen` are laid out
+/// inside of a window. All presentation-only changes (rotation, flipping,
+/// magnification) happen here, so that `Screen` and the emulated program
+/// never need to know about them.
+///
+/// Internally, everything is first laid out in an unrotated window
+/// and then flipped and rotated as a whole.
+pub struct Viewport {
+    rotation: Rotation,
+    flip: Option<Flip>,
+    // The resolution of the emulated screen.
+    res_width: u32,
+    res_height: u32,
+    // The size of one emulated pixel in the window (before rotation),
+    // as requested by the screen.
+    x_display_scale: u32,
+    y_display_scale: u32,
+    // The (possibly fractional) size pixels are actually drawn at to fit the window
+    // (which keeps the requested aspect ratio), and where the image starts
+    // (it's centered, leaving bars along the window's edges).
+    pixel_size: (f64, f64),
+    offset: (u32, u32),
+    // Whether pixels may only be drawn at whole multiples of their size,
+    // so that every pixel is drawn the same size.
+    integer_scaling: bool,
+    // The size of the window (before rotation).
+    width: u32,
+    height: u32,
+
+    // Magnifier components.
+    // Whether or not the magnifier is currently shown.
+    zoomed: bool,
+    // How many times larger magnified pixels are drawn.
+    zoom: u32,
+    // The top-left screen pixel of the magnified region.
+    pan: (u32, u32),
+}
+
+impl Viewport {
+    pub fn new(screen: &Screen, config: &Config) -> Viewport {
+        let zoom = config.zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        let mut viewport = Viewport {
+            rotation: config.rotation,
+            flip: config.flip,
+            res_width: screen.res_width,
+            res_height: screen.res_height,
+            x_display_scale: screen.x_display_scale,
+            y_display_scale: screen.y_display_scale,
+            pixel_size: (
+                f64::from(screen.x_display_scale),
+                f64::from(screen.y_display_scale),
+            ),
+            offset: (0, 0),
+            integer_scaling: config.integer_scaling,
+            width: screen.width,
+            height: screen.height,
+            zoomed: false,
+            zoom,
+            pan: (0, 0),
+        };
+
+        // Start the magnifier off in the middle of the screen.
+        let (zoom_width, zoom_height) = viewport.zoom_region_size();
+        viewport.pan = (
+            (viewport.res_width - zoom_width) / 2,
+            (viewport.res_height - zoom_height) / 2,
+        );
+        viewport
+    }
+
+    /// The resolution of the screen being laid out.
+    pub fn resolution(&self) -> (u32, u32) {
+        (self.res_width, self.res_height)
+    }
+
+    /// Follow the screen to a new resolution, re-fitting it to the window
+    /// (and moving the magnifier back to the middle of the screen).
+    pub fn set_resolution(&mut self, screen: &Screen) {
+        self.res_width = screen.res_width;
+        self.res_height = screen.res_height;
+        self.x_display_scale = screen.x_display_scale;
+        self.y_display_scale = screen.y_display_scale;
+        let (window_width, window_height) = self.window_size();
+        self.resize(window_width, window_height);
+
+        let (zoom_width, zoom_height) = self.zoom_region_size();
+        self.pan = (
+            (self.res_width - zoom_width) / 2,
+            (self.res_height - zoom_height) / 2,
+        );
+    }
+
+    fn is_sideways(&self) -> bool {
+        matches!(
+            self.rotation,
+            Rotation::Clockwise90 | Rotation::Clockwise270
+        )
+    }
+
+    /// The size of the window: at first, the size required to show the whole screen,
+    /// and after that, whatever it was resized to
+    /// (width and height are swapped when rotated sideways).
+    pub fn window_size(&self) -> (u32, u32) {
+        if self.is_sideways() {
+            (self.height, self.width)
+        } else {
+            (self.width, self.height)
+        }
+    }
+
+    /// Fit the screen to a window that has been resized to `window_width` x `window_height`,
+    /// scaling it as large as it will go without stretching it (and centering it).
+    pub fn resize(&mut self, window_width: u32, window_height: u32) {
+        (self.width, self.height) = if self.is_sideways() {
+            (window_height, window_width)
+        } else {
+            (window_width, window_height)
+        };
+
+        let natural_width = f64::from(self.res_width * self.x_display_scale);
+        let natural_height = f64::from(self.res_height * self.y_display_scale);
+        let scale =
+            (f64::from(self.width) / natural_width).min(f64::from(self.height) / natural_height);
+        let pixel_width = f64::from(self.x_display_scale) * scale;
+        let pixel_height = f64::from(self.y_display_scale) * scale;
+        self.pixel_size = if self.integer_scaling {
+            (pixel_width.floor().max(1.0), pixel_height.floor().max(1.0))
+        } else {
+            (pixel_width, pixel_height)
+        };
+
+        let (image_width, image_height) = self.image_size();
+        self.offset = (
+            self.width.saturating_sub(image_width) / 2,
+            self.height.saturating_sub(image_height) / 2,
+        );
+    }
+
+    fn image_size(&self) -> (u32, u32) {
+        // The size of the whole screen in the window.
+        let (pixel_width, pixel_height) = self.pixel_size;
+        (
+            span(0, self.res_width, pixel_width).1,
+            span(0, self.res_height, pixel_height).1,
+        )
+    }
+
+    /// The smallest window that still has room for one window pixel per screen pixel.
+    pub fn minimum_window_size(&self) -> (u32, u32) {
+        if self.is_sideways() {
+            (self.res_height, self.res_width)
+        } else {
+            (self.res_width, self.res_height)
+        }
+    }
+
+    fn orient(&self, x: i32, y: i32, w: u32, h: u32) -> Rect {
+        // Flip and rotate a rectangle laid out in the unrotated window.
+        let (win_w, win_h) = (self.width as i32, self.height as i32);
+        let (iw, ih) = (w as i32, h as i32);
+
+        // Mirror first...
+        let (x, y) = match self.flip {
+            Some(Flip::Horizontal) => (win_w - x - iw, y),
+            Some(Flip::Vertical) => (x, win_h - y - ih),
+            None => (x, y),
+        };
+
+        // ...then rotate the rectangle around the window.
+        match self.rotation {
+            Rotation::None => Rect::new(x, y, w, h),
+            Rotation::Clockwise90 => Rect::new(win_h - y - ih, x, h, w),
+            Rotation::Clockwise180 => Rect::new(win_w - x - iw, win_h - y - ih, w, h),
+            Rotation::Clockwise270 => Rect::new(y, win_w - x - iw, h, w),
+        }
+    }
+
+    /// Translate a block of `w` x `h` screen pixels starting at [x, y]
+    /// into the window-space rectangle that displays it
+    /// (ignoring the magnifier).
+    pub fn rect(&self, x: u32, y: u32, w: u32, h: u32) -> Rect {
+        let (pixel_width, pixel_height) = self.pixel_size;
+        let (x, w) = span(x, w, pixel_width);
+        let (y, h) = span(y, h, pixel_height);
+        self.orient(self.offset.0 as i32 + x, self.offset.1 as i32 + y, w, h)
+    }
+
+    /// Translate a single screen pixel into the window-space rectangle that displays it,
+    /// or None if the magnifier is shown and the pixel is outside of the magnified region.
+    pub fn pixel_rect(&self, x: u32, y: u32) -> Option<Rect> {
+        if !self.zoomed {
+            return Some(self.rect(x, y, 1, 1));
+        }
+
+        let (zoom_width, zoom_height) = self.zoom_region_size();
+        let (pan_x, pan_y) = self.pan;
+        if x < pan_x || y < pan_y || x >= pan_x + zoom_width || y >= pan_y + zoom_height {
+            return None;
+        }
+
+        let (pixel_width, pixel_height) = self.pixel_size;
+        let zoom = f64::from(self.zoom);
+        let (x, w) = span(x - pan_x, 1, pixel_width * zoom);
+        let (y, h) = span(y - pan_y, 1, pixel_height * zoom);
+        Some(self.orient(self.offset.0 as i32 + x, self.offset.1 as i32 + y, w, h))
+    }
+
+    // Magnifier handling.
+    fn zoom_region_size(&self) -> (u32, u32) {
+        // The size (in screen pixels) of the magnified region.
+        (
+            self.res_width.div_ceil(self.zoom),
+            self.res_height.div_ceil(self.zoom),
+        )
+    }
+
+    pub fn is_zoomed(&self) -> bool {
+        self.zoomed
+    }
+
+    /// Show or hide the magnifier.
+    pub fn toggle_zoom(&mut self) {
+        self.zoomed = !self.zoomed;
+    }
+
+    /// Move the magnified region by the given number of steps in each direction.
+    /// Steps are taken in screen orientation, so callers
+    /// that pan by what the user sees must account for rotation and flipping.
+    pub fn pan(&mut self, x_steps: i32, y_steps: i32) {
+        let (zoom_width, zoom_height) = self.zoom_region_size();
+        let x_step = (zoom_width / PAN_FRACTION).max(1) as i32;
+        let y_step = (zoom_height / PAN_FRACTION).max(1) as i32;
+        let max_x = self.res_width.saturating_sub(zoom_width) as i32;
+        let max_y = self.res_height.saturating_sub(zoom_height) as i32;
+
+        self.pan = (
+            (self.pan.0 as i32 + x_steps * x_step).clamp(0, max_x) as u32,
+            (self.pan.1 as i32 + y_steps * y_step).clamp(0, max_y) as u32,
+        );
+    }
+
+    /// Translate a panning direction as seen in the window
+    /// into the direction the magnified region must move on the screen.
+    pub fn screen_direction(&self, x_steps: i32, y_steps: i32) -> (i32, i32) {
+        // Undo the rotation first (the flip was applied before it)...
+        let (x, y) = match self.rotation {
+            Rotation::None => (x_steps, y_steps),
+            Rotation::Clockwise90 => (y_steps, -x_steps),
+            Rotation::Clockwise180 => (-x_steps, -y_steps),
+            Rotation::Clockwise270 => (-y_steps, x_steps),
+        };
+
+        // ...and then the flip.
+        match self.flip {
+            Some(Flip::Horizontal) => (-x, y),
+            Some(Flip::Vertical) => (x, -y),
+            None => (x, y),
+        }
+    }
+
+    fn thumbnail_origin(&self) -> (i32, i32) {
+        // The thumbnail sits in the bottom right corner of the (unrotated) image.
+        let (thumb_w, thumb_h) = self.thumbnail_pixel_size();
+        let (image_width, image_height) = self.image_size();
+        (
+            (self.offset.0 + image_width).saturating_sub(self.res_width * thumb_w) as i32,
+            (self.offset.1 + image_height).saturating_sub(self.res_height * thumb_h) as i32,
+        )
+    }
+
+    fn thumbnail_pixel_size(&self) -> (u32, u32) {
+        let (pixel_width, pixel_height) = self.pixel_size;
+        let shrink = f64::from(THUMBNAIL_SHRINK);
+        (
+            ((pixel_width / shrink) as u32).max(1),
+            ((pixel_height / shrink) as u32).max(1),
+        )
+    }
+
+    /// The window-space rectangle holding the thumbnail of the whole screen
+    /// (shown alongside the magnifier).
+    pub fn thumbnail(&self) -> Rect {
+        let (x, y) = self.thumbnail_origin();
+        let (thumb_w, thumb_h) = self.thumbnail_pixel_size();
+        self.orient(x, y, self.res_width * thumb_w, self.res_height * thumb_h)
+    }
+
+    /// Translate a single screen pixel into its rectangle inside of the thumbnail.
+    pub fn thumbnail_pixel_rect(&self, x: u32, y: u32) -> Rect {
+        let (origin_x, origin_y) = self.thumbnail_origin();
+        let (thumb_w, thumb_h) = self.thumbnail_pixel_size();
+        self.orient(
+            origin_x + (x * thumb_w) as i32,
+            origin_y + (y * thumb_h) as i32,
+            thumb_w,
+            thumb_h,
+        )
+    }
+
+    /// The window-space rectangle of one cell of the keypad overlay,
+    /// laid out in a grid in the top left corner of the window.
+    /// (The overlay isn't part of the screen, so it is never rotated or flipped.)
+    pub fn keypad_cell(&self, row: u32, column: u32) -> Rect {
+        let (width, height) = self.window_size();
+        let size = (width.min(height) / KEYPAD_CELL_SHRINK).max(1);
+        let step = size + KEYPAD_CELL_GAP;
+        Rect::new(
+            (KEYPAD_CELL_GAP + column * step) as i32,
+            (KEYPAD_CELL_GAP + row * step) as i32,
+            size,
+            size,
+        )
+    }
+
+    /// The window-space rectangle of one pixel of on-screen display text,
+    /// given the pixel's position within its character (characters are `char_size` large),
+    /// the character's position in the text, the length of the text, and which line it's on.
+    /// Text is right-aligned in the top right corner of the window
+    /// (and like the keypad overlay, never rotated or flipped).
+    pub fn osd_pixel(
+        &self,
+        x: u32,
+        y: u32,
+        index: u32,
+        length: u32,
+        line: u32,
+        char_size: (u32, u32),
+    ) -> Rect {
+        let (width, height) = self.window_size();
+        let size = (width.min(height) / OSD_PIXEL_SHRINK).max(1);
+        // (Characters and lines are separated by one text pixel.)
+        let (char_width, char_height) = char_size;
+        let char_step = (char_width + 1) * size;
+        let line_step = (char_height + 1) * size;
+        let left = width.saturating_sub(length * char_step + KEYPAD_CELL_GAP);
+        Rect::new(
+            (left + index * char_step + x * size) as i32,
+            (KEYPAD_CELL_GAP + line * line_step + y * size) as i32,
+            size,
+            size,
+        )
+    }
+
+    /// The rectangle inside of the thumbnail outlining the magnified region.
+    pub fn thumbnail_zoom_region(&self) -> Rect {
+        let (origin_x, origin_y) = self.thumbnail_origin();
+        let (thumb_w, thumb_h) = self.thumbnail_pixel_size();
+        let (zoom_width, zoom_height) = self.zoom_region_size();
+        self.orient(
+            origin_x + (self.pan.0 * thumb_w) as i32,
+            origin_y + (self.pan.1 * thumb_h) as i32,
+            zoom_width * thumb_w,
+            zoom_height * thumb_h,
+        )
+    }
+}
+
+fn span(start: u32, length: u32, unit: f64) -> (i32, u32) {
+    // Scale a run of `length` pixels starting at `start` into window pixels,
+    // rounding both ends down so that neighboring runs never overlap or leave gaps.
+    let begin = (f64::from(start) * unit) as u32;
+    let end = (f64::from(start + length) * unit) as u32;
+    (begin as i32, end - begin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The corners of the (default, 64x32) screen: top left, top right, and bottom left.
+    const CORNERS: [(u32, u32); 3] = [(0, 0), (63, 0), (0, 31)];
+
+    fn viewport(rotation: Rotation, flip: Option<Flip>) -> Viewport {
+        let config = Config {
+            rotation,
+            flip,
+            zoom: 2,
+            ..Config::default()
+        };
+        Viewport::new(&Screen::default(), &config)
+    }
+
+    fn corners(viewport: &Viewport) -> Vec<(i32, i32)> {
+        CORNERS
+            .iter()
+            .map(|&(x, y)| {
+                let rect = viewport.pixel_rect(x, y).unwrap();
+                (rect.x(), rect.y())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn corners_under_each_rotation() {
+        // (Pixels are 10 window pixels square, in a 640x320 window before rotating.)
+        let cases = [
+            (Rotation::None, (640, 320), [(0, 0), (630, 0), (0, 310)]),
+            (
+                Rotation::Clockwise90,
+                (320, 640),
+                [(310, 0), (310, 630), (0, 0)],
+            ),
+            (
+                Rotation::Clockwise180,
+                (640, 320),
+                [(630, 310), (0, 310), (630, 0)],
+            ),
+            (
+                Rotation::Clockwise270,
+                (320, 640),
+                [(0, 630), (0, 0), (310, 630)],
+            ),
+        ];
+        for (rotation, window_size, expected) in cases {
+            let viewport = viewport(rotation, None);
+            assert_eq!(viewport.window_size(), window_size, "{rotation:?}");
+            assert_eq!(corners(&viewport), expected, "{rotation:?}");
+        }
+    }
+
+    #[test]
+    fn corners_under_each_flip() {
+        let cases = [
+            (Flip::Horizontal, [(630, 0), (0, 0), (630, 310)]),
+            (Flip::Vertical, [(0, 310), (630, 310), (0, 0)]),
+        ];
+        for (flip, expected) in cases {
+            assert_eq!(corners(&viewport(Rotation::None, Some(flip))), expected);
+        }
+
+        // Flipping happens before rotating.
+        let viewport = viewport(Rotation::Clockwise90, Some(Flip::Horizontal));
+        assert_eq!(corners(&viewport), [(310, 630), (310, 0), (0, 630)]);
+        // (So panning right in the window moves the magnifier back up the screen.)
+        assert_eq!(viewport.screen_direction(1, 0), (0, -1));
+    }
+
+    #[test]
+    fn magnified_pixels() {
+        // Magnified twice, the middle 32x16 pixels fill the window, at 20 window pixels each.
+        let mut viewport = viewport(Rotation::None, None);
+        viewport.toggle_zoom();
+        assert_eq!(viewport.pixel_rect(16, 8), Some(Rect::new(0, 0, 20, 20)));
+        assert_eq!(
+            viewport.pixel_rect(47, 23),
+            Some(Rect::new(620, 300, 20, 20))
+        );
+        assert_eq!(viewport.pixel_rect(15, 8), None);
+        assert_eq!(viewport.pixel_rect(48, 23), None);
+
+        // Panning stops at the edges of the screen.
+        viewport.pan(-10, -10);
+        assert_eq!(viewport.pixel_rect(0, 0), Some(Rect::new(0, 0, 20, 20)));
+        viewport.pan(10, 10);
+        assert_eq!(
+            viewport.pixel_rect(63, 31),
+            Some(Rect::new(620, 300, 20, 20))
+        );
+
+        // And it's rotated like the rest of the screen.
+        let mut viewport = self::viewport(Rotation::Clockwise180, None);
+        viewport.toggle_zoom();
+        assert_eq!(
+            viewport.pixel_rect(16, 8),
+            Some(Rect::new(620, 300, 20, 20))
+        );
+    }
+}
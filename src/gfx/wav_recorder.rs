@@ -0,0 +1,68 @@
+use std::fs;
+use std::io::Error;
+
+// WAV format constants (mono, 16-bit PCM).
+const CHANNELS: u16 = 1;
+const BITS_PER_SAMPLE: u16 = 16;
+const PCM_FORMAT: u16 = 1;
+// How large the header is (before the samples themselves).
+const HEADER_SIZE: u32 = 44;
+
+/// Records the buzzer's audio (exactly as it's played) into a WAV file.
+pub struct WavRecorder {
+    // How many samples are recorded each second.
+    sample_rate: u32,
+    // The samples recorded so far.
+    samples: Vec<i16>,
+}
+
+impl WavRecorder {
+    /// Start recording audio played at the given sample rate (in hz).
+    pub fn new(sample_rate: u32) -> WavRecorder {
+        WavRecorder {
+            sample_rate,
+            samples: Vec::new(),
+        }
+    }
+
+    /// Record some samples (from -1 to 1) as they're played.
+    pub fn capture(&mut self, samples: &[f32]) {
+        self.samples.extend(
+            samples
+                .iter()
+                .map(|sample| (sample.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16),
+        );
+    }
+
+    /// The recording so far, as the contents of a WAV file.
+    pub fn encode(&self) -> Vec<u8> {
+        let block_align = CHANNELS * BITS_PER_SAMPLE / 8;
+        let data_size = self.samples.len() as u32 * u32::from(block_align);
+
+        let mut data = Vec::with_capacity((HEADER_SIZE + data_size) as usize);
+        data.extend_from_slice(b"RIFF");
+        data.extend_from_slice(&(HEADER_SIZE - 8 + data_size).to_le_bytes());
+        data.extend_from_slice(b"WAVE");
+        // The format chunk.
+        data.extend_from_slice(b"fmt ");
+        data.extend_from_slice(&16u32.to_le_bytes());
+        data.extend_from_slice(&PCM_FORMAT.to_le_bytes());
+        data.extend_from_slice(&CHANNELS.to_le_bytes());
+        data.extend_from_slice(&self.sample_rate.to_le_bytes());
+        data.extend_from_slice(&(self.sample_rate * u32::from(block_align)).to_le_bytes());
+        data.extend_from_slice(&block_align.to_le_bytes());
+        data.extend_from_slice(&BITS_PER_SAMPLE.to_le_bytes());
+        // The samples themselves.
+        data.extend_from_slice(b"data");
+        data.extend_from_slice(&data_size.to_le_bytes());
+        for sample in &self.samples {
+            data.extend_from_slice(&sample.to_le_bytes());
+        }
+        data
+    }
+
+    /// Stop recording, and save the recording to a file.
+    pub fn save(self, path: &str) -> Result<(), Error> {
+        fs::write(path, self.encode())
+    }
+}
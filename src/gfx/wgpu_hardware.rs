@@ -0,0 +1,440 @@
+use super::screen::Screen;
+use super::winit_hardware::{WindowRenderer, WinitHardware};
+use super::Drawable;
+use crate::config::{Config, Rgb};
+use crate::error::Chip8Error;
+use log::warn;
+use std::borrow::Cow;
+use wgpu::util::DeviceExt;
+use wgpu::{
+    AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, Buffer,
+    BufferBindingType, BufferUsages, Color, ColorTargetState, ColorWrites,
+    CommandEncoderDescriptor, Device, DeviceDescriptor, Extent3d, FilterMode, FragmentState,
+    ImageCopyTexture, ImageDataLayout, Instance, InstanceDescriptor, Limits, LoadOp,
+    MultisampleState, Operations, Origin3d, PipelineLayoutDescriptor, PresentMode, PrimitiveState,
+    Queue, RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline,
+    RenderPipelineDescriptor, RequestAdapterOptions, Sampler, SamplerBindingType,
+    SamplerDescriptor, ShaderModuleDescriptor, ShaderSource, ShaderStages, Surface,
+    SurfaceConfiguration, SurfaceError, Texture, TextureAspect, TextureDescriptor,
+    TextureDimension, TextureFormat, TextureSampleType, TextureUsages, TextureViewDescriptor,
+    TextureViewDimension, VertexState,
+};
+use winit::window::Window;
+
+// Draws the screen (as a texture, a texel per screen pixel) on a quad, scaled to fit
+// the window while keeping its shape. Effects (such as a CRT's scanlines) belong in
+// the fragment shader.
+const SHADER: &str = r"
+struct Quad {
+    // The quad's size, as a share of the window's (in each direction).
+    scale: vec2<f32>,
+}
+
+@group(0) @binding(0) var screen: texture_2d<f32>;
+@group(0) @binding(1) var screen_sampler: sampler;
+@group(0) @binding(2) var<uniform> quad: Quad;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // The quad's corners (as two triangles), from the top left.
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+    );
+    let uv = corners[index];
+    var out: VertexOutput;
+    out.position = vec4<f32>((uv * 2.0 - 1.0) * vec2<f32>(1.0, -1.0) * quad.scale, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(screen, screen_sampler, in.uv);
+}
+";
+// The size of the uniform the shader reads (the quad's scale, as two 32-bit floats).
+const QUAD_SIZE: u64 = 8;
+
+/// Hardware that draws the screen in a winit window with wgpu directly, on a single quad
+/// scaled to fit the window: the place to add shader effects (such as a CRT's scanlines,
+/// or ghosting), and quick to draw at any window size or refresh rate.
+pub type WgpuHardware = WinitHardware<WgpuRenderer>;
+
+/// Draws the screen into a window with wgpu (see `WgpuHardware`).
+pub struct WgpuRenderer {
+    surface: Surface,
+    device: Device,
+    queue: Queue,
+    surface_config: SurfaceConfiguration,
+    pipeline: RenderPipeline,
+    bind_group_layout: BindGroupLayout,
+    sampler: Sampler,
+    quad: Buffer,
+    // The screen, as drawn last (in a texture at its resolution, and as its texels),
+    // and what the shader draws it with.
+    texture: Texture,
+    texels: Vec<u8>,
+    bind_group: BindGroup,
+    // The colors the screen is drawn with (as texels), and what the
+    // window is cleared to around the screen.
+    foreground: [u8; 4],
+    background: [u8; 4],
+    clear_color: Color,
+}
+
+impl WgpuRenderer {
+    fn color(Rgb(red, green, blue): Rgb) -> [u8; 4] {
+        // A color as a texel (RGBA, in sRGB).
+        [red, green, blue, 0xFF]
+    }
+
+    fn linear(Rgb(red, green, blue): Rgb) -> Color {
+        // A color as wgpu clears a surface to it (linear, rather than sRGB).
+        let linear = |channel: u8| {
+            let channel = f64::from(channel) / 255.0;
+            if channel <= 0.04045 {
+                channel / 12.92
+            } else {
+                ((channel + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        Color {
+            r: linear(red),
+            g: linear(green),
+            b: linear(blue),
+            a: 1.0,
+        }
+    }
+
+    fn create_texture(
+        device: &Device,
+        layout: &BindGroupLayout,
+        sampler: &Sampler,
+        quad: &Buffer,
+        (width, height): (u32, u32),
+    ) -> (Texture, BindGroup) {
+        // A texture to draw the screen into (at this resolution), and its bind group.
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("screen"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8UnormSrgb,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("screen"),
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(sampler),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: quad.as_entire_binding(),
+                },
+            ],
+        });
+        (texture, bind_group)
+    }
+
+    fn fit_quad(&self) {
+        // Scale the quad to fill as much of the window as it can, keeping its shape
+        // (the screen's texture always has the shape of the screen).
+        let size = self.texture.size();
+        let (width, height) = (
+            self.surface_config.width as f32,
+            self.surface_config.height as f32,
+        );
+        let aspect = size.width as f32 / size.height as f32;
+        let scale = if width / height > aspect {
+            [aspect * height / width, 1.0]
+        } else {
+            [1.0, width / (aspect * height)]
+        };
+        let bytes: Vec<u8> = scale.iter().flat_map(|value| value.to_ne_bytes()).collect();
+        self.queue.write_buffer(&self.quad, 0, &bytes);
+    }
+
+    fn render(&mut self) {
+        // Show the screen's texture on the quad.
+        let frame = match self.surface.get_current_texture() {
+            Ok(frame) => frame,
+            // (The surface needs configuring again, so try again next time.)
+            Err(SurfaceError::Lost | SurfaceError::Outdated) => {
+                self.surface.configure(&self.device, &self.surface_config);
+                return;
+            }
+            Err(error) => {
+                warn!("Failed to draw the screen: {error}");
+                return;
+            }
+        };
+        let view = frame.texture.create_view(&TextureViewDescriptor::default());
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor { label: None });
+        {
+            let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("screen"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(self.clear_color),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &self.bind_group, &[]);
+            pass.draw(0..6, 0..1);
+        }
+        self.queue.submit(Some(encoder.finish()));
+        frame.present();
+    }
+}
+
+impl WindowRenderer for WgpuRenderer {
+    fn new(window: &Window, screen: &Screen, config: &Config) -> Result<Self, Chip8Error> {
+        let failed = |what: &str, error: &dyn std::fmt::Display| {
+            Chip8Error::Window(format!("{what} failed: {error}"))
+        };
+        let instance = Instance::new(InstanceDescriptor::default());
+        // Safety: the window outlives the renderer (and so the surface).
+        let surface = unsafe { instance.create_surface(window) }
+            .map_err(|err| failed("creating the window's surface", &err))?;
+        let adapter = pollster::block_on(instance.request_adapter(&RequestAdapterOptions {
+            compatible_surface: Some(&surface),
+            ..RequestAdapterOptions::default()
+        }))
+        .ok_or_else(|| failed("finding a graphics adapter", &"none can draw to the window"))?;
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &DeviceDescriptor {
+                label: None,
+                features: wgpu::Features::empty(),
+                limits: Limits::downlevel_webgl2_defaults().using_resolution(adapter.limits()),
+            },
+            None,
+        ))
+        .map_err(|err| failed("setting up the graphics device", &err))?;
+
+        // (Prefer a surface that expects sRGB colors, as the screen's texture holds.)
+        let capabilities = surface.get_capabilities(&adapter);
+        let format = capabilities
+            .formats
+            .iter()
+            .copied()
+            .find(TextureFormat::is_srgb)
+            .or(capabilities.formats.first().copied())
+            .ok_or_else(|| failed("setting up drawing", &"the window can't be drawn to"))?;
+        let size = window.inner_size();
+        let surface_config = SurfaceConfiguration {
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            format,
+            width: size.width.max(1),
+            height: size.height.max(1),
+            present_mode: if config.vsync {
+                PresentMode::AutoVsync
+            } else {
+                PresentMode::AutoNoVsync
+            },
+            alpha_mode: capabilities.alpha_modes[0],
+            view_formats: vec![],
+        };
+        surface.configure(&device, &surface_config);
+
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("screen"),
+            source: ShaderSource::Wgsl(Cow::Borrowed(SHADER)),
+        });
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("screen"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("screen"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("screen"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        });
+
+        // (Screen pixels stay sharp, however large they're drawn.)
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("screen"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Nearest,
+            ..SamplerDescriptor::default()
+        });
+        let quad = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("quad"),
+            contents: &[0; QUAD_SIZE as usize],
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+        let (texture, bind_group) = Self::create_texture(
+            &device,
+            &bind_group_layout,
+            &sampler,
+            &quad,
+            (screen.res_width, screen.res_height),
+        );
+
+        let renderer = WgpuRenderer {
+            surface,
+            device,
+            queue,
+            surface_config,
+            pipeline,
+            bind_group_layout,
+            sampler,
+            quad,
+            texture,
+            texels: Vec::new(),
+            bind_group,
+            foreground: Self::color(config.palette.foreground),
+            background: Self::color(config.palette.background),
+            clear_color: if format.is_srgb() {
+                Self::linear(config.palette.background)
+            } else {
+                let Rgb(red, green, blue) = config.palette.background;
+                Color {
+                    r: f64::from(red) / 255.0,
+                    g: f64::from(green) / 255.0,
+                    b: f64::from(blue) / 255.0,
+                    a: 1.0,
+                }
+            },
+        };
+        renderer.fit_quad();
+        Ok(renderer)
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        // (A minimized window has no size, and nothing to draw to.)
+        if width == 0 || height == 0 {
+            return;
+        }
+        self.surface_config.width = width;
+        self.surface_config.height = height;
+        self.surface.configure(&self.device, &self.surface_config);
+        self.fit_quad();
+        self.render();
+    }
+
+    fn draw(&mut self, screen: &Screen) {
+        // Draw a texel per screen pixel, and let the shader scale it to the window.
+        let resolution = (screen.res_width, screen.res_height);
+        let size = self.texture.size();
+        if resolution != (size.width, size.height) {
+            (self.texture, self.bind_group) = Self::create_texture(
+                &self.device,
+                &self.bind_group_layout,
+                &self.sampler,
+                &self.quad,
+                resolution,
+            );
+            self.fit_quad();
+        }
+        self.texels.clear();
+        for y in 0..screen.res_height as u16 {
+            for x in 0..screen.res_width as u16 {
+                self.texels.extend(if screen.get_pixel(x, y) {
+                    self.foreground
+                } else {
+                    self.background
+                });
+            }
+        }
+        let size = self.texture.size();
+        self.queue.write_texture(
+            ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            &self.texels,
+            ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * size.width),
+                rows_per_image: None,
+            },
+            size,
+        );
+        self.render();
+    }
+}
@@ -0,0 +1,177 @@
+use super::interactible::{PlaybackSpeed, SetKeysResult};
+use std::time::{Duration, Instant};
+
+// How long the speed is shown for after it changes.
+const STATUS_DURATION: Duration = Duration::from_secs(2);
+
+/// The hotkeys of the lightweight window frontends (minifb, winit),
+/// which each bind them to their own keys (matching SDL's hotkeys).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Hotkey {
+    Quit,
+    Pause,
+    SaveState,
+    LoadState,
+    DumpMemory,
+    Stats,
+    SpeedUp,
+    SlowDown,
+    // Held (rather than toggled) to change the playback speed.
+    FastForward,
+    SlowMotion,
+}
+
+/// A window library's keys for the key names that layouts use (as SDL names them):
+/// letters, digits, space, return, and the arrow keys.
+pub struct KeyNames<K: 'static> {
+    pub letters: [K; 26],
+    pub digits: [K; 10],
+    // Space, Return, Up, Down, Left, and Right (in that order).
+    pub named: [K; 6],
+}
+
+impl<K: Copy> KeyNames<K> {
+    /// The key named `name`, if it's one of these.
+    pub fn key(&self, name: &str) -> Option<K> {
+        const NAMED: [&str; 6] = ["Space", "Return", "Up", "Down", "Left", "Right"];
+        if let Some(index) = NAMED.iter().position(|named| *named == name) {
+            return Some(self.named[index]);
+        }
+        if name.len() != 1 {
+            return None;
+        }
+        let character = name.as_bytes()[0].to_ascii_uppercase();
+        match character {
+            b'A'..=b'Z' => Some(self.letters[usize::from(character - b'A')]),
+            b'0'..=b'9' => Some(self.digits[usize::from(character - b'0')]),
+            _ => None,
+        }
+    }
+}
+
+/// How the lightweight window frontends act on their hotkeys, and what they show
+/// in their title bars instead of the SDL frontend's overlays.
+pub struct WindowControls {
+    // The title of this device (shown in the title bar, along with any status).
+    title: String,
+    // The speed to show in the title bar (and until when), if any.
+    status: Option<(u64, Instant)>,
+    // Whether to show the stats (toggled with a hotkey), and the stats last reported
+    // (frames and instructions per second), if any.
+    show_stats: bool,
+    stats: Option<(u64, u64)>,
+    // Whether the title bar needs showing again.
+    changed: bool,
+    // Whether to pause once the first frame has been drawn, and whether
+    // the screen has been drawn yet.
+    start_paused: bool,
+    drawn: bool,
+}
+
+impl WindowControls {
+    pub fn new(title: &str, start_paused: bool) -> WindowControls {
+        WindowControls {
+            title: String::from(title),
+            status: None,
+            show_stats: false,
+            stats: None,
+            changed: false,
+            start_paused,
+            drawn: false,
+        }
+    }
+
+    pub fn set_title(&mut self, title: &str) {
+        self.title = String::from(title);
+        self.changed = true;
+    }
+
+    /// The title bar's text: the title, along with any status.
+    pub fn title(&self, paused: bool) -> String {
+        let mut title = self.title.clone();
+        if paused {
+            title.push_str(" (paused)");
+        } else if let Some((instructions_per_frame, _)) = self.status {
+            title.push_str(&format!(
+                " ({instructions_per_frame} instructions per frame)"
+            ));
+        }
+        if let Some((frames, instructions)) = self.stats.filter(|_| self.show_stats) {
+            title.push_str(&format!(" [{frames} FPS, {instructions} IPS]"));
+        }
+        title
+    }
+
+    /// The title bar's text, if it has changed since it was last shown
+    /// (clearing the status once it has been shown for long enough).
+    pub fn changed_title(&mut self) -> Option<String> {
+        if self
+            .status
+            .is_some_and(|(_, until)| Instant::now() >= until)
+        {
+            self.status = None;
+            self.changed = true;
+        }
+        if !self.changed {
+            return None;
+        }
+        self.changed = false;
+        Some(self.title(false))
+    }
+
+    /// Note that the screen has been drawn (so that starting out paused shows it).
+    pub fn drawn(&mut self) {
+        self.drawn = true;
+    }
+
+    /// What the caller should do about the hotkeys pressed since they were last
+    /// checked (as told by `pressed`), and whether to pause first.
+    pub fn action(&mut self, pressed: impl Fn(Hotkey) -> bool) -> (SetKeysResult, bool) {
+        let caller_action = if pressed(Hotkey::Quit) {
+            return (SetKeysResult::ShouldExit, false);
+        } else if pressed(Hotkey::SaveState) {
+            SetKeysResult::ShouldSaveState
+        } else if pressed(Hotkey::LoadState) {
+            SetKeysResult::ShouldLoadState
+        } else if pressed(Hotkey::DumpMemory) {
+            SetKeysResult::ShouldDumpMemory
+        } else if pressed(Hotkey::SpeedUp) {
+            SetKeysResult::ShouldSpeedUp
+        } else if pressed(Hotkey::SlowDown) {
+            SetKeysResult::ShouldSlowDown
+        } else {
+            SetKeysResult::ShouldContinue
+        };
+        if pressed(Hotkey::Stats) {
+            self.show_stats = !self.show_stats;
+            self.changed = true;
+        }
+        let mut pause = pressed(Hotkey::Pause);
+        if self.start_paused && self.drawn {
+            self.start_paused = false;
+            pause = true;
+        }
+        (caller_action, pause)
+    }
+
+    /// How fast to run, given which hotkeys are held (as told by `held`).
+    pub fn playback_speed(held: impl Fn(Hotkey) -> bool) -> PlaybackSpeed {
+        if held(Hotkey::FastForward) {
+            PlaybackSpeed::FastForward
+        } else if held(Hotkey::SlowMotion) {
+            PlaybackSpeed::SlowMotion
+        } else {
+            PlaybackSpeed::Normal
+        }
+    }
+
+    pub fn show_speed(&mut self, instructions_per_frame: u64) {
+        self.status = Some((instructions_per_frame, Instant::now() + STATUS_DURATION));
+        self.changed = true;
+    }
+
+    pub fn show_stats(&mut self, frames_per_second: u64, instructions_per_second: u64) {
+        self.stats = Some((frames_per_second, instructions_per_second));
+        self.changed |= self.show_stats;
+    }
+}
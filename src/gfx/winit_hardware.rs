@@ -0,0 +1,369 @@
+use super::interactible::{Interactible, MachineState, PlaybackSpeed, SetKeysResult};
+use super::screen::Screen;
+use super::window_controls::{Hotkey, KeyNames, WindowControls};
+#[cfg(feature = "cpal")]
+use super::CpalBuzzer;
+use crate::config::Config;
+use crate::error::Chip8Error;
+use log::warn;
+use std::collections::HashSet;
+use std::io::Error;
+use std::mem;
+use std::time::Duration;
+use winit::application::ApplicationHandler;
+use winit::dpi::PhysicalSize;
+use winit::event::{ElementState, KeyEvent, WindowEvent};
+use winit::event_loop::{ActiveEventLoop, EventLoop};
+use winit::keyboard::{KeyCode, PhysicalKey};
+use winit::platform::pump_events::{EventLoopExtPumpEvents, PumpStatus};
+use winit::window::{Window, WindowAttributes, WindowId};
+
+// Keyboard layout constants.
+const KEYPAD_SIZE: usize = 16;
+const KEY_NAMES: KeyNames<KeyCode> = KeyNames {
+    letters: [
+        KeyCode::KeyA,
+        KeyCode::KeyB,
+        KeyCode::KeyC,
+        KeyCode::KeyD,
+        KeyCode::KeyE,
+        KeyCode::KeyF,
+        KeyCode::KeyG,
+        KeyCode::KeyH,
+        KeyCode::KeyI,
+        KeyCode::KeyJ,
+        KeyCode::KeyK,
+        KeyCode::KeyL,
+        KeyCode::KeyM,
+        KeyCode::KeyN,
+        KeyCode::KeyO,
+        KeyCode::KeyP,
+        KeyCode::KeyQ,
+        KeyCode::KeyR,
+        KeyCode::KeyS,
+        KeyCode::KeyT,
+        KeyCode::KeyU,
+        KeyCode::KeyV,
+        KeyCode::KeyW,
+        KeyCode::KeyX,
+        KeyCode::KeyY,
+        KeyCode::KeyZ,
+    ],
+    digits: [
+        KeyCode::Digit0,
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ],
+    named: [
+        KeyCode::Space,
+        KeyCode::Enter,
+        KeyCode::ArrowUp,
+        KeyCode::ArrowDown,
+        KeyCode::ArrowLeft,
+        KeyCode::ArrowRight,
+    ],
+};
+// How long to wait for the window's events at a time while paused.
+const PAUSED_POLL_INTERVAL: Duration = Duration::from_millis(16);
+
+// The window, and what has happened to it, as gathered while its events are pumped.
+struct WindowEvents {
+    // How to create the window (once the event loop says it can be), and the window.
+    attributes: WindowAttributes,
+    window: Option<Window>,
+    error: Option<String>,
+    // The keys held down, and those pressed since last checked (in order).
+    held: HashSet<KeyCode>,
+    pressed: Vec<KeyCode>,
+    // The window's new size, if it has been resized since last checked,
+    // and whether the user has closed it.
+    resized: Option<PhysicalSize<u32>>,
+    closed: bool,
+}
+
+impl ApplicationHandler for WindowEvents {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.window.is_none() {
+            match event_loop.create_window(self.attributes.clone()) {
+                Ok(window) => self.window = Some(window),
+                Err(error) => self.error = Some(error.to_string()),
+            }
+        }
+    }
+
+    fn window_event(&mut self, _event_loop: &ActiveEventLoop, _id: WindowId, event: WindowEvent) {
+        match event {
+            WindowEvent::CloseRequested => self.closed = true,
+            WindowEvent::Resized(size) => self.resized = Some(size),
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(key),
+                        state,
+                        repeat,
+                        ..
+                    },
+                ..
+            } => match state {
+                ElementState::Pressed => {
+                    self.held.insert(key);
+                    if !repeat {
+                        self.pressed.push(key);
+                    }
+                }
+                ElementState::Released => {
+                    self.held.remove(&key);
+                }
+            },
+            // (Keys released while the window is unfocused are never reported.)
+            WindowEvent::Focused(false) => self.held.clear(),
+            _ => (),
+        }
+    }
+}
+
+/// What draws the screen into a window for `WinitHardware` (such as pixels, or wgpu).
+pub trait WindowRenderer: Sized {
+    /// Set up drawing the screen into a window (which outlives the renderer).
+    fn new(window: &Window, screen: &Screen, config: &Config) -> Result<Self, Chip8Error>;
+
+    /// Fit drawing to the window once it has been resized (to this many physical pixels),
+    /// and show the screen again.
+    fn resize(&mut self, width: u32, height: u32);
+
+    /// Draw the screen (at whatever resolution it's at), and show it.
+    fn draw(&mut self, screen: &Screen);
+}
+
+/// Hardware that shows the screen in a window created with winit (drawn into by
+/// a `WindowRenderer`), and reads keys from it. Unlike SDL, winit needs no development
+/// libraries installed to build. There are no gamepads or debugging windows (or sound,
+/// without the `cpal` feature); the title bar shows what the SDL frontend's overlays would.
+pub struct WinitHardware<R> {
+    // (The renderer draws into the window, so it's dropped first.)
+    renderer: R,
+    events: WindowEvents,
+    event_loop: EventLoop<()>,
+    // The title bar, and how hotkeys are acted on.
+    controls: WindowControls,
+    // The keys bound to each keypad key, if any.
+    layout: [Option<KeyCode>; KEYPAD_SIZE],
+    keyboard: [bool; KEYPAD_SIZE],
+    // The buzzer (if an audio device could be opened).
+    #[cfg(feature = "cpal")]
+    buzzer: Option<CpalBuzzer>,
+}
+
+impl<R: WindowRenderer> WinitHardware<R> {
+    pub fn new(
+        screen: &Screen,
+        config: &Config,
+        title: &str,
+    ) -> Result<WinitHardware<R>, Chip8Error> {
+        let mut event_loop = EventLoop::new().map_err(|err| Chip8Error::Window(err.to_string()))?;
+        let (width, height) = (screen.width, screen.height);
+        let mut events = WindowEvents {
+            attributes: Window::default_attributes()
+                .with_title(title)
+                .with_inner_size(PhysicalSize::new(width, height))
+                // (Don't let the window shrink below one window pixel per screen pixel.)
+                .with_min_inner_size(PhysicalSize::new(screen.res_width, screen.res_height)),
+            window: None,
+            error: None,
+            held: HashSet::new(),
+            pressed: Vec::new(),
+            resized: None,
+            closed: false,
+        };
+        // (Desktop event loops start out resuming, which is when windows can be created.)
+        event_loop.pump_app_events(Some(Duration::ZERO), &mut events);
+        let Some(window) = &events.window else {
+            let error = events.error.take().unwrap_or_default();
+            return Err(Chip8Error::Window(format!(
+                "window creation ({width} x {height}) failed: {error}"
+            )));
+        };
+        let renderer = R::new(window, screen, config)?;
+
+        let mut layout = [None; KEYPAD_SIZE];
+        for (key, name) in layout.iter_mut().zip(config.key_bindings.names()) {
+            *key = KEY_NAMES.key(name);
+            if key.is_none() {
+                warn!("This frontend can't bind '{name}', so it's left unbound.");
+            }
+        }
+
+        // (Games are still playable without sound, so a missing audio device isn't fatal.)
+        #[cfg(feature = "cpal")]
+        let buzzer = CpalBuzzer::new(
+            config.beep_frequency,
+            config.beep_volume,
+            config.beep_waveform,
+        )
+        .inspect_err(|err| warn!("Failed to open audio device: {err}"))
+        .ok();
+
+        Ok(WinitHardware {
+            renderer,
+            events,
+            event_loop,
+            controls: WindowControls::new(title, config.start_paused),
+            layout,
+            keyboard: [false; KEYPAD_SIZE],
+            #[cfg(feature = "cpal")]
+            buzzer,
+        })
+    }
+
+    fn hotkeys(hotkey: Hotkey) -> &'static [KeyCode] {
+        // The keys for each hotkey (as in the SDL frontend).
+        match hotkey {
+            Hotkey::Quit => &[KeyCode::Escape],
+            Hotkey::Pause => &[KeyCode::KeyP],
+            Hotkey::SaveState => &[KeyCode::F5],
+            Hotkey::LoadState => &[KeyCode::F9],
+            Hotkey::DumpMemory => &[KeyCode::KeyO],
+            Hotkey::Stats => &[KeyCode::KeyI],
+            Hotkey::SpeedUp => &[KeyCode::Equal, KeyCode::NumpadAdd],
+            Hotkey::SlowDown => &[KeyCode::Minus, KeyCode::NumpadSubtract],
+            Hotkey::FastForward => &[KeyCode::Tab],
+            Hotkey::SlowMotion => &[KeyCode::ShiftLeft],
+        }
+    }
+
+    fn hotkey(layout: &[Option<KeyCode>], keys: &HashSet<KeyCode>, hotkey: Hotkey) -> bool {
+        // Whether a hotkey is among the keys.
+        // (Hotkeys the user has bound to keypad keys are left to the keypad.)
+        Self::hotkeys(hotkey)
+            .iter()
+            .any(|key| !layout.contains(&Some(*key)) && keys.contains(key))
+    }
+
+    fn pump(&mut self, timeout: Duration) -> HashSet<KeyCode> {
+        // Gather the window's events (waiting for some, up to the timeout),
+        // keeping drawing fitted to the window, and return the keys pressed since
+        // the last time.
+        if let PumpStatus::Exit(_) = self
+            .event_loop
+            .pump_app_events(Some(timeout), &mut self.events)
+        {
+            self.events.closed = true;
+        }
+        if let Some(size) = self.events.resized.take() {
+            self.renderer.resize(size.width, size.height);
+        }
+        mem::take(&mut self.events.pressed).into_iter().collect()
+    }
+
+    fn show_title(&self, title: &str) {
+        if let Some(window) = &self.events.window {
+            window.set_title(title);
+        }
+    }
+
+    fn refresh_title(&mut self) {
+        if let Some(title) = self.controls.changed_title() {
+            self.show_title(&title);
+        }
+    }
+
+    fn wait_while_paused(&mut self) -> bool {
+        // Keep the window responsive (showing the screen as it was) until unpaused,
+        // returning false if the user quit instead.
+        self.show_title(&self.controls.title(true));
+        loop {
+            let pressed = self.pump(PAUSED_POLL_INTERVAL);
+            if self.events.closed || Self::hotkey(&self.layout, &pressed, Hotkey::Quit) {
+                return false;
+            }
+            if Self::hotkey(&self.layout, &pressed, Hotkey::Pause) {
+                self.show_title(&self.controls.title(false));
+                return true;
+            }
+        }
+    }
+}
+
+impl<R: WindowRenderer> Interactible for WinitHardware<R> {
+    fn init(&mut self) {}
+
+    fn set_title(&mut self, title: &str) -> Result<(), Error> {
+        self.controls.set_title(title);
+        self.refresh_title();
+        Ok(())
+    }
+
+    fn update_display(&mut self, screen: &Screen) {
+        self.controls.drawn();
+        self.renderer.draw(screen);
+    }
+
+    fn set_keys(&mut self, screen: &Screen) -> SetKeysResult {
+        let pressed = self.pump(Duration::ZERO);
+        if self.events.closed {
+            return SetKeysResult::ShouldExit;
+        }
+
+        for (held, key) in self.keyboard.iter_mut().zip(&self.layout) {
+            *held = key.is_some_and(|key| self.events.held.contains(&key));
+        }
+        let layout = &self.layout;
+        let (mut caller_action, pause) = self
+            .controls
+            .action(|hotkey| Self::hotkey(layout, &pressed, hotkey));
+        if pause {
+            self.update_display(screen);
+            // (The buzzer stays quiet while paused.)
+            self.set_buzzer(false);
+            if !self.wait_while_paused() {
+                return SetKeysResult::ShouldExit;
+            }
+            // (We don't allow saving states while paused, just like `Hardware`.)
+            caller_action = SetKeysResult::ShouldContinue;
+        }
+        self.refresh_title();
+        caller_action
+    }
+
+    fn get_keys(&self) -> &[bool] {
+        &self.keyboard
+    }
+
+    fn key_is_pressed(&self, key: u8) -> bool {
+        self.keyboard[key as usize]
+    }
+
+    fn playback_speed(&self) -> PlaybackSpeed {
+        WindowControls::playback_speed(|hotkey| {
+            Self::hotkey(&self.layout, &self.events.held, hotkey)
+        })
+    }
+
+    fn show_speed(&mut self, instructions_per_frame: u64) {
+        self.controls.show_speed(instructions_per_frame);
+        self.refresh_title();
+    }
+
+    fn show_stats(&mut self, frames_per_second: u64, instructions_per_second: u64) {
+        self.controls
+            .show_stats(frames_per_second, instructions_per_second);
+        self.refresh_title();
+    }
+
+    // (There's no sound without cpal.)
+    fn set_buzzer(&mut self, _playing: bool) {
+        #[cfg(feature = "cpal")]
+        if let Some(buzzer) = &mut self.buzzer {
+            buzzer.set_playing(_playing);
+        }
+    }
+
+    fn show_machine_state(&mut self, _state: &MachineState) {}
+}
@@ -0,0 +1,5 @@
+pub mod arch;
+pub mod config;
+pub mod error;
+pub mod gfx;
+pub mod rom_db;
@@ -1,37 +1,767 @@
-pub mod arch;
-pub mod gfx;
+use clap::{Parser, Subcommand, ValueEnum};
+use env_logger::fmt::Formatter;
+use jchip8_rs::arch::{
+    chip8, new_emulator, read_rom, OpcodeClass, OpcodeCosts, Profile, Quirk, StateFormat, Variant,
+};
+#[cfg(feature = "netplay")]
+use jchip8_rs::config::NetplayPeer;
+use jchip8_rs::config::{
+    parse_start_address, Config, Flip, Frontend, GamepadBindings, KeyBindings, KeyLayout,
+    RenderStyle, Rgb, Rotation, Settings, Theme, Waveform, MAX_SPEED, STDIN_PATH,
+};
+#[cfg(feature = "sdl")]
+use jchip8_rs::gfx::{Gamepads, Hardware, RomBrowser};
+use jchip8_rs::rom_db::{KnownRom, RomDatabase};
+use log::kv::{self, Key, VisitSource};
+use log::{info, warn, LevelFilter, Record};
+use serde_json::{Map, Value};
+use std::fs::File;
+use std::io::{Error, ErrorKind, Write};
+use std::process::ExitCode;
 
-use arch::{chip8, Emulator};
-use clap::Parser;
-
-#[derive(Parser)]
+#[derive(Clone, Parser)]
 #[command(version, about)]
 struct Args {
-    /// Path to the game to load
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Path to the game to load, or - to read it from standard input
+    /// (if neither this nor a game state is given, a game can be picked from a list)
     #[arg(short, long)]
     path: Option<String>,
 
+    /// List the games played most recently to pick from
+    /// (or, without a window, print them)
+    #[arg(long, conflicts_with_all = ["path", "load_state"])]
+    recent: bool,
+
+    /// Directory to list games (.ch8 files) from when no game is given
+    /// (defaults to the one in the settings file, or the current directory)
+    #[arg(long, env = "JCHIP8_ROM_DIR")]
+    rom_dir: Option<String>,
+
     /// Path of game state to load
     #[arg(short, long)]
     load_state: Option<String>,
 
     /// Path of game state to save
-    /// (can be the same as the game state to load)
+    /// (can be the same as the game state to load; defaults to
+    /// <path>.state, or the game state that was loaded)
     #[arg(short, long)]
     save_state: Option<String>,
 
-    /// Whether or not to turn on debug logging
-    #[arg(short, long)]
+    /// Directory to keep default game states in
+    /// (instead of next to the game)
+    #[arg(long, env = "JCHIP8_STATE_DIR")]
+    state_dir: Option<String>,
+
+    /// Whether to save the game state when quitting
+    #[arg(long)]
+    save_on_exit: bool,
+
+    /// Encoding to write game states in (states load in any of them)
+    /// [default: from the state's extension (.cbor, .msgpack), or else json]
+    #[arg(long, value_enum)]
+    state_format: Option<StateFormat>,
+
+    /// Path to dump the emulated memory to
+    /// (defaults to <path>.mem, or <load-state>.mem)
+    #[arg(long)]
+    memory_dump: Option<String>,
+
+    /// Whether memory dumps only hold the program region
+    /// (instead of all 4KB of memory)
+    #[arg(long)]
+    memory_dump_program_only: bool,
+
+    /// Path to write the game state to once running stops, or - for standard output
+    /// (in the same format as saved game states)
+    #[arg(long, visible_alias = "dump-state")]
+    final_state: Option<String>,
+
+    /// Path to write a crash dump to if the emulator itself crashes: the panic,
+    /// the latest instructions run, and the game state, for reporting the crash
+    /// (default: <game>.crash.json)
+    #[arg(long)]
+    crash_dump: Option<String>,
+
+    /// Path to write the screen to (as a plain PBM image) once running stops
+    #[arg(long)]
+    dump_screen: Option<String>,
+
+    /// Also write the screen every this many 60hz frames of emulated time,
+    /// to numbered files next to the screen dump (e.g. <dump-screen>-000060.pbm)
+    #[arg(long, requires = "dump_screen", value_parser = clap::value_parser!(u64).range(1..))]
+    dump_screen_every: Option<u64>,
+
+    /// Stop running headless after this many cycles
+    /// (exiting with a failure status, unless the game exits first)
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    max_cycles: Option<u64>,
+
+    /// Stop running headless after this many 60hz frames of emulated time
+    /// (exiting with a failure status, unless the game exits first)
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    max_frames: Option<u64>,
+
+    /// Run headless for exactly this many cycles (or until the game exits),
+    /// as fast as possible, then exit successfully; pair with --dump-state
+    #[arg(long, conflicts_with_all = ["max_cycles", "max_frames"], value_parser = clap::value_parser!(u64).range(1..))]
+    run_cycles: Option<u64>,
+
+    /// Stop running once the game halts: jumps to itself forever (as most programs end),
+    /// or waits for a key when running headless (where none can ever be pressed)
+    #[arg(long)]
+    stop_on_halt: bool,
+
+    /// Print statistics once running stops (instructions executed, frames drawn,
+    /// average speed, time spent emulating and sleeping, and states saved)
+    #[arg(long)]
+    print_stats: bool,
+
+    /// Print the screen (as text) once running stops
+    #[arg(long)]
+    print_screen: bool,
+
+    /// Path to write how many times each instruction ran to (as CSV, by address
+    /// and opcode) once running stops
+    #[arg(long)]
+    opcode_stats: Option<String>,
+
+    /// Whether or not to turn on debug logging (short for `--log-level debug`)
+    #[arg(short, long, env = "JCHIP8_DEBUG", value_parser = clap::builder::BoolishValueParser::new())]
     debug: bool,
+
+    /// The most detailed messages to log: off, error, warn, info, debug, or trace
+    /// (defaults to warn; trace logs every instruction run)
+    #[arg(long)]
+    log_level: Option<LevelFilter>,
+
+    /// Path of a file to write log messages to (instead of standard error)
+    #[arg(long)]
+    log_file: Option<String>,
+
+    /// How log messages are written
+    #[arg(long, value_enum, default_value = "text")]
+    log_format: LogFormat,
+
+    /// How the emulator is presented (defaults to sdl, when built with it)
+    #[arg(long, value_enum, default_value_t)]
+    frontend: Frontend,
+
+    /// Run without a window, sound, or input (short for `--frontend headless`)
+    #[arg(long, conflicts_with = "frontend")]
+    headless: bool,
+
+    /// The interpreter the game was written for
+    /// (defaults to the one a loaded state was saved with)
+    #[arg(long, value_enum)]
+    variant: Option<Variant>,
+
+    /// The interpreter the game was written for, setting its variant and every quirk at once
+    /// (individual variants and quirks given here still take precedence)
+    #[arg(long, value_enum)]
+    profile: Option<Profile>,
+
+    /// Enable an interpreter quirk (can be repeated)
+    #[arg(long, value_enum)]
+    quirk: Vec<Quirk>,
+
+    /// Disable an interpreter quirk (can be repeated)
+    #[arg(long, value_enum)]
+    no_quirk: Vec<Quirk>,
+
+    /// How many cycles a kind of instruction takes, as <class>=<cycles>
+    /// (e.g. draw=4; can be repeated): clear, draw, scroll, flow, memory, or register.
+    /// Each frame runs a speed's worth of cycles, and every instruction takes 1 by default
+    #[arg(long, value_parser = OpcodeCosts::parse_cost)]
+    opcode_cost: Vec<(OpcodeClass, u64)>,
+
+    /// How many instructions to run per second
+    /// (defaults to 600, or the speed a loaded state was saved with)
+    #[arg(long, env = "JCHIP8_SPEED", value_parser = clap::value_parser!(u64).range(1..))]
+    speed: Option<u64>,
+
+    /// Explain each instruction as it runs, in words, along with what it changed
+    /// (on standard error), running slowly enough to read along with
+    #[arg(long)]
+    explain: bool,
+
+    /// How many instructions to run (and explain) per second when explaining
+    #[arg(long, requires = "explain", default_value_t = 2, value_parser = clap::value_parser!(u64).range(1..))]
+    explain_rate: u64,
+
+    /// How many instructions to run per 60hz frame (overriding --speed)
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..=1000))]
+    instructions_per_frame: Option<u64>,
+
+    /// A ROM database file (keyed by SHA-1 hash) adding to the built-in one,
+    /// whose settings apply to known games unless overridden
+    #[arg(long, conflicts_with = "no_rom_db")]
+    rom_db: Option<String>,
+
+    /// Don't recognize games from the ROM database
+    #[arg(long)]
+    no_rom_db: bool,
+
+    /// The built-in keyboard layout to bind keypad keys to
+    #[arg(long, value_enum, default_value = "hex")]
+    layout: KeyLayout,
+
+    /// The seed for random numbers
+    /// (defaults to a random seed, or the state of a loaded state)
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Where to load the game and start running it, in hex (default: 0x200),
+    /// or eti-660 for games written for the ETI-660 (which start at 0x600)
+    #[arg(long, value_parser = parse_start_address)]
+    start_address: Option<u16>,
+
+    /// Whether the game is a raw image of all 4KB of memory, including the interpreter
+    /// area (such as a full memory dump), to load in place of memory,
+    /// instead of a program to load at the start address
+    #[arg(long, requires = "path")]
+    memory_image: bool,
+
+    /// Path to an IPS patch (such as a ROM hack or translation)
+    /// to apply to the game before starting it
+    #[arg(long, requires = "path", conflicts_with = "memory_image")]
+    ips: Option<String>,
+
+    /// Path to record a movie of all keypad input to
+    /// (saved when quitting)
+    #[cfg(feature = "recorder")]
+    #[arg(long)]
+    record_movie: Option<String>,
+
+    /// Path of a movie to play back
+    /// (live input takes over once it finishes)
+    #[cfg(feature = "recorder")]
+    #[arg(long)]
+    play_movie: Option<String>,
+
+    /// Verify that playing the movie back reproduces the recorded run exactly
+    /// (as fast as possible, stopping once it finishes, or failing at the first cycle
+    /// whose state differs from the recording's)
+    #[cfg(feature = "recorder")]
+    #[arg(long, requires = "play_movie")]
+    verify_movie: bool,
+
+    /// Path to save GIF recordings (started and stopped with G) to
+    /// (default: <game>.gif)
+    #[cfg(feature = "recorder")]
+    #[arg(long)]
+    gif: Option<String>,
+
+    /// Path to save audio recordings (started and stopped with U) to
+    /// (default: <game>.wav)
+    #[cfg(all(feature = "recorder", feature = "audio"))]
+    #[arg(long)]
+    wav: Option<String>,
+
+    /// Host a two-player game, waiting for the other player to connect on this address
+    /// (e.g. 0.0.0.0:5020)
+    #[cfg(feature = "netplay")]
+    #[arg(long, conflicts_with_all = ["join", "load_state"])]
+    host: Option<String>,
+
+    /// Join a two-player game hosted at this address (e.g. 192.168.1.2:5020)
+    #[cfg(feature = "netplay")]
+    #[arg(long, conflicts_with = "load_state")]
+    join: Option<String>,
+
+    /// Delay both players' input by this many frames during netplay
+    /// (raise it if the game stutters; the host's is used)
+    #[cfg(feature = "netplay")]
+    #[arg(long, default_value_t = 2, value_parser = clap::value_parser!(u32).range(0..=30))]
+    netplay_delay: u32,
+
+    /// Listen for remote control commands (JSON lines, e.g. {"command": "pause"})
+    /// on this address (e.g. 127.0.0.1:5030)
+    #[cfg(feature = "scripting")]
+    #[arg(long)]
+    remote: Option<String>,
+
+    /// Serve a debugger (showing the registers, disassembly, and screen live)
+    /// to browsers on this address (e.g. 127.0.0.1:8080)
+    #[cfg(all(feature = "debugger", feature = "scripting"))]
+    #[arg(long)]
+    web_debugger: Option<String>,
+
+    /// The pitch of the buzzer, in hz
+    #[cfg(feature = "audio")]
+    #[arg(long, default_value_t = 440, value_parser = clap::value_parser!(u32).range(20..=20000))]
+    beep_frequency: u32,
+
+    /// How loud the buzzer is, as a percentage (changed with [ and ])
+    #[cfg(feature = "audio")]
+    #[arg(long, default_value_t = 25, value_parser = clap::value_parser!(u32).range(0..=100))]
+    volume: u32,
+
+    /// The shape of the buzzer's tone
+    #[cfg(feature = "audio")]
+    #[arg(long, value_enum, default_value = "square")]
+    waveform: Waveform,
+
+    /// Path of a JSON file binding keypad keys to keyboard keys
+    /// (overriding the layout)
+    /// (e.g. {"C": "4", "D": "R"})
+    #[arg(long)]
+    keymap: Option<String>,
+
+    /// Bind a keypad key to a keyboard key, e.g. C=4
+    /// (can be repeated, and overrides the keymap)
+    #[arg(long, value_parser = KeyBindings::parse_binding)]
+    bind: Vec<(u8, String)>,
+
+    /// Bind a gamepad button to a keypad key, e.g. a=5
+    /// (can be repeated)
+    #[arg(long, value_parser = GamepadBindings::parse_binding)]
+    pad_bind: Vec<(String, u8)>,
+
+    /// Start paused, once the first frame has been drawn
+    /// (unpaused with P)
+    #[arg(long)]
+    start_paused: bool,
+
+    /// Keep running while the window is unfocused
+    /// (instead of pausing)
+    #[arg(long)]
+    keep_running_unfocused: bool,
+
+    /// Delay keypad input by this many frames (for testing poor input conditions)
+    #[arg(long, default_value_t = 0)]
+    input_latency: u32,
+
+    /// Randomly delay keypad input by up to this many extra frames
+    #[arg(long, default_value_t = 0)]
+    input_jitter: u32,
+
+    /// Start the window at this many times the screen's resolution (10 by default)
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..=40))]
+    scale: Option<u32>,
+
+    /// Rotate the display clockwise (in degrees)
+    #[arg(long, value_enum, default_value = "0")]
+    rotate: Rotation,
+
+    /// Mirror the display horizontally or vertically
+    #[arg(long, value_enum)]
+    flip: Option<Flip>,
+
+    /// Magnification level of the low-vision magnifier (toggled with M, panned with the arrow keys)
+    #[arg(long, default_value_t = 2, value_parser = clap::value_parser!(u32).range(2..=8))]
+    zoom: u32,
+
+    /// Only scale the image by whole multiples (keeping every pixel the same size)
+    /// when the window is resized
+    #[arg(long)]
+    integer_scaling: bool,
+
+    /// Synchronize drawing with the display's refresh (preventing tearing)
+    #[arg(long)]
+    vsync: bool,
+
+    /// Draw on a separate thread, so that slow drawing never slows down emulation
+    #[arg(long)]
+    render_thread: bool,
+
+    /// How to draw pixels
+    #[arg(long, value_enum, default_value = "flat")]
+    render_style: RenderStyle,
+
+    /// Fade cleared pixels out over this many frames (reducing flicker)
+    #[arg(long, default_value_t = 0, value_parser = clap::value_parser!(u32).range(0..=60))]
+    phosphor: u32,
+
+    /// Start with scanlines drawn over the screen, for a CRT look (toggled with N)
+    #[arg(long)]
+    scanlines: bool,
+
+    /// The color theme (cycled with T; defaults to the last one selected)
+    #[arg(long, value_enum)]
+    theme: Option<Theme>,
+
+    /// The color of set pixels, e.g. "#33FF66" (overrides the theme's)
+    #[arg(long)]
+    foreground: Option<Rgb>,
+
+    /// The color of the background, e.g. "#001100" (overrides the theme's)
+    #[arg(long)]
+    background: Option<Rgb>,
+}
+
+/// How log messages are written.
+#[derive(Clone, Copy, ValueEnum)]
+enum LogFormat {
+    /// Human-readable lines
+    Text,
+    /// One JSON object per line, with the time, level, target, message,
+    /// and the event's fields (such as the PC, or the key pressed)
+    Json,
 }
 
-fn main() -> Result<(), std::io::Error> {
-    let args = Args::parse();
+/// The fields of a log message, collected into a JSON object.
+struct JsonFields(Map<String, Value>);
 
-    // Chip8::new() will enforce that one of path and load_state is present;
-    // if both are path will take precedence.
-    let mut emulator = chip8::Chip8::new(args.debug, args.path, args.load_state, args.save_state)?;
-    emulator.run();
+impl<'kvs> VisitSource<'kvs> for JsonFields {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+        // (Numbers and booleans are kept as such, and anything else is written as text.)
+        let value = if let Some(number) = value.to_u64() {
+            Value::from(number)
+        } else if let Some(number) = value.to_i64() {
+            Value::from(number)
+        } else if let Some(boolean) = value.to_bool() {
+            Value::from(boolean)
+        } else {
+            Value::from(value.to_string())
+        };
+        self.0.insert(key.to_string(), value);
+        Ok(())
+    }
+}
 
+fn format_json(buf: &mut Formatter, record: &Record) -> Result<(), Error> {
+    let mut fields = JsonFields(Map::new());
+    record
+        .key_values()
+        .visit(&mut fields)
+        .map_err(Error::other)?;
+    let mut event = fields.0;
+    event.insert("time".into(), buf.timestamp().to_string().into());
+    event.insert("level".into(), record.level().as_str().into());
+    event.insert("target".into(), record.target().into());
+    event.insert("message".into(), record.args().to_string().into());
+    writeln!(buf, "{}", Value::Object(event))
+}
+
+/// Tools to run instead of playing a game.
+#[derive(Clone, Subcommand)]
+enum Command {
+    /// Print an annotated hex dump of the memory of a game or saved game state
+    DumpMemory {
+        /// Path to the game or game state
+        file: String,
+    },
+    /// Run a game headless as fast as possible for a fixed number of instructions,
+    /// and report how fast it ran (in all, and fetching, executing, and drawing)
+    Bench {
+        /// Path to the game
+        rom: String,
+        /// How many instructions to run
+        #[arg(long, default_value_t = 10_000_000, value_parser = clap::value_parser!(u64).range(1..))]
+        instructions: u64,
+    },
+}
+
+impl TryFrom<Args> for Config {
+    type Error = Error;
+
+    fn try_from(args: Args) -> Result<Config, Error> {
+        // Key bindings come from the layout, then the keymap (if any),
+        // and then individual overrides.
+        let mut key_bindings = KeyBindings::from(args.layout);
+        if let Some(path) = &args.keymap {
+            key_bindings.load_file(path)?;
+        }
+        for (key, name) in &args.bind {
+            key_bindings.bind(*key, name);
+        }
+        let mut gamepad_bindings = GamepadBindings::default();
+        for (button, key) in &args.pad_bind {
+            gamepad_bindings.bind(button, *key);
+        }
+        let frontend = if args.headless || args.run_cycles.is_some() {
+            Frontend::Headless
+        } else {
+            args.frontend
+        };
+        if (args.max_cycles.is_some() || args.max_frames.is_some())
+            && frontend != Frontend::Headless
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "--max-cycles and --max-frames only apply when running headless. Please check usage with '-h'.",
+            ));
+        }
+        // (Catch unknown keys now, instead of once the window is being created.
+        // Other frontends don't use SDL's keys.)
+        #[cfg(feature = "sdl")]
+        if frontend == Frontend::Sdl {
+            Hardware::layout(&key_bindings)?;
+            Gamepads::layout(&gamepad_bindings)?;
+        }
+        // Games can have their own settings (in the settings file),
+        // which apply unless they're given here.
+        let settings = Settings::load();
+        let rom_settings = args
+            .path
+            .as_deref()
+            .and_then(|path| settings.rom_settings(path))
+            .cloned()
+            .unwrap_or_default();
+        // Known games also have settings in the ROM database,
+        // which apply unless the settings file or the options given here say otherwise.
+        let known_rom = if args.no_rom_db {
+            None
+        } else {
+            let mut database = RomDatabase::built_in();
+            if let Some(path) = &args.rom_db {
+                database.extend_from_file(path)?;
+            }
+            args.path
+                .as_deref()
+                .filter(|path| *path != STDIN_PATH)
+                .and_then(|path| read_rom(path).ok())
+                .and_then(|contents| database.lookup(&contents).cloned())
+        };
+        let known_rom = known_rom.inspect(|rom| info!("Recognized {}!", rom.full_title()));
+        let known_profile = known_rom.as_ref().and_then(|rom| rom.profile);
+        // Colors come from the theme (the game's, or the last one selected, if not given),
+        // and then individual overrides.
+        let theme = args
+            .theme
+            .or(rom_settings.theme)
+            .or(settings.theme)
+            .unwrap_or_default();
+        let mut palette = theme.palette();
+        if let Some(foreground) = args.foreground.or(rom_settings.foreground) {
+            palette.foreground = foreground;
+        }
+        if let Some(background) = args.background.or(rom_settings.background) {
+            palette.background = background;
+        }
+
+        Ok(Config {
+            frontend,
+            game_path: args.path,
+            game_title: known_rom.as_ref().map(KnownRom::full_title),
+            rom_dir: args.rom_dir.or(settings.rom_dir),
+            load_state_path: args.load_state,
+            save_state_path: args.save_state,
+            state_dir: args.state_dir,
+            save_on_exit: args.save_on_exit,
+            state_format: args.state_format,
+            memory_dump_path: args.memory_dump,
+            memory_dump_program_only: args.memory_dump_program_only,
+            final_state_path: args.final_state,
+            crash_dump_path: args.crash_dump,
+            screen_dump_path: args.dump_screen,
+            screen_dump_interval: args.dump_screen_every,
+            max_cycles: args.max_cycles,
+            max_frames: args.max_frames,
+            run_cycles: args.run_cycles,
+            explain_rate: args.explain.then_some(args.explain_rate),
+            stop_on_halt: args.stop_on_halt,
+            print_stats: args.print_stats,
+            print_screen: args.print_screen,
+            opcode_stats_path: args.opcode_stats,
+            variant: args
+                .variant
+                .or(args.profile.map(Profile::variant))
+                .or(rom_settings.variant)
+                .or(known_profile.map(Profile::variant)),
+            // (The known game's quirks come first, then the game's settings, then the profile's,
+            // so that the ones given here win.)
+            quirks: known_profile
+                .into_iter()
+                .flat_map(Profile::quirks)
+                .chain(rom_settings.quirks)
+                .chain(args.profile.into_iter().flat_map(Profile::quirks))
+                .chain(args.quirk.into_iter().map(|quirk| (quirk, true)))
+                .chain(args.no_quirk.into_iter().map(|quirk| (quirk, false)))
+                .collect(),
+            opcode_costs: rom_settings
+                .opcode_costs
+                .into_iter()
+                .chain(args.opcode_cost)
+                .collect(),
+            speed: args
+                .instructions_per_frame
+                .map(|instructions| instructions * 60)
+                .or(args.speed)
+                .or(rom_settings.speed)
+                .or(known_rom.as_ref().and_then(KnownRom::speed))
+                .map(|speed| speed.clamp(1, MAX_SPEED)),
+            seed: args.seed,
+            start_address: args.start_address,
+            memory_image: args.memory_image,
+            ips_path: args.ips,
+            #[cfg(feature = "recorder")]
+            record_movie_path: args.record_movie,
+            #[cfg(feature = "recorder")]
+            play_movie_path: args.play_movie,
+            #[cfg(feature = "recorder")]
+            verify_movie: args.verify_movie,
+            #[cfg(feature = "recorder")]
+            gif_path: args.gif,
+            #[cfg(all(feature = "recorder", feature = "audio"))]
+            wav_path: args.wav,
+            #[cfg(not(all(feature = "recorder", feature = "audio")))]
+            wav_path: None,
+            #[cfg(not(feature = "recorder"))]
+            record_movie_path: None,
+            #[cfg(not(feature = "recorder"))]
+            play_movie_path: None,
+            #[cfg(not(feature = "recorder"))]
+            verify_movie: false,
+            #[cfg(not(feature = "recorder"))]
+            gif_path: None,
+            #[cfg(feature = "netplay")]
+            netplay: args
+                .host
+                .map(NetplayPeer::Host)
+                .or(args.join.map(NetplayPeer::Join)),
+            #[cfg(feature = "netplay")]
+            netplay_delay: args.netplay_delay,
+            #[cfg(not(feature = "netplay"))]
+            netplay: None,
+            #[cfg(not(feature = "netplay"))]
+            netplay_delay: 0,
+            #[cfg(feature = "scripting")]
+            remote_address: args.remote,
+            #[cfg(not(feature = "scripting"))]
+            remote_address: None,
+            #[cfg(all(feature = "debugger", feature = "scripting"))]
+            web_debugger_address: args.web_debugger,
+            #[cfg(not(all(feature = "debugger", feature = "scripting")))]
+            web_debugger_address: None,
+            #[cfg(feature = "audio")]
+            beep_frequency: args.beep_frequency,
+            #[cfg(feature = "audio")]
+            beep_volume: args.volume,
+            #[cfg(feature = "audio")]
+            beep_waveform: args.waveform,
+            #[cfg(not(feature = "audio"))]
+            beep_frequency: 0,
+            #[cfg(not(feature = "audio"))]
+            beep_volume: 0,
+            #[cfg(not(feature = "audio"))]
+            beep_waveform: Waveform::default(),
+            key_bindings,
+            gamepad_bindings,
+            start_paused: args.start_paused,
+            keep_running_unfocused: args.keep_running_unfocused,
+            input_latency: args.input_latency,
+            input_jitter: args.input_jitter,
+            scale: args.scale,
+            rotation: args.rotate,
+            flip: args.flip,
+            zoom: args.zoom,
+            integer_scaling: args.integer_scaling,
+            vsync: args.vsync,
+            render_thread: args.render_thread,
+            render_style: args.render_style,
+            phosphor: args.phosphor,
+            scanlines: args.scanlines,
+            theme,
+            palette,
+        })
+    }
+}
+
+fn init_logging(args: &Args) -> Result<(), Error> {
+    // Log warnings by default (or the levels picked in RUST_LOG),
+    // unless a level was given here.
+    let mut builder = env_logger::Builder::new();
+    builder.filter_level(LevelFilter::Warn).parse_default_env();
+    if let Some(level) = args.log_level.or(args.debug.then_some(LevelFilter::Debug)) {
+        builder.filter_level(level);
+    }
+    if let LogFormat::Json = args.log_format {
+        builder.format(format_json);
+    }
+    if let Some(path) = &args.log_file {
+        builder.target(env_logger::Target::Pipe(Box::new(File::create(path)?)));
+    }
+    builder.init();
     Ok(())
 }
+
+fn main() -> Result<ExitCode, Error> {
+    // (Without SDL, there's no game list to fill the path in from.)
+    #[cfg_attr(not(feature = "sdl"), allow(unused_mut))]
+    let mut args = Args::parse();
+    init_logging(&args)?;
+    match &args.command {
+        Some(Command::DumpMemory { file }) => {
+            print!("{}", chip8::Chip8::from_file(file)?.memory_hex_dump());
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Bench { rom, instructions }) => {
+            let config = Config {
+                game_path: Some(rom.clone()),
+                frontend: Frontend::Headless,
+                ..Default::default()
+            };
+            println!("{}", chip8::Chip8::new(&config)?.bench(*instructions)?);
+            return Ok(ExitCode::SUCCESS);
+        }
+        None => (),
+    }
+
+    // Without a game (or state) to start, let the user pick a game from a list
+    // (and then set everything up again, since the game may have its own settings).
+    if args.path.is_none() && args.load_state.is_none() {
+        // (Only SDL windows can show the list.)
+        #[cfg(feature = "sdl")]
+        {
+            let config = Config::try_from(args.clone())?;
+            if config.frontend == Frontend::Sdl {
+                let browser = if args.recent {
+                    RomBrowser::recent(&config)?
+                } else {
+                    RomBrowser::new(&config)?
+                };
+                match browser.choose() {
+                    Some(game) => args.path = Some(game),
+                    None => return Ok(ExitCode::SUCCESS),
+                }
+            }
+        }
+        if args.path.is_none() && args.recent {
+            for game in Settings::load().recent_games() {
+                println!("{game}");
+            }
+            return Ok(ExitCode::SUCCESS);
+        }
+    }
+    let config = Config::try_from(args)?;
+
+    // new_emulator() will enforce that one of path and load_state is present;
+    // if both are path will take precedence.
+    let mut emulator = new_emulator(&config)?;
+    // Remember games played (once they've loaded, so that mistyped paths aren't remembered),
+    // but not ones run headless, which are usually run by scripts rather than played.
+    if let Some(game) = &config.game_path {
+        if !config.game_from_stdin() && config.frontend != Frontend::Headless {
+            let mut settings = Settings::load();
+            settings.add_recent(game);
+            if let Err(err) = settings.save() {
+                warn!("Failed to save settings: {err}");
+            }
+        }
+    }
+    // (Statistics are printed even if running stopped at a fault.)
+    let result = emulator.run();
+    if config.print_stats {
+        eprintln!("{}", emulator.run_stats());
+    }
+    if config.print_screen {
+        eprint!("{:#}", emulator.screen());
+    }
+    result?;
+    if config.verify_movie {
+        eprintln!("Replay verified: it reproduced the recorded run exactly.");
+    }
+
+    // Runs that were cut short (instead of being finished by the game or the user) fail,
+    // so that scripts can tell when a game never got to the end.
+    // (Runs of a fixed number of cycles are meant to end there, though.)
+    if emulator.reached_run_limit() && config.run_cycles.is_none() {
+        eprintln!("Stopped at the run limit before the game exited.");
+        return Ok(ExitCode::FAILURE);
+    }
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,82 @@
+use crate::arch::Profile;
+use crate::config::rom_hash;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Error, ErrorKind};
+
+// The database shipped with the emulator (covering the bundled games, at least).
+const BUILT_IN: &str = include_str!("rom_db.json");
+
+/// What's known about a game: what it's called, who wrote it,
+/// and how it should be run.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct KnownRom {
+    pub title: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    // The interpreter the game was written for, if it matters.
+    #[serde(default)]
+    pub profile: Option<Profile>,
+    // How many instructions to run per 60hz frame, if the game needs a particular speed.
+    #[serde(default)]
+    pub tickrate: Option<u64>,
+}
+
+impl KnownRom {
+    /// The game's title, along with its author (if known).
+    pub fn full_title(&self) -> String {
+        match &self.author {
+            Some(author) => format!("{} by {author}", self.title),
+            None => self.title.clone(),
+        }
+    }
+
+    /// How many instructions to run per second, if the game needs a particular speed.
+    pub fn speed(&self) -> Option<u64> {
+        self.tickrate.map(|tickrate| tickrate * 60)
+    }
+}
+
+/// A database of known games, keyed by their SHA-1 hash (in hex),
+/// whose settings apply automatically whenever one of them is loaded
+/// (unless they're overridden in the settings file or on the command line).
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(transparent)]
+pub struct RomDatabase {
+    roms: HashMap<String, KnownRom>,
+}
+
+impl RomDatabase {
+    /// The database shipped with the emulator.
+    pub fn built_in() -> RomDatabase {
+        serde_json::from_str(BUILT_IN).expect("The built-in ROM database is invalid.")
+    }
+
+    /// Add the games in a database file (in the same format as the built-in one),
+    /// replacing what's already known about any of them.
+    pub fn extend_from_file(&mut self, path: &str) -> Result<(), Error> {
+        let contents = fs::read(path)?;
+        let database: RomDatabase = serde_json::from_slice(&contents).map_err(|error| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("'{path}' does not appear to be a valid ROM database: {error}"),
+            )
+        })?;
+        self.roms.extend(
+            database
+                .roms
+                .into_iter()
+                .map(|(hash, rom)| (hash.to_ascii_lowercase(), rom)),
+        );
+        Ok(())
+    }
+
+    /// What's known about the game with the given contents, if anything.
+    pub fn lookup(&self, contents: &[u8]) -> Option<&KnownRom> {
+        if self.roms.is_empty() {
+            return None;
+        }
+        self.roms.get(&rom_hash(contents))
+    }
+}
@@ -0,0 +1,53 @@
+use jchip8_rs::arch::chip8::Chip8;
+use jchip8_rs::arch::Emulator;
+use jchip8_rs::config::{Config, Frontend};
+use std::fs;
+use std::path::Path;
+
+// Games bundled in c8games/, and how many frames each runs for
+// before its screen is compared against its golden image (in tests/golden/).
+const GOLDEN_GAMES: [(&str, u64); 6] = [
+    ("BRIX", 120),
+    ("INVADERS", 180),
+    ("MAZE", 120),
+    ("PONG", 120),
+    ("TETRIS", 120),
+    ("UFO", 120),
+];
+
+#[test]
+fn golden_screens() {
+    // (Set UPDATE_GOLDEN to write the golden images instead,
+    // once any changes to them have been checked to be intended.)
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let config = Config {
+        frontend: Frontend::Headless,
+        seed: Some(8),
+        ..Default::default()
+    };
+    let mut mismatches = Vec::new();
+    for (game, frames) in GOLDEN_GAMES {
+        let rom = fs::read(root.join("c8games").join(game)).unwrap();
+        let mut c8 = Chip8::from_bytes(&rom, &config).unwrap();
+        for _ in 0..frames {
+            c8.run_frame().unwrap();
+        }
+
+        let screen = c8.screen().to_pbm();
+        let golden_path = root.join(format!("tests/golden/{game}.pbm"));
+        if update {
+            fs::write(&golden_path, &screen).unwrap();
+        } else if fs::read_to_string(&golden_path).ok().as_deref() != Some(screen.as_str()) {
+            // (The screen is written out, to compare against the golden image.)
+            let actual_path = std::env::temp_dir().join(format!("jchip8-golden-{game}.pbm"));
+            fs::write(&actual_path, &screen).unwrap();
+            mismatches.push(format!("{game} (see {})", actual_path.display()));
+        }
+    }
+    assert!(
+        mismatches.is_empty(),
+        "Screens differ from their golden images: {}",
+        mismatches.join(", ")
+    );
+}